@@ -0,0 +1,33 @@
+CREATE TYPE public.delivery_status AS ENUM ('queued', 'delivered', 'failed');
+
+-- Notifications received before the queue existed were sent inline
+ALTER TABLE public.notifications
+    ADD COLUMN status       public.delivery_status not null default 'delivered',
+    ADD COLUMN attempts     integer                not null default 0,
+    ADD COLUMN last_error   text,
+    ADD COLUMN delivered_at timestamptz;
+
+ALTER TABLE public.notifications
+    ALTER COLUMN status SET DEFAULT 'queued';
+
+CREATE TABLE IF NOT EXISTS public.notification_queue
+(
+    notification_id varchar(255) not null,
+    client_id       varchar(255) not null,
+    tenant_id       varchar(255) not null,
+
+    payload         jsonb        not null,
+    attempts        integer      not null default 0,
+
+    next_attempt_at timestamptz  not null default now(),
+    created_at      timestamptz  not null default now(),
+
+    PRIMARY KEY (notification_id, client_id),
+
+    CONSTRAINT fk_notification_queue_client_id FOREIGN KEY (client_id)
+        REFERENCES public.clients (id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX notification_queue_next_attempt_at_idx
+    ON public.notification_queue (next_attempt_at);
@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS public.webhook_events
+(
+    id                   varchar(255) primary key,
+    tenant_id            varchar(255)           not null,
+    event_type           varchar(255)           not null,
+    payload              jsonb                  not null,
+
+    status               public.delivery_status not null default 'queued',
+    attempts             integer                not null default 0,
+    response_status      integer,
+    last_error           text,
+
+    next_attempt_at      timestamptz            not null default now(),
+    delivered_at         timestamptz,
+    created_at           timestamptz            not null default now()
+);
+
+CREATE INDEX webhook_events_next_attempt_at_idx
+    ON public.webhook_events (next_attempt_at)
+    WHERE status = 'queued';
+
+CREATE INDEX webhook_events_tenant_id_idx
+    ON public.webhook_events (tenant_id, created_at);
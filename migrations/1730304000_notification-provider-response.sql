@@ -0,0 +1,7 @@
+-- The provider that accepted the notification and its id for the message
+ALTER TABLE public.notifications
+    ADD COLUMN provider            varchar(32),
+    ADD COLUMN provider_message_id text;
+
+CREATE INDEX notifications_client_last_received_at_idx
+    ON public.notifications (tenant_id, client_id, last_received_at DESC);
@@ -0,0 +1,52 @@
+-- Notifications are partitioned by day so expired days can be dropped by the
+-- janitor instead of deleted row by row.
+--
+-- The primary key has to include the partition key, so from here on nothing in
+-- the schema prevents duplicate (id, client_id) rows: uniqueness is only kept by
+-- `create_or_update_notification` taking a per client advisory lock
+-- (`pg_advisory_xact_lock(abs(hashtext(client_id)))`). Anything writing
+-- notifications must take the same lock.
+--
+-- The existing notifications aren't copied here, which would rewrite the whole
+-- table in one transaction. They stay in `notifications_unpartitioned` and are
+-- moved into their daily partitions in batches by
+-- `backfill_notification_partitions` when the server starts, which drops the
+-- old table once it's empty.
+ALTER TABLE public.notifications
+    RENAME TO notifications_unpartitioned;
+ALTER INDEX public.notifications_pkey
+    RENAME TO notifications_unpartitioned_pkey;
+DROP INDEX public.notifications_client_last_received_at_idx;
+
+CREATE TABLE public.notifications
+(
+    id                  varchar(255)           not null,
+    client_id           varchar(255)           not null,
+    tenant_id           varchar(255)           not null default '0000-0000-0000-0000',
+
+    last_payload        jsonb                  not null default '{}'::jsonb,
+    previous_payloads   jsonb[]                not null default array []::jsonb[],
+
+    status              public.delivery_status not null default 'queued',
+    attempts            integer                not null default 0,
+    last_error          text,
+    delivered_at        timestamptz,
+    provider            varchar(32),
+    provider_message_id text,
+
+    last_received_at    timestamptz            not null default now(),
+    created_at          timestamptz            not null default now(),
+
+    PRIMARY KEY (id, client_id, created_at),
+
+    CONSTRAINT fk_notifications_client_id FOREIGN KEY (client_id)
+        REFERENCES public.clients (id)
+        ON DELETE CASCADE
+) PARTITION BY RANGE (created_at);
+
+-- Catches rows without a daily partition
+CREATE TABLE public.notifications_default
+    PARTITION OF public.notifications DEFAULT;
+
+CREATE INDEX notifications_client_last_received_at_idx
+    ON public.notifications (tenant_id, client_id, last_received_at DESC);
@@ -0,0 +1,32 @@
+CREATE TYPE public.client_event_type AS ENUM ('registered', 'updated', 'token_moved', 'deleted');
+CREATE TYPE public.client_event_reason AS ENUM ('register', 'bad_device_token', 'manual_delete');
+
+-- Append-only, rows outlive the clients they describe
+CREATE TABLE IF NOT EXISTS public.client_events
+(
+    id                    bigserial primary key,
+    event_type            public.client_event_type   not null,
+    reason                public.client_event_reason not null,
+
+    client_id             varchar(255)               not null,
+    tenant_id             varchar(255)               not null,
+    device_token          text,
+
+    -- The values replaced by the event
+    previous_client_id    varchar(255),
+    previous_tenant_id    varchar(255),
+    previous_device_token text,
+
+    created_at            timestamptz                not null default now()
+);
+
+CREATE INDEX client_events_client_id_idx
+    ON public.client_events (client_id);
+CREATE INDEX client_events_previous_client_id_idx
+    ON public.client_events (previous_client_id)
+    WHERE previous_client_id IS NOT NULL;
+CREATE INDEX client_events_device_token_idx
+    ON public.client_events (device_token);
+CREATE INDEX client_events_previous_device_token_idx
+    ON public.client_events (previous_device_token)
+    WHERE previous_device_token IS NOT NULL;
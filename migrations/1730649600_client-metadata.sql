@@ -0,0 +1,9 @@
+ALTER TABLE public.clients
+    ADD COLUMN platform      varchar(255),
+    ADD COLUMN os_version    varchar(255),
+    ADD COLUMN app_bundle_id varchar(255),
+    ADD COLUMN app_version   varchar(255),
+    ADD COLUMN sdk_name      varchar(255),
+    ADD COLUMN sdk_version   varchar(255),
+    ADD COLUMN locale        varchar(255),
+    ADD COLUMN timezone      varchar(255);
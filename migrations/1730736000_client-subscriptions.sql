@@ -0,0 +1,18 @@
+-- Clients are only subscribed under the tenant they are registered with, so
+-- the tenant comes from the clients table
+CREATE TABLE IF NOT EXISTS public.client_subscriptions
+(
+    client_id  varchar(255) not null,
+    topic      varchar(255) not null,
+
+    created_at timestamptz  not null default now(),
+
+    PRIMARY KEY (topic, client_id),
+
+    CONSTRAINT fk_client_subscriptions_client_id FOREIGN KEY (client_id)
+        REFERENCES public.clients (id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX client_subscriptions_client_id_idx
+    ON public.client_subscriptions (client_id);
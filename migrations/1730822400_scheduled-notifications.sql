@@ -0,0 +1,26 @@
+ALTER TYPE public.delivery_status ADD VALUE 'scheduled';
+ALTER TYPE public.delivery_status ADD VALUE 'cancelled';
+
+-- Moved to the notification queue when they are due
+CREATE TABLE IF NOT EXISTS public.scheduled_notifications
+(
+    notification_id varchar(255) not null,
+    client_id       varchar(255) not null,
+    tenant_id       varchar(255) not null,
+
+    payload         jsonb        not null,
+    send_at         timestamptz  not null,
+
+    created_at      timestamptz  not null default now(),
+
+    PRIMARY KEY (notification_id, client_id),
+
+    CONSTRAINT fk_scheduled_notifications_client_id FOREIGN KEY (client_id)
+        REFERENCES public.clients (id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX scheduled_notifications_send_at_idx
+    ON public.scheduled_notifications (send_at);
+CREATE INDEX scheduled_notifications_client_id_idx
+    ON public.scheduled_notifications (client_id);
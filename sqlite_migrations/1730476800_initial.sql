@@ -0,0 +1,90 @@
+-- Timestamps are RFC 3339 text written by echo-server, which sorts in time
+-- order as they are all UTC. Enums are stored as their lowercase names.
+CREATE TABLE IF NOT EXISTS clients
+(
+    id           TEXT    NOT NULL PRIMARY KEY,
+    tenant_id    TEXT    NOT NULL,
+
+    push_type    TEXT    NOT NULL,
+    device_token TEXT    NOT NULL UNIQUE,
+    always_raw   BOOLEAN NOT NULL DEFAULT FALSE,
+
+    created_at   TEXT    NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS notifications
+(
+    id                  TEXT    NOT NULL,
+    client_id           TEXT    NOT NULL,
+    tenant_id           TEXT    NOT NULL,
+
+    last_payload        TEXT    NOT NULL,
+
+    status              TEXT    NOT NULL DEFAULT 'queued',
+    attempts            INTEGER NOT NULL DEFAULT 0,
+    last_error          TEXT,
+    delivered_at        TEXT,
+    provider            TEXT,
+    provider_message_id TEXT,
+
+    last_received_at    TEXT    NOT NULL,
+    created_at          TEXT    NOT NULL,
+
+    PRIMARY KEY (id, client_id),
+
+    CONSTRAINT fk_notifications_client_id FOREIGN KEY (client_id)
+        REFERENCES clients (id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX notifications_client_last_received_at_idx
+    ON notifications (tenant_id, client_id, last_received_at DESC);
+
+CREATE INDEX notifications_created_at_idx
+    ON notifications (created_at);
+
+CREATE TABLE IF NOT EXISTS notification_queue
+(
+    notification_id TEXT    NOT NULL,
+    client_id       TEXT    NOT NULL,
+    tenant_id       TEXT    NOT NULL,
+
+    payload         TEXT    NOT NULL,
+    attempts        INTEGER NOT NULL DEFAULT 0,
+
+    next_attempt_at TEXT    NOT NULL,
+    created_at      TEXT    NOT NULL,
+
+    PRIMARY KEY (notification_id, client_id),
+
+    CONSTRAINT fk_notification_queue_client_id FOREIGN KEY (client_id)
+        REFERENCES clients (id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX notification_queue_next_attempt_at_idx
+    ON notification_queue (next_attempt_at);
+
+CREATE TABLE IF NOT EXISTS webhook_events
+(
+    id              TEXT    NOT NULL PRIMARY KEY,
+    tenant_id       TEXT    NOT NULL,
+    event_type      TEXT    NOT NULL,
+    payload         TEXT    NOT NULL,
+
+    status          TEXT    NOT NULL DEFAULT 'queued',
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER,
+    last_error      TEXT,
+
+    next_attempt_at TEXT    NOT NULL,
+    delivered_at    TEXT,
+    created_at      TEXT    NOT NULL
+);
+
+CREATE INDEX webhook_events_next_attempt_at_idx
+    ON webhook_events (next_attempt_at)
+    WHERE status = 'queued';
+
+CREATE INDEX webhook_events_tenant_id_idx
+    ON webhook_events (tenant_id, created_at);
@@ -0,0 +1,27 @@
+-- Append-only, rows outlive the clients they describe
+CREATE TABLE IF NOT EXISTS client_events
+(
+    id                    INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    event_type            TEXT    NOT NULL,
+    reason                TEXT    NOT NULL,
+
+    client_id             TEXT    NOT NULL,
+    tenant_id             TEXT    NOT NULL,
+    device_token          TEXT,
+
+    -- The values replaced by the event
+    previous_client_id    TEXT,
+    previous_tenant_id    TEXT,
+    previous_device_token TEXT,
+
+    created_at            TEXT    NOT NULL
+);
+
+CREATE INDEX client_events_client_id_idx
+    ON client_events (client_id);
+CREATE INDEX client_events_previous_client_id_idx
+    ON client_events (previous_client_id);
+CREATE INDEX client_events_device_token_idx
+    ON client_events (device_token);
+CREATE INDEX client_events_previous_device_token_idx
+    ON client_events (previous_device_token);
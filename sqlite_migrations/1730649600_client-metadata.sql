@@ -0,0 +1,8 @@
+ALTER TABLE clients ADD COLUMN platform TEXT;
+ALTER TABLE clients ADD COLUMN os_version TEXT;
+ALTER TABLE clients ADD COLUMN app_bundle_id TEXT;
+ALTER TABLE clients ADD COLUMN app_version TEXT;
+ALTER TABLE clients ADD COLUMN sdk_name TEXT;
+ALTER TABLE clients ADD COLUMN sdk_version TEXT;
+ALTER TABLE clients ADD COLUMN locale TEXT;
+ALTER TABLE clients ADD COLUMN timezone TEXT;
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS client_subscriptions
+(
+    client_id  TEXT NOT NULL,
+    topic      TEXT NOT NULL,
+
+    created_at TEXT NOT NULL,
+
+    PRIMARY KEY (topic, client_id),
+
+    CONSTRAINT fk_client_subscriptions_client_id FOREIGN KEY (client_id)
+        REFERENCES clients (id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX client_subscriptions_client_id_idx
+    ON client_subscriptions (client_id);
@@ -0,0 +1,23 @@
+-- Moved to the notification queue when they are due
+CREATE TABLE IF NOT EXISTS scheduled_notifications
+(
+    notification_id TEXT NOT NULL,
+    client_id       TEXT NOT NULL,
+    tenant_id       TEXT NOT NULL,
+
+    payload         TEXT NOT NULL,
+    send_at         TEXT NOT NULL,
+
+    created_at      TEXT NOT NULL,
+
+    PRIMARY KEY (notification_id, client_id),
+
+    CONSTRAINT fk_scheduled_notifications_client_id FOREIGN KEY (client_id)
+        REFERENCES clients (id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX scheduled_notifications_send_at_idx
+    ON scheduled_notifications (send_at);
+CREATE INDEX scheduled_notifications_client_id_idx
+    ON scheduled_notifications (client_id);
@@ -0,0 +1,2 @@
+DESCRIPTION=$1
+touch "./$(date +%s)_$DESCRIPTION.sql"
\ No newline at end of file
@@ -10,5 +10,13 @@ pub struct ClientInfo {
     pub client_id: Arc<str>,
     pub push_provider: Arc<str>,
     pub always_raw: bool,
+    pub platform: Option<Arc<str>>,
+    pub os_version: Option<Arc<str>>,
+    pub app_bundle_id: Option<Arc<str>>,
+    pub app_version: Option<Arc<str>>,
+    pub sdk_name: Option<Arc<str>>,
+    pub sdk_version: Option<Arc<str>>,
+    pub locale: Option<Arc<str>>,
+    pub timezone: Option<Arc<str>>,
     pub registered_at: chrono::NaiveDateTime,
 }
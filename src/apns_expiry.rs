@@ -0,0 +1,163 @@
+use {
+    crate::{
+        error::Result,
+        log::prelude::*,
+        providers::{validation::apns_certificate_expiry, PROVIDER_APNS},
+        state::AppState,
+        stores::{tenant::Tenant, webhook::WebhookEvent},
+        webhooks,
+    },
+    chrono::{DateTime, Utc},
+    serde::Serialize,
+    std::{collections::HashMap, sync::Arc, time::Duration},
+    tracing::instrument,
+};
+
+pub const APNS_CERTIFICATE_EXPIRING_EVENT: &str = "apns_certificate_expiring";
+
+#[derive(Serialize, Debug)]
+pub struct ApnsExpiryWebhook<'a> {
+    pub event: &'static str,
+    pub tenant_id: &'a str,
+    pub apns_topic: Option<&'a str>,
+    pub expires_at: DateTime<Utc>,
+    pub days_remaining: i64,
+}
+
+/// Whether the tenant's certificate expires within the warning period and it
+/// hasn't been warned about it yet
+pub fn needs_warning(
+    tenant: &Tenant,
+    warning_period: chrono::Duration,
+    now: DateTime<Utc>,
+) -> bool {
+    tenant.apns_expiry_notified_at.is_none()
+        && tenant
+            .apns_certificate_expires_at
+            .is_some_and(|expires_at| expires_at - warning_period <= now)
+}
+
+/// Reports the days until expiry of every tenant's APNs certificate and warns
+/// the ones expiring soon
+#[instrument(skip_all)]
+pub async fn check(state: &AppState) -> Result<()> {
+    let now = Utc::now();
+    let warning_period = chrono::Duration::days(state.config.apns_expiry_warning_days.into());
+
+    let mut days_remaining = HashMap::new();
+    for mut tenant in state.tenant_store.get_apns_certificate_tenants().await? {
+        // Certificates uploaded before the expiry was stored
+        if tenant.apns_certificate_expires_at.is_none() {
+            tenant.apns_certificate_expires_at = backfill_expiry(state, &tenant).await;
+        }
+        let Some(expires_at) = tenant.apns_certificate_expires_at else {
+            continue;
+        };
+        days_remaining.insert(tenant.id.clone(), (expires_at - now).num_days());
+
+        if needs_warning(&tenant, warning_period, now) {
+            if let Err(e) = warn_tenant(state, &tenant, expires_at, now).await {
+                error!(
+                    tenant_id = %tenant.id,
+                    "failed to warn about expiring apns certificate: {e:?}"
+                );
+            }
+        }
+    }
+
+    if let Some(metrics) = &state.metrics {
+        metrics.set_apns_certificate_expiry(days_remaining);
+    }
+
+    Ok(())
+}
+
+async fn backfill_expiry(state: &AppState, tenant: &Tenant) -> Option<DateTime<Utc>> {
+    let (Some(certificate), Some(password)) =
+        (&tenant.apns_certificate, &tenant.apns_certificate_password)
+    else {
+        return None;
+    };
+
+    match apns_certificate_expiry(certificate, password) {
+        Ok(expires_at) => {
+            if let Err(e) = state
+                .tenant_store
+                .update_tenant_apns_certificate_expiry(&tenant.id, Some(expires_at))
+                .await
+            {
+                warn!(tenant_id = %tenant.id, "failed to store apns certificate expiry: {e:?}");
+            }
+            Some(expires_at)
+        }
+        Err(e) => {
+            warn!(tenant_id = %tenant.id, "failed to parse apns certificate: {e:?}");
+            None
+        }
+    }
+}
+
+async fn warn_tenant(
+    state: &AppState,
+    tenant: &Tenant,
+    expires_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    // Claimed first so only one instance sends the warning
+    if !state
+        .tenant_store
+        .mark_tenant_apns_expiry_notified(&tenant.id)
+        .await?
+    {
+        return Ok(());
+    }
+
+    let days_remaining = (expires_at - now).num_days();
+    warn!(
+        tenant_id = %tenant.id,
+        %expires_at,
+        days_remaining,
+        "apns certificate is about to expire"
+    );
+    webhooks::emit(
+        state,
+        tenant,
+        WebhookEvent::CredentialsExpiring {
+            provider: PROVIDER_APNS.to_string(),
+            expires_at,
+        },
+    )
+    .await;
+
+    if let Some(url) = &state.config.apns_expiry_webhook_url {
+        state
+            .http_client
+            .post(url)
+            .json(&ApnsExpiryWebhook {
+                event: APNS_CERTIFICATE_EXPIRING_EVENT,
+                tenant_id: &tenant.id,
+                apns_topic: tenant.apns_topic.as_deref(),
+                expires_at,
+                days_remaining,
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+    }
+
+    Ok(())
+}
+
+/// Periodically runs `check`
+pub async fn run(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        state.config.apns_expiry_check_interval_secs.max(1),
+    ));
+
+    loop {
+        interval.tick().await;
+        if let Err(e) = check(&state).await {
+            warn!("error checking apns certificate expiry: {e:?}");
+        }
+    }
+}
@@ -7,12 +7,30 @@ use {
 pub type Flag = u32;
 pub const ENCRYPTED_FLAG: Flag = 1 << 0;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq)]
 pub struct DecryptedPayloadBlob {
     pub title: String,
     pub body: String,
+    /// URL of an image shown with the notification
     pub image: Option<String>,
+    /// Deep link opened when the notification is tapped
     pub url: Option<String>,
+    /// Number shown on the app icon
+    pub badge: Option<u32>,
+    /// Name of a sound bundled with the app, or `default`
+    pub sound: Option<String>,
+    /// Category registered by the app, selecting the notification's actions
+    pub category: Option<String>,
+    /// Notifications with the same thread id are grouped together
+    pub thread_id: Option<String>,
+    /// Keys of strings bundled with the app, shown instead of the title and
+    /// body with the arguments filled in
+    pub title_loc_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub title_loc_args: Vec<String>,
+    pub body_loc_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub body_loc_args: Vec<String>,
 }
 
 impl DecryptedPayloadBlob {
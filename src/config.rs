@@ -5,7 +5,8 @@ use {
             Error,
             Error::{InvalidConfiguration, NoApnsConfigured},
         },
-        stores::tenant::ApnsType,
+        middleware::rate_limit::RateLimitBackend,
+        stores::{redis_notification::NotificationDedupeBackend, tenant::ApnsType, StoreBackend},
     },
     serde::Deserialize,
 };
@@ -27,7 +28,10 @@ pub struct Config {
     pub relay_public_key: String,
     #[serde(default = "default_validate_signatures")]
     pub validate_signatures: bool,
-    pub database_url: String,
+    #[serde(default = "default_store_backend")]
+    pub store_backend: StoreBackend,
+    /// Required by the sqlite and postgres store backends
+    pub database_url: Option<String>,
     #[serde(default = "default_is_test", skip)]
     /// This is an internal flag to disable logging, cannot be defined by user
     pub is_test: bool,
@@ -40,6 +44,68 @@ pub struct Config {
     pub otel_exporter_otlp_endpoint: Option<String>,
     pub telemetry_prometheus_port: Option<u16>,
 
+    // DELIVERY
+    #[serde(default = "default_delivery_max_attempts")]
+    pub delivery_max_attempts: u32,
+    #[serde(default = "default_delivery_backoff_base_ms")]
+    pub delivery_backoff_base_ms: u64,
+    #[serde(default = "default_delivery_backoff_max_ms")]
+    pub delivery_backoff_max_ms: u64,
+    #[serde(default = "default_delivery_poll_interval_ms")]
+    pub delivery_poll_interval_ms: u64,
+    #[serde(default = "default_delivery_batch_size")]
+    pub delivery_batch_size: u32,
+
+    // RATE LIMITS
+    #[serde(default = "default_rate_limit_backend")]
+    pub rate_limit_backend: RateLimitBackend,
+    pub redis_url: Option<String>,
+    #[serde(default = "default_rate_limit_max_requests")]
+    pub rate_limit_default_max_requests: u32,
+    #[serde(default = "default_rate_limit_window_secs")]
+    pub rate_limit_default_window_secs: u64,
+    #[serde(default = "default_rate_limit_max_requests")]
+    pub rate_limit_tenants_max_requests: u32,
+    #[serde(default = "default_rate_limit_window_secs")]
+    pub rate_limit_tenants_window_secs: u64,
+    #[serde(default = "default_rate_limit_max_requests")]
+    pub rate_limit_clients_max_requests: u32,
+    #[serde(default = "default_rate_limit_window_secs")]
+    pub rate_limit_clients_window_secs: u64,
+
+    // WEBHOOKS
+    #[serde(default = "default_webhook_max_attempts")]
+    pub webhook_max_attempts: u32,
+    #[serde(default = "default_webhook_backoff_base_ms")]
+    pub webhook_backoff_base_ms: u64,
+    #[serde(default = "default_webhook_backoff_max_ms")]
+    pub webhook_backoff_max_ms: u64,
+    #[serde(default = "default_webhook_poll_interval_ms")]
+    pub webhook_poll_interval_ms: u64,
+
+    // NOTIFICATION RETENTION
+    /// Notifications are deleted this many days after they were first
+    /// received, `0` (the default) keeps them forever
+    #[serde(default = "default_notification_retention_days")]
+    pub notification_retention_days: u32,
+    #[serde(default = "default_notification_prune_interval_secs")]
+    pub notification_prune_interval_secs: u64,
+    #[serde(default = "default_notification_prune_batch_size")]
+    pub notification_prune_batch_size: u32,
+
+    // NOTIFICATION DEDUPLICATION
+    #[serde(default = "default_notification_dedupe_backend")]
+    pub notification_dedupe_backend: NotificationDedupeBackend,
+    /// How long Redis remembers received message ids
+    #[serde(default = "default_notification_dedupe_ttl_secs")]
+    pub notification_dedupe_ttl_secs: u64,
+
+    // PUSH RATE LIMITS
+    pub push_rate_limit_per_minute: Option<u32>,
+    pub push_rate_limit_burst: Option<u32>,
+    pub client_push_rate_limit_per_minute: Option<u32>,
+    pub client_push_rate_limit_burst: Option<u32>,
+
     // APNS
     #[cfg(not(feature = "multitenant"))]
     pub apns_type: Option<ApnsType>,
@@ -64,10 +130,56 @@ pub struct Config {
     #[cfg(not(feature = "multitenant"))]
     pub fcm_v1_credentials: Option<String>,
 
+    // HMS
+    #[cfg(not(feature = "multitenant"))]
+    pub hms_app_id: Option<String>,
+    #[cfg(not(feature = "multitenant"))]
+    pub hms_app_secret: Option<String>,
+
+    // Web Push
+    #[cfg(not(feature = "multitenant"))]
+    pub web_push_vapid_key: Option<String>,
+    #[cfg(not(feature = "multitenant"))]
+    pub web_push_vapid_subject: Option<String>,
+
     // Multi-tenancy
-    pub tenant_database_url: String,
+    /// Required by the postgres store backend in multi-tenant mode
+    pub tenant_database_url: Option<String>,
     #[cfg(feature = "multitenant")]
     pub jwt_secret: String,
+    /// Comma separated `<key_id>:<base64 key>` pairs, the first is current
+    #[cfg(feature = "multitenant")]
+    pub credentials_master_keys: Option<String>,
+    #[cfg(feature = "multitenant")]
+    pub credentials_master_keys_file: Option<String>,
+    /// Bearer token for the admin API, which is disabled when unset
+    #[cfg(feature = "multitenant")]
+    pub admin_api_token: Option<String>,
+    /// Dry-run uploaded credentials against the providers before saving them
+    #[cfg(feature = "multitenant")]
+    #[serde(default = "default_validate_credentials")]
+    pub validate_credentials: bool,
+    #[cfg(feature = "multitenant")]
+    #[serde(default = "default_validation_apns_production_url")]
+    pub validation_apns_production_url: String,
+    #[cfg(feature = "multitenant")]
+    #[serde(default = "default_validation_apns_sandbox_url")]
+    pub validation_apns_sandbox_url: String,
+    #[cfg(feature = "multitenant")]
+    #[serde(default = "default_validation_fcm_api_url")]
+    pub validation_fcm_api_url: String,
+    /// Overrides the `token_uri` of FCM v1 service accounts
+    #[cfg(feature = "multitenant")]
+    pub validation_fcm_token_url: Option<String>,
+    /// Called for tenants whose APNs certificate is about to expire
+    #[cfg(feature = "multitenant")]
+    pub apns_expiry_webhook_url: Option<String>,
+    #[cfg(feature = "multitenant")]
+    #[serde(default = "default_apns_expiry_warning_days")]
+    pub apns_expiry_warning_days: u32,
+    #[cfg(feature = "multitenant")]
+    #[serde(default = "default_apns_expiry_check_interval_secs")]
+    pub apns_expiry_check_interval_secs: u64,
 
     // Analytics
     #[cfg(any(feature = "analytics", feature = "geoblock"))]
@@ -90,7 +202,7 @@ impl Config {
     pub fn is_valid(&self) -> error::Result<()> {
         #[cfg(feature = "multitenant")]
         {
-            if self.tenant_database_url == self.database_url {
+            if self.tenant_database_url.is_some() && self.tenant_database_url == self.database_url {
                 return Err(InvalidConfiguration(
                     "`TENANT_DATABASE_URL` is equal to `DATABASE_URL`, this is not allowed"
                         .to_string(),
@@ -98,6 +210,39 @@ impl Config {
             }
         }
 
+        match self.store_backend {
+            StoreBackend::Memory => {}
+            StoreBackend::Sqlite => {
+                if !self
+                    .database_url
+                    .as_deref()
+                    .is_some_and(|url| url.starts_with("sqlite:"))
+                {
+                    return Err(InvalidConfiguration(
+                        "`STORE_BACKEND` of sqlite requires a `sqlite:` `DATABASE_URL`".to_string(),
+                    ));
+                }
+                if cfg!(feature = "multitenant") {
+                    return Err(InvalidConfiguration(
+                        "`STORE_BACKEND` of sqlite is only supported in single tenant mode"
+                            .to_string(),
+                    ));
+                }
+            }
+            StoreBackend::Postgres => {
+                if self.database_url.is_none() {
+                    return Err(InvalidConfiguration(
+                        "`STORE_BACKEND` of postgres requires `DATABASE_URL`".to_string(),
+                    ));
+                }
+                if cfg!(feature = "multitenant") && self.tenant_database_url.is_none() {
+                    return Err(InvalidConfiguration(
+                        "`STORE_BACKEND` of postgres requires `TENANT_DATABASE_URL`".to_string(),
+                    ));
+                }
+            }
+        }
+
         // Check that APNS config is valid when it has been configured
         match self.get_apns_type() {
             Ok(_) => Ok(()),
@@ -105,6 +250,32 @@ impl Config {
             Err(e) => Err(e),
         }?;
 
+        if self.delivery_max_attempts == 0 {
+            return Err(InvalidConfiguration(
+                "`DELIVERY_MAX_ATTEMPTS` must be at least 1".to_string(),
+            ));
+        }
+
+        if self.notification_prune_batch_size == 0 {
+            return Err(InvalidConfiguration(
+                "`NOTIFICATION_PRUNE_BATCH_SIZE` must be at least 1".to_string(),
+            ));
+        }
+
+        if self.rate_limit_backend == RateLimitBackend::Redis && self.redis_url.is_none() {
+            return Err(InvalidConfiguration(
+                "`RATE_LIMIT_BACKEND` of redis requires `REDIS_URL`".to_string(),
+            ));
+        }
+
+        if self.notification_dedupe_backend == NotificationDedupeBackend::Redis
+            && self.redis_url.is_none()
+        {
+            return Err(InvalidConfiguration(
+                "`NOTIFICATION_DEDUPE_BACKEND` of redis requires `REDIS_URL`".to_string(),
+            ));
+        }
+
         // Empty Relay public key is not allowed
         if self.relay_public_key.is_empty() {
             return Err(InvalidConfiguration(
@@ -128,6 +299,14 @@ impl Config {
             supported.push(ProviderKind::Fcm);
         }
 
+        if self.hms_app_id.is_some() && self.hms_app_secret.is_some() {
+            supported.push(ProviderKind::Hms);
+        }
+
+        if self.web_push_vapid_key.is_some() && self.web_push_vapid_subject.is_some() {
+            supported.push(ProviderKind::WebPush);
+        }
+
         // Only available in debug/testing
         #[cfg(any(debug_assertions, test))]
         supported.push(ProviderKind::Noop);
@@ -192,6 +371,10 @@ fn default_validate_signatures() -> bool {
     true
 }
 
+fn default_store_backend() -> StoreBackend {
+    StoreBackend::Postgres
+}
+
 fn default_is_test() -> bool {
     false
 }
@@ -200,6 +383,104 @@ fn default_cors_allowed_origins() -> Vec<String> {
     vec!["*".to_string()]
 }
 
+fn default_delivery_max_attempts() -> u32 {
+    10
+}
+
+fn default_delivery_backoff_base_ms() -> u64 {
+    1000
+}
+
+fn default_delivery_backoff_max_ms() -> u64 {
+    15 * 60 * 1000
+}
+
+fn default_delivery_poll_interval_ms() -> u64 {
+    1000
+}
+
+fn default_delivery_batch_size() -> u32 {
+    100
+}
+
+fn default_rate_limit_backend() -> RateLimitBackend {
+    RateLimitBackend::Memory
+}
+
+fn default_rate_limit_max_requests() -> u32 {
+    100
+}
+
+fn default_rate_limit_window_secs() -> u64 {
+    60
+}
+
+fn default_notification_retention_days() -> u32 {
+    0
+}
+
+fn default_notification_prune_interval_secs() -> u64 {
+    3_600
+}
+
+fn default_notification_prune_batch_size() -> u32 {
+    10_000
+}
+
+fn default_notification_dedupe_backend() -> NotificationDedupeBackend {
+    NotificationDedupeBackend::Database
+}
+
+fn default_notification_dedupe_ttl_secs() -> u64 {
+    86_400
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    8
+}
+
+fn default_webhook_backoff_base_ms() -> u64 {
+    5_000
+}
+
+fn default_webhook_backoff_max_ms() -> u64 {
+    3_600_000
+}
+
+fn default_webhook_poll_interval_ms() -> u64 {
+    1_000
+}
+
+#[cfg(feature = "multitenant")]
+fn default_validate_credentials() -> bool {
+    true
+}
+
+#[cfg(feature = "multitenant")]
+fn default_validation_apns_production_url() -> String {
+    crate::providers::validation::DEFAULT_APNS_PRODUCTION_URL.to_string()
+}
+
+#[cfg(feature = "multitenant")]
+fn default_validation_apns_sandbox_url() -> String {
+    crate::providers::validation::DEFAULT_APNS_SANDBOX_URL.to_string()
+}
+
+#[cfg(feature = "multitenant")]
+fn default_validation_fcm_api_url() -> String {
+    crate::providers::validation::DEFAULT_FCM_API_URL.to_string()
+}
+
+#[cfg(feature = "multitenant")]
+fn default_apns_expiry_warning_days() -> u32 {
+    30
+}
+
+#[cfg(feature = "multitenant")]
+fn default_apns_expiry_check_interval_secs() -> u64 {
+    3600
+}
+
 pub fn get_config() -> error::Result<Config> {
     let config = envy::from_env::<Config>()?;
     Ok(config)
@@ -0,0 +1,537 @@
+use {
+    crate::{
+        error::Error,
+        increment_counter,
+        log::prelude::*,
+        providers::{Provider, PushMessage, PushProvider},
+        state::AppState,
+        stores::{
+            client::{Client, ClientEventReason},
+            delivery::QueuedDelivery,
+            notification::DeliveryStatus,
+            tenant::{
+                Tenant, TenantSuspendParams, APNS_CERTIFICATE_EXPIRED_REASON,
+                APNS_CERTIFICATE_UNKNOWN_CA_REASON, APNS_INVALID_PROVIDER_TOKEN_REASON,
+                INVALID_APNS_CREDENTIALS_REASON, INVALID_FCM_CREDENTIALS_REASON,
+                INVALID_HMS_CREDENTIALS_REASON, INVALID_WEB_PUSH_CREDENTIALS_REASON,
+            },
+            webhook::WebhookEvent,
+            StoreError,
+        },
+        templates, webhooks,
+    },
+    chrono::{DateTime, Utc},
+    futures_util::StreamExt,
+    std::{sync::Arc, time::Duration},
+    tracing::instrument,
+};
+
+/// How long a delivery is reserved for the instance attempting it before other
+/// instances may pick it up again
+pub const DELIVERY_LEASE: Duration = Duration::from_secs(60);
+
+/// Number of queued deliveries sent concurrently by the worker
+const DELIVERY_CONCURRENCY: usize = 16;
+
+#[derive(Debug)]
+pub enum DeliveryResult {
+    Delivered {
+        /// Name of the provider that accepted the notification
+        provider: &'static str,
+        provider_message_id: Option<String>,
+    },
+    /// The provider failed with a transient error, another attempt is scheduled
+    Retrying {
+        next_attempt_at: DateTime<Utc>,
+        error: Error,
+    },
+    Failed(Error),
+}
+
+/// Calculates the exponential backoff for the given attempt with jitter. Half
+/// of the delay is kept and the other half is randomised so retries from a
+/// provider outage don't all land at the same time
+pub fn backoff_delay(attempts: u32, base: Duration, max: Duration) -> Duration {
+    let exponent = attempts.saturating_sub(1).min(31);
+    let delay = base.saturating_mul(1 << exponent).min(max);
+    let half = delay / 2;
+    half + half.mul_f64(rand::random::<f64>())
+}
+
+fn is_retryable(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::ProviderUnavailable(_)
+            | Error::Apns(_)
+            | Error::Fcm(_)
+            | Error::FcmV1(_)
+            | Error::HttpRequest(_)
+            | Error::Io(_)
+            | Error::Database(_)
+            | Error::Redis(_)
+            | Error::Store(StoreError::Database(_) | StoreError::Redis(_))
+    )
+}
+
+/// Attempts to send a queued notification and settles the queue entry and the
+/// notification's delivery state with the outcome
+#[instrument(skip_all, fields(tenant_id = %delivery.tenant_id, client_id = %delivery.client_id, notification_id = %delivery.notification_id, attempt = delivery.attempts))]
+pub async fn deliver(
+    state: &AppState,
+    delivery: &QueuedDelivery,
+    tenant: &Tenant,
+    client: &Client,
+    message: PushMessage,
+) -> DeliveryResult {
+    let result = match tenant
+        .provider(
+            &client.push_type,
+            state.http_client.clone(),
+            &state.provider_cache,
+            &state.pinned_clients,
+        )
+        .await
+    {
+        Ok(provider) => send(state, delivery, tenant, client, &provider, message)
+            .await
+            .map(|provider_message_id| (provider.name(), provider_message_id)),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok((provider, provider_message_id)) => {
+            debug!(
+                push_type = client.push_type.as_str(),
+                provider_message_id = provider_message_id.as_deref(),
+                "delivered notification"
+            );
+            if let Err(e) = state
+                .notification_store
+                .update_notification_provider(
+                    &delivery.notification_id,
+                    &delivery.client_id,
+                    &delivery.tenant_id,
+                    provider,
+                    provider_message_id.as_deref(),
+                )
+                .await
+            {
+                warn!("error recording notification provider: {e:?}");
+            }
+            settle(state, delivery, DeliveryStatus::Delivered, None).await;
+            DeliveryResult::Delivered {
+                provider,
+                provider_message_id,
+            }
+        }
+        Err(error)
+            if is_retryable(&error)
+                && delivery.attempts < state.config.delivery_max_attempts as i32 =>
+        {
+            let backoff = backoff_delay(
+                delivery.attempts as u32,
+                Duration::from_millis(state.config.delivery_backoff_base_ms),
+                Duration::from_millis(state.config.delivery_backoff_max_ms),
+            );
+            // Honour the provider's `Retry-After` when it asks for a longer wait
+            let delay = match &error {
+                Error::ProviderUnavailable(Some(retry_after)) => backoff.max(*retry_after),
+                _ => backoff,
+            };
+            let next_attempt_at = Utc::now()
+                + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+
+            if let Err(e) = state
+                .delivery_store
+                .reschedule_delivery(
+                    &delivery.notification_id,
+                    &delivery.client_id,
+                    next_attempt_at,
+                )
+                .await
+            {
+                // The lease will expire and the delivery will be retried anyway
+                warn!("error rescheduling delivery: {e:?}");
+            }
+            if let Err(e) = state
+                .notification_store
+                .update_notification_status(
+                    &delivery.notification_id,
+                    &delivery.client_id,
+                    &delivery.tenant_id,
+                    DeliveryStatus::Queued,
+                    delivery.attempts,
+                    Some(&error.to_string()),
+                )
+                .await
+            {
+                warn!("error updating notification status: {e:?}");
+            }
+            increment_counter!(state.metrics, delivery_retries);
+
+            DeliveryResult::Retrying {
+                next_attempt_at,
+                error,
+            }
+        }
+        Err(error) => {
+            warn!(
+                push_type = client.push_type.as_str(),
+                "notification delivery failed: {error:?}"
+            );
+            settle(
+                state,
+                delivery,
+                DeliveryStatus::Failed,
+                Some(&error.to_string()),
+            )
+            .await;
+            increment_counter!(state.metrics, failed_deliveries);
+            // Deleted clients and suspended tenants have their own events
+            if !matches!(error, Error::ClientDeleted | Error::TenantSuspended) {
+                webhooks::emit(
+                    state,
+                    tenant,
+                    WebhookEvent::DeliveryFailed {
+                        client_id: delivery.client_id.clone(),
+                        notification_id: delivery.notification_id.clone(),
+                        error: error.to_string(),
+                    },
+                )
+                .await;
+            }
+            DeliveryResult::Failed(error)
+        }
+    }
+}
+
+/// Sends the notification and applies the side effects of provider errors,
+/// deleting clients with bad tokens and suspending tenants with bad credentials
+async fn send(
+    state: &AppState,
+    delivery: &QueuedDelivery,
+    tenant: &Tenant,
+    client: &Client,
+    provider: &Provider,
+    message: PushMessage,
+) -> Result<Option<String>, Error> {
+    let tenant_id = delivery.tenant_id.as_str();
+    let client_id = delivery.client_id.as_str();
+
+    let error = match provider
+        .send_notification(
+            client.token.clone(),
+            message,
+            delivery.payload.options.clone().unwrap_or_default(),
+        )
+        .await
+    {
+        Ok(provider_message_id) => {
+            // Provider specific metrics
+            match provider {
+                Provider::Fcm(_) => increment_counter!(state.metrics, sent_fcm_notifications),
+                Provider::FcmV1(_) => increment_counter!(state.metrics, sent_fcm_v1_notifications),
+                Provider::Apns(_) => increment_counter!(state.metrics, sent_apns_notifications),
+                Provider::Hms(_) => increment_counter!(state.metrics, sent_hms_notifications),
+                Provider::WebPush(_) => {
+                    increment_counter!(state.metrics, sent_web_push_notifications)
+                }
+                #[cfg(any(debug_assertions, test))]
+                Provider::Noop(_) => {}
+            }
+            return Ok(provider_message_id);
+        }
+        Err(Error::BadDeviceToken(_)) => {
+            state
+                .client_store
+                .delete_client(tenant_id, client_id, ClientEventReason::BadDeviceToken)
+                .await?;
+            increment_counter!(state.metrics, client_suspensions);
+            warn!(
+                %tenant_id,
+                %client_id,
+                push_type = client.push_type.as_str(),
+                "client has been deleted due to a bad device token"
+            );
+            webhooks::emit(
+                state,
+                tenant,
+                WebhookEvent::ClientDeleted {
+                    client_id: client_id.to_string(),
+                    reason: "Bad device token".to_string(),
+                },
+            )
+            .await;
+            return Err(Error::ClientDeleted);
+        }
+        Err(e) => e,
+    };
+
+    let reason = match &error {
+        Error::BadApnsCredentials => INVALID_APNS_CREDENTIALS_REASON,
+        Error::ApnsCertificateExpired => APNS_CERTIFICATE_EXPIRED_REASON,
+        Error::ApnsCertificateUnknownCA => APNS_CERTIFICATE_UNKNOWN_CA_REASON,
+        Error::ApnsInvalidProviderToken => APNS_INVALID_PROVIDER_TOKEN_REASON,
+        Error::BadFcmApiKey => INVALID_FCM_CREDENTIALS_REASON,
+        Error::BadHmsCredentials => INVALID_HMS_CREDENTIALS_REASON,
+        Error::BadWebPushCredentials => INVALID_WEB_PUSH_CREDENTIALS_REASON,
+        _ => return Err(error),
+    };
+
+    // The credentials of single tenant mode come from the config and can't be
+    // replaced, so there is no tenant to suspend
+    if cfg!(not(feature = "multitenant")) {
+        warn!(
+            %tenant_id,
+            %client_id,
+            push_type = client.push_type.as_str(),
+            "provider rejected the configured credentials: {reason}"
+        );
+        return Err(error);
+    }
+
+    let suspended = state
+        .tenant_store
+        .suspend_tenant(
+            tenant_id,
+            TenantSuspendParams {
+                reason: reason.to_string(),
+                client_id: Some(client_id.to_string()),
+                notification_id: Some(delivery.notification_id.clone()),
+            },
+        )
+        .await?;
+    if suspended {
+        increment_counter!(state.metrics, tenant_suspensions);
+        warn!(
+            %tenant_id,
+            %client_id,
+            push_type = client.push_type.as_str(),
+            "tenant has been suspended due to: {reason}"
+        );
+        webhooks::emit(
+            state,
+            tenant,
+            WebhookEvent::TenantSuspended {
+                reason: reason.to_string(),
+                client_id: Some(client_id.to_string()),
+                notification_id: Some(delivery.notification_id.clone()),
+            },
+        )
+        .await;
+    }
+    Err(Error::TenantSuspended)
+}
+
+/// Removes the delivery from the queue and records its final state
+async fn settle(
+    state: &AppState,
+    delivery: &QueuedDelivery,
+    status: DeliveryStatus,
+    last_error: Option<&str>,
+) {
+    if let Err(e) = state
+        .delivery_store
+        .remove_delivery(&delivery.notification_id, &delivery.client_id)
+        .await
+    {
+        warn!("error removing delivery from the queue: {e:?}");
+    }
+    if let Err(e) = state
+        .notification_store
+        .update_notification_status(
+            &delivery.notification_id,
+            &delivery.client_id,
+            &delivery.tenant_id,
+            status,
+            delivery.attempts,
+            last_error,
+        )
+        .await
+    {
+        warn!("error updating notification status: {e:?}");
+    }
+}
+
+/// Handles an error preparing a claimed delivery. Terminal errors and
+/// deliveries out of attempts are settled as failed, anything else is
+/// rescheduled with backoff
+async fn retry_later(
+    state: &AppState,
+    delivery: &QueuedDelivery,
+    context: &str,
+    error: Error,
+    terminal: bool,
+) {
+    if terminal || delivery.attempts >= state.config.delivery_max_attempts as i32 {
+        warn!(
+            tenant_id = %delivery.tenant_id,
+            client_id = %delivery.client_id,
+            "dropping queued delivery, {context}: {error:?}"
+        );
+        settle(
+            state,
+            delivery,
+            DeliveryStatus::Failed,
+            Some(&error.to_string()),
+        )
+        .await;
+        return;
+    }
+
+    let delay = backoff_delay(
+        delivery.attempts as u32,
+        Duration::from_millis(state.config.delivery_backoff_base_ms),
+        Duration::from_millis(state.config.delivery_backoff_max_ms),
+    );
+    let next_attempt_at =
+        Utc::now() + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+    warn!(
+        tenant_id = %delivery.tenant_id,
+        client_id = %delivery.client_id,
+        %next_attempt_at,
+        "retrying queued delivery later, {context}: {error:?}"
+    );
+    if let Err(e) = state
+        .delivery_store
+        .reschedule_delivery(
+            &delivery.notification_id,
+            &delivery.client_id,
+            next_attempt_at,
+        )
+        .await
+    {
+        // The lease will expire and the delivery will be retried anyway
+        warn!("error rescheduling delivery: {e:?}");
+    }
+}
+
+/// Sends a delivery claimed from the queue or the schedule by a worker
+async fn redeliver(state: &AppState, delivery: QueuedDelivery) {
+    let client = match state
+        .client_store
+        .get_client(&delivery.tenant_id, &delivery.client_id)
+        .await
+    {
+        Ok(client) => client,
+        Err(e) => {
+            let terminal = matches!(e, StoreError::NotFound(..));
+            let error = Error::Store(e);
+            retry_later(state, &delivery, "failed to fetch client", error, terminal).await;
+            return;
+        }
+    };
+
+    let tenant = match state.tenant_store.get_tenant(&delivery.tenant_id).await {
+        Ok(tenant) if tenant.suspended => {
+            settle(
+                state,
+                &delivery,
+                DeliveryStatus::Failed,
+                Some(&Error::TenantSuspended.to_string()),
+            )
+            .await;
+            return;
+        }
+        Ok(tenant) => tenant,
+        Err(e) => {
+            let terminal = matches!(e, Error::InvalidTenantId(_));
+            retry_later(state, &delivery, "failed to fetch tenant", e, terminal).await;
+            return;
+        }
+    };
+
+    let message = match templates::push_message(
+        &state.tenant_store,
+        &delivery.tenant_id,
+        &client,
+        &delivery.payload,
+    )
+    .await
+    {
+        Ok(message) => message,
+        Err(e) => {
+            // Missing templates and rendering errors fail the same way every time
+            let terminal = !is_retryable(&e);
+            retry_later(state, &delivery, "failed to render message", e, terminal).await;
+            return;
+        }
+    };
+
+    match deliver(state, &delivery, &tenant, &client, message).await {
+        DeliveryResult::Delivered { .. } => {}
+        DeliveryResult::Retrying {
+            next_attempt_at,
+            error,
+        } => debug!(
+            notification_id = %delivery.notification_id,
+            %next_attempt_at,
+            "notification delivery retry failed: {error:?}"
+        ),
+        DeliveryResult::Failed(error) => debug!(
+            notification_id = %delivery.notification_id,
+            "notification delivery given up: {error:?}"
+        ),
+    }
+}
+
+/// Polls the queue for deliveries that are due for a retry
+pub async fn run(state: Arc<AppState>) {
+    let poll_interval = Duration::from_millis(state.config.delivery_poll_interval_ms);
+    let batch_size = state.config.delivery_batch_size;
+
+    loop {
+        match state
+            .delivery_store
+            .claim_due_deliveries(batch_size as i64, DELIVERY_LEASE)
+            .await
+        {
+            Ok(deliveries) => {
+                let claimed = deliveries.len();
+                futures_util::stream::iter(deliveries)
+                    .for_each_concurrent(DELIVERY_CONCURRENCY, |delivery| {
+                        redeliver(&state, delivery)
+                    })
+                    .await;
+
+                // Keep draining the queue without waiting while there is a backlog
+                if claimed == batch_size as usize {
+                    continue;
+                }
+            }
+            Err(e) => warn!("error claiming queued deliveries: {e:?}"),
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Moves scheduled notifications to the queue when they are due and sends them
+pub async fn run_scheduler(state: Arc<AppState>) {
+    let poll_interval = Duration::from_millis(state.config.delivery_poll_interval_ms);
+    let batch_size = state.config.delivery_batch_size;
+
+    loop {
+        match state
+            .delivery_store
+            .claim_due_scheduled_deliveries(batch_size as i64, DELIVERY_LEASE)
+            .await
+        {
+            Ok(deliveries) => {
+                let claimed = deliveries.len();
+                futures_util::stream::iter(deliveries)
+                    .for_each_concurrent(DELIVERY_CONCURRENCY, |delivery| {
+                        redeliver(&state, delivery)
+                    })
+                    .await;
+
+                if claimed == batch_size as usize {
+                    continue;
+                }
+            }
+            Err(e) => warn!("error claiming scheduled deliveries: {e:?}"),
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
@@ -0,0 +1,255 @@
+#[cfg(feature = "multitenant")]
+use crate::config::Config;
+use {
+    crate::error::{Error, Result},
+    async_trait::async_trait,
+    base64::Engine as _,
+    openssl::{
+        rand::rand_bytes,
+        symm::{decrypt_aead, encrypt_aead, Cipher},
+    },
+    serde::Deserialize,
+    std::{collections::HashMap, fmt::Debug, sync::Arc},
+};
+
+/// Prefix of encrypted values, anything else is treated as legacy plaintext
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+
+pub type KeyProviderArc = Arc<dyn KeyProvider + Send + Sync + 'static>;
+
+/// Source of the master keys that wrap the per-value data keys, so a KMS can
+/// be swapped in without the master keys ever leaving it
+#[async_trait]
+pub trait KeyProvider: Debug {
+    /// Key used to wrap data keys for new values
+    fn current_key_id(&self) -> &str;
+    async fn wrap_key(&self, key_id: &str, data_key: &[u8]) -> Result<Vec<u8>>;
+    async fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> Result<Vec<u8>>;
+}
+
+#[derive(Deserialize)]
+struct KeyFile {
+    current: String,
+    keys: HashMap<String, String>,
+}
+
+/// Master keys held in memory, loaded from config or a local JSON file
+pub struct LocalKeyProvider {
+    current: String,
+    keys: HashMap<String, [u8; KEY_LENGTH]>,
+}
+
+impl LocalKeyProvider {
+    /// Parses comma separated `<key_id>:<base64 key>` pairs, the first key is
+    /// used for new values and the rest are kept to decrypt older ones
+    pub fn from_keys(value: &str) -> Result<Self> {
+        let mut current = None;
+        let mut keys = HashMap::new();
+        for pair in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key_id, key) = pair.split_once(':').ok_or_else(|| {
+                Error::InvalidConfiguration(
+                    "`CREDENTIALS_MASTER_KEYS` entries must be `<key_id>:<base64 key>`".to_string(),
+                )
+            })?;
+            current.get_or_insert_with(|| key_id.to_string());
+            keys.insert(key_id.to_string(), decode_master_key(key_id, key)?);
+        }
+
+        Ok(LocalKeyProvider {
+            current: current.ok_or_else(|| {
+                Error::InvalidConfiguration("`CREDENTIALS_MASTER_KEYS` is empty".to_string())
+            })?,
+            keys,
+        })
+    }
+
+    /// Reads `{"current": "<key_id>", "keys": {"<key_id>": "<base64 key>"}}`
+    pub fn from_file(path: &str) -> Result<Self> {
+        let file: KeyFile = serde_json::from_slice(&std::fs::read(path)?).map_err(|e| {
+            Error::InvalidConfiguration(format!("Invalid credentials master key file: {e}"))
+        })?;
+        if !file.keys.contains_key(&file.current) {
+            return Err(Error::InvalidConfiguration(format!(
+                "Credentials master key file has no `{}` key",
+                file.current
+            )));
+        }
+
+        let keys = file
+            .keys
+            .iter()
+            .map(|(key_id, key)| Ok((key_id.clone(), decode_master_key(key_id, key)?)))
+            .collect::<Result<_>>()?;
+
+        Ok(LocalKeyProvider {
+            current: file.current,
+            keys,
+        })
+    }
+
+    fn key(&self, key_id: &str) -> Result<&[u8; KEY_LENGTH]> {
+        self.keys
+            .get(key_id)
+            .ok_or_else(|| Error::CredentialEncryption(format!("Unknown master key `{key_id}`")))
+    }
+}
+
+fn decode_master_key(key_id: &str, key: &str) -> Result<[u8; KEY_LENGTH]> {
+    base64::engine::general_purpose::STANDARD
+        .decode(key)
+        .ok()
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| {
+            Error::InvalidConfiguration(format!(
+                "Credentials master key `{key_id}` must be {KEY_LENGTH} base64 encoded bytes"
+            ))
+        })
+}
+
+#[async_trait]
+impl KeyProvider for LocalKeyProvider {
+    fn current_key_id(&self) -> &str {
+        &self.current
+    }
+
+    async fn wrap_key(&self, key_id: &str, data_key: &[u8]) -> Result<Vec<u8>> {
+        seal(self.key(key_id)?, data_key, key_id.as_bytes())
+    }
+
+    async fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> Result<Vec<u8>> {
+        open(self.key(key_id)?, wrapped_key, key_id.as_bytes())
+    }
+}
+
+// Manual Impl Because the keys should not be logged
+impl Debug for LocalKeyProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[LocalKeyProvider] current = {}", self.current)
+    }
+}
+
+/// Builds the configured key provider, `None` if credentials are not encrypted
+#[cfg(feature = "multitenant")]
+pub fn key_provider(config: &Config) -> Result<Option<KeyProviderArc>> {
+    Ok(
+        match (
+            &config.credentials_master_keys,
+            &config.credentials_master_keys_file,
+        ) {
+            (Some(keys), _) => Some(Arc::new(LocalKeyProvider::from_keys(keys)?)),
+            (None, Some(path)) => Some(Arc::new(LocalKeyProvider::from_file(path)?)),
+            (None, None) => None,
+        },
+    )
+}
+
+/// Envelope encryption of credentials, every value is encrypted with its own
+/// data key which is wrapped by a master key
+#[derive(Debug, Clone)]
+pub struct CredentialCipher {
+    key_provider: KeyProviderArc,
+}
+
+impl CredentialCipher {
+    pub fn new(key_provider: KeyProviderArc) -> Self {
+        CredentialCipher { key_provider }
+    }
+
+    pub fn current_key_id(&self) -> &str {
+        self.key_provider.current_key_id()
+    }
+
+    /// Encrypts `value`, the context (e.g. tenant and column) is authenticated
+    /// so ciphertexts can't be moved between rows
+    pub async fn encrypt(&self, value: &str, context: &str) -> Result<String> {
+        let key_id = self.key_provider.current_key_id();
+        let mut data_key = [0u8; KEY_LENGTH];
+        rand_bytes(&mut data_key).map_err(|e| Error::CredentialEncryption(e.to_string()))?;
+
+        let wrapped_key = self.key_provider.wrap_key(key_id, &data_key).await?;
+        let ciphertext = seal(&data_key, value.as_bytes(), context.as_bytes())?;
+
+        let engine = base64::engine::general_purpose::STANDARD_NO_PAD;
+        Ok(format!(
+            "{ENCRYPTED_PREFIX}{key_id}:{}:{}",
+            engine.encode(wrapped_key),
+            engine.encode(ciphertext)
+        ))
+    }
+
+    /// Decrypts a value produced by `encrypt`, plaintext values are returned
+    /// unchanged so rows can be read before they are backfilled
+    pub async fn decrypt(&self, value: &str, context: &str) -> Result<String> {
+        let Some(encrypted) = value.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(value.to_string());
+        };
+
+        let invalid = || Error::CredentialEncryption("Malformed encrypted value".to_string());
+        let mut parts = encrypted.splitn(3, ':');
+        let (Some(key_id), Some(wrapped_key), Some(ciphertext)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let engine = base64::engine::general_purpose::STANDARD_NO_PAD;
+        let wrapped_key = engine.decode(wrapped_key).map_err(|_| invalid())?;
+        let ciphertext = engine.decode(ciphertext).map_err(|_| invalid())?;
+
+        let data_key = self.key_provider.unwrap_key(key_id, &wrapped_key).await?;
+        let plaintext = open(&data_key, &ciphertext, context.as_bytes())?;
+
+        String::from_utf8(plaintext).map_err(|_| invalid())
+    }
+
+    /// Whether `value` still needs to be (re-)encrypted with the current key
+    pub fn needs_encryption(&self, value: &str) -> bool {
+        value
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .and_then(|encrypted| encrypted.split_once(':'))
+            .map(|(key_id, _)| key_id != self.current_key_id())
+            .unwrap_or(true)
+    }
+}
+
+/// AES-256-GCM, the output is `nonce || ciphertext || tag`
+fn seal(key: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LENGTH];
+    rand_bytes(&mut nonce).map_err(|e| Error::CredentialEncryption(e.to_string()))?;
+
+    let mut tag = [0u8; TAG_LENGTH];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        key,
+        Some(&nonce),
+        aad,
+        plaintext,
+        &mut tag,
+    )
+    .map_err(|e| Error::CredentialEncryption(e.to_string()))?;
+
+    Ok([&nonce[..], &ciphertext, &tag].concat())
+}
+
+fn open(key: &[u8], sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LENGTH + TAG_LENGTH {
+        return Err(Error::CredentialEncryption(
+            "Encrypted value is too short".to_string(),
+        ));
+    }
+    let (nonce, rest) = sealed.split_at(NONCE_LENGTH);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LENGTH);
+
+    decrypt_aead(
+        Cipher::aes_256_gcm(),
+        key,
+        Some(nonce),
+        aad,
+        ciphertext,
+        tag,
+    )
+    .map_err(|_| Error::CredentialEncryption("Failed to decrypt value".to_string()))
+}
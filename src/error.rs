@@ -37,12 +37,33 @@ pub enum Error {
     #[error("FCM v1 Responded with an error")]
     FcmV1Response(fcm_v1::ErrorReason),
 
+    #[error("HMS Responded with an error, {0}: {1}")]
+    HmsResponse(String, String),
+
+    #[error(transparent)]
+    WebPushEncryption(#[from] ece::Error),
+
+    #[error("Web Push service responded with {0}")]
+    WebPushResponse(reqwest::StatusCode),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
     #[error(transparent)]
     Database(#[from] sqlx::Error),
 
+    #[error(transparent)]
+    Redis(#[from] redis::RedisError),
+
+    #[error("credential encryption failed: {0}")]
+    CredentialEncryption(String),
+
+    #[error("credential validation failed: {0}")]
+    CredentialValidation(String),
+
+    #[error(transparent)]
+    OpenSsl(#[from] openssl::error::ErrorStack),
+
     #[error(transparent)]
     Hex(hex::FromHexError),
 
@@ -88,6 +109,9 @@ pub enum Error {
     #[error("the `{0}` field must not be empty")]
     EmptyField(String),
 
+    #[error("the `{0}` field must be at most {1} characters")]
+    FieldTooLong(String, usize),
+
     #[error("a required environment variable cannot be found")]
     RequiredEnvNotFound,
 
@@ -118,6 +142,9 @@ pub enum Error {
     #[error("invalid tenant id: {0}")]
     InvalidTenantId(String),
 
+    #[error("invalid pagination cursor")]
+    InvalidCursor,
+
     #[error("invalid options provided for {0}")]
     InvalidOptionsProvided(String),
 
@@ -181,6 +208,30 @@ pub enum Error {
     #[error("Invalid APNs creds")]
     BadApnsCredentials,
 
+    #[error("Invalid HMS app credentials")]
+    BadHmsCredentials,
+
+    #[error("Invalid Web Push VAPID key")]
+    BadWebPushCredentials,
+
+    #[error("Web Push VAPID subject must be a mailto: or https: URL")]
+    InvalidWebPushSubject,
+
+    #[error("Webhook URL must be an https: URL of a public host")]
+    InvalidWebhookUrl,
+
+    #[error("tenant has no webhook configured")]
+    WebhookNotConfigured,
+
+    #[error("template {0} cannot be found")]
+    TemplateNotFound(String),
+
+    #[error("invalid template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("invalid schedule: {0}")]
+    InvalidSchedule(String),
+
     #[error("Expired APNs certificate")]
     ApnsCertificateExpired,
 
@@ -198,11 +249,20 @@ pub enum Error {
 
     #[error("Payload is too large")]
     PayloadTooLarge,
+
+    #[error("Push provider is temporarily unavailable")]
+    ProviderUnavailable(Option<std::time::Duration>),
+
+    #[error("batch of {0} messages exceeds the maximum batch size")]
+    BatchTooLarge(usize),
+
+    #[error("push rate limit exceeded, retry after {0:?}")]
+    PushRateLimited(std::time::Duration),
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        let response = match &self {
+        let mut response = match &self {
             Error::BadDeviceToken(e) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
                 ResponseError {
                     name: "invalid_token".to_string(),
@@ -281,12 +341,108 @@ impl IntoResponse for Error {
                     location: ErrorLocation::Body,
                 }
             ]),
+            Error::BadHmsCredentials => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "bad_hms_credentials".to_string(),
+                    message: "The provided app id and secret were not valid".to_string(),
+                }
+            ], vec![
+                ErrorField {
+                    field: "app_secret".to_string(),
+                    description: "HMS app secret".to_string(),
+                    location: ErrorLocation::Body,
+                }
+            ]),
+            Error::BadWebPushCredentials => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "bad_web_push_credentials".to_string(),
+                    message: "The provided VAPID key was not a valid P-256 private key".to_string(),
+                }
+            ], vec![
+                ErrorField {
+                    field: "vapid_key".to_string(),
+                    description: "Base64 encoded PEM of the VAPID private key".to_string(),
+                    location: ErrorLocation::Body,
+                }
+            ]),
+            Error::InvalidWebPushSubject => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_web_push_subject".to_string(),
+                    message: "The VAPID subject must be a mailto: or https: URL".to_string(),
+                }
+            ], vec![
+                ErrorField {
+                    field: "subject".to_string(),
+                    description: "Contact URL sent to push services".to_string(),
+                    location: ErrorLocation::Body,
+                }
+            ]),
+            Error::InvalidWebhookUrl => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_webhook_url".to_string(),
+                    message: "The webhook URL must be an https: URL of a public host".to_string(),
+                }
+            ], vec![
+                ErrorField {
+                    field: "url".to_string(),
+                    description: "URL events are sent to".to_string(),
+                    location: ErrorLocation::Body,
+                }
+            ]),
+            Error::WebhookNotConfigured => crate::handlers::Response::new_failure(StatusCode::NOT_FOUND, vec![
+                ResponseError {
+                    name: "webhook_not_configured".to_string(),
+                    message: "The tenant has no webhook configured, register one with POST /tenants/:id/webhook".to_string(),
+                }
+            ], vec![]),
+            Error::TemplateNotFound(name) => crate::handlers::Response::new_failure(StatusCode::NOT_FOUND, vec![
+                ResponseError {
+                    name: "template_not_found".to_string(),
+                    message: format!("The tenant has no template named {name}"),
+                }
+            ], vec![
+                ErrorField {
+                    field: "template_id".to_string(),
+                    description: "Name of the tenant's template".to_string(),
+                    location: ErrorLocation::Body,
+                }
+            ]),
+            Error::InvalidTemplate(message) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_template".to_string(),
+                    message: message.to_owned(),
+                }
+            ], vec![]),
+            Error::InvalidSchedule(message) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_schedule".to_string(),
+                    message: message.to_owned(),
+                }
+            ], vec![]),
             Error::Database(e) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
                 ResponseError {
                     name: "sqlx".to_string(),
                     message: e.to_string(),
                 }
             ], vec![]),
+            Error::Redis(e) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
+                ResponseError {
+                    name: "redis".to_string(),
+                    message: e.to_string(),
+                }
+            ], vec![]),
+            Error::CredentialEncryption(_) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
+                ResponseError {
+                    name: "credential_encryption".to_string(),
+                    message: "Failed to encrypt or decrypt the tenant's credentials".to_string(),
+                }
+            ], vec![]),
+            Error::CredentialValidation(reason) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "credential_validation".to_string(),
+                    message: format!("The provider rejected the credentials: {reason}"),
+                }
+            ], vec![]),
             Error::Hex(e) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
                 ResponseError {
                     name: "from_hex".to_string(),
@@ -320,6 +476,14 @@ impl IntoResponse for Error {
                     }],
                     vec![],
                 ),
+                StoreError::Redis(e) => crate::handlers::Response::new_failure(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    vec![ResponseError {
+                        name: "redis".to_string(),
+                        message: e.to_string(),
+                    }],
+                    vec![],
+                ),
                 StoreError::NotFound(entity, id) => crate::handlers::Response::new_failure(
                     StatusCode::NOT_FOUND,
                     vec![],
@@ -498,6 +662,18 @@ impl IntoResponse for Error {
                     location: ErrorLocation::Body,
                 }
             ]),
+            Error::FieldTooLong(f, max) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "field".to_string(),
+                    message: "field is too long".to_string(),
+                },
+            ], vec![
+                ErrorField {
+                    field: f.to_owned(),
+                    description: format!("must be at most {max} characters"),
+                    location: ErrorLocation::Body,
+                }
+            ]),
             Error::FromRequestError => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
                 ResponseError {
                     name: "unknown".to_string(),
@@ -601,6 +777,47 @@ impl IntoResponse for Error {
                 }],
                 vec![],
             ),
+            Error::ProviderUnavailable(_) => crate::handlers::Response::new_failure(
+                StatusCode::SERVICE_UNAVAILABLE,
+                vec![ResponseError {
+                    name: "provider_unavailable".to_string(),
+                    message: "The push provider is temporarily unavailable".to_string(),
+                }],
+                vec![],
+            ),
+            Error::PushRateLimited(_) => crate::handlers::Response::new_failure(
+                StatusCode::TOO_MANY_REQUESTS,
+                vec![ResponseError {
+                    name: "rate_limited".to_string(),
+                    message: "Push rate limit exceeded, retry after the `Retry-After` period"
+                        .to_string(),
+                }],
+                vec![],
+            ),
+            Error::InvalidCursor => crate::handlers::Response::new_failure(
+                StatusCode::BAD_REQUEST,
+                vec![ResponseError {
+                    name: "cursor".to_string(),
+                    message: "The pagination cursor is invalid".to_string(),
+                }],
+                vec![ErrorField {
+                    field: "cursor".to_string(),
+                    description: "Cursor returned as `next_cursor` by the previous page"
+                        .to_string(),
+                    location: ErrorLocation::Query,
+                }],
+            ),
+            Error::BatchTooLarge(size) => crate::handlers::Response::new_failure(
+                StatusCode::BAD_REQUEST,
+                vec![ResponseError {
+                    name: "batch_too_large".to_string(),
+                    message: format!(
+                        "Batch contains {size} messages but at most {} are allowed",
+                        crate::handlers::push_batch::MAX_BATCH_SIZE
+                    ),
+                }],
+                vec![],
+            ),
             e => {
                 warn!("Error does not have response clause, {:?}", e);
 
@@ -617,6 +834,14 @@ impl IntoResponse for Error {
             }
         }.into_response();
 
+        if let Error::PushRateLimited(retry_after) = &self {
+            // Rounded up so clients never retry before a token is available
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(axum::http::header::RETRY_AFTER, seconds.into());
+        }
+
         if response.status().is_client_error() {
             warn!("HTTP client error: {self:?}");
         }
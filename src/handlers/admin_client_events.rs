@@ -0,0 +1,76 @@
+use {
+    crate::{
+        error::{Error, Result},
+        state::AppState,
+        stores::client::{ClientEvent, ClientEventListParams},
+    },
+    axum::{
+        extract::{Query, State},
+        Json,
+    },
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+
+/// Filters match the value before or after the event, so a device token's
+/// history is followed across client ids and tenants
+#[derive(Deserialize, Debug)]
+pub struct ClientEventsQuery {
+    pub tenant_id: Option<String>,
+    pub client_id: Option<String>,
+    pub device_token: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ClientEventsResponse {
+    /// Most recent first
+    pub events: Vec<ClientEvent>,
+    /// Pass as `cursor` to fetch the next page, unset on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[instrument(skip(state), name = "admin_client_events_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ClientEventsQuery>,
+) -> Result<Json<ClientEventsResponse>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let before_id = query
+        .cursor
+        .as_deref()
+        .map(|cursor| cursor.parse::<i64>().map_err(|_| Error::InvalidCursor))
+        .transpose()?;
+
+    // One extra event is fetched to know if there is a next page
+    let mut events = state
+        .client_store
+        .get_client_events(ClientEventListParams {
+            tenant_id: query.tenant_id,
+            client_id: query.client_id,
+            device_token: query.device_token,
+            before_id,
+            limit: limit + 1,
+        })
+        .await?;
+
+    let next_cursor = if events.len() as i64 > limit {
+        events.truncate(limit as usize);
+        events.last().map(|event| event.id.to_string())
+    } else {
+        None
+    };
+
+    Ok(Json(ClientEventsResponse {
+        events,
+        next_cursor,
+    }))
+}
@@ -0,0 +1,117 @@
+use {
+    crate::{
+        error::{Error, Result},
+        providers::ProviderKind,
+        state::AppState,
+        stores::tenant::{Tenant, TenantListParams},
+    },
+    axum::{
+        extract::{Query, State},
+        Json,
+    },
+    base64::Engine as _,
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+
+#[derive(Deserialize, Debug)]
+pub struct ListTenantsQuery {
+    pub suspended: Option<bool>,
+    pub provider: Option<String>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    /// Matches tenants whose id starts with this value
+    pub search: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AdminTenant {
+    pub id: String,
+    pub enabled_providers: Vec<String>,
+    pub suspended: bool,
+    pub suspended_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Tenant> for AdminTenant {
+    fn from(tenant: Tenant) -> Self {
+        AdminTenant {
+            enabled_providers: tenant.enabled_providers(),
+            id: tenant.id,
+            suspended: tenant.suspended,
+            suspended_reason: tenant.suspended_reason,
+            created_at: tenant.created_at,
+            updated_at: tenant.updated_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ListTenantsResponse {
+    pub tenants: Vec<AdminTenant>,
+    /// Pass as `cursor` to fetch the next page, unset on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[instrument(skip(state), name = "admin_list_tenants_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListTenantsQuery>,
+) -> Result<Json<ListTenantsResponse>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let after_id = query
+        .cursor
+        .as_deref()
+        .map(|cursor| {
+            base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .decode(cursor)
+                .ok()
+                .and_then(|id| String::from_utf8(id).ok())
+                .ok_or(Error::InvalidCursor)
+        })
+        .transpose()?;
+    let provider = query
+        .provider
+        .as_deref()
+        .map(ProviderKind::try_from)
+        .transpose()?;
+
+    // One extra tenant is fetched to know if there is a next page
+    let mut tenants = state
+        .tenant_store
+        .list_tenants(TenantListParams {
+            suspended: query.suspended,
+            provider,
+            updated_after: query.updated_after,
+            updated_before: query.updated_before,
+            id_prefix: query.search,
+            after_id,
+            limit: limit + 1,
+        })
+        .await?;
+
+    let next_cursor = if tenants.len() as i64 > limit {
+        tenants.truncate(limit as usize);
+        tenants
+            .last()
+            .map(|tenant| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&tenant.id))
+    } else {
+        None
+    };
+
+    Ok(Json(ListTenantsResponse {
+        tenants: tenants.into_iter().map(Into::into).collect(),
+        next_cursor,
+    }))
+}
@@ -0,0 +1,11 @@
+use {
+    crate::{error::Result, state::AppState, stores::tenant::TenantSummary},
+    axum::{extract::State, Json},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+#[instrument(skip_all, name = "admin_tenant_summary_handler")]
+pub async fn handler(State(state): State<Arc<AppState>>) -> Result<Json<TenantSummary>> {
+    Ok(Json(state.tenant_store.get_tenant_summary().await?))
+}
@@ -0,0 +1,32 @@
+use {
+    crate::{error::Result, state::AppState, stores::tenant::TenantSuspension},
+    axum::{
+        extract::{Path, State},
+        Json,
+    },
+    serde::Serialize,
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+#[derive(Serialize, Debug)]
+pub struct TenantSuspensionsResponse {
+    /// Most recent first
+    pub history: Vec<TenantSuspension>,
+}
+
+#[instrument(skip(state), name = "admin_tenant_suspensions_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<TenantSuspensionsResponse>> {
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    let history = state
+        .tenant_store
+        .get_tenant_suspension_history(&id)
+        .await?;
+
+    Ok(Json(TenantSuspensionsResponse { history }))
+}
@@ -0,0 +1,37 @@
+use {
+    crate::{error::Result, state::AppState},
+    axum::{
+        extract::{Path, State},
+        Json,
+    },
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::{info, instrument},
+};
+
+const ADMIN_UNSUSPEND_REASON: &str = "Unsuspended by admin";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnsuspendTenantResponse {
+    /// False if the tenant wasn't suspended
+    pub unsuspended: bool,
+}
+
+#[instrument(skip(state), name = "admin_unsuspend_tenant_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<UnsuspendTenantResponse>> {
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    let unsuspended = state
+        .tenant_store
+        .unsuspend_tenant(&id, ADMIN_UNSUSPEND_REASON)
+        .await?;
+    if unsuspended {
+        info!(tenant_id = %id, "tenant has been unsuspended by an admin");
+    }
+
+    Ok(Json(UnsuspendTenantResponse { unsuspended }))
+}
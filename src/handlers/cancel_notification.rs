@@ -0,0 +1,61 @@
+use {
+    crate::{
+        error::Result,
+        log::prelude::*,
+        middleware::validate_signature::RequireValidSignature,
+        state::AppState,
+        stores::{notification::DeliveryStatus, StoreError},
+    },
+    axum::{
+        body::Bytes,
+        extract::{Json, Path, State as StateExtractor},
+    },
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CancelNotificationResponse {
+    /// Number of clients the notification was scheduled for
+    pub cancelled: usize,
+}
+
+/// Cancels a scheduled notification for every client it was pushed to
+#[instrument(skip_all, name = "cancel_notification_handler")]
+pub async fn handler(
+    Path((tenant_id, id)): Path<(String, String)>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    RequireValidSignature(_): RequireValidSignature<Bytes>,
+) -> Result<Json<CancelNotificationResponse>> {
+    let client_ids = state
+        .delivery_store
+        .cancel_scheduled_delivery(&tenant_id, &id)
+        .await?;
+    if client_ids.is_empty() {
+        return Err(StoreError::NotFound("scheduled notification".to_string(), id).into());
+    }
+
+    for client_id in &client_ids {
+        if let Err(e) = state
+            .notification_store
+            .update_notification_status(
+                &id,
+                client_id,
+                &tenant_id,
+                DeliveryStatus::Cancelled,
+                0,
+                None,
+            )
+            .await
+        {
+            warn!("error updating notification status: {e:?}");
+        }
+    }
+
+    debug!(%tenant_id, notification_id = %id, clients = client_ids.len(), "cancelled notification");
+
+    Ok(Json(CancelNotificationResponse {
+        cancelled: client_ids.len(),
+    }))
+}
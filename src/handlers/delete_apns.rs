@@ -40,13 +40,7 @@ pub async fn handler(
     // Ensure tenant real
     let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
 
-    let new_tenant = state.tenant_store.update_tenant_delete_apns(&id).await?;
-
-    if new_tenant.suspended {
-        // If suspended, it can be restored now because valid credentials have been
-        // provided
-        state.tenant_store.unsuspend_tenant(&new_tenant.id).await?;
-    }
+    let _new_tenant = state.tenant_store.update_tenant_delete_apns(&id).await?;
 
     increment_counter!(state.metrics, tenant_apns_updates);
 
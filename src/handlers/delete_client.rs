@@ -4,6 +4,7 @@ use {
         handlers::{authenticate_client, Response, DECENTRALIZED_IDENTIFIER_PREFIX},
         log::prelude::*,
         state::AppState,
+        stores::client::ClientEventReason,
     },
     axum::{
         extract::{Path, State as StateExtractor},
@@ -53,7 +54,10 @@ pub async fn handler(
         return Err(InvalidAuthentication);
     }
 
-    state.client_store.delete_client(&tenant_id, &id).await?;
+    state
+        .client_store
+        .delete_client(&tenant_id, &id, ClientEventReason::ManualDelete)
+        .await?;
     debug!("client ({}) deleted for tenant ({})", id, tenant_id);
 
     debug!(
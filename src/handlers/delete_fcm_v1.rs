@@ -40,13 +40,7 @@ pub async fn handler(
         return Err(e);
     }
 
-    let new_tenant = state.tenant_store.update_tenant_delete_fcm_v1(&id).await?;
-
-    if new_tenant.suspended {
-        // If suspended, it can be restored now because valid credentials have been
-        // provided
-        state.tenant_store.unsuspend_tenant(&new_tenant.id).await?;
-    }
+    let _new_tenant = state.tenant_store.update_tenant_delete_fcm_v1(&id).await?;
 
     increment_counter!(state.metrics, tenant_fcm_v1_updates);
 
@@ -0,0 +1,60 @@
+use {
+    crate::{
+        error::Result,
+        handlers::{
+            get_notification::NotificationStatus, require_client, DECENTRALIZED_IDENTIFIER_PREFIX,
+        },
+        state::AppState,
+    },
+    axum::{
+        extract::{Path, Query, State as StateExtractor},
+        http::HeaderMap,
+        Json,
+    },
+    relay_rpc::domain::ClientId,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct ClientNotificationsQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ClientNotificationsResponse {
+    /// Most recently received first
+    pub notifications: Vec<NotificationStatus>,
+}
+
+#[instrument(skip_all, name = "get_client_notifications_handler")]
+pub async fn handler(
+    Path((tenant_id, id)): Path<(String, String)>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    Query(query): Query<ClientNotificationsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<ClientNotificationsResponse>> {
+    let id = id
+        .trim_start_matches(DECENTRALIZED_IDENTIFIER_PREFIX)
+        .to_string();
+    require_client(
+        headers,
+        &state.config.public_url,
+        &ClientId::new(id.clone().into()),
+    )?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let notifications = state
+        .notification_store
+        .get_client_notifications(&tenant_id, &id, limit)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    Ok(Json(ClientNotificationsResponse { notifications }))
+}
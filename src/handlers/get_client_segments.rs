@@ -0,0 +1,68 @@
+use {
+    crate::{
+        error::Result,
+        handlers::validate_tenant_request,
+        log::prelude::*,
+        state::AppState,
+        stores::client::{ClientSegment, ClientSegmentCount},
+    },
+    axum::{
+        extract::{Path, Query, State},
+        http::HeaderMap,
+        Json,
+    },
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+#[derive(Deserialize)]
+pub struct ClientSegmentsQuery {
+    /// The metadata field to count clients by
+    pub by: ClientSegment,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ClientSegmentsResponse {
+    /// Most common first
+    pub segments: Vec<ClientSegmentCount>,
+}
+
+#[instrument(skip_all, name = "get_client_segments_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<ClientSegmentsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<ClientSegmentsResponse>> {
+    #[cfg(feature = "cloud")]
+    let verification_res =
+        validate_tenant_request(&state.jwt_validation_client, &headers, &id).await;
+
+    #[cfg(not(feature = "cloud"))]
+    let verification_res = validate_tenant_request(&state.jwt_validation_client, &headers);
+
+    if let Err(e) = verification_res {
+        error!(
+            tenant_id = %id,
+            err = ?e,
+            "JWT verification failed"
+        );
+        return Err(e);
+    }
+
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let segments = state
+        .client_store
+        .get_client_segments(&id, query.by, limit)
+        .await?;
+
+    Ok(Json(ClientSegmentsResponse { segments }))
+}
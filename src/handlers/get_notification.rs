@@ -0,0 +1,71 @@
+use {
+    crate::{
+        error::Result,
+        handlers::{require_client, DECENTRALIZED_IDENTIFIER_PREFIX},
+        state::AppState,
+        stores::notification::{DeliveryStatus, Notification},
+    },
+    axum::{
+        extract::{Path, State as StateExtractor},
+        http::HeaderMap,
+        Json,
+    },
+    chrono::{DateTime, Utc},
+    relay_rpc::domain::ClientId,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NotificationStatus {
+    pub id: String,
+    pub status: DeliveryStatus,
+    /// Send attempts made so far, retries are every attempt after the first
+    pub attempts: i32,
+    pub provider: Option<String>,
+    pub provider_message_id: Option<String>,
+    pub last_error: Option<String>,
+    pub first_received_at: DateTime<Utc>,
+    pub last_received_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<Notification> for NotificationStatus {
+    fn from(notification: Notification) -> Self {
+        NotificationStatus {
+            id: notification.id,
+            status: notification.status,
+            attempts: notification.attempts,
+            provider: notification.provider,
+            provider_message_id: notification.provider_message_id,
+            last_error: notification.last_error,
+            first_received_at: notification.created_at,
+            last_received_at: notification.last_received_at,
+            delivered_at: notification.delivered_at,
+        }
+    }
+}
+
+#[instrument(skip_all, name = "get_notification_handler")]
+pub async fn handler(
+    Path((tenant_id, id, notification_id)): Path<(String, String, String)>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<NotificationStatus>> {
+    let id = id
+        .trim_start_matches(DECENTRALIZED_IDENTIFIER_PREFIX)
+        .to_string();
+    require_client(
+        headers,
+        &state.config.public_url,
+        &ClientId::new(id.clone().into()),
+    )?;
+
+    let notification = state
+        .notification_store
+        .get_notification(&notification_id, &id, &tenant_id)
+        .await?;
+
+    Ok(Json(notification.into()))
+}
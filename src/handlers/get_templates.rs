@@ -0,0 +1,49 @@
+use {
+    crate::{
+        error::Result, handlers::validate_tenant_request, log::prelude::*, state::AppState,
+        stores::tenant::NotificationTemplate,
+    },
+    axum::{
+        extract::{Path, State},
+        http::HeaderMap,
+        Json,
+    },
+    serde::Serialize,
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+#[derive(Serialize, Debug)]
+pub struct GetTemplatesResponse {
+    pub templates: Vec<NotificationTemplate>,
+}
+
+#[instrument(skip_all, name = "get_templates_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<GetTemplatesResponse>> {
+    #[cfg(feature = "cloud")]
+    let verification_res =
+        validate_tenant_request(&state.jwt_validation_client, &headers, &id).await;
+
+    #[cfg(not(feature = "cloud"))]
+    let verification_res = validate_tenant_request(&state.jwt_validation_client, &headers);
+
+    if let Err(e) = verification_res {
+        error!(
+            tenant_id = %id,
+            err = ?e,
+            "JWT verification failed"
+        );
+        return Err(e);
+    }
+
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    let templates = state.tenant_store.get_tenant_templates(&id).await?;
+
+    Ok(Json(GetTemplatesResponse { templates }))
+}
@@ -1,17 +1,14 @@
 use {
     crate::{
-        error::Error,
-        handlers::validate_tenant_request,
-        log::prelude::*,
-        providers::{ProviderKind, PROVIDER_FCM_V1},
-        state::AppState,
-        stores::tenant::ApnsType,
+        error::Error, handlers::validate_tenant_request, log::prelude::*, providers::ProviderKind,
+        state::AppState, stores::tenant::ApnsType,
     },
     axum::{
         extract::{Path, State},
         http::HeaderMap,
         Json,
     },
+    chrono::{DateTime, Utc},
     serde::{Deserialize, Serialize},
     std::sync::Arc,
     tracing::instrument,
@@ -23,8 +20,12 @@ pub struct GetTenantResponse {
     pub enabled_providers: Vec<String>,
     pub apns_topic: Option<String>,
     pub apns_type: Option<ApnsType>,
+    pub apns_certificate_expires_at: Option<DateTime<Utc>>,
+    pub web_push_vapid_public_key: Option<String>,
+    pub web_push_vapid_subject: Option<String>,
     pub suspended: bool,
     pub suspended_reason: Option<String>,
+    pub webhook_url: Option<String>,
 }
 
 #[instrument(skip_all, name = "get_tenant_handler")]
@@ -52,29 +53,30 @@ pub async fn handler(
     let tenant = state.tenant_store.get_tenant(&id).await?;
 
     let providers = tenant.providers();
+    let web_push_vapid_public_key = tenant.web_push_public_key();
 
     let mut res = GetTenantResponse {
         url: format!("{}/{}", state.config.public_url, tenant.id),
-        enabled_providers: tenant
-            .providers()
-            .iter()
-            .map(Into::into)
-            // Special case on fcm_v1 for credentials because providers() is also used for token management (of which FCM and FCM V1 tokens are the same)
-            .chain(if tenant.fcm_v1_credentials.is_some() {
-                vec![PROVIDER_FCM_V1.to_string()]
-            } else {
-                vec![]
-            })
-            .collect(),
+        enabled_providers: tenant.enabled_providers(),
         apns_topic: None,
         apns_type: None,
+        apns_certificate_expires_at: None,
+        web_push_vapid_public_key: None,
+        web_push_vapid_subject: None,
         suspended: tenant.suspended,
         suspended_reason: tenant.suspended_reason,
+        webhook_url: tenant.webhook_url,
     };
 
     if providers.contains(&ProviderKind::Apns) {
         res.apns_topic = tenant.apns_topic;
         res.apns_type = tenant.apns_type;
+        res.apns_certificate_expires_at = tenant.apns_certificate_expires_at;
+    }
+
+    if providers.contains(&ProviderKind::WebPush) {
+        res.web_push_vapid_public_key = web_push_vapid_public_key;
+        res.web_push_vapid_subject = tenant.web_push_vapid_subject;
     }
 
     debug!(
@@ -0,0 +1,92 @@
+use {
+    crate::{
+        error::Result,
+        handlers::validate_tenant_request,
+        log::prelude::*,
+        state::AppState,
+        stores::{notification::DeliveryStatus, webhook::WebhookEvent},
+    },
+    axum::{
+        extract::{Path, Query, State},
+        http::HeaderMap,
+        Json,
+    },
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+#[derive(Deserialize)]
+pub struct WebhookEventsQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WebhookEventLog {
+    pub id: String,
+    #[serde(flatten)]
+    pub event: WebhookEvent,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WebhookEventsResponse {
+    /// Most recent first
+    pub events: Vec<WebhookEventLog>,
+}
+
+#[instrument(skip_all, name = "get_webhook_events_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<WebhookEventsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<WebhookEventsResponse>> {
+    #[cfg(feature = "cloud")]
+    let verification_res =
+        validate_tenant_request(&state.jwt_validation_client, &headers, &id).await;
+
+    #[cfg(not(feature = "cloud"))]
+    let verification_res = validate_tenant_request(&state.jwt_validation_client, &headers);
+
+    if let Err(e) = verification_res {
+        error!(
+            tenant_id = %id,
+            err = ?e,
+            "JWT verification failed"
+        );
+        return Err(e);
+    }
+
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let events = state
+        .webhook_store
+        .get_webhook_events(&id, limit)
+        .await?
+        .into_iter()
+        .map(|event| WebhookEventLog {
+            id: event.id,
+            event: event.payload.0,
+            status: event.status,
+            attempts: event.attempts,
+            response_status: event.response_status,
+            last_error: event.last_error,
+            created_at: event.created_at,
+            delivered_at: event.delivered_at,
+        })
+        .collect();
+
+    Ok(Json(WebhookEventsResponse { events }))
+}
@@ -20,14 +20,31 @@ use {
 };
 
 // Push
+pub mod cancel_notification;
 pub mod delete_client;
+pub mod get_client_notifications;
+pub mod get_notification;
 pub mod metrics;
+pub mod push_batch;
 pub mod push_message;
+pub mod push_topic;
 pub mod register_client;
 #[cfg(not(feature = "multitenant"))]
 pub mod single_tenant_wrappers;
+pub mod subscribe_topic;
+pub mod unsubscribe_topic;
 // Tenant Management
 #[cfg(feature = "multitenant")]
+pub mod admin_client_events;
+#[cfg(feature = "multitenant")]
+pub mod admin_list_tenants;
+#[cfg(feature = "multitenant")]
+pub mod admin_tenant_summary;
+#[cfg(feature = "multitenant")]
+pub mod admin_tenant_suspensions;
+#[cfg(feature = "multitenant")]
+pub mod admin_unsuspend_tenant;
+#[cfg(feature = "multitenant")]
 pub mod create_tenant;
 #[cfg(feature = "multitenant")]
 pub mod delete_apns;
@@ -36,9 +53,23 @@ pub mod delete_fcm;
 #[cfg(feature = "multitenant")]
 pub mod delete_fcm_v1;
 #[cfg(feature = "multitenant")]
+pub mod delete_hms;
+#[cfg(feature = "multitenant")]
+pub mod delete_template;
+#[cfg(feature = "multitenant")]
 pub mod delete_tenant;
 #[cfg(feature = "multitenant")]
+pub mod delete_web_push;
+#[cfg(feature = "multitenant")]
+pub mod delete_webhook;
+#[cfg(feature = "multitenant")]
+pub mod get_client_segments;
+#[cfg(feature = "multitenant")]
+pub mod get_templates;
+#[cfg(feature = "multitenant")]
 pub mod get_tenant;
+#[cfg(feature = "multitenant")]
+pub mod get_webhook_events;
 pub mod health;
 pub mod rate_limit_test;
 #[cfg(feature = "multitenant")]
@@ -47,6 +78,16 @@ pub mod update_apns;
 pub mod update_fcm;
 #[cfg(feature = "multitenant")]
 pub mod update_fcm_v1;
+#[cfg(feature = "multitenant")]
+pub mod update_hms;
+#[cfg(feature = "multitenant")]
+pub mod update_template;
+#[cfg(feature = "multitenant")]
+pub mod update_web_push;
+#[cfg(feature = "multitenant")]
+pub mod update_webhook;
+#[cfg(feature = "multitenant")]
+pub mod validate_tenant;
 
 pub const DECENTRALIZED_IDENTIFIER_PREFIX: &str = "did:key:";
 
@@ -78,12 +119,29 @@ where
     };
 }
 
+/// Like `authenticate_client` but for new endpoints, where the client's JWT is
+/// required rather than optional
+pub fn require_client(headers: HeaderMap, aud: &str, client_id: &ClientId) -> Result<()> {
+    if !headers.contains_key(AUTHORIZATION) {
+        debug!(%client_id, "client_id verification failed: missing token");
+        return Err(InvalidAuthentication);
+    }
+
+    if authenticate_client(headers, aud, |token_client_id| {
+        token_client_id.as_ref() == Some(client_id)
+    })? {
+        Ok(())
+    } else {
+        debug!(%client_id, "client_id verification failed: invalid client_id");
+        Err(InvalidAuthentication)
+    }
+}
+
 #[derive(serde::Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ErrorLocation {
     Body,
-    // Note (Harry): Spec supports this but it currently isn't used
-    // Query,
+    Query,
     Header,
     Path,
     Unknown,
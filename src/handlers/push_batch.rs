@@ -0,0 +1,196 @@
+#[cfg(feature = "analytics")]
+use {
+    crate::{analytics::message_info::MessageInfo, handlers::push_message::publish_message_info},
+    axum_client_ip::SecureClientIp,
+};
+use {
+    crate::{
+        error::{Error, Result},
+        handlers::push_message::{handler_internal, PushMessageBody, PushOutcome},
+        log::prelude::*,
+        middleware::validate_signature::RequireValidSignature,
+        state::AppState,
+    },
+    axum::{
+        extract::{Json, Path, State as StateExtractor},
+        response::IntoResponse,
+    },
+    futures_util::StreamExt,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+/// Maximum number of clients that can be addressed by a single batch request
+pub const MAX_BATCH_SIZE: usize = 500;
+
+/// Number of clients pushed to concurrently while handling a batch
+pub(crate) const BATCH_CONCURRENCY: usize = 32;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct BatchPushMessage {
+    pub client_id: String,
+    #[serde(flatten)]
+    pub body: PushMessageBody,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum BatchPushBody {
+    /// A different message for every client
+    Messages { messages: Vec<BatchPushMessage> },
+    /// The same message for all clients
+    Broadcast {
+        client_ids: Vec<String>,
+        #[serde(flatten)]
+        body: PushMessageBody,
+    },
+}
+
+impl BatchPushBody {
+    fn into_messages(self) -> Vec<BatchPushMessage> {
+        match self {
+            Self::Messages { messages } => messages,
+            Self::Broadcast { client_ids, body } => client_ids
+                .into_iter()
+                .map(|client_id| BatchPushMessage {
+                    client_id,
+                    body: body.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchPushOutcome {
+    Delivered,
+    Queued,
+    Scheduled,
+    Duplicate,
+    ClientDeleted,
+    TenantSuspended,
+    NotFound,
+    RateLimited,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BatchPushResult {
+    pub client_id: String,
+    pub outcome: BatchPushOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchPushResponse {
+    pub results: Vec<BatchPushResult>,
+}
+
+#[instrument(skip_all, name = "push_batch_handler")]
+pub async fn handler(
+    #[cfg(feature = "analytics")] SecureClientIp(client_ip): SecureClientIp,
+    Path(tenant_id): Path<String>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    RequireValidSignature(Json(body)): RequireValidSignature<Json<BatchPushBody>>,
+) -> Result<Json<BatchPushResponse>> {
+    let messages = body.into_messages();
+    if messages.is_empty() {
+        return Err(Error::EmptyField("client_ids".to_string()));
+    }
+    if messages.len() > MAX_BATCH_SIZE {
+        return Err(Error::BatchTooLarge(messages.len()));
+    }
+
+    debug!(%tenant_id, size = messages.len(), "pushing batch");
+
+    let results = futures_util::stream::iter(messages)
+        .map(|message| {
+            push_to_client(
+                #[cfg(feature = "analytics")]
+                client_ip,
+                state.clone(),
+                tenant_id.clone(),
+                message.client_id,
+                message.body,
+            )
+        })
+        .buffered(BATCH_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    Ok(Json(BatchPushResponse { results }))
+}
+
+/// Pushes the message to one of the tenant's clients, reporting errors as the
+/// outcome rather than failing the request
+pub(crate) async fn push_to_client(
+    #[cfg(feature = "analytics")] client_ip: std::net::IpAddr,
+    state: Arc<AppState>,
+    tenant_id: String,
+    client_id: String,
+    body: PushMessageBody,
+) -> BatchPushResult {
+    let res = handler_internal(
+        Path((tenant_id.clone(), client_id.clone())),
+        StateExtractor(state.clone()),
+        RequireValidSignature(Json(body)),
+    )
+    .await;
+
+    let (status, outcome, error, analytics_option) = match res {
+        Ok((receipt, analytics_option)) => {
+            let status = receipt.outcome.status_code().as_u16();
+            let (outcome, error) = match receipt.outcome {
+                PushOutcome::Delivered => (BatchPushOutcome::Delivered, None),
+                PushOutcome::Queued => (BatchPushOutcome::Queued, None),
+                PushOutcome::Scheduled => (BatchPushOutcome::Scheduled, None),
+                PushOutcome::AlreadyReceived | PushOutcome::AlreadyProcessed => {
+                    (BatchPushOutcome::Duplicate, None)
+                }
+                PushOutcome::ClientDeleted => (
+                    BatchPushOutcome::ClientDeleted,
+                    Some(Error::ClientDeleted.to_string()),
+                ),
+            };
+            (status, outcome, error, analytics_option)
+        }
+        Err((error, analytics_option)) => {
+            warn!(%client_id, "error handling batch push message: {error:?}");
+
+            #[cfg(feature = "analytics")]
+            let analytics_option = analytics_option.map(|message_info| MessageInfo {
+                response_message: Some(format!("{error:?}").into()),
+                ..message_info
+            });
+
+            let outcome = match error {
+                Error::ClientNotFound => BatchPushOutcome::NotFound,
+                Error::ClientDeleted => BatchPushOutcome::ClientDeleted,
+                Error::TenantSuspended => BatchPushOutcome::TenantSuspended,
+                Error::PushRateLimited(_) => BatchPushOutcome::RateLimited,
+                _ => BatchPushOutcome::Failed,
+            };
+            let message = error.to_string();
+            let status = error.into_response().status().as_u16();
+            (status, outcome, Some(message), analytics_option)
+        }
+    };
+
+    #[cfg(not(feature = "analytics"))]
+    let _ = (status, analytics_option);
+
+    #[cfg(feature = "analytics")]
+    if let Some(mut message_info) = analytics_option {
+        message_info.status = status;
+        publish_message_info(state, client_ip, tenant_id, client_id.clone(), message_info);
+    }
+
+    BatchPushResult {
+        client_id,
+        outcome,
+        error,
+    }
+}
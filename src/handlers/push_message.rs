@@ -1,30 +1,34 @@
-#[cfg(feature = "analytics")]
-use axum_client_ip::SecureClientIp;
 use {
     crate::{
         analytics::message_info::MessageInfo,
+        delivery::{self, DeliveryResult, DELIVERY_LEASE},
         error::{
             Error,
             Error::{ClientNotFound, Store},
         },
-        handlers::DECENTRALIZED_IDENTIFIER_PREFIX,
+        handlers::{Response, ResponseError, DECENTRALIZED_IDENTIFIER_PREFIX},
         increment_counter,
         log::prelude::*,
         middleware::validate_signature::RequireValidSignature,
-        providers::{LegacyPushMessage, Provider, PushMessage, PushProvider, RawPushMessage},
+        providers::{LegacyPushMessage, PushMessage, PushOptions, RawPushMessage},
+        push_rate_limit::RateLimitScope,
         state::AppState,
-        stores::StoreError,
+        stores::{notification::DeliveryStatus, StoreError},
+        templates::{self, TemplatePushMessage},
     },
     axum::{
         extract::{Json, Path, State as StateExtractor},
         http::StatusCode,
         response::IntoResponse,
     },
+    chrono::{DateTime, NaiveDateTime, TimeZone, Utc},
     serde::{Deserialize, Serialize},
     std::sync::Arc,
     tap::TapFallible,
     tracing::instrument,
 };
+#[cfg(feature = "analytics")]
+use {axum_client_ip::SecureClientIp, std::net::IpAddr};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub struct PushMessageBody {
@@ -34,6 +38,207 @@ pub struct PushMessageBody {
     // Legacy (deprecating) fields
     #[serde(flatten)]
     pub legacy: Option<LegacyPushMessage>,
+
+    /// Priority, TTL, collapse key and push type of the notification
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<PushOptions>,
+
+    /// Sent instead of the raw or legacy message when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<TemplatePushMessage>,
+
+    /// Delivers the notification at this time rather than right away
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_at: Option<SendAt>,
+
+    /// Delivers the notification after this many seconds rather than right away
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<u64>,
+}
+
+/// When a scheduled notification is delivered
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum SendAt {
+    /// An RFC 3339 time, e.g. `2024-11-05T09:00:00+01:00`
+    Absolute(DateTime<Utc>),
+    /// A time without an offset, e.g. `2024-11-05T09:00:00`, in the timezone
+    /// the client registered
+    Local(NaiveDateTime),
+}
+
+/// The furthest ahead a notification can be scheduled (30 days)
+pub const MAX_SCHEDULE_DELAY: u64 = 2_592_000;
+
+impl PushMessageBody {
+    /// Selects the message format the client has registered for
+    pub fn push_message(&self, always_raw: bool) -> Result<PushMessage, Error> {
+        self.validate()?;
+
+        if always_raw {
+            self.raw
+                .clone()
+                .map(PushMessage::RawPushMessage)
+                .ok_or_else(|| {
+                    Error::EmptyField("missing topic, tag, or message field".to_string())
+                })
+        } else {
+            self.legacy
+                .clone()
+                .map(PushMessage::LegacyPushMessage)
+                .ok_or_else(|| Error::EmptyField("missing id or payload field".to_string()))
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        match (self.send_at, self.delay) {
+            // Checked once the client's timezone is known
+            (Some(SendAt::Local(_)), None) => {}
+            _ => {
+                self.scheduled_at(None)?;
+            }
+        }
+        match &self.options {
+            Some(options) => options.validate(),
+            None => Ok(()),
+        }
+    }
+
+    /// When the notification should be delivered, `None` when it is due now.
+    /// Local times are resolved in the client's `timezone`
+    pub fn scheduled_at(&self, timezone: Option<&str>) -> Result<Option<DateTime<Utc>>, Error> {
+        let now = Utc::now();
+        let send_at = match (self.send_at, self.delay) {
+            (Some(_), Some(_)) => {
+                return Err(Error::InvalidSchedule(
+                    "only one of send_at and delay can be set".to_string(),
+                ))
+            }
+            (Some(SendAt::Absolute(send_at)), None) => send_at,
+            (Some(SendAt::Local(send_at)), None) => resolve_local_time(send_at, timezone)?,
+            (None, Some(delay)) => {
+                now + chrono::Duration::seconds(delay.min(MAX_SCHEDULE_DELAY + 1) as i64)
+            }
+            (None, None) => return Ok(None),
+        };
+
+        if send_at > now + chrono::Duration::seconds(MAX_SCHEDULE_DELAY as i64) {
+            return Err(Error::InvalidSchedule(format!(
+                "notifications can be scheduled at most {MAX_SCHEDULE_DELAY} seconds ahead"
+            )));
+        }
+        Ok((send_at > now).then_some(send_at))
+    }
+}
+
+/// A local time skipped by a daylight saving change is rejected, one that
+/// occurs twice resolves to the earlier of the two
+fn resolve_local_time(
+    send_at: NaiveDateTime,
+    timezone: Option<&str>,
+) -> Result<DateTime<Utc>, Error> {
+    let Some(timezone) = timezone else {
+        return Err(Error::InvalidSchedule(
+            "a send_at without an offset needs the client to have registered its timezone"
+                .to_string(),
+        ));
+    };
+    let tz = timezone.parse::<chrono_tz::Tz>().map_err(|_| {
+        Error::InvalidSchedule(format!("the client's timezone {timezone} is unknown"))
+    })?;
+
+    tz.from_local_datetime(&send_at)
+        .earliest()
+        .map(|send_at| send_at.with_timezone(&Utc))
+        .ok_or_else(|| Error::InvalidSchedule(format!("{send_at} doesn't exist in {timezone}")))
+}
+
+/// Result of a successfully handled push request
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PushOutcome {
+    /// The provider accepted the notification
+    Delivered,
+    /// The provider failed transiently, the notification will be retried
+    Queued,
+    /// The notification was already received for this client
+    AlreadyReceived,
+    /// The notification is already being processed for this client
+    AlreadyProcessed,
+    /// The provider rejected the client's token so the client was deleted
+    ClientDeleted,
+    /// The notification will be delivered at its `send_at` time
+    Scheduled,
+}
+
+impl PushOutcome {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Delivered | Self::Queued | Self::ClientDeleted | Self::Scheduled => {
+                StatusCode::ACCEPTED
+            }
+            Self::AlreadyReceived | Self::AlreadyProcessed => StatusCode::OK,
+        }
+    }
+}
+
+/// Per-provider receipt returned in the push response body
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PushReceipt {
+    pub outcome: PushOutcome,
+    /// The provider the notification was sent with, or the client's provider
+    /// when it wasn't sent
+    pub provider: String,
+    /// The provider's id for the message, e.g. the APNs `apns-id` or the FCM
+    /// v1 message name
+    pub provider_message_id: Option<String>,
+    pub client_deleted: bool,
+}
+
+impl PushReceipt {
+    pub fn new(outcome: PushOutcome, provider: impl Into<String>) -> Self {
+        Self {
+            outcome,
+            provider: provider.into(),
+            provider_message_id: None,
+            client_deleted: outcome == PushOutcome::ClientDeleted,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PushResponse {
+    #[serde(flatten)]
+    response: Response,
+    #[serde(flatten)]
+    receipt: PushReceipt,
+}
+
+impl IntoResponse for PushReceipt {
+    fn into_response(self) -> axum::response::Response {
+        let status = self.outcome.status_code();
+        let response = match self.outcome {
+            // Still reported as the `client_deleted` error it used to be returned as
+            PushOutcome::ClientDeleted => Response::new_failure(
+                status,
+                vec![ResponseError {
+                    name: "client_deleted".to_string(),
+                    message: "Request Accepted, client deleted due to invalid token".to_string(),
+                }],
+                vec![],
+            ),
+            _ => Response::new_success(status),
+        };
+
+        (
+            status,
+            Json(PushResponse {
+                response,
+                receipt: self,
+            }),
+        )
+            .into_response()
+    }
 }
 
 #[instrument(skip_all, name = "push_message_handler")]
@@ -51,7 +256,10 @@ pub async fn handler(
     .await;
 
     let inner_packed = match res {
-        Ok((res, analytics_options_inner)) => (res.status().as_u16(), res, analytics_options_inner),
+        Ok((receipt, analytics_options_inner)) => {
+            let res = receipt.into_response();
+            (res.status().as_u16(), res, analytics_options_inner)
+        }
         Err((error, analytics_option_inner)) => {
             warn!("error handling push message: {error:?}");
 
@@ -89,32 +297,44 @@ pub async fn handler(
     #[cfg(feature = "analytics")]
     if let Some(mut message_info) = analytics_option {
         message_info.status = status;
+        publish_message_info(state, client_ip, tenant_id, client_id, message_info);
+    }
 
-        tokio::spawn(async move {
-            if let Some(analytics) = &state.analytics {
-                let (country, continent, region) = analytics
-                    .lookup_geo_data(client_ip)
-                    .map_or((None, None, None), |geo| {
-                        (geo.country, geo.continent, geo.region)
-                    });
+    Ok(response)
+}
 
-                debug!(
-                    %tenant_id,
-                    client_id = %client_id,
-                    ip = %client_ip,
-                    "loaded geo data"
-                );
+/// Enriches the message info with geo data and sends it to analytics in the
+/// background
+#[cfg(feature = "analytics")]
+pub fn publish_message_info(
+    state: Arc<AppState>,
+    client_ip: IpAddr,
+    tenant_id: String,
+    client_id: String,
+    mut message_info: MessageInfo,
+) {
+    tokio::spawn(async move {
+        if let Some(analytics) = &state.analytics {
+            let (country, continent, region) = analytics
+                .lookup_geo_data(client_ip)
+                .map_or((None, None, None), |geo| {
+                    (geo.country, geo.continent, geo.region)
+                });
 
-                message_info.country = country;
-                message_info.continent = continent;
-                message_info.region = region.map(|r| Arc::from(r.join(", ")));
+            debug!(
+                %tenant_id,
+                client_id = %client_id,
+                ip = %client_ip,
+                "loaded geo data"
+            );
 
-                analytics.message(message_info);
-            }
-        });
-    }
+            message_info.country = country;
+            message_info.continent = continent;
+            message_info.region = region.map(|r| Arc::from(r.join(", ")));
 
-    Ok(response)
+            analytics.message(message_info);
+        }
+    });
 }
 
 #[instrument(name = "push_message_internal", skip_all, fields(tenant_id = tenant_id, client_id = client_id))]
@@ -122,7 +342,7 @@ pub async fn handler_internal(
     Path((tenant_id, client_id)): Path<(String, String)>,
     StateExtractor(state): StateExtractor<Arc<AppState>>,
     RequireValidSignature(Json(body)): RequireValidSignature<Json<PushMessageBody>>,
-) -> Result<(axum::response::Response, Option<MessageInfo>), (Error, Option<MessageInfo>)> {
+) -> Result<(PushReceipt, Option<MessageInfo>), (Error, Option<MessageInfo>)> {
     let client = match state.client_store.get_client(&tenant_id, &client_id).await {
         Ok(c) => Ok(c),
         Err(StoreError::NotFound(_, _)) => Err(ClientNotFound),
@@ -169,26 +389,9 @@ pub async fn handler_internal(
     })?;
 
     let cloned_body = body.clone();
-    let push_message = if client.always_raw {
-        if let Some(body) = body.raw {
-            PushMessage::RawPushMessage(body)
-        } else {
-            return Err((
-                Error::EmptyField("missing topic, tag, or message field".to_string()),
-                None,
-            ));
-        }
-    } else {
-        #[allow(clippy::collapsible_else_if)]
-        if let Some(body) = body.legacy {
-            PushMessage::LegacyPushMessage(body)
-        } else {
-            return Err((
-                Error::EmptyField("missing id or payload field".to_string()),
-                None,
-            ));
-        }
-    };
+    let push_message = templates::push_message(&state.tenant_store, &tenant_id, &client, &body)
+        .await
+        .map_err(|e| (e, None))?;
 
     let message_id = push_message.message_id();
 
@@ -280,16 +483,37 @@ pub async fn handler_internal(
         }
     }
 
-    if let Ok(notification) = state
+    // Resolved before the notification is claimed so a sender can fix a
+    // rejected local time and push again
+    let scheduled_at = cloned_body
+        .scheduled_at(client.metadata.timezone.as_deref())
+        .map_err(|e| (e, analytics.clone()))?;
+
+    let tenant = state
+        .tenant_store
+        .get_tenant(&tenant_id)
+        .await
+        .tap_err(|e| warn!("error fetching tenant: {e:?}"))
+        .map_err(|e| (e, analytics.clone()))?;
+    debug!(
+        %tenant_id,
+        client_id = %client_id,
+        "fetched tenant"
+    );
+
+    let claimed = state
         .notification_store
-        .get_notification(&message_id, &client_id, &tenant_id)
+        .claim_notification(&message_id, &tenant_id, &client_id)
         .await
-    {
+        // Storing the notification dedupes as well, so this can fail open
+        .tap_err(|e| warn!("error claiming notification: {e:?}"))
+        .unwrap_or(true);
+    if !claimed {
+        increment_counter!(state.metrics, deduplicated_notifications);
         warn!(
             %tenant_id,
             client_id = %client_id,
-            notification_id = %notification.id,
-            last_recieved_at = %notification.last_received_at,
+            notification_id = %message_id,
             "notification has already been received"
         );
 
@@ -300,19 +524,51 @@ pub async fn handler_internal(
                 ..analytics.unwrap()
             });
 
-            return Ok(((StatusCode::OK).into_response(), analytics));
+            return Ok((
+                PushReceipt::new(PushOutcome::AlreadyReceived, client.push_type.as_str()),
+                analytics,
+            ));
         }
 
         #[cfg(not(feature = "analytics"))]
-        return Ok(((StatusCode::OK).into_response(), None));
+        return Ok((
+            PushReceipt::new(PushOutcome::AlreadyReceived, client.push_type.as_str()),
+            None,
+        ));
+    }
+
+    // Checked before the notification is stored so the sender can retry it
+    if let Err(limited) = state.push_rate_limiter.check(&tenant, &client_id).await {
+        match limited.scope {
+            RateLimitScope::Tenant => increment_counter!(state.metrics, tenant_push_rate_limited),
+            RateLimitScope::Client => increment_counter!(state.metrics, client_push_rate_limited),
+        }
+        warn!(
+            %tenant_id,
+            client_id = %client_id,
+            scope = ?limited.scope,
+            retry_after = ?limited.retry_after,
+            "push rate limited"
+        );
+        release_notification(&state, &message_id, &tenant_id, &client_id).await;
+        return Err((
+            Error::PushRateLimited(limited.retry_after),
+            analytics.clone(),
+        ));
     }
 
-    let notification = state
+    let notification = match state
         .notification_store
         .create_or_update_notification(&message_id, &tenant_id, &client_id, &cloned_body)
         .await
-        .tap_err(|e| warn!("error create_or_update_notification: {e:?}"))
-        .map_err(|e| (Error::Store(e), analytics.clone()))?;
+    {
+        Ok(notification) => notification,
+        Err(e) => {
+            warn!("error create_or_update_notification: {e:?}");
+            release_notification(&state, &message_id, &tenant_id, &client_id).await;
+            return Err((Error::Store(e), analytics.clone()));
+        }
+    };
 
     debug!(
         %tenant_id,
@@ -339,185 +595,196 @@ pub async fn handler_internal(
                 ..analytics.unwrap()
             });
 
-            return Ok(((StatusCode::OK).into_response(), analytics));
+            return Ok((
+                PushReceipt::new(PushOutcome::AlreadyProcessed, client.push_type.as_str()),
+                analytics,
+            ));
         }
 
         #[cfg(not(feature = "analytics"))]
-        return Ok(((StatusCode::OK).into_response(), None));
+        return Ok((
+            PushReceipt::new(PushOutcome::AlreadyProcessed, client.push_type.as_str()),
+            None,
+        ));
     }
 
-    let tenant = state
-        .tenant_store
-        .get_tenant(&tenant_id)
-        .await
-        .tap_err(|e| warn!("error fetching tenant: {e:?}"))
-        .map_err(|e| (e, analytics.clone()))?;
-    debug!(
-        %tenant_id,
-        client_id = %client_id,
-        notification_id = %notification.id,
-        "fetched tenant"
-    );
-
     if tenant.suspended {
         warn!("tenant suspended");
         return Err((Error::TenantSuspended, analytics.clone()));
     }
 
-    let provider = tenant
-        .provider(
-            &client.push_type,
-            state.http_client.clone(),
-            &state.provider_cache,
+    if let Some(send_at) = scheduled_at {
+        let scheduled = state
+            .delivery_store
+            .schedule_delivery(&message_id, &tenant_id, &client_id, &cloned_body, send_at)
+            .await
+            .tap_err(|e| warn!("error schedule_delivery: {e:?}"))
+            .map_err(|e| (Error::Store(e), analytics.clone()))?;
+        let outcome = if scheduled {
+            if let Err(e) = state
+                .notification_store
+                .update_notification_status(
+                    &message_id,
+                    &client_id,
+                    &tenant_id,
+                    DeliveryStatus::Scheduled,
+                    0,
+                    None,
+                )
+                .await
+            {
+                warn!("error updating notification status: {e:?}");
+            }
+            debug!(
+                %tenant_id,
+                client_id = %client_id,
+                notification_id = %notification.id,
+                %send_at,
+                "scheduled notification"
+            );
+            PushOutcome::Scheduled
+        } else {
+            increment_counter!(state.metrics, deduplicated_notifications);
+            PushOutcome::AlreadyProcessed
+        };
+
+        #[cfg(feature = "analytics")]
+        {
+            analytics = Some(MessageInfo {
+                response_message: Some(format!("Scheduled for {send_at}").into()),
+                ..analytics.unwrap()
+            });
+        }
+
+        return Ok((
+            PushReceipt::new(outcome, client.push_type.as_str()),
+            analytics,
+        ));
+    }
+
+    let Some(delivery) = state
+        .delivery_store
+        .enqueue_delivery(
+            &message_id,
+            &tenant_id,
+            &client_id,
+            &cloned_body,
+            DELIVERY_LEASE,
         )
         .await
-        .tap_err(|e| warn!("error fetching provider: {e:?}"))
-        .map_err(|e| (e, analytics.clone()))?;
-    debug!(
-        %tenant_id,
-        client_id = %client_id,
-        notification_id = %notification.id,
-        push_type = client.push_type.as_str(),
-        "fetched provider"
-    );
+        .tap_err(|e| warn!("error enqueue_delivery: {e:?}"))
+        .map_err(|e| (Error::Store(e), analytics.clone()))?
+    else {
+        increment_counter!(state.metrics, deduplicated_notifications);
+        warn!(
+            %tenant_id,
+            client_id = %client_id,
+            notification_id = %notification.id,
+            "notification has already been queued"
+        );
 
-    match provider.send_notification(client.token, push_message).await {
-        Ok(()) => Ok(()),
-        Err(error) => {
-            warn!("error sending notification: {error:?}");
-            match error {
-                Error::BadDeviceToken(_) => {
-                    state
-                        .client_store
-                        .delete_client(&tenant_id, &client_id)
-                        .await
-                        .map_err(|e| (Error::Store(e), analytics.clone()))?;
-                    increment_counter!(state.metrics, client_suspensions);
-                    warn!(
-                        %tenant_id,
-                        client_id = %client_id,
-                        notification_id = %notification.id,
-                        push_type = client.push_type.as_str(),
-                        "client has been deleted due to a bad device token"
-                    );
-                    Err(Error::ClientDeleted)
-                }
-                Error::BadApnsCredentials => {
-                    state
-                        .tenant_store
-                        .suspend_tenant(&tenant_id, "Invalid APNS Credentials")
-                        .await
-                        .map_err(|e| (e, analytics.clone()))?;
-                    increment_counter!(state.metrics, tenant_suspensions);
-                    warn!(
-                        %tenant_id,
-                        client_id = %client_id,
-                        notification_id = %notification.id,
-                        push_type = client.push_type.as_str(),
-                        "tenant has been suspended due to invalid provider credentials"
-                    );
-                    Err(Error::TenantSuspended)
-                }
-                Error::ApnsCertificateExpired => {
-                    let reason = "APNs certificate expired";
-                    state
-                        .tenant_store
-                        .suspend_tenant(&tenant_id, reason)
-                        .await
-                        .map_err(|e| (e, analytics.clone()))?;
-                    increment_counter!(state.metrics, tenant_suspensions);
-                    warn!(
-                        %tenant_id,
-                        client_id = %client_id,
-                        notification_id = %notification.id,
-                        push_type = client.push_type.as_str(),
-                        "tenant has been suspended due to: {reason}"
-                    );
-                    Err(Error::TenantSuspended)
-                }
-                Error::ApnsCertificateUnknownCA => {
-                    let reason = "Unknown APNs certificate's CA";
-                    state
-                        .tenant_store
-                        .suspend_tenant(&tenant_id, reason)
-                        .await
-                        .map_err(|e| (e, analytics.clone()))?;
-                    increment_counter!(state.metrics, tenant_suspensions);
-                    warn!(
-                        %tenant_id,
-                        client_id = %client_id,
-                        notification_id = %notification.id,
-                        push_type = client.push_type.as_str(),
-                        "tenant has been suspended due to: {reason}"
-                    );
-                    Err(Error::TenantSuspended)
-                }
-                Error::ApnsInvalidProviderToken => {
-                    let reason = "APNs certificate invalid provider token";
-                    state
-                        .tenant_store
-                        .suspend_tenant(&tenant_id, reason)
-                        .await
-                        .map_err(|e| (e, analytics.clone()))?;
-                    increment_counter!(state.metrics, tenant_suspensions);
-                    warn!(
-                        %tenant_id,
-                        client_id = %client_id,
-                        notification_id = %notification.id,
-                        push_type = client.push_type.as_str(),
-                        "tenant has been suspended due to: {reason}"
-                    );
-                    Err(Error::TenantSuspended)
-                }
-                Error::BadFcmApiKey => {
-                    state
-                        .tenant_store
-                        .suspend_tenant(&tenant_id, "Invalid FCM Credentials")
-                        .await
-                        .map_err(|e| (e, analytics.clone()))?;
-                    increment_counter!(state.metrics, tenant_suspensions);
-                    warn!(
-                        %tenant_id,
-                        client_id = %client_id,
-                        notification_id = %notification.id,
-                        push_type = client.push_type.as_str(),
-                        "tenant has been suspended due to invalid provider credentials"
-                    );
-                    Err(Error::TenantSuspended)
-                }
-                e => Err(e),
-            }
+        #[cfg(feature = "analytics")]
+        {
+            analytics = Some(MessageInfo {
+                response_message: Some("Notification has already been queued".into()),
+                ..analytics.unwrap()
+            });
+
+            return Ok((
+                PushReceipt::new(PushOutcome::AlreadyProcessed, client.push_type.as_str()),
+                analytics,
+            ));
         }
-    }
-    .map_err(|e| (e, analytics.clone()))?;
 
+        #[cfg(not(feature = "analytics"))]
+        return Ok((
+            PushReceipt::new(PushOutcome::AlreadyProcessed, client.push_type.as_str()),
+            None,
+        ));
+    };
     debug!(
         %tenant_id,
         client_id = %client_id,
         notification_id = %notification.id,
-        push_type = client.push_type.as_str(),
-        "sent notification"
+        "queued notification"
     );
 
-    // Provider specific metrics
-    match provider {
-        Provider::Fcm(_) => increment_counter!(state.metrics, sent_fcm_notifications),
-        Provider::FcmV1(_) => increment_counter!(state.metrics, sent_fcm_v1_notifications),
-        Provider::Apns(_) => increment_counter!(state.metrics, sent_apns_notifications),
-        #[cfg(any(debug_assertions, test))]
-        Provider::Noop(_) => {}
-    }
+    // The notification is queued before the first attempt, which is made here
+    // rather than by the worker so the receipt can report the provider's
+    // outcome. Should this instance go away mid-request the lease expires and
+    // a worker sends it
+    let receipt = match delivery::deliver(&state, &delivery, &tenant, &client, push_message).await {
+        DeliveryResult::Delivered {
+            provider,
+            provider_message_id,
+        } => PushReceipt {
+            provider_message_id,
+            ..PushReceipt::new(PushOutcome::Delivered, provider)
+        },
+        DeliveryResult::Retrying {
+            next_attempt_at,
+            error,
+        } => {
+            warn!(
+                %tenant_id,
+                client_id = %client_id,
+                notification_id = %notification.id,
+                push_type = client.push_type.as_str(),
+                %next_attempt_at,
+                "error sending notification, retry scheduled: {error:?}"
+            );
+
+            #[cfg(feature = "analytics")]
+            {
+                analytics = Some(MessageInfo {
+                    response_message: Some(format!("Queued for retry: {error}").into()),
+                    ..analytics.unwrap()
+                });
+
+                return Ok((
+                    PushReceipt::new(PushOutcome::Queued, client.push_type.as_str()),
+                    analytics,
+                ));
+            }
+
+            #[cfg(not(feature = "analytics"))]
+            return Ok((
+                PushReceipt::new(PushOutcome::Queued, client.push_type.as_str()),
+                None,
+            ));
+        }
+        DeliveryResult::Failed(Error::ClientDeleted) => {
+            PushReceipt::new(PushOutcome::ClientDeleted, client.push_type.as_str())
+        }
+        DeliveryResult::Failed(error) => return Err((error, analytics.clone())),
+    };
 
     #[cfg(feature = "analytics")]
     {
+        let response_message = match receipt.outcome {
+            PushOutcome::ClientDeleted => "Client deleted due to invalid token",
+            _ => "Delivered",
+        };
         analytics = Some(MessageInfo {
-            response_message: Some("Delivered".into()),
+            response_message: Some(response_message.into()),
             ..analytics.unwrap()
         });
 
-        return Ok(((StatusCode::ACCEPTED).into_response(), analytics));
+        return Ok((receipt, analytics));
     }
 
     #[cfg(not(feature = "analytics"))]
-    Ok(((StatusCode::ACCEPTED).into_response(), None))
+    Ok((receipt, None))
+}
+
+/// Releases the claim on a notification that wasn't stored, so the sender can
+/// retry it
+async fn release_notification(state: &AppState, id: &str, tenant_id: &str, client_id: &str) {
+    if let Err(e) = state
+        .notification_store
+        .release_notification(id, tenant_id, client_id)
+        .await
+    {
+        warn!("error releasing notification: {e:?}");
+    }
 }
@@ -0,0 +1,136 @@
+#[cfg(feature = "analytics")]
+use axum_client_ip::SecureClientIp;
+use {
+    crate::{
+        error::Result,
+        handlers::{
+            push_batch::{push_to_client, BATCH_CONCURRENCY},
+            push_message::PushMessageBody,
+        },
+        log::prelude::*,
+        middleware::validate_signature::RequireValidSignature,
+        state::AppState,
+        stores::{tenant::Tenant, webhook::WebhookEvent},
+        webhooks,
+    },
+    axum::{
+        extract::{Json, Path, State as StateExtractor},
+        http::StatusCode,
+    },
+    futures_util::StreamExt,
+    serde::{Deserialize, Serialize},
+    std::{collections::HashMap, sync::Arc},
+    tracing::instrument,
+    uuid::Uuid,
+};
+
+/// Number of subscribers loaded at a time while fanning out
+const SUBSCRIBER_PAGE_SIZE: i64 = 500;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TopicPushResponse {
+    /// Identifies the fan-out in its `topic_push_completed` webhook event
+    pub job_id: String,
+}
+
+/// Accepts the push and fans it out to the topic's subscribers in the
+/// background, as a large topic can take longer than the request may
+#[instrument(skip_all, name = "push_topic_handler")]
+pub async fn handler(
+    #[cfg(feature = "analytics")] SecureClientIp(client_ip): SecureClientIp,
+    Path((tenant_id, topic)): Path<(String, String)>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    RequireValidSignature(Json(body)): RequireValidSignature<Json<PushMessageBody>>,
+) -> Result<(StatusCode, Json<TopicPushResponse>)> {
+    body.validate()?;
+    let tenant = state.tenant_store.get_tenant(&tenant_id).await?;
+
+    let job_id = Uuid::new_v4().to_string();
+    tokio::spawn(fan_out(
+        #[cfg(feature = "analytics")]
+        client_ip,
+        state,
+        tenant,
+        topic,
+        body,
+        job_id.clone(),
+    ));
+
+    Ok((StatusCode::ACCEPTED, Json(TopicPushResponse { job_id })))
+}
+
+/// Pushes to the subscribers a page at a time, then reports the outcomes to
+/// the tenant's webhook
+#[instrument(skip_all, fields(tenant_id = %tenant.id, %topic, %job_id))]
+async fn fan_out(
+    #[cfg(feature = "analytics")] client_ip: std::net::IpAddr,
+    state: Arc<AppState>,
+    tenant: Tenant,
+    topic: String,
+    body: PushMessageBody,
+    job_id: String,
+) {
+    let mut clients = 0;
+    let mut outcomes = HashMap::new();
+    let mut after_id = None;
+    loop {
+        let client_ids = match state
+            .client_store
+            .get_topic_subscribers(
+                &tenant.id,
+                &topic,
+                after_id.as_deref(),
+                SUBSCRIBER_PAGE_SIZE,
+            )
+            .await
+        {
+            Ok(client_ids) => client_ids,
+            Err(e) => {
+                // Pushing again with the same message id resumes the fan-out,
+                // the clients already pushed to are skipped as duplicates
+                warn!("error fetching topic subscribers, abandoning the fan-out: {e:?}");
+                return;
+            }
+        };
+        let is_last_page = client_ids.len() < SUBSCRIBER_PAGE_SIZE as usize;
+        after_id = client_ids.last().cloned();
+
+        debug!(size = client_ids.len(), "pushing to topic subscribers");
+
+        let results = futures_util::stream::iter(client_ids)
+            .map(|client_id| {
+                push_to_client(
+                    #[cfg(feature = "analytics")]
+                    client_ip,
+                    state.clone(),
+                    tenant.id.clone(),
+                    client_id,
+                    body.clone(),
+                )
+            })
+            .buffer_unordered(BATCH_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        for result in results {
+            clients += 1;
+            *outcomes.entry(result.outcome).or_default() += 1;
+        }
+
+        if is_last_page {
+            break;
+        }
+    }
+
+    info!(clients, "pushed to topic subscribers");
+    webhooks::emit(
+        &state,
+        &tenant,
+        WebhookEvent::TopicPushCompleted {
+            job_id,
+            topic,
+            clients,
+            outcomes,
+        },
+    )
+    .await;
+}
@@ -3,14 +3,15 @@ use {crate::analytics::client_info::ClientInfo, axum_client_ip::SecureClientIp};
 use {
     crate::{
         error::{
-            Error::{EmptyField, InvalidAuthentication, ProviderNotAvailable},
+            Error::{EmptyField, FieldTooLong, InvalidAuthentication, ProviderNotAvailable},
             Result,
         },
         handlers::{authenticate_client, Response, DECENTRALIZED_IDENTIFIER_PREFIX},
         increment_counter,
         log::prelude::*,
+        providers::{web_push::WebPushSubscription, ProviderKind},
         state::AppState,
-        stores::client::Client,
+        stores::client::{Client, ClientMetadata},
     },
     axum::{
         extract::{Json, Path, State as StateExtractor},
@@ -29,6 +30,8 @@ pub struct RegisterBody {
     pub push_type: String,
     pub token: String,
     pub always_raw: Option<bool>,
+    #[serde(flatten)]
+    pub metadata: ClientMetadata,
 }
 
 #[instrument(skip_all, name = "register_client_handler")]
@@ -78,6 +81,17 @@ pub async fn handler(
         return Err(EmptyField("token".to_string()));
     }
 
+    // Web Push tokens are the browser's JSON encoded subscription
+    if push_type == ProviderKind::WebPush {
+        WebPushSubscription::from_token(&body.token)?;
+    }
+
+    for (field, value) in body.metadata.fields() {
+        if value.is_some_and(|value| value.chars().count() > ClientMetadata::MAX_LENGTH) {
+            return Err(FieldTooLong(field.to_string(), ClientMetadata::MAX_LENGTH));
+        }
+    }
+
     let client_id = body
         .client_id
         .as_ref()
@@ -85,6 +99,8 @@ pub async fn handler(
         .to_owned();
 
     let always_raw = body.always_raw.unwrap_or(false);
+    #[cfg(feature = "analytics")]
+    let metadata = body.metadata.clone();
     state
         .client_store
         .create_client(
@@ -95,6 +111,7 @@ pub async fn handler(
                 push_type,
                 token: body.token,
                 always_raw,
+                metadata: body.metadata,
             },
             state.metrics.as_ref(),
         )
@@ -131,6 +148,14 @@ pub async fn handler(
                 client_id: client_id.into(),
                 push_provider: body.push_type.as_str().into(),
                 always_raw,
+                platform: metadata.platform.map(Into::into),
+                os_version: metadata.os_version.map(Into::into),
+                app_bundle_id: metadata.app_bundle_id.map(Into::into),
+                app_version: metadata.app_version.map(Into::into),
+                sdk_name: metadata.sdk_name.map(Into::into),
+                sdk_version: metadata.sdk_version.map(Into::into),
+                locale: metadata.locale.map(Into::into),
+                timezone: metadata.timezone.map(Into::into),
                 registered_at: wc::analytics::time::now(),
             };
 
@@ -3,13 +3,24 @@ use axum_client_ip::SecureClientIp;
 use {
     crate::{
         error::Result,
-        handlers::{push_message::PushMessageBody, register_client::RegisterBody, Response},
+        handlers::{
+            cancel_notification::CancelNotificationResponse,
+            get_client_notifications::{ClientNotificationsQuery, ClientNotificationsResponse},
+            get_notification::NotificationStatus,
+            push_batch::{BatchPushBody, BatchPushResponse},
+            push_message::PushMessageBody,
+            push_topic::TopicPushResponse,
+            register_client::RegisterBody,
+            Response,
+        },
         middleware::validate_signature::RequireValidSignature,
         state::AppState,
         stores::tenant::DEFAULT_TENANT_ID,
     },
     axum::{
-        extract::{Path, State as StateExtractor},
+        body::Bytes,
+        extract::{Path, Query, State as StateExtractor},
+        http::StatusCode,
         Json,
     },
     hyper::HeaderMap,
@@ -19,6 +30,23 @@ use {
 #[cfg(feature = "multitenant")]
 use crate::error::Error::MissingTenantId;
 
+pub async fn cancel_notification_handler(
+    Path(id): Path<String>,
+    state: StateExtractor<Arc<AppState>>,
+    valid_sig: RequireValidSignature<Bytes>,
+) -> Result<Json<CancelNotificationResponse>> {
+    #[cfg(feature = "multitenant")]
+    return Err(MissingTenantId);
+
+    #[cfg(not(feature = "multitenant"))]
+    crate::handlers::cancel_notification::handler(
+        Path((DEFAULT_TENANT_ID.to_string(), id)),
+        state,
+        valid_sig,
+    )
+    .await
+}
+
 pub async fn delete_handler(
     Path(id): Path<String>,
     state: StateExtractor<Arc<AppState>>,
@@ -64,6 +92,93 @@ pub async fn push_handler(
     .await;
 }
 
+pub async fn push_batch_handler(
+    #[cfg(feature = "analytics")] SecureClientIp(client_ip): SecureClientIp,
+    state: StateExtractor<Arc<AppState>>,
+    valid_sig: RequireValidSignature<Json<BatchPushBody>>,
+) -> Result<Json<BatchPushResponse>> {
+    #[cfg(feature = "multitenant")]
+    return Err(MissingTenantId);
+
+    #[cfg(all(not(feature = "multitenant"), feature = "analytics"))]
+    return crate::handlers::push_batch::handler(
+        SecureClientIp(client_ip),
+        Path(DEFAULT_TENANT_ID.to_string()),
+        state,
+        valid_sig,
+    )
+    .await;
+
+    #[cfg(all(not(feature = "multitenant"), not(feature = "analytics")))]
+    return crate::handlers::push_batch::handler(
+        Path(DEFAULT_TENANT_ID.to_string()),
+        state,
+        valid_sig,
+    )
+    .await;
+}
+
+pub async fn push_topic_handler(
+    #[cfg(feature = "analytics")] SecureClientIp(client_ip): SecureClientIp,
+    Path(topic): Path<String>,
+    state: StateExtractor<Arc<AppState>>,
+    valid_sig: RequireValidSignature<Json<PushMessageBody>>,
+) -> Result<(StatusCode, Json<TopicPushResponse>)> {
+    #[cfg(feature = "multitenant")]
+    return Err(MissingTenantId);
+
+    #[cfg(all(not(feature = "multitenant"), feature = "analytics"))]
+    return crate::handlers::push_topic::handler(
+        SecureClientIp(client_ip),
+        Path((DEFAULT_TENANT_ID.to_string(), topic)),
+        state,
+        valid_sig,
+    )
+    .await;
+
+    #[cfg(all(not(feature = "multitenant"), not(feature = "analytics")))]
+    return crate::handlers::push_topic::handler(
+        Path((DEFAULT_TENANT_ID.to_string(), topic)),
+        state,
+        valid_sig,
+    )
+    .await;
+}
+
+pub async fn subscribe_topic_handler(
+    Path((id, topic)): Path<(String, String)>,
+    state: StateExtractor<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    #[cfg(feature = "multitenant")]
+    return Err(MissingTenantId);
+
+    #[cfg(not(feature = "multitenant"))]
+    crate::handlers::subscribe_topic::handler(
+        Path((DEFAULT_TENANT_ID.to_string(), id, topic)),
+        state,
+        headers,
+    )
+    .await
+}
+
+pub async fn unsubscribe_topic_handler(
+    Path((id, topic)): Path<(String, String)>,
+    state: StateExtractor<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    #[cfg(feature = "multitenant")]
+    return Err(MissingTenantId);
+
+    #[cfg(not(feature = "multitenant"))]
+    crate::handlers::unsubscribe_topic::handler(
+        Path((DEFAULT_TENANT_ID.to_string(), id, topic)),
+        state,
+        headers,
+    )
+    .await
+}
+
 pub async fn register_handler(
     #[cfg(feature = "analytics")] SecureClientIp(client_ip): SecureClientIp,
     state: StateExtractor<Arc<AppState>>,
@@ -92,3 +207,39 @@ pub async fn register_handler(
     )
     .await;
 }
+
+pub async fn notification_handler(
+    Path((id, notification_id)): Path<(String, String)>,
+    state: StateExtractor<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<NotificationStatus>> {
+    #[cfg(feature = "multitenant")]
+    return Err(MissingTenantId);
+
+    #[cfg(not(feature = "multitenant"))]
+    crate::handlers::get_notification::handler(
+        Path((DEFAULT_TENANT_ID.to_string(), id, notification_id)),
+        state,
+        headers,
+    )
+    .await
+}
+
+pub async fn client_notifications_handler(
+    Path(id): Path<String>,
+    state: StateExtractor<Arc<AppState>>,
+    query: Query<ClientNotificationsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<ClientNotificationsResponse>> {
+    #[cfg(feature = "multitenant")]
+    return Err(MissingTenantId);
+
+    #[cfg(not(feature = "multitenant"))]
+    crate::handlers::get_client_notifications::handler(
+        Path((DEFAULT_TENANT_ID.to_string(), id)),
+        state,
+        query,
+        headers,
+    )
+    .await
+}
@@ -0,0 +1,52 @@
+use {
+    crate::{
+        error::{
+            Error::{EmptyField, FieldTooLong},
+            Result,
+        },
+        handlers::{require_client, Response, DECENTRALIZED_IDENTIFIER_PREFIX},
+        log::prelude::*,
+        state::AppState,
+    },
+    axum::{
+        extract::{Path, State as StateExtractor},
+        http::HeaderMap,
+    },
+    relay_rpc::domain::ClientId,
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+pub const MAX_TOPIC_LENGTH: usize = 255;
+
+#[instrument(skip_all, name = "subscribe_topic_handler")]
+pub async fn handler(
+    Path((tenant_id, id, topic)): Path<(String, String, String)>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let id = id
+        .trim_start_matches(DECENTRALIZED_IDENTIFIER_PREFIX)
+        .to_string();
+    require_client(
+        headers,
+        &state.config.public_url,
+        &ClientId::new(id.clone().into()),
+    )?;
+
+    if topic.is_empty() {
+        return Err(EmptyField("topic".to_string()));
+    }
+    if topic.len() > MAX_TOPIC_LENGTH {
+        return Err(FieldTooLong("topic".to_string(), MAX_TOPIC_LENGTH));
+    }
+
+    state
+        .client_store
+        .subscribe_client(&tenant_id, &id, &topic)
+        .await?;
+
+    debug!(%tenant_id, client_id = %id, %topic, "subscribed client to topic");
+
+    Ok(Response::default())
+}
@@ -0,0 +1,40 @@
+use {
+    crate::{
+        error::Result,
+        handlers::{require_client, Response, DECENTRALIZED_IDENTIFIER_PREFIX},
+        log::prelude::*,
+        state::AppState,
+    },
+    axum::{
+        extract::{Path, State as StateExtractor},
+        http::HeaderMap,
+    },
+    relay_rpc::domain::ClientId,
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+#[instrument(skip_all, name = "unsubscribe_topic_handler")]
+pub async fn handler(
+    Path((tenant_id, id, topic)): Path<(String, String, String)>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let id = id
+        .trim_start_matches(DECENTRALIZED_IDENTIFIER_PREFIX)
+        .to_string();
+    require_client(
+        headers,
+        &state.config.public_url,
+        &ClientId::new(id.clone().into()),
+    )?;
+
+    state
+        .client_store
+        .unsubscribe_client(&tenant_id, &id, &topic)
+        .await?;
+
+    debug!(%tenant_id, client_id = %id, %topic, "unsubscribed client from topic");
+
+    Ok(Response::default())
+}
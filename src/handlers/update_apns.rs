@@ -3,6 +3,7 @@ use {
         error::{Error, Error::InvalidMultipartBody},
         handlers::validate_tenant_request,
         increment_counter,
+        providers::validation::{apns_certificate_expiry, validate_apns, ValidationEndpoints},
         state::AppState,
         stores::tenant::{TenantApnsUpdateAuth, TenantApnsUpdateParams},
     },
@@ -142,7 +143,7 @@ pub async fn handler(
     }
 
     // Ensure tenant real
-    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+    let existing_tenant = state.tenant_store.get_tenant(&id).await?;
 
     // ---- retrieve body from form
     let mut body = ApnsUpdateBody {
@@ -191,6 +192,21 @@ pub async fn handler(
 
     let apns_updates = body.validate()?;
 
+    // ---- Dry-run the resulting configuration before anything is written
+    if state.config.validate_credentials {
+        let topic = apns_updates
+            .topic
+            .clone()
+            .or_else(|| existing_tenant.apns_topic.clone());
+        let auth = apns_updates
+            .auth
+            .clone()
+            .or_else(|| existing_tenant.apns_auth().map(|(_, auth)| auth));
+        if let (Some(topic), Some(auth)) = (topic, auth) {
+            validate_apns(&ValidationEndpoints::from(&state.config), &topic, &auth).await?;
+        }
+    }
+
     if let Some(topic) = apns_updates.topic {
         // Just update topic
         let update_body = TenantApnsUpdateParams { apns_topic: topic };
@@ -255,19 +271,31 @@ pub async fn handler(
 
     // ---- handler
     if let Some(auth) = apns_updates.auth {
-        let new_tenant = state
+        let expires_at = match &auth {
+            TenantApnsUpdateAuth::Certificate {
+                apns_certificate,
+                apns_certificate_password,
+            } => Some(apns_certificate_expiry(
+                apns_certificate,
+                apns_certificate_password,
+            )?),
+            TenantApnsUpdateAuth::Token { .. } => None,
+        };
+
+        let _new_tenant = state
             .tenant_store
             .update_tenant_apns_auth(&id, auth)
             .await?;
 
-        increment_counter!(state.metrics, tenant_apns_updates);
-
-        if new_tenant.suspended {
-            // If suspended, it can be restored now because valid credentials have been
-            // provided
-            state.tenant_store.unsuspend_tenant(&new_tenant.id).await?;
+        if expires_at.is_some() {
+            state
+                .tenant_store
+                .update_tenant_apns_certificate_expiry(&id, expires_at)
+                .await?;
         }
 
+        increment_counter!(state.metrics, tenant_apns_updates);
+
         return Ok(Json(UpdateTenantApnsResponse { success: true }));
     }
 
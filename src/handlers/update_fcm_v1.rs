@@ -3,6 +3,7 @@ use {
         error::{Error, Error::InvalidMultipartBody},
         handlers::validate_tenant_request,
         increment_counter,
+        providers::validation::{validate_fcm_v1, ValidationEndpoints},
         state::AppState,
         stores::tenant::TenantFcmV1UpdateParams,
     },
@@ -82,22 +83,20 @@ pub async fn handler(
         Error::BadFcmV1Credentials
     })?;
 
+    if state.config.validate_credentials {
+        validate_fcm_v1(&ValidationEndpoints::from(&state.config), &body.credentials).await?;
+    }
+
     // ---- handler
     let update_body = TenantFcmV1UpdateParams {
         fcm_v1_credentials: body.credentials,
     };
 
-    let new_tenant = state
+    let _new_tenant = state
         .tenant_store
         .update_tenant_fcm_v1(&id, update_body)
         .await?;
 
-    if new_tenant.suspended {
-        // If suspended, it can be restored now because valid credentials have been
-        // provided
-        state.tenant_store.unsuspend_tenant(&new_tenant.id).await?;
-    }
-
     increment_counter!(state.metrics, tenant_fcm_v1_updates);
 
     Ok(Json(UpdateTenantFcmV1Response { success: true }))
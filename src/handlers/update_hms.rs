@@ -0,0 +1,103 @@
+use {
+    crate::{
+        error::{Error, Error::InvalidMultipartBody},
+        handlers::validate_tenant_request,
+        increment_counter,
+        providers::hms::HmsProvider,
+        state::AppState,
+        stores::tenant::TenantHmsUpdateParams,
+    },
+    axum::{
+        extract::{Multipart, Path, State},
+        http::HeaderMap,
+        Json,
+    },
+    serde::Serialize,
+    std::sync::Arc,
+    tracing::{debug, error, instrument},
+};
+
+pub struct HmsUpdateBody {
+    app_id: Option<String>,
+    app_secret: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UpdateTenantHmsResponse {
+    success: bool,
+}
+
+#[instrument(skip_all, name = "update_hms_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    mut form_body: Multipart,
+) -> Result<Json<UpdateTenantHmsResponse>, Error> {
+    // JWT token verification
+    #[cfg(feature = "cloud")]
+    let jwt_verification_result =
+        validate_tenant_request(&state.jwt_validation_client, &headers, &id).await;
+
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    #[cfg(not(feature = "cloud"))]
+    let jwt_verification_result = validate_tenant_request(&state.jwt_validation_client, &headers);
+
+    if let Err(e) = jwt_verification_result {
+        error!(
+            tenant_id = %id,
+            err = ?e,
+            "JWT verification failed"
+        );
+        return Err(e);
+    }
+
+    // ---- retrieve body from form
+    let mut body = HmsUpdateBody {
+        app_id: None,
+        app_secret: None,
+    };
+    while let Some(field) = form_body.next_field().await? {
+        let name = field.name().unwrap_or("unknown").to_string();
+        let data = field.text().await?;
+
+        match name.to_lowercase().as_str() {
+            "app_id" => body.app_id = Some(data),
+            "app_secret" => body.app_secret = Some(data),
+            _ => {}
+        };
+    }
+    let (Some(app_id), Some(app_secret)) = (body.app_id, body.app_secret) else {
+        return Err(InvalidMultipartBody);
+    };
+
+    // Fetching an access token validates the credentials
+    HmsProvider::new(
+        app_id.clone(),
+        app_secret.clone(),
+        state.http_client.clone(),
+    )
+    .access_token()
+    .await
+    .map_err(|e| {
+        debug!("Failed credential validation: {e}");
+        Error::BadHmsCredentials
+    })?;
+
+    // ---- handler
+    let update_body = TenantHmsUpdateParams {
+        hms_app_id: app_id,
+        hms_app_secret: app_secret,
+    };
+
+    let _new_tenant = state
+        .tenant_store
+        .update_tenant_hms(&id, update_body)
+        .await?;
+
+    increment_counter!(state.metrics, tenant_hms_updates);
+
+    Ok(Json(UpdateTenantHmsResponse { success: true }))
+}
@@ -0,0 +1,83 @@
+use {
+    crate::{
+        error::{Error, Result},
+        handlers::validate_tenant_request,
+        log::prelude::*,
+        state::AppState,
+        stores::tenant::{
+            LocalizedTemplate, NotificationTemplate, TemplateMode, TenantTemplateUpdateParams,
+        },
+    },
+    axum::{
+        extract::{Path, State},
+        http::HeaderMap,
+        Json,
+    },
+    serde::Deserialize,
+    std::{collections::BTreeMap, sync::Arc},
+    tracing::instrument,
+};
+
+const MAX_NAME_LENGTH: usize = 255;
+
+#[derive(Deserialize)]
+pub struct TemplateUpdateBody {
+    #[serde(default)]
+    pub mode: TemplateMode,
+    pub default_locale: String,
+    /// Title and body keyed by locale
+    pub localizations: BTreeMap<String, LocalizedTemplate>,
+}
+
+#[instrument(skip_all, name = "update_template_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path((id, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    Json(body): Json<TemplateUpdateBody>,
+) -> Result<Json<NotificationTemplate>> {
+    #[cfg(feature = "cloud")]
+    let jwt_verification_result =
+        validate_tenant_request(&state.jwt_validation_client, &headers, &id).await;
+
+    #[cfg(not(feature = "cloud"))]
+    let jwt_verification_result = validate_tenant_request(&state.jwt_validation_client, &headers);
+
+    if let Err(e) = jwt_verification_result {
+        error!(
+            tenant_id = %id,
+            err = ?e,
+            "JWT verification failed"
+        );
+        return Err(e);
+    }
+
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(Error::InvalidTemplate(format!(
+            "the name must be 1 to {MAX_NAME_LENGTH} characters"
+        )));
+    }
+    if !body.localizations.contains_key(&body.default_locale) {
+        return Err(Error::InvalidTemplate(
+            "the default locale must be one of the localizations".to_string(),
+        ));
+    }
+
+    let template = state
+        .tenant_store
+        .update_tenant_template(
+            &id,
+            &name,
+            TenantTemplateUpdateParams {
+                mode: body.mode,
+                default_locale: body.default_locale,
+                localizations: body.localizations,
+            },
+        )
+        .await?;
+
+    Ok(Json(template))
+}
@@ -0,0 +1,107 @@
+use {
+    crate::{
+        error::{Error, Error::InvalidMultipartBody},
+        handlers::validate_tenant_request,
+        increment_counter,
+        providers::web_push::vapid_keys,
+        state::AppState,
+        stores::tenant::TenantWebPushUpdateParams,
+    },
+    axum::{
+        extract::{Multipart, Path, State},
+        http::HeaderMap,
+        Json,
+    },
+    base64::Engine as _,
+    serde::Serialize,
+    std::sync::Arc,
+    tracing::{error, instrument},
+};
+
+pub struct WebPushUpdateBody {
+    vapid_key: Option<String>,
+    subject: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UpdateTenantWebPushResponse {
+    success: bool,
+    /// The `applicationServerKey` browsers have to subscribe with
+    vapid_public_key: String,
+}
+
+#[instrument(skip_all, name = "update_web_push_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    mut form_body: Multipart,
+) -> Result<Json<UpdateTenantWebPushResponse>, Error> {
+    // JWT token verification
+    #[cfg(feature = "cloud")]
+    let jwt_verification_result =
+        validate_tenant_request(&state.jwt_validation_client, &headers, &id).await;
+
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    #[cfg(not(feature = "cloud"))]
+    let jwt_verification_result = validate_tenant_request(&state.jwt_validation_client, &headers);
+
+    if let Err(e) = jwt_verification_result {
+        error!(
+            tenant_id = %id,
+            err = ?e,
+            "JWT verification failed"
+        );
+        return Err(e);
+    }
+
+    // ---- retrieve body from form
+    let mut body = WebPushUpdateBody {
+        vapid_key: None,
+        subject: None,
+    };
+    while let Some(field) = form_body.next_field().await? {
+        let name = field.name().unwrap_or("unknown").to_string();
+        let data = field.text().await?;
+
+        match name.to_lowercase().as_str() {
+            "vapid_key" => body.vapid_key = Some(data),
+            "subject" => body.subject = Some(data),
+            _ => {}
+        };
+    }
+    let (Some(vapid_key), Some(subject)) = (body.vapid_key, body.subject) else {
+        return Err(InvalidMultipartBody);
+    };
+
+    // Push services use the subject to contact the sender, RFC 8292 requires
+    // it to be a mailto: or https: URL
+    if !(subject.starts_with("mailto:") || subject.starts_with("https://")) {
+        return Err(Error::InvalidWebPushSubject);
+    }
+
+    let pem = base64::engine::general_purpose::STANDARD
+        .decode(&vapid_key)
+        .map_err(|_| Error::BadWebPushCredentials)?;
+    let (_, vapid_public_key) = vapid_keys(&pem)?;
+
+    // ---- handler
+    let update_body = TenantWebPushUpdateParams {
+        web_push_vapid_key: vapid_key,
+        web_push_vapid_subject: subject,
+    };
+
+    let _new_tenant = state
+        .tenant_store
+        .update_tenant_web_push(&id, update_body)
+        .await?;
+
+    increment_counter!(state.metrics, tenant_web_push_updates);
+
+    Ok(Json(UpdateTenantWebPushResponse {
+        success: true,
+        vapid_public_key,
+    }))
+}
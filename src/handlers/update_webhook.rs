@@ -0,0 +1,77 @@
+use {
+    crate::{
+        error::Result, handlers::validate_tenant_request, log::prelude::*, state::AppState,
+        stores::tenant::TenantWebhookUpdateParams, webhooks,
+    },
+    axum::{
+        extract::{Path, State},
+        http::HeaderMap,
+        Json,
+    },
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+#[derive(Deserialize)]
+pub struct WebhookUpdateBody {
+    pub url: String,
+    /// Generated when not given
+    pub secret: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateTenantWebhookResponse {
+    pub url: String,
+    /// Used to verify the `X-Echo-Signature` header of events
+    pub secret: String,
+}
+
+#[instrument(skip_all, name = "update_webhook_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(body): Json<WebhookUpdateBody>,
+) -> Result<Json<UpdateTenantWebhookResponse>> {
+    #[cfg(feature = "cloud")]
+    let jwt_verification_result =
+        validate_tenant_request(&state.jwt_validation_client, &headers, &id).await;
+
+    #[cfg(not(feature = "cloud"))]
+    let jwt_verification_result = validate_tenant_request(&state.jwt_validation_client, &headers);
+
+    if let Err(e) = jwt_verification_result {
+        error!(
+            tenant_id = %id,
+            err = ?e,
+            "JWT verification failed"
+        );
+        return Err(e);
+    }
+
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    let url = webhooks::validate_url(&body.url)?;
+    let secret = match body.secret {
+        Some(secret) if !secret.is_empty() => secret,
+        _ => hex::encode(rand::random::<[u8; 32]>()),
+    };
+
+    let tenant = state
+        .tenant_store
+        .update_tenant_webhook(
+            &id,
+            TenantWebhookUpdateParams {
+                webhook_url: url.to_string(),
+                webhook_secret: secret,
+            },
+        )
+        .await?;
+
+    Ok(Json(UpdateTenantWebhookResponse {
+        url: tenant.webhook_url.unwrap_or_default(),
+        secret: tenant.webhook_secret.unwrap_or_default(),
+    }))
+}
@@ -0,0 +1,115 @@
+use {
+    crate::{
+        error::{Error, Result},
+        handlers::validate_tenant_request,
+        log::prelude::*,
+        providers::{
+            hms::HmsProvider,
+            validation::{validate_apns, validate_fcm_v1, ValidationEndpoints},
+            web_push::vapid_keys,
+            PROVIDER_APNS, PROVIDER_FCM_V1, PROVIDER_HMS, PROVIDER_WEB_PUSH,
+        },
+        state::AppState,
+    },
+    axum::{
+        extract::{Path, State},
+        http::HeaderMap,
+        Json,
+    },
+    base64::Engine,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+    tracing::instrument,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProviderValidation {
+    pub provider: String,
+    pub valid: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ValidateTenantResponse {
+    pub valid: bool,
+    pub providers: Vec<ProviderValidation>,
+}
+
+impl ProviderValidation {
+    fn new(provider: &str, result: Result<()>) -> Self {
+        ProviderValidation {
+            provider: provider.to_string(),
+            valid: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Dry-runs the stored credentials of every configured provider
+#[instrument(skip_all, name = "validate_tenant_handler")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<ValidateTenantResponse>> {
+    #[cfg(feature = "cloud")]
+    let verification_res =
+        validate_tenant_request(&state.jwt_validation_client, &headers, &id).await;
+
+    #[cfg(not(feature = "cloud"))]
+    let verification_res = validate_tenant_request(&state.jwt_validation_client, &headers);
+
+    if let Err(e) = verification_res {
+        error!(
+            tenant_id = %id,
+            err = ?e,
+            "JWT verification failed"
+        );
+        return Err(e);
+    }
+
+    let tenant = state.tenant_store.get_tenant(&id).await?;
+    let endpoints = ValidationEndpoints::from(&state.config);
+
+    let mut providers = vec![];
+    if let Some((topic, auth)) = tenant.apns_auth() {
+        let result = validate_apns(&endpoints, &topic, &auth).await;
+        providers.push(ProviderValidation::new(PROVIDER_APNS, result));
+    }
+
+    if let Some(credentials) = &tenant.fcm_v1_credentials {
+        let result = validate_fcm_v1(&endpoints, credentials).await;
+        providers.push(ProviderValidation::new(PROVIDER_FCM_V1, result));
+    }
+
+    if let (Some(app_id), Some(app_secret)) = (&tenant.hms_app_id, &tenant.hms_app_secret) {
+        let result = HmsProvider::new(
+            app_id.clone(),
+            app_secret.clone(),
+            state.http_client.clone(),
+        )
+        .access_token()
+        .await
+        .map(|_| ());
+        providers.push(ProviderValidation::new(PROVIDER_HMS, result));
+    }
+
+    if let Some(vapid_key) = &tenant.web_push_vapid_key {
+        let result = base64::engine::general_purpose::STANDARD
+            .decode(vapid_key)
+            .map_err(Error::from)
+            .and_then(|pem| vapid_keys(&pem))
+            .map(|_| ());
+        providers.push(ProviderValidation::new(PROVIDER_WEB_PUSH, result));
+    }
+
+    debug!(
+        tenant_id = %id,
+        "validated tenant credentials"
+    );
+
+    Ok(Json(ValidateTenantResponse {
+        valid: providers.iter().all(|provider| provider.valid),
+        providers,
+    }))
+}
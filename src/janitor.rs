@@ -0,0 +1,116 @@
+use {
+    crate::{error::Result, log::prelude::*, state::AppState},
+    chrono::{Days, Utc},
+    std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+};
+
+/// Daily notification partitions are created this many days ahead so new
+/// notifications never fall into the default partition
+const PARTITIONS_AHEAD_DAYS: u64 = 7;
+
+/// Pause between delete batches so other queries aren't starved of locks
+const PRUNE_BATCH_PAUSE: Duration = Duration::from_millis(100);
+
+/// Creates upcoming partitions, then drops and deletes expired notifications
+pub async fn prune(state: &AppState) -> Result<()> {
+    let start = Instant::now();
+    let today = Utc::now().date_naive();
+
+    for days in 0..=PARTITIONS_AHEAD_DAYS {
+        let day = today + Days::new(days);
+        // Fails if the default partition holds notifications for that day,
+        // they are caught by the default partition until they expire
+        if let Err(e) = state
+            .notification_store
+            .create_notification_partition(day)
+            .await
+        {
+            debug!(%day, "error creating notification partition: {e:?}");
+        }
+    }
+
+    if state.config.notification_retention_days == 0 {
+        return Ok(());
+    }
+    let before =
+        Utc::now() - chrono::Duration::days(state.config.notification_retention_days.into());
+
+    let partitions = state
+        .notification_store
+        .drop_notification_partitions(before)
+        .await?;
+
+    // Expired notifications left in the default and current partitions
+    let batch_size = state.config.notification_prune_batch_size as i64;
+    let mut rows = 0;
+    loop {
+        let deleted = state
+            .notification_store
+            .prune_notifications(before, batch_size)
+            .await?;
+        rows += deleted;
+        if deleted < batch_size as u64 {
+            break;
+        }
+        tokio::time::sleep(PRUNE_BATCH_PAUSE).await;
+    }
+
+    info!(
+        rows,
+        partitions,
+        elapsed_ms = start.elapsed().as_millis() as u64,
+        "pruned expired notifications"
+    );
+    if let Some(metrics) = &state.metrics {
+        metrics.notification_prune(rows, partitions, start);
+    }
+
+    Ok(())
+}
+
+/// Moves the notifications stored before the table was partitioned into their
+/// partitions, a batch per transaction. Runs before the server accepts
+/// requests as lookups only see the notifications that were moved
+pub async fn backfill(state: &AppState) -> Result<()> {
+    let start = Instant::now();
+    let batch_size = state.config.notification_prune_batch_size as i64;
+    let mut rows = 0;
+    loop {
+        let moved = state
+            .notification_store
+            .backfill_notification_partitions(batch_size)
+            .await?;
+        if moved == 0 {
+            break;
+        }
+        rows += moved;
+        tokio::time::sleep(PRUNE_BATCH_PAUSE).await;
+    }
+
+    if rows > 0 {
+        info!(
+            rows,
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "moved notifications into their partitions"
+        );
+    }
+
+    Ok(())
+}
+
+/// Prunes expired notifications on an interval
+pub async fn run(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        state.config.notification_prune_interval_secs.max(1),
+    ));
+
+    loop {
+        interval.tick().await;
+        if let Err(e) = prune(&state).await {
+            warn!("error pruning notifications: {e:?}");
+        }
+    }
+}
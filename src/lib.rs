@@ -8,7 +8,21 @@ use {
     wc::geoip::MaxMindResolver,
 };
 use {
-    crate::{log::prelude::*, state::TenantStoreArc},
+    crate::{
+        log::prelude::*,
+        state::{
+            ClientStoreArc, DeliveryStoreArc, NotificationStoreArc, TenantStoreArc, WebhookStoreArc,
+        },
+        stores::{
+            client::ClientStore,
+            delivery::DeliveryStore,
+            memory::MemoryStore,
+            notification::NotificationStore,
+            redis_notification::{NotificationDedupeBackend, RedisNotificationStore},
+            webhook::WebhookStore,
+            StoreBackend,
+        },
+    },
     axum::{
         extract::Request,
         routing::{delete, get, post},
@@ -17,9 +31,10 @@ use {
     axum_client_ip::SecureClientIpSource,
     config::Config,
     hyper::http::Method,
-    middleware::rate_limit::rate_limit_middleware,
+    middleware::rate_limit::{rate_limit_middleware, RouteGroup},
     sqlx::{
         postgres::{PgConnectOptions, PgPoolOptions},
+        sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
         ConnectOptions,
     },
     std::{future::IntoFuture, net::SocketAddr, str::FromStr, sync::Arc, time::Duration},
@@ -37,6 +52,11 @@ use {
 
 #[cfg(not(feature = "multitenant"))]
 use crate::stores::tenant::DefaultTenantStore;
+#[cfg(feature = "multitenant")]
+use crate::{
+    encryption::CredentialCipher, middleware::admin_auth::require_admin_token,
+    stores::encrypted_tenant::EncryptedTenantStore,
+};
 
 #[cfg(feature = "analytics")]
 pub mod analytics;
@@ -49,10 +69,15 @@ pub mod analytics {
     }
 }
 
+#[cfg(feature = "multitenant")]
+pub mod apns_expiry;
 pub mod blob;
 pub mod config;
+pub mod delivery;
+pub mod encryption;
 pub mod error;
 pub mod handlers;
+pub mod janitor;
 pub mod jwt_validation;
 pub mod log;
 pub mod macros;
@@ -60,9 +85,12 @@ pub mod metrics;
 pub mod middleware;
 pub mod networking;
 pub mod providers;
+pub mod push_rate_limit;
 pub mod relay;
 pub mod state;
 pub mod stores;
+pub mod templates;
+pub mod webhooks;
 
 const PG_CONNECTION_POOL_SIZE: u32 = 100;
 
@@ -70,49 +98,64 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
     // Check config is valid and then throw the error if its not
     config.is_valid()?;
 
-    let pg_options = PgConnectOptions::from_str(&config.database_url)?
-        .log_statements(LevelFilter::Trace)
-        .log_slow_statements(LevelFilter::Info, Duration::from_millis(250))
-        .clone();
+    // The memory backend shares one store between all of them
+    let memory_store = MemoryStore::new();
 
-    let store = PgPoolOptions::new()
-        .max_connections(PG_CONNECTION_POOL_SIZE)
-        .connect_with(pg_options)
-        .await?;
-
-    // Run database migrations. `./migrations` is the path to migrations, relative
-    // to the root dir (the directory containing `Cargo.toml`).
-    sqlx::migrate!("./migrations").run(&store).await?;
+    // `Config::is_valid` checked the URLs the backend needs are set
+    let database_url = config.database_url.as_deref().unwrap_or_default();
+    let stores = match config.store_backend {
+        StoreBackend::Memory => {
+            warn!("`STORE_BACKEND` is memory, nothing is persisted or shared between instances");
+            Stores::new(memory_store.clone())
+        }
+        StoreBackend::Sqlite => Stores::new(connect_sqlite_database(database_url).await?),
+        StoreBackend::Postgres => Stores::new(connect_database(database_url).await?),
+    };
 
     #[cfg(not(feature = "multitenant"))]
     let tenant_store: TenantStoreArc = Arc::new(DefaultTenantStore::new(Arc::new(config.clone()))?);
 
     #[cfg(feature = "multitenant")]
     let tenant_store: TenantStoreArc = {
-        let tenant_pg_options = PgConnectOptions::from_str(&config.tenant_database_url)?
-            .log_statements(LevelFilter::Trace)
-            .log_slow_statements(LevelFilter::Info, Duration::from_millis(250))
-            .clone();
-
-        let tenant_database = PgPoolOptions::new()
-            .max_connections(PG_CONNECTION_POOL_SIZE)
-            .connect_with(tenant_pg_options)
-            .await?;
-
-        // Run database migrations. `./tenant_migrations` is the path to migrations,
-        // relative to the root dir (the directory containing `Cargo.toml`).
-        sqlx::migrate!("./tenant_migrations")
-            .run(&tenant_database)
-            .await?;
-
-        Arc::new(tenant_database)
+        let tenant_database: TenantStoreArc = match config.store_backend {
+            StoreBackend::Memory => Arc::new(memory_store),
+            StoreBackend::Sqlite | StoreBackend::Postgres => {
+                Arc::new(connect_tenant_database(&config).await?.ok_or_else(|| {
+                    error::Error::InvalidConfiguration(
+                        "`TENANT_DATABASE_URL` is required".to_string(),
+                    )
+                })?)
+            }
+        };
+
+        match encryption::key_provider(&config)? {
+            Some(key_provider) => Arc::new(EncryptedTenantStore::new(
+                tenant_database,
+                CredentialCipher::new(key_provider),
+            )),
+            None => {
+                warn!("No credentials master key configured, tenant credentials are stored unencrypted");
+                tenant_database
+            }
+        }
+    };
+
+    let notification_store: NotificationStoreArc = match config.notification_dedupe_backend {
+        NotificationDedupeBackend::Database => stores.notification,
+        NotificationDedupeBackend::Redis => Arc::new(RedisNotificationStore::new(
+            stores.notification,
+            config.redis_url.as_deref().unwrap_or_default(),
+            Duration::from_secs(config.notification_dedupe_ttl_secs),
+        )?),
     };
 
     let mut state = state::new_state(
         config,
-        Arc::new(store.clone()),
-        Arc::new(store.clone()),
+        stores.client,
+        notification_store,
         tenant_store,
+        stores.delivery,
+        stores.webhook,
     )?;
 
     #[cfg(any(feature = "analytics", feature = "geoblock"))]
@@ -216,6 +259,27 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
             .route("/:id/fcm_v1", delete(handlers::delete_fcm_v1::handler))
             .route("/:id/apns", post(handlers::update_apns::handler))
             .route("/:id/apns", delete(handlers::delete_apns::handler))
+            .route("/:id/hms", post(handlers::update_hms::handler))
+            .route("/:id/hms", delete(handlers::delete_hms::handler))
+            .route("/:id/web_push", post(handlers::update_web_push::handler))
+            .route("/:id/web_push", delete(handlers::delete_web_push::handler))
+            .route("/:id/validate", post(handlers::validate_tenant::handler))
+            .route("/:id/webhook", post(handlers::update_webhook::handler))
+            .route("/:id/webhook", delete(handlers::delete_webhook::handler))
+            .route(
+                "/:id/webhook/events",
+                get(handlers::get_webhook_events::handler),
+            )
+            .route(
+                "/:id/clients/segments",
+                get(handlers::get_client_segments::handler),
+            )
+            .route("/:id/templates", get(handlers::get_templates::handler))
+            .route(
+                "/:id/templates/:name",
+                post(handlers::update_template::handler)
+                    .delete(handlers::delete_template::handler),
+            )
             .layer(
                 global_middleware.clone().layer(
                     CorsLayer::new()
@@ -230,33 +294,103 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
                         .allow_headers([hyper::http::header::CONTENT_TYPE, hyper::http::header::AUTHORIZATION]),
                 ),
             )
-            .layer(axum::middleware::from_fn_with_state(state_arc.clone(), rate_limit_middleware));
+            .layer(axum::middleware::from_fn_with_state(
+                (state_arc.clone(), RouteGroup::Tenants),
+                rate_limit_middleware,
+            ));
+
+        let admin_routes = Router::new()
+            .route("/tenants", get(handlers::admin_list_tenants::handler))
+            .route(
+                "/tenants/summary",
+                get(handlers::admin_tenant_summary::handler),
+            )
+            .route(
+                "/tenants/:id/unsuspend",
+                post(handlers::admin_unsuspend_tenant::handler),
+            )
+            .route(
+                "/tenants/:id/suspensions",
+                get(handlers::admin_tenant_suspensions::handler),
+            )
+            .route(
+                "/client_events",
+                get(handlers::admin_client_events::handler),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                state_arc.clone(),
+                require_admin_token,
+            ));
 
         Router::new()
             .route("/health", get(handlers::health::handler))
             .route("/rate_limit_test", get(handlers::rate_limit_test::handler).layer(
-                axum::middleware::from_fn_with_state(state_arc.clone(), rate_limit_middleware),
-            ))
-            .nest("/tenants", tenancy_routes.layer(
-                axum::middleware::from_fn_with_state(state_arc.clone(), rate_limit_middleware),
+                axum::middleware::from_fn_with_state(
+                    (state_arc.clone(), RouteGroup::Default),
+                    rate_limit_middleware,
+                ),
             ))
+            .nest("/tenants", tenancy_routes)
+            .nest("/admin", admin_routes)
             .route(
                 "/:tenant_id/clients",
                 post(handlers::register_client::handler).layer(
-                    axum::middleware::from_fn_with_state(state_arc.clone(), rate_limit_middleware),
+                    axum::middleware::from_fn_with_state(
+                        (state_arc.clone(), RouteGroup::Clients),
+                        rate_limit_middleware,
+                    ),
                 ),
             )
             .route(
                 "/:tenant_id/clients/:id",
                 delete(handlers::delete_client::handler).layer(
-                    axum::middleware::from_fn_with_state(state_arc.clone(), rate_limit_middleware),
+                    axum::middleware::from_fn_with_state(
+                        (state_arc.clone(), RouteGroup::Clients),
+                        rate_limit_middleware,
+                    ),
                 ),
             )
+            .route(
+                "/:tenant_id/clients/:id/notifications",
+                get(handlers::get_client_notifications::handler).layer(
+                    axum::middleware::from_fn_with_state(
+                        (state_arc.clone(), RouteGroup::Clients),
+                        rate_limit_middleware,
+                    ),
+                ),
+            )
+            .route(
+                "/:tenant_id/clients/:id/notifications/:notification_id",
+                get(handlers::get_notification::handler).layer(
+                    axum::middleware::from_fn_with_state(
+                        (state_arc.clone(), RouteGroup::Clients),
+                        rate_limit_middleware,
+                    ),
+                ),
+            )
+            .route(
+                "/:tenant_id/clients/:id/topics/:topic",
+                post(handlers::subscribe_topic::handler)
+                    .delete(handlers::unsubscribe_topic::handler)
+                    .layer(axum::middleware::from_fn_with_state(
+                        (state_arc.clone(), RouteGroup::Clients),
+                        rate_limit_middleware,
+                    )),
+            )
             // Rate limiting middleware is not applying to push_handler because it is used by the relay
             .route(
                 "/:tenant_id/clients/:id",
                 post(handlers::push_message::handler),
             )
+            .route("/:tenant_id/batch", post(handlers::push_batch::handler))
+            .route(
+                "/:tenant_id/topics/:topic",
+                post(handlers::push_topic::handler),
+            )
+            .route(
+                "/:tenant_id/notifications/:id",
+                delete(handlers::cancel_notification::handler),
+            )
             .layer(global_middleware)
     };
 
@@ -264,25 +398,73 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
     let app = Router::new()
         .route("/health", get(handlers::health::handler))
         .route("/rate_limit_test", get(handlers::rate_limit_test::handler).layer(
-            axum::middleware::from_fn_with_state(state_arc.clone(), rate_limit_middleware),
+            axum::middleware::from_fn_with_state(
+                (state_arc.clone(), RouteGroup::Default),
+                rate_limit_middleware,
+            ),
         ))
         .route(
             "/clients",
             post(handlers::single_tenant_wrappers::register_handler).layer(
-                axum::middleware::from_fn_with_state(state_arc.clone(), rate_limit_middleware),
+                axum::middleware::from_fn_with_state(
+                    (state_arc.clone(), RouteGroup::Clients),
+                    rate_limit_middleware,
+                ),
             ),
         )
         .route(
             "/clients/:id",
             delete(handlers::single_tenant_wrappers::delete_handler).layer(
-                axum::middleware::from_fn_with_state(state_arc.clone(), rate_limit_middleware),
+                axum::middleware::from_fn_with_state(
+                    (state_arc.clone(), RouteGroup::Clients),
+                    rate_limit_middleware,
+                ),
+            ),
+        )
+        .route(
+            "/clients/:id/notifications",
+            get(handlers::single_tenant_wrappers::client_notifications_handler).layer(
+                axum::middleware::from_fn_with_state(
+                    (state_arc.clone(), RouteGroup::Clients),
+                    rate_limit_middleware,
+                ),
+            ),
+        )
+        .route(
+            "/clients/:id/notifications/:notification_id",
+            get(handlers::single_tenant_wrappers::notification_handler).layer(
+                axum::middleware::from_fn_with_state(
+                    (state_arc.clone(), RouteGroup::Clients),
+                    rate_limit_middleware,
+                ),
             ),
         )
+        .route(
+            "/clients/:id/topics/:topic",
+            post(handlers::single_tenant_wrappers::subscribe_topic_handler)
+                .delete(handlers::single_tenant_wrappers::unsubscribe_topic_handler)
+                .layer(axum::middleware::from_fn_with_state(
+                    (state_arc.clone(), RouteGroup::Clients),
+                    rate_limit_middleware,
+                )),
+        )
         // Rate limiting middleware is not applying to push_handler because it is used by the relay
         .route(
             "/clients/:id",
             post(handlers::single_tenant_wrappers::push_handler),
         )
+        .route(
+            "/batch",
+            post(handlers::single_tenant_wrappers::push_batch_handler),
+        )
+        .route(
+            "/topics/:topic",
+            post(handlers::single_tenant_wrappers::push_topic_handler),
+        )
+        .route(
+            "/notifications/:id",
+            delete(handlers::single_tenant_wrappers::cancel_notification_handler),
+        )
         .layer(global_middleware);
 
     // If geoblock is enabled, add the geoblock middleware to the app
@@ -295,7 +477,7 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
     let app = app.with_state(state_arc.clone());
     let private_app = Router::new()
         .route("/metrics", get(handlers::metrics::handler))
-        .with_state(state_arc);
+        .with_state(state_arc.clone());
 
     if show_header {
         let header = format!(
@@ -323,13 +505,25 @@ providers: [{}]
         debug!("Online and listening at http://0.0.0.0:{}", port.clone())
     }
 
+    janitor::backfill(&state_arc).await?;
+
     let listener = TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], port))).await?;
     let private_listener =
         TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], private_port))).await?;
 
+    #[cfg(feature = "multitenant")]
+    let apns_expiry_monitor = apns_expiry::run(state_arc.clone());
+    #[cfg(not(feature = "multitenant"))]
+    let apns_expiry_monitor = std::future::pending::<()>();
+
     select! {
         _ = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).into_future() => info!("Server terminating"),
         _ = axum::serve(private_listener, private_app.into_make_service()).into_future() => info!("Internal Server terminating"),
+        _ = delivery::run(state_arc.clone()) => info!("Delivery worker terminating"),
+        _ = delivery::run_scheduler(state_arc.clone()) => info!("Scheduler terminating"),
+        _ = apns_expiry_monitor => info!("APNs expiry monitor terminating"),
+        _ = janitor::run(state_arc.clone()) => info!("Notification janitor terminating"),
+        _ = webhooks::run(state_arc) => info!("Webhook worker terminating"),
         _ = shutdown.recv() => info!("Shutdown signal received, killing servers"),
     }
 
@@ -359,6 +553,118 @@ async fn get_geoip_resolver(config: &Config, s3_client: &S3Client) -> Option<Arc
     }
 }
 
+/// The stores kept in the main database
+struct Stores {
+    client: ClientStoreArc,
+    notification: NotificationStoreArc,
+    delivery: DeliveryStoreArc,
+    webhook: WebhookStoreArc,
+}
+
+impl Stores {
+    fn new<S>(store: S) -> Self
+    where
+        S: ClientStore + NotificationStore + DeliveryStore + WebhookStore,
+        S: Clone + Send + Sync + 'static,
+    {
+        Self {
+            client: Arc::new(store.clone()),
+            notification: Arc::new(store.clone()),
+            delivery: Arc::new(store.clone()),
+            webhook: Arc::new(store),
+        }
+    }
+}
+
+async fn connect_database(database_url: &str) -> error::Result<sqlx::PgPool> {
+    let pg_options = PgConnectOptions::from_str(database_url)?
+        .log_statements(LevelFilter::Trace)
+        .log_slow_statements(LevelFilter::Info, Duration::from_millis(250))
+        .clone();
+
+    let store = PgPoolOptions::new()
+        .max_connections(PG_CONNECTION_POOL_SIZE)
+        .connect_with(pg_options)
+        .await?;
+
+    // Run database migrations. `./migrations` is the path to migrations, relative
+    // to the root dir (the directory containing `Cargo.toml`).
+    sqlx::migrate!("./migrations").run(&store).await?;
+
+    Ok(store)
+}
+
+/// Connects to a SQLite database file, creating it if it doesn't exist
+async fn connect_sqlite_database(database_url: &str) -> error::Result<sqlx::SqlitePool> {
+    let sqlite_options = SqliteConnectOptions::from_str(database_url)?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .log_statements(LevelFilter::Trace)
+        .log_slow_statements(LevelFilter::Info, Duration::from_millis(250))
+        .clone();
+
+    // SQLite allows one writer at a time, a single connection queues writes in
+    // the pool rather than failing them with `SQLITE_BUSY`
+    let store = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(sqlite_options)
+        .await?;
+
+    sqlx::migrate!("./sqlite_migrations").run(&store).await?;
+
+    Ok(store)
+}
+
+/// Connects to the tenant database, `None` when none is configured
+#[cfg(feature = "multitenant")]
+async fn connect_tenant_database(config: &Config) -> error::Result<Option<sqlx::PgPool>> {
+    let Some(tenant_database_url) = &config.tenant_database_url else {
+        return Ok(None);
+    };
+    let tenant_pg_options = PgConnectOptions::from_str(tenant_database_url)?
+        .log_statements(LevelFilter::Trace)
+        .log_slow_statements(LevelFilter::Info, Duration::from_millis(250))
+        .clone();
+
+    let tenant_database = PgPoolOptions::new()
+        .max_connections(PG_CONNECTION_POOL_SIZE)
+        .connect_with(tenant_pg_options)
+        .await?;
+
+    // Run database migrations. `./tenant_migrations` is the path to migrations,
+    // relative to the root dir (the directory containing `Cargo.toml`).
+    sqlx::migrate!("./tenant_migrations")
+        .run(&tenant_database)
+        .await?;
+
+    Ok(Some(tenant_database))
+}
+
+/// Encrypts the credentials of existing tenants with the current master key,
+/// this is also how they are re-encrypted after the master key is rotated
+#[cfg(feature = "multitenant")]
+pub async fn encrypt_tenant_credentials(config: Config) -> error::Result<usize> {
+    let key_provider = encryption::key_provider(&config)?.ok_or_else(|| {
+        error::Error::InvalidConfiguration(
+            "`CREDENTIALS_MASTER_KEYS` or `CREDENTIALS_MASTER_KEYS_FILE` is required to encrypt \
+             credentials"
+                .to_string(),
+        )
+    })?;
+    let tenant_database = connect_tenant_database(&config).await?.ok_or_else(|| {
+        error::Error::InvalidConfiguration(
+            "`TENANT_DATABASE_URL` is required to encrypt credentials".to_string(),
+        )
+    })?;
+
+    EncryptedTenantStore::new(
+        Arc::new(tenant_database),
+        CredentialCipher::new(key_provider),
+    )
+    .encrypt_existing()
+    .await
+}
+
 #[cfg(any(feature = "analytics", feature = "geoblock"))]
 async fn get_s3_client(config: &Config) -> S3Client {
     let region_provider = RegionProviderChain::first_try(Region::new("eu-central-1"));
@@ -13,6 +13,16 @@ async fn main() -> echo_server::error::Result<()> {
     let config = config::get_config()
         .expect("Failed to load config, please ensure all env vars are defined.");
 
+    // `echo-server encrypt-credentials` backfills encryption of existing tenants
+    #[cfg(feature = "multitenant")]
+    if std::env::args().nth(1).as_deref() == Some("encrypt-credentials") {
+        let result = echo_server::encrypt_tenant_credentials(config)
+            .await
+            .map(|updated| tracing::info!("Encrypted credentials of {updated} tenants"));
+        logger.stop();
+        return result;
+    }
+
     let result = echo_server::bootstap(shutdown, config).await;
 
     logger.stop();
@@ -1,8 +1,12 @@
 use {
-    std::time::Instant,
+    std::{
+        collections::HashMap,
+        sync::{Arc, PoisonError, RwLock},
+        time::Instant,
+    },
     wc::metrics::{
         otel::{
-            metrics::{Counter, Histogram},
+            metrics::{Counter, Histogram, ObservableGauge},
             KeyValue,
         },
         ServiceMetrics,
@@ -12,9 +16,15 @@ use {
 #[derive(Clone)]
 pub struct Metrics {
     pub received_notifications: Counter<u64>,
+    pub deduplicated_notifications: Counter<u64>,
     pub sent_fcm_notifications: Counter<u64>,
     pub sent_fcm_v1_notifications: Counter<u64>,
     pub sent_apns_notifications: Counter<u64>,
+    pub sent_hms_notifications: Counter<u64>,
+    pub sent_web_push_notifications: Counter<u64>,
+
+    pub delivery_retries: Counter<u64>,
+    pub failed_deliveries: Counter<u64>,
 
     pub registered_clients: Counter<u64>,
     pub registered_tenants: Counter<u64>,
@@ -22,10 +32,23 @@ pub struct Metrics {
     pub tenant_apns_updates: Counter<u64>,
     pub tenant_fcm_updates: Counter<u64>,
     pub tenant_fcm_v1_updates: Counter<u64>,
+    pub tenant_hms_updates: Counter<u64>,
+    pub tenant_web_push_updates: Counter<u64>,
+
+    pub tenant_push_rate_limited: Counter<u64>,
+    pub client_push_rate_limited: Counter<u64>,
 
     pub tenant_suspensions: Counter<u64>,
     pub client_suspensions: Counter<u64>,
 
+    /// Days until each tenant's APNs certificate expires, keyed by tenant id
+    apns_certificate_expiry_days: Arc<RwLock<HashMap<String, i64>>>,
+    _apns_certificate_expiry: ObservableGauge<i64>,
+
+    pruned_notifications: Counter<u64>,
+    dropped_notification_partitions: Counter<u64>,
+    notification_prune_duration: Histogram<u64>,
+
     postgres_queries: Counter<u64>,
     postgres_query_latency: Histogram<u64>,
 }
@@ -56,6 +79,11 @@ impl Metrics {
             .with_description("The number of notification received")
             .init();
 
+        let deduplicated_notification_counter = meter
+            .u64_counter("deduplicated_notifications")
+            .with_description("The number of notifications discarded as already received")
+            .init();
+
         let sent_fcm_notification_counter = meter
             .u64_counter("sent_fcm_notifications")
             .with_description("The number of notifications sent to FCM")
@@ -71,6 +99,26 @@ impl Metrics {
             .with_description("The number of notifications sent to APNS")
             .init();
 
+        let sent_hms_notification_counter = meter
+            .u64_counter("sent_hms_notifications")
+            .with_description("The number of notifications sent to HMS")
+            .init();
+
+        let sent_web_push_notification_counter = meter
+            .u64_counter("sent_web_push_notifications")
+            .with_description("The number of notifications sent to Web Push services")
+            .init();
+
+        let delivery_retries_counter = meter
+            .u64_counter("delivery_retries")
+            .with_description("The number of notification deliveries scheduled for a retry")
+            .init();
+
+        let failed_deliveries_counter = meter
+            .u64_counter("failed_deliveries")
+            .with_description("The number of notifications that could not be delivered")
+            .init();
+
         let tenant_apns_updates_counter = meter
             .u64_counter("tenant_apns_updates")
             .with_description("The number of times tenants have updated their APNS")
@@ -86,6 +134,26 @@ impl Metrics {
             .with_description("The number of times tenants have updated their FCM")
             .init();
 
+        let tenant_hms_updates_counter = meter
+            .u64_counter("tenant_hms_updates")
+            .with_description("The number of times tenants have updated their HMS")
+            .init();
+
+        let tenant_web_push_updates_counter = meter
+            .u64_counter("tenant_web_push_updates")
+            .with_description("The number of times tenants have updated their Web Push")
+            .init();
+
+        let tenant_push_rate_limited_counter = meter
+            .u64_counter("tenant_push_rate_limited")
+            .with_description("The number of pushes rejected by a tenant's rate limit")
+            .init();
+
+        let client_push_rate_limited_counter = meter
+            .u64_counter("client_push_rate_limited")
+            .with_description("The number of pushes rejected by a client's rate limit")
+            .init();
+
         let tenant_suspensions_counter = meter
             .u64_counter("tenant_suspensions")
             .with_description("The number of tenants that have been suspended")
@@ -96,6 +164,36 @@ impl Metrics {
             .with_description("The number of clients that have been suspended")
             .init();
 
+        let apns_certificate_expiry_days: Arc<RwLock<HashMap<String, i64>>> = Default::default();
+        let apns_certificate_expiry = {
+            let days = apns_certificate_expiry_days.clone();
+            meter
+                .i64_observable_gauge("apns_certificate_expiry_days")
+                .with_description("The number of days until a tenant's APNs certificate expires")
+                .with_callback(move |observer| {
+                    let days = days.read().unwrap_or_else(PoisonError::into_inner);
+                    for (tenant_id, days) in days.iter() {
+                        observer.observe(*days, &[KeyValue::new("tenant_id", tenant_id.clone())]);
+                    }
+                })
+                .init()
+        };
+
+        let pruned_notifications: Counter<u64> = meter
+            .u64_counter("pruned_notifications")
+            .with_description("The number of expired notifications deleted by the janitor")
+            .init();
+
+        let dropped_notification_partitions: Counter<u64> = meter
+            .u64_counter("dropped_notification_partitions")
+            .with_description("The number of expired daily notification partitions dropped")
+            .init();
+
+        let notification_prune_duration: Histogram<u64> = meter
+            .u64_histogram("notification_prune_duration")
+            .with_description("The time taken to prune expired notifications in milliseconds")
+            .init();
+
         let postgres_queries: Counter<u64> = meter
             .u64_counter("postgres_queries")
             .with_description("The number of Postgres queries executed")
@@ -109,20 +207,50 @@ impl Metrics {
         Metrics {
             registered_clients: clients_counter,
             received_notifications: received_notification_counter,
+            deduplicated_notifications: deduplicated_notification_counter,
             sent_fcm_notifications: sent_fcm_notification_counter,
             sent_fcm_v1_notifications: sent_fcm_v1_notification_counter,
             sent_apns_notifications: sent_apns_notification_counter,
+            sent_hms_notifications: sent_hms_notification_counter,
+            sent_web_push_notifications: sent_web_push_notification_counter,
+            delivery_retries: delivery_retries_counter,
+            failed_deliveries: failed_deliveries_counter,
             registered_tenants: tenants_counter,
             tenant_apns_updates: tenant_apns_updates_counter,
             tenant_fcm_updates: tenant_fcm_updates_counter,
             tenant_fcm_v1_updates: tenant_fcm_v1_updates_counter,
+            tenant_hms_updates: tenant_hms_updates_counter,
+            tenant_web_push_updates: tenant_web_push_updates_counter,
+            tenant_push_rate_limited: tenant_push_rate_limited_counter,
+            client_push_rate_limited: client_push_rate_limited_counter,
             tenant_suspensions: tenant_suspensions_counter,
             client_suspensions: client_suspensions_counter,
+            apns_certificate_expiry_days,
+            _apns_certificate_expiry: apns_certificate_expiry,
+            pruned_notifications,
+            dropped_notification_partitions,
+            notification_prune_duration,
             postgres_queries,
             postgres_query_latency,
         }
     }
 
+    /// Replaces the days until expiry reported for tenants' APNs certificates
+    pub fn set_apns_certificate_expiry(&self, days: HashMap<String, i64>) {
+        *self
+            .apns_certificate_expiry_days
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = days;
+    }
+
+    /// Records a run of the notification janitor
+    pub fn notification_prune(&self, rows: u64, partitions: u64, start: Instant) {
+        self.pruned_notifications.add(rows, &[]);
+        self.dropped_notification_partitions.add(partitions, &[]);
+        self.notification_prune_duration
+            .record(start.elapsed().as_millis() as u64, &[]);
+    }
+
     pub fn postgres_query(&self, query_name: &'static str, start: Instant) {
         let elapsed = start.elapsed();
 
@@ -0,0 +1,40 @@
+use {
+    crate::{error::Error, state::AppState},
+    axum::{
+        extract::{Request, State},
+        http::header::AUTHORIZATION,
+        middleware::Next,
+        response::{IntoResponse, Response},
+    },
+    std::sync::Arc,
+    tracing::warn,
+};
+
+/// Requires `Authorization: Bearer <ADMIN_API_TOKEN>`, admin routes are
+/// rejected entirely when no token is configured
+pub async fn require_admin_token(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(admin_token) = state.config.admin_api_token.as_deref() else {
+        warn!("Admin API request rejected, `ADMIN_API_TOKEN` is not configured");
+        return Error::InvalidAuthentication.into_response();
+    };
+
+    let token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match token {
+        Some(token) if token_matches(token, admin_token) => next.run(req).await,
+        _ => Error::InvalidAuthentication.into_response(),
+    }
+}
+
+/// Constant time comparison so the token can't be guessed from response times
+fn token_matches(token: &str, expected: &str) -> bool {
+    token.len() == expected.len() && openssl::memcmp::eq(token.as_bytes(), expected.as_bytes())
+}
@@ -1,2 +1,4 @@
+#[cfg(feature = "multitenant")]
+pub mod admin_auth;
 pub mod rate_limit;
 pub mod validate_signature;
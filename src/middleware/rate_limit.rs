@@ -1,35 +1,221 @@
-use crate::{networking, state::AppState};
-use axum::{
-    extract::Request,
-    extract::State,
-    http::StatusCode,
-    middleware::Next,
-    response::{IntoResponse, Response},
+use {
+    crate::{
+        config::Config,
+        error::Result,
+        networking,
+        state::{AppState, RateLimiterArc},
+    },
+    async_trait::async_trait,
+    axum::{
+        extract::{Request, State},
+        http::{header::RETRY_AFTER, StatusCode},
+        middleware::Next,
+        response::{IntoResponse, Response},
+    },
+    moka::future::Cache,
+    serde::Deserialize,
+    std::{
+        sync::{Arc, Mutex},
+        time::Instant,
+    },
+    tokio::{sync::OnceCell, time::Duration},
+    tracing::error,
 };
-use moka::future::Cache;
-use std::{net::IpAddr, sync::Arc};
-use tokio::time::Duration;
-use tracing::error;
 
+/// Sliding window log, the members are unique per request so concurrent
+/// requests in the same millisecond are all counted. Returns 0 when the request
+/// is allowed, otherwise the number of milliseconds until it would be
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+local key = KEYS[1]
+local now = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local limit = tonumber(ARGV[3])
+
+redis.call('ZREMRANGEBYSCORE', key, 0, now - window)
+if redis.call('ZCARD', key) < limit then
+    redis.call('ZADD', key, now, ARGV[4])
+    redis.call('PEXPIRE', key, window)
+    return 0
+end
+
+local oldest = redis.call('ZRANGE', key, 0, 0, 'WITHSCORES')
+return math.max(tonumber(oldest[2]) + window - now, 1)
+"#;
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    Memory,
+    Redis,
+}
+
+/// Routes sharing a rate limit
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RouteGroup {
+    Default,
+    Tenants,
+    Clients,
+}
+
+impl RouteGroup {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Tenants => "tenants",
+            Self::Clients => "clients",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RouteLimit {
+    pub max_requests: u32,
+    pub window: Duration,
+}
+
+impl RouteLimit {
+    pub fn for_group(config: &Config, group: RouteGroup) -> Self {
+        let (max_requests, window_secs) = match group {
+            RouteGroup::Default => (
+                config.rate_limit_default_max_requests,
+                config.rate_limit_default_window_secs,
+            ),
+            RouteGroup::Tenants => (
+                config.rate_limit_tenants_max_requests,
+                config.rate_limit_tenants_window_secs,
+            ),
+            RouteGroup::Clients => (
+                config.rate_limit_clients_max_requests,
+                config.rate_limit_clients_window_secs,
+            ),
+        };
+        RouteLimit {
+            max_requests,
+            window: Duration::from_secs(window_secs),
+        }
+    }
+}
+
+#[async_trait]
+pub trait RateLimiter {
+    /// Counts a request against `key`. Returns `None` if it is within the
+    /// limit, otherwise how long until it would be
+    async fn check(&self, key: &str, limit: RouteLimit) -> Result<Option<Duration>>;
+}
+
+pub fn new_rate_limiter(config: &Config) -> Result<RateLimiterArc> {
+    Ok(match config.rate_limit_backend {
+        RateLimitBackend::Memory => Arc::new(InMemoryRateLimiter::new()),
+        RateLimitBackend::Redis => Arc::new(RedisRateLimiter::new(
+            config.redis_url.as_deref().unwrap_or_default(),
+        )?),
+    })
+}
+
+struct Window {
+    started_at: Instant,
+    requests: u32,
+}
+
+/// Fixed window counter kept in memory, so limits apply per instance
 #[derive(Clone)]
-pub struct RateLimiter {
-    cache: Arc<Cache<IpAddr, u32>>,
-    max_requests: u32,
+pub struct InMemoryRateLimiter {
+    cache: Cache<String, Arc<Mutex<Window>>>,
 }
 
-impl RateLimiter {
-    pub fn new(max_requests: u32, window: Duration) -> Self {
+impl InMemoryRateLimiter {
+    pub fn new() -> Self {
         Self {
-            cache: Arc::new(Cache::builder().time_to_live(window).build()),
-            max_requests,
+            cache: Cache::builder()
+                .max_capacity(1_000_000)
+                .time_to_idle(Duration::from_secs(60 * 60))
+                .build(),
+        }
+    }
+}
+
+impl Default for InMemoryRateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl RateLimiter for InMemoryRateLimiter {
+    async fn check(&self, key: &str, limit: RouteLimit) -> Result<Option<Duration>> {
+        let window = self
+            .cache
+            .get_with(key.to_string(), async {
+                Arc::new(Mutex::new(Window {
+                    started_at: Instant::now(),
+                    requests: 0,
+                }))
+            })
+            .await;
+        let mut window = window.lock().unwrap_or_else(|e| e.into_inner());
+
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(window.started_at);
+        if elapsed >= limit.window {
+            window.started_at = now;
+            window.requests = 0;
+        }
+
+        if window.requests < limit.max_requests {
+            window.requests += 1;
+            Ok(None)
+        } else {
+            Ok(Some(limit.window.saturating_sub(elapsed)))
         }
     }
 }
 
-/// Rate limit middleware that limits the number of requests per second from a single IP address and
-/// uses in-memory caching to store the number of requests.
+/// Sliding window shared by all instances through Redis
+pub struct RedisRateLimiter {
+    client: redis::Client,
+    connection: OnceCell<redis::aio::ConnectionManager>,
+    script: redis::Script,
+}
+
+impl RedisRateLimiter {
+    pub fn new(redis_url: &str) -> Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(redis_url)?,
+            connection: OnceCell::new(),
+            script: redis::Script::new(SLIDING_WINDOW_SCRIPT),
+        })
+    }
+}
+
+#[async_trait]
+impl RateLimiter for RedisRateLimiter {
+    async fn check(&self, key: &str, limit: RouteLimit) -> Result<Option<Duration>> {
+        // Connected on first use so startup doesn't depend on Redis
+        let mut connection = self
+            .connection
+            .get_or_try_init(|| self.client.get_connection_manager())
+            .await?
+            .clone();
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let retry_after_ms: u64 = self
+            .script
+            .key(format!("rate_limit:{key}"))
+            .arg(now)
+            .arg(limit.window.as_millis() as u64)
+            .arg(limit.max_requests)
+            .arg(format!("{now}-{}", uuid::Uuid::new_v4()))
+            .invoke_async(&mut connection)
+            .await?;
+
+        Ok((retry_after_ms > 0).then(|| Duration::from_millis(retry_after_ms)))
+    }
+}
+
+/// Rate limit middleware that limits the number of requests from a single IP
+/// address per route group
 pub async fn rate_limit_middleware(
-    State(state): State<Arc<AppState>>,
+    State((state, group)): State<(Arc<AppState>, RouteGroup)>,
     req: Request,
     next: Next,
 ) -> Response {
@@ -48,14 +234,20 @@ pub async fn rate_limit_middleware(
         }
     };
 
-    let rate_limiter = &state.rate_limit;
-    let mut rate_limit = rate_limiter.cache.get_with(client_ip, async { 0 }).await;
-
-    if rate_limit < rate_limiter.max_requests {
-        rate_limit += 1;
-        rate_limiter.cache.insert(client_ip, rate_limit).await;
-        next.run(req).await
-    } else {
-        (StatusCode::TOO_MANY_REQUESTS, "Too many requests").into_response()
+    let key = format!("{}:{client_ip}", group.as_str());
+    let limit = RouteLimit::for_group(&state.config, group);
+    match state.rate_limit.check(&key, limit).await {
+        Ok(None) => next.run(req).await,
+        Ok(Some(retry_after)) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, retry_after.as_secs().max(1).to_string())],
+            "Too many requests",
+        )
+            .into_response(),
+        Err(e) => {
+            // Failing open, an unavailable limiter shouldn't take the API down
+            error!("Rate limiter failed, skipping the rate-limiting: {e:?}");
+            next.run(req).await
+        }
     }
 }
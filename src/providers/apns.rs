@@ -1,8 +1,12 @@
 use {
-    super::{LegacyPushMessage, PushMessage, RawPushMessage},
+    super::{LegacyPushMessage, PushMessage, PushOptions, PushPriority, PushType, RawPushMessage},
     crate::{blob::DecryptedPayloadBlob, error::Error, providers::PushProvider},
-    a2::{ClientConfig, ErrorReason, NotificationBuilder, NotificationOptions},
+    a2::{
+        ClientConfig, CollapseId, DefaultNotificationBuilder, ErrorReason, NotificationBuilder,
+        NotificationOptions, Priority,
+    },
     async_trait::async_trait,
+    chrono::Utc,
     std::io::Read,
     tracing::{debug, info, instrument, warn},
 };
@@ -53,15 +57,31 @@ impl PushProvider for ApnsProvider {
         &self,
         token: String,
         body: PushMessage,
-    ) -> crate::error::Result<()> {
+        options: PushOptions,
+    ) -> crate::error::Result<Option<String>> {
         let opt = NotificationOptions {
             apns_id: None,
-            apns_expiration: None,
-            apns_priority: None,
+            // `0` tells APNs to not store the notification at all
+            apns_expiration: options.ttl().map(|ttl| match ttl {
+                0 => 0,
+                ttl => Utc::now().timestamp() as u64 + u64::from(ttl),
+            }),
+            apns_priority: options.priority().map(|priority| match priority {
+                PushPriority::High => Priority::High,
+                PushPriority::Normal => Priority::Normal,
+            }),
             apns_topic: Some(&self.topic),
-            apns_collapse_id: None,
-            apns_push_type: None,
+            apns_collapse_id: options
+                .collapse_key
+                .as_deref()
+                .map(CollapseId::new)
+                .transpose()?,
+            apns_push_type: options.push_type.map(|push_type| match push_type {
+                PushType::Alert => a2::PushType::Alert,
+                PushType::Background => a2::PushType::Background,
+            }),
         };
+        let background = options.is_background();
 
         let result = match body {
             PushMessage::RawPushMessage(RawPushMessage {
@@ -71,11 +91,9 @@ impl PushProvider for ApnsProvider {
             }) => {
                 // Sending `always_raw` encrypted message
                 debug!("Sending raw encrypted message");
-                let mut notification_payload = a2::DefaultNotificationBuilder::new()
-                    .set_content_available()
-                    .set_mutable_content()
-                    .set_title("You have new notifications. Open to view")
-                    .build(token.as_str(), opt);
+                let mut notification_payload =
+                    notification_builder(background, "You have new notifications. Open to view")
+                        .build(token.as_str(), opt);
 
                 notification_payload.add_custom_data("topic", &topic)?;
                 notification_payload.add_custom_data("tag", &tag)?;
@@ -87,11 +105,11 @@ impl PushProvider for ApnsProvider {
                 // TODO tidy after https://github.com/WalletConnect/a2/issues/67 is closed
                 if payload.is_encrypted() {
                     debug!("Sending legacy `is_encrypted` message");
-                    let mut notification_payload = a2::DefaultNotificationBuilder::new()
-                        .set_content_available()
-                        .set_mutable_content()
-                        .set_title("You have new notifications. Open to view")
-                        .build(token.as_str(), opt);
+                    let mut notification_payload = notification_builder(
+                        background,
+                        "You have new notifications. Open to view",
+                    )
+                    .build(token.as_str(), opt);
 
                     notification_payload.add_custom_data("topic", &payload.topic)?;
                     notification_payload.add_custom_data("blob", &payload.blob)?;
@@ -101,14 +119,23 @@ impl PushProvider for ApnsProvider {
                     debug!("Sending plain message");
                     let blob = DecryptedPayloadBlob::from_base64_encoded(&payload.blob)?;
 
-                    let mut notification_payload = a2::DefaultNotificationBuilder::new()
-                        .set_content_available()
-                        .set_mutable_content()
-                        .set_title(&blob.title)
-                        .set_body(&blob.body)
-                        .build(token.as_str(), opt);
+                    let builder = notification_builder(background, &blob.title);
+                    let builder = if background {
+                        builder
+                    } else {
+                        rich_notification_builder(builder, &blob)
+                    };
+                    let mut notification_payload = builder.build(token.as_str(), opt);
 
                     notification_payload.add_custom_data("topic", &payload.topic)?;
+                    // Attached by the app's notification service extension, which
+                    // `mutable-content` wakes for alert notifications
+                    if let Some(image) = &blob.image {
+                        notification_payload.add_custom_data("image", image)?;
+                    }
+                    if let Some(url) = &blob.url {
+                        notification_payload.add_custom_data("url", url)?;
+                    }
 
                     self.client.send(notification_payload).await
                 }
@@ -125,7 +152,7 @@ impl PushProvider for ApnsProvider {
                     );
                     Err(Error::Apns(a2::Error::ResponseError(response)))
                 } else {
-                    Ok(())
+                    Ok(response.apns_id)
                 }
             }
             Err(e) => match e {
@@ -147,6 +174,11 @@ impl PushProvider for ApnsProvider {
                         // InvalidProviderToken reflecting that APNS certificate must be reissued
                         ErrorReason::InvalidProviderToken => Err(Error::ApnsInvalidProviderToken),
                         ErrorReason::PayloadTooLarge => Err(Error::PayloadTooLarge),
+                        // Transient APNs failures, the delivery will be retried
+                        ErrorReason::TooManyRequests
+                        | ErrorReason::InternalServerError
+                        | ErrorReason::ServiceUnavailable
+                        | ErrorReason::Shutdown => Err(Error::ProviderUnavailable(None)),
                         reason => Err(Error::ApnsResponse(reason)),
                     },
                 },
@@ -187,3 +219,46 @@ impl PushProvider for ApnsProvider {
         }
     }
 }
+
+/// Background notifications must only contain `content-available`, anything
+/// visible is set on alert notifications only
+fn notification_builder(background: bool, title: &str) -> DefaultNotificationBuilder<'_> {
+    let builder = DefaultNotificationBuilder::new().set_content_available();
+    if background {
+        builder
+    } else {
+        builder.set_mutable_content().set_title(title)
+    }
+}
+
+/// Sets the body and the optional badge, sound, category, thread and
+/// localization keys of plain notifications
+fn rich_notification_builder<'a>(
+    builder: DefaultNotificationBuilder<'a>,
+    blob: &'a DecryptedPayloadBlob,
+) -> DefaultNotificationBuilder<'a> {
+    let mut builder = builder.set_body(&blob.body);
+    if let Some(badge) = blob.badge {
+        builder = builder.set_badge(badge);
+    }
+    if let Some(sound) = &blob.sound {
+        builder = builder.set_sound(sound);
+    }
+    if let Some(category) = &blob.category {
+        builder = builder.set_category(category);
+    }
+    if let Some(thread_id) = &blob.thread_id {
+        builder = builder.set_thread_id(thread_id);
+    }
+    if let Some(title_loc_key) = &blob.title_loc_key {
+        builder = builder
+            .set_title_loc_key(title_loc_key)
+            .set_title_loc_args(&blob.title_loc_args);
+    }
+    if let Some(body_loc_key) = &blob.body_loc_key {
+        builder = builder
+            .set_loc_key(body_loc_key)
+            .set_loc_args(&blob.body_loc_args);
+    }
+    builder
+}
@@ -1,8 +1,12 @@
 use {
-    super::{LegacyPushMessage, PushMessage},
+    super::{LegacyPushMessage, PushMessage, PushOptions, PushPriority},
     crate::{blob::DecryptedPayloadBlob, error::Error, providers::PushProvider},
     async_trait::async_trait,
-    fcm::{ErrorReason, FcmError, FcmResponse, MessageBuilder, NotificationBuilder, Priority},
+    chrono::Utc,
+    fcm::{
+        ErrorReason, FcmError, FcmResponse, MessageBuilder, NotificationBuilder, Priority,
+        RetryAfter,
+    },
     std::fmt::{Debug, Formatter},
     tracing::{debug, instrument},
 };
@@ -28,8 +32,15 @@ impl PushProvider for FcmProvider {
         &self,
         token: String,
         body: PushMessage,
-    ) -> crate::error::Result<()> {
+        options: PushOptions,
+    ) -> crate::error::Result<Option<String>> {
         let mut message_builder = MessageBuilder::new(self.api_key.as_str(), token.as_str());
+        if let Some(ttl) = options.ttl() {
+            message_builder.time_to_live(ttl as i32);
+        }
+        if let Some(collapse_key) = options.collapse_key.as_deref() {
+            message_builder.collapse_key(collapse_key);
+        }
 
         let result = match body {
             PushMessage::RawPushMessage(message) => {
@@ -38,7 +49,7 @@ impl PushProvider for FcmProvider {
                 message_builder
                     .data(&message)
                     .map_err(Error::InternalSerializationError)?;
-                set_message_priority_high(&mut message_builder);
+                set_data_message_priority(&mut message_builder, &options);
                 let fcm_message = message_builder.finalize();
                 self.client.send(fcm_message).await
             }
@@ -48,19 +59,51 @@ impl PushProvider for FcmProvider {
                     message_builder
                         .data(&payload)
                         .map_err(Error::InternalSerializationError)?;
-                    set_message_priority_high(&mut message_builder);
+                    set_data_message_priority(&mut message_builder, &options);
+                    let fcm_message = message_builder.finalize();
+                    self.client.send(fcm_message).await
+                } else if options.is_background() {
+                    debug!("Sending plain message as data-only background message");
+                    message_builder
+                        .data(&payload)
+                        .map_err(Error::InternalSerializationError)?;
+                    set_data_message_priority(&mut message_builder, &options);
                     let fcm_message = message_builder.finalize();
                     self.client.send(fcm_message).await
                 } else {
                     debug!("Sending plain message");
                     let blob = DecryptedPayloadBlob::from_base64_encoded(&payload.blob)?;
 
+                    let badge = blob.badge.map(|badge| badge.to_string());
+
                     let mut notification_builder = NotificationBuilder::new();
                     notification_builder.title(blob.title.as_str());
                     notification_builder.body(blob.body.as_str());
+                    if let Some(sound) = &blob.sound {
+                        notification_builder.sound(sound);
+                    }
+                    if let Some(badge) = &badge {
+                        notification_builder.badge(badge);
+                    }
+                    // Delivered to iOS as the APNs category. The legacy API has no
+                    // image, the app finds it and the url in the payload's blob
+                    if let Some(category) = &blob.category {
+                        notification_builder.click_action(category);
+                    }
+                    if let Some(title_loc_key) = &blob.title_loc_key {
+                        notification_builder.title_loc_key(title_loc_key);
+                        notification_builder.title_loc_args(&blob.title_loc_args);
+                    }
+                    if let Some(body_loc_key) = &blob.body_loc_key {
+                        notification_builder.body_loc_key(body_loc_key);
+                        notification_builder.body_loc_args(&blob.body_loc_args);
+                    }
                     let notification = notification_builder.finalize();
 
                     message_builder.notification(notification);
+                    if let Some(priority) = options.priority() {
+                        message_builder.priority(fcm_priority(priority));
+                    }
                     message_builder
                         .data(&payload.to_owned())
                         .map_err(Error::InternalSerializationError)?;
@@ -72,7 +115,7 @@ impl PushProvider for FcmProvider {
 
         match result {
             Ok(val) => {
-                let FcmResponse { error, .. } = val;
+                let FcmResponse { error, results, .. } = val;
                 if let Some(error) = error {
                     match error {
                         ErrorReason::MissingRegistration => Err(Error::BadDeviceToken(
@@ -85,14 +128,30 @@ impl PushProvider for FcmProvider {
                             Err(Error::BadDeviceToken("Token is not registered".into()))
                         }
                         ErrorReason::InvalidApnsCredential => Err(Error::BadApnsCredentials),
+                        ErrorReason::Unavailable
+                        | ErrorReason::InternalServerError
+                        | ErrorReason::DeviceMessageRateExceeded => {
+                            Err(Error::ProviderUnavailable(None))
+                        }
                         e => Err(Error::FcmResponse(e)),
                     }
                 } else {
-                    Ok(())
+                    Ok(results
+                        .into_iter()
+                        .flatten()
+                        .find_map(|result| result.message_id))
                 }
             }
             Err(e) => match e {
                 FcmError::Unauthorized => Err(Error::BadFcmApiKey),
+                FcmError::ServerError(retry_after) => Err(Error::ProviderUnavailable(
+                    retry_after.and_then(|retry_after| match retry_after {
+                        RetryAfter::Delay(delay) => delay.to_std().ok(),
+                        RetryAfter::DateTime(date) => {
+                            (date.with_timezone(&Utc) - Utc::now()).to_std().ok()
+                        }
+                    }),
+                )),
                 e => Err(Error::Fcm(e)),
             },
         }
@@ -128,10 +187,20 @@ impl Debug for FcmProvider {
     }
 }
 
-/// Setting message priority to high and content-available to true
-/// on data-only messages or they don't show unless app is active
+/// Setting message priority to high, unless requested otherwise, and
+/// content-available to true on data-only messages or they don't show unless
+/// app is active
 /// https://rnfirebase.io/messaging/usage#data-only-messages
-fn set_message_priority_high(builder: &mut MessageBuilder) {
-    builder.priority(Priority::High);
+fn set_data_message_priority(builder: &mut MessageBuilder, options: &PushOptions) {
+    builder.priority(fcm_priority(
+        options.priority().unwrap_or(PushPriority::High),
+    ));
     builder.content_available(true);
 }
+
+fn fcm_priority(priority: PushPriority) -> Priority {
+    match priority {
+        PushPriority::High => Priority::High,
+        PushPriority::Normal => Priority::Normal,
+    }
+}
@@ -1,10 +1,12 @@
 use {
-    super::{LegacyPushMessage, PushMessage},
+    super::{LegacyPushMessage, PushMessage, PushOptions, PushPriority, PushType},
     crate::{blob::DecryptedPayloadBlob, error::Error, providers::PushProvider},
     async_trait::async_trait,
+    chrono::Utc,
     fcm_v1::{
-        gauth::serv_account::ServiceAccountKey, AndroidConfig, AndroidMessagePriority, ApnsConfig,
-        Client, ClientBuildError, Message, Notification, SendError, Target,
+        gauth::serv_account::ServiceAccountKey, AndroidConfig, AndroidMessagePriority,
+        AndroidNotification, ApnsConfig, Client, ClientBuildError, Message, Notification,
+        SendError, Target,
     },
     serde::Serialize,
     serde_json::json,
@@ -37,27 +39,127 @@ impl PushProvider for FcmV1Provider {
         &self,
         token: String,
         body: PushMessage,
-    ) -> crate::error::Result<()> {
+        options: PushOptions,
+    ) -> crate::error::Result<Option<String>> {
         fn make_message(
             token: String,
-            notification: Option<Notification>,
+            blob: Option<DecryptedPayloadBlob>,
             data: serde_json::Value,
+            options: &PushOptions,
         ) -> Message {
+            let priority = options.priority().unwrap_or(PushPriority::High);
+
+            // The same options as the APNs provider sends, as request headers
+            let mut apns_headers = serde_json::Map::new();
+            if let Some(priority) = options.priority() {
+                let priority = match priority {
+                    PushPriority::High => "10",
+                    PushPriority::Normal => "5",
+                };
+                apns_headers.insert("apns-priority".to_string(), priority.into());
+            }
+            if let Some(ttl) = options.ttl() {
+                let expiration = match ttl {
+                    0 => 0,
+                    ttl => Utc::now().timestamp() + i64::from(ttl),
+                };
+                apns_headers.insert("apns-expiration".to_string(), expiration.to_string().into());
+            }
+            if let Some(collapse_key) = &options.collapse_key {
+                apns_headers.insert("apns-collapse-id".to_string(), collapse_key.as_str().into());
+            }
+            if let Some(push_type) = options.push_type {
+                let push_type = match push_type {
+                    PushType::Alert => "alert",
+                    PushType::Background => "background",
+                };
+                apns_headers.insert("apns-push-type".to_string(), push_type.into());
+            }
+
+            let mut apns_payload = json!({
+                "aps": {
+                    "content-available": 1,
+                }
+            });
+            let mut android_notification = None;
+            let notification = blob.map(|blob| {
+                let aps = &mut apns_payload["aps"];
+                if let Some(badge) = blob.badge {
+                    aps["badge"] = badge.into();
+                }
+                if let Some(sound) = &blob.sound {
+                    aps["sound"] = sound.as_str().into();
+                }
+                if let Some(category) = &blob.category {
+                    aps["category"] = category.as_str().into();
+                }
+                if let Some(thread_id) = &blob.thread_id {
+                    aps["thread-id"] = thread_id.as_str().into();
+                }
+                // The image is attached by the app's notification service extension
+                if blob.image.is_some() {
+                    aps["mutable-content"] = 1.into();
+                }
+                // Replaces the alert FCM builds from the notification
+                if let Some(title_loc_key) = &blob.title_loc_key {
+                    aps["alert"]["title"] = blob.title.as_str().into();
+                    aps["alert"]["title-loc-key"] = title_loc_key.as_str().into();
+                    aps["alert"]["title-loc-args"] = blob.title_loc_args.clone().into();
+                }
+                if let Some(body_loc_key) = &blob.body_loc_key {
+                    aps["alert"]["body"] = blob.body.as_str().into();
+                    aps["alert"]["loc-key"] = body_loc_key.as_str().into();
+                    aps["alert"]["loc-args"] = blob.body_loc_args.clone().into();
+                }
+                if let Some(url) = &blob.url {
+                    apns_payload["url"] = url.as_str().into();
+                }
+
+                android_notification = Some(AndroidNotification {
+                    sound: blob.sound.clone(),
+                    click_action: blob.category.clone(),
+                    tag: blob.thread_id.clone(),
+                    notification_count: blob.badge.map(|badge| badge as i32),
+                    title_loc_key: blob.title_loc_key.clone(),
+                    title_loc_args: blob
+                        .title_loc_key
+                        .as_ref()
+                        .map(|_| blob.title_loc_args.clone()),
+                    body_loc_key: blob.body_loc_key.clone(),
+                    body_loc_args: blob
+                        .body_loc_key
+                        .as_ref()
+                        .map(|_| blob.body_loc_args.clone()),
+                    ..Default::default()
+                });
+
+                Notification {
+                    title: Some(blob.title),
+                    body: Some(blob.body),
+                    image: blob.image,
+                }
+            });
+
             Message {
                 data: Some(data),
                 notification,
                 target: Target::Token(token),
                 android: Some(AndroidConfig {
-                    priority: Some(AndroidMessagePriority::High),
+                    priority: Some(match priority {
+                        PushPriority::High => AndroidMessagePriority::High,
+                        PushPriority::Normal => AndroidMessagePriority::Normal,
+                    }),
+                    // Durations are encoded as seconds with an `s` suffix
+                    ttl: options.ttl().map(|ttl| format!("{ttl}s")),
+                    collapse_key: options.collapse_key.clone(),
+                    notification: android_notification,
                     ..Default::default()
                 }),
                 webpush: None,
                 apns: Some(ApnsConfig {
-                    payload: Some(json!({
-                        "aps": {
-                            "content-available": 1,
-                        }
-                    })),
+                    headers: (!apns_headers.is_empty())
+                        .then(|| serde_json::Value::Object(apns_headers)),
+                    payload: Some(apns_payload),
                     ..Default::default()
                 }),
                 fcm_options: None,
@@ -81,7 +183,7 @@ impl PushProvider for FcmV1Provider {
                     message: message.message,
                 })
                 .map_err(Error::InternalSerializationError)?;
-                let message = make_message(token, None, data);
+                let message = make_message(token, None, data, &options);
                 self.client.send(message).await
             }
             PushMessage::LegacyPushMessage(LegacyPushMessage { id: _, payload }) => {
@@ -99,29 +201,26 @@ impl PushProvider for FcmV1Provider {
                 })
                 .map_err(Error::InternalSerializationError)?;
 
-                if payload.is_encrypted() {
-                    debug!("Sending legacy `is_encrypted` message");
-                    let message = make_message(token, None, data);
+                if payload.is_encrypted() || options.is_background() {
+                    debug!("Sending legacy `is_encrypted` or background message");
+                    let message = make_message(token, None, data, &options);
                     self.client.send(message).await
                 } else {
                     debug!("Sending plain message");
                     let blob = DecryptedPayloadBlob::from_base64_encoded(&payload.blob)?;
-
-                    let notification = Notification {
-                        title: Some(blob.title),
-                        body: Some(blob.body),
-                        ..Default::default()
-                    };
-                    let message = make_message(token, Some(notification), data);
+                    let message = make_message(token, Some(blob), data, &options);
                     self.client.send(message).await
                 }
             }
         };
 
-        result.map(|_| ()).map_err(|e| match e {
-            SendError::Unregistered => Error::BadDeviceToken("Token was unregistered".into()),
-            SendError::Forbidden => Error::BadFcmV1Credentials,
-            e => Error::FcmV1(e),
-        })
+        // The message name, e.g. `projects/<project>/messages/<id>`
+        result
+            .map(|response| Some(response.name))
+            .map_err(|e| match e {
+                SendError::Unregistered => Error::BadDeviceToken("Token was unregistered".into()),
+                SendError::Forbidden => Error::BadFcmV1Credentials,
+                e => Error::FcmV1(e),
+            })
     }
 }
@@ -0,0 +1,280 @@
+use {
+    super::{LegacyPushMessage, PushMessage, PushOptions, PushPriority},
+    crate::{blob::DecryptedPayloadBlob, error::Error, providers::PushProvider},
+    async_trait::async_trait,
+    reqwest::StatusCode,
+    serde::Deserialize,
+    serde_json::{json, Map, Value},
+    std::{
+        fmt::{Debug, Formatter},
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::RwLock,
+    tracing::{debug, instrument},
+};
+
+const HMS_TOKEN_URL: &str = "https://oauth-login.cloud.huawei.com/oauth2/v3/token";
+const HMS_PUSH_URL: &str = "https://push-api.cloud.huawei.com/v1";
+
+/// Access tokens are refreshed this long before they expire so in-flight
+/// requests don't race the expiry
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+// Push Kit result codes
+const CODE_SUCCESS: &str = "80000000";
+const CODE_PARTIAL_SUCCESS: &str = "80100000";
+const CODE_OAUTH_AUTHENTICATION_ERROR: &str = "80200001";
+const CODE_OAUTH_TOKEN_EXPIRED: &str = "80200003";
+const CODE_NO_PERMISSION: &str = "80300002";
+const CODE_ALL_TOKENS_INVALID: &str = "80300007";
+const CODE_MESSAGE_TOO_LARGE: &str = "80300008";
+const CODE_OAUTH_TOKEN_ERROR: &str = "80600003";
+const CODE_INTERNAL_ERROR: &str = "81000001";
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct SendResponse {
+    code: String,
+    msg: String,
+    #[serde(rename = "requestId", default)]
+    request_id: Option<String>,
+}
+
+struct AccessToken {
+    token: String,
+    expires_at: Instant,
+}
+
+/// Huawei Push Kit, for Android devices without Google services
+#[derive(Clone)]
+pub struct HmsProvider {
+    app_id: String,
+    app_secret: String,
+    client: reqwest::Client,
+    /// Shared between clones so the token survives in `provider_cache`
+    access_token: Arc<RwLock<Option<AccessToken>>>,
+}
+
+impl HmsProvider {
+    pub fn new(app_id: String, app_secret: String, client: reqwest::Client) -> Self {
+        HmsProvider {
+            app_id,
+            app_secret,
+            client,
+            access_token: Default::default(),
+        }
+    }
+
+    /// Returns the cached OAuth access token, fetching a new one with the
+    /// client credentials grant if it is missing or about to expire
+    pub async fn access_token(&self) -> Result<String, Error> {
+        if let Some(token) = self.access_token.read().await.as_ref() {
+            if token.expires_at > Instant::now() {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let mut lock = self.access_token.write().await;
+        // Another request may have refreshed it while waiting for the lock
+        if let Some(token) = lock.as_ref() {
+            if token.expires_at > Instant::now() {
+                return Ok(token.token.clone());
+            }
+        }
+
+        debug!("fetching hms access token");
+        let response = self
+            .client
+            .post(HMS_TOKEN_URL)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", self.app_id.as_str()),
+                ("client_secret", self.app_secret.as_str()),
+            ])
+            .send()
+            .await?;
+
+        match response.status() {
+            status if status.is_success() => {}
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                return Err(Error::BadHmsCredentials)
+            }
+            status if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() => {
+                return Err(Error::ProviderUnavailable(None))
+            }
+            status => {
+                return Err(Error::HmsResponse(
+                    status.as_str().to_string(),
+                    "Failed to fetch access token".to_string(),
+                ))
+            }
+        }
+
+        let TokenResponse {
+            access_token,
+            expires_in,
+        } = response.json().await?;
+        *lock = Some(AccessToken {
+            token: access_token.clone(),
+            expires_at: Instant::now() + Duration::from_secs(expires_in)
+                - TOKEN_EXPIRY_MARGIN.min(Duration::from_secs(expires_in)),
+        });
+
+        Ok(access_token)
+    }
+
+    async fn clear_access_token(&self) {
+        *self.access_token.write().await = None;
+    }
+
+    async fn send(&self, body: &Value) -> Result<SendResponse, Error> {
+        let response = self
+            .client
+            .post(format!("{HMS_PUSH_URL}/{}/messages:send", self.app_id))
+            .bearer_auth(self.access_token().await?)
+            .json(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            return Err(Error::ProviderUnavailable(None));
+        }
+
+        // Push Kit reports errors in the body, including for 400 and 401 responses
+        Ok(response.json().await?)
+    }
+}
+
+#[async_trait]
+impl PushProvider for HmsProvider {
+    #[instrument(name = "send_hms_notification", skip_all)]
+    async fn send_notification(
+        &self,
+        token: String,
+        body: PushMessage,
+        options: PushOptions,
+    ) -> crate::error::Result<Option<String>> {
+        // Data messages default to high urgency, like on FCM
+        let mut data_android = android_config(&options);
+        data_android
+            .entry("urgency")
+            .or_insert_with(|| "HIGH".into());
+
+        let message = match body {
+            PushMessage::RawPushMessage(message) => {
+                // Sending `always_raw` encrypted message
+                debug!("Sending raw encrypted message");
+                json!({
+                    "data": serde_json::to_string(&message)
+                        .map_err(Error::InternalSerializationError)?,
+                    "android": data_android,
+                    "token": [token],
+                })
+            }
+            PushMessage::LegacyPushMessage(LegacyPushMessage { id: _, payload }) => {
+                let data =
+                    serde_json::to_string(&payload).map_err(Error::InternalSerializationError)?;
+                if payload.is_encrypted() || options.is_background() {
+                    debug!("Sending legacy `is_encrypted` or background message");
+                    json!({
+                        "data": data,
+                        "android": data_android,
+                        "token": [token],
+                    })
+                } else {
+                    debug!("Sending plain message");
+                    let blob = DecryptedPayloadBlob::from_base64_encoded(&payload.blob)?;
+                    let mut notification = json!({
+                        "title": blob.title,
+                        "body": blob.body,
+                        // Opens the deep link, or the app, when the notification is tapped
+                        "click_action": match &blob.url {
+                            Some(url) => json!({ "type": 2, "url": url }),
+                            None => json!({ "type": 3 }),
+                        },
+                    });
+                    if let Some(image) = blob.image {
+                        notification["image"] = image.into();
+                    }
+                    if let Some(sound) = blob.sound {
+                        notification["sound"] = sound.into();
+                        notification["default_sound"] = false.into();
+                    }
+                    if let Some(thread_id) = blob.thread_id {
+                        notification["group"] = thread_id.into();
+                    }
+                    if let Some(title_loc_key) = blob.title_loc_key {
+                        notification["title_loc_key"] = title_loc_key.into();
+                        notification["title_loc_args"] = blob.title_loc_args.into();
+                    }
+                    if let Some(body_loc_key) = blob.body_loc_key {
+                        notification["body_loc_key"] = body_loc_key.into();
+                        notification["body_loc_args"] = blob.body_loc_args.into();
+                    }
+                    let mut android = android_config(&options);
+                    android.insert("notification".to_string(), notification);
+                    json!({
+                        "data": data,
+                        "android": android,
+                        "token": [token],
+                    })
+                }
+            }
+        };
+        let body = json!({ "validate_only": false, "message": message });
+
+        let response = match self.send(&body).await? {
+            // Tokens can be revoked before they expire, fetch a new one and retry once
+            response if response.code == CODE_OAUTH_TOKEN_EXPIRED => {
+                self.clear_access_token().await;
+                self.send(&body).await?
+            }
+            response => response,
+        };
+
+        match response.code.as_str() {
+            CODE_SUCCESS => Ok(response.request_id),
+            CODE_PARTIAL_SUCCESS | CODE_ALL_TOKENS_INVALID => {
+                Err(Error::BadDeviceToken(response.msg))
+            }
+            CODE_OAUTH_AUTHENTICATION_ERROR | CODE_NO_PERMISSION | CODE_OAUTH_TOKEN_ERROR => {
+                self.clear_access_token().await;
+                Err(Error::BadHmsCredentials)
+            }
+            CODE_OAUTH_TOKEN_EXPIRED | CODE_INTERNAL_ERROR => Err(Error::ProviderUnavailable(None)),
+            CODE_MESSAGE_TOO_LARGE => Err(Error::PayloadTooLarge),
+            _ => Err(Error::HmsResponse(response.code, response.msg)),
+        }
+    }
+}
+
+/// The `AndroidConfig` fields for the message's delivery options. Push Kit
+/// collapse keys are numeric so the collapse key isn't mapped
+fn android_config(options: &PushOptions) -> Map<String, Value> {
+    let mut android = Map::new();
+    if let Some(priority) = options.priority() {
+        let urgency = match priority {
+            PushPriority::High => "HIGH",
+            PushPriority::Normal => "NORMAL",
+        };
+        android.insert("urgency".to_string(), urgency.into());
+    }
+    if let Some(ttl) = options.ttl() {
+        android.insert("ttl".to_string(), format!("{ttl}s").into());
+    }
+    android
+}
+
+// Manual Impl Because the access token should not be logged
+impl Debug for HmsProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[HmsProvider] app_id = {}", self.app_id)
+    }
+}
@@ -1,15 +1,20 @@
 pub mod apns;
 pub mod fcm;
 pub mod fcm_v1;
+pub mod hms;
 #[cfg(any(debug_assertions, test))]
 pub mod noop;
+pub mod validation;
+pub mod web_push;
 
 use {
     self::fcm_v1::FcmV1Provider,
     crate::{
         blob::ENCRYPTED_FLAG,
         error,
-        providers::{apns::ApnsProvider, fcm::FcmProvider},
+        providers::{
+            apns::ApnsProvider, fcm::FcmProvider, hms::HmsProvider, web_push::WebPushProvider,
+        },
     },
     async_trait::async_trait,
     relay_rpc::rpc::msg_id::get_message_id,
@@ -76,15 +81,87 @@ pub struct RawPushMessage {
     pub message: Arc<str>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PushPriority {
+    High,
+    Normal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PushType {
+    /// Shown to the user
+    Alert,
+    /// Only wakes the app, without a visible notification
+    Background,
+}
+
+/// Optional delivery options of a message, each provider maps them to its own
+/// equivalent and falls back to its defaults when they're unset
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq)]
+pub struct PushOptions {
+    pub priority: Option<PushPriority>,
+    /// Seconds the provider stores the message for an offline device, `0`
+    /// delivers it immediately or not at all
+    pub ttl: Option<u32>,
+    /// Pending messages with the same key replace each other
+    pub collapse_key: Option<String>,
+    pub push_type: Option<PushType>,
+}
+
+impl PushOptions {
+    /// The longest TTL supported by all providers (28 days)
+    pub const MAX_TTL: u32 = 2_419_200;
+    /// The APNs `apns-collapse-id` limit
+    pub const MAX_COLLAPSE_KEY_LENGTH: usize = 64;
+
+    pub fn validate(&self) -> error::Result<()> {
+        match &self.collapse_key {
+            Some(key) if key.len() > Self::MAX_COLLAPSE_KEY_LENGTH => {
+                Err(error::Error::FieldTooLong(
+                    "collapse_key".to_string(),
+                    Self::MAX_COLLAPSE_KEY_LENGTH,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn ttl(&self) -> Option<u32> {
+        self.ttl.map(|ttl| ttl.min(Self::MAX_TTL))
+    }
+
+    pub fn is_background(&self) -> bool {
+        self.push_type == Some(PushType::Background)
+    }
+
+    /// Background pushes are sent with normal priority unless set, as APNs
+    /// rejects them with high priority
+    pub fn priority(&self) -> Option<PushPriority> {
+        self.priority
+            .or_else(|| self.is_background().then_some(PushPriority::Normal))
+    }
+}
+
 #[async_trait]
 pub trait PushProvider {
-    async fn send_notification(&self, token: String, body: PushMessage) -> error::Result<()>;
+    /// Sends the notification, returning the provider's id for the message if
+    /// it assigns one
+    async fn send_notification(
+        &self,
+        token: String,
+        body: PushMessage,
+        options: PushOptions,
+    ) -> error::Result<Option<String>>;
 }
 
 pub const PROVIDER_APNS: &str = "apns";
 pub const PROVIDER_APNS_SANDBOX: &str = "apns-sandbox";
 pub const PROVIDER_FCM: &str = "fcm";
 pub const PROVIDER_FCM_V1: &str = "fcm_v1";
+pub const PROVIDER_HMS: &str = "hms";
+pub const PROVIDER_WEB_PUSH: &str = "webpush";
 #[cfg(any(debug_assertions, test))]
 pub const PROVIDER_NOOP: &str = "noop";
 
@@ -96,6 +173,8 @@ pub enum ProviderKind {
     ApnsSandbox,
     Fcm,
     // Intentionally no FcmV1 variant because ProviderKind is also used to determine token type (of which FCM and FCM V1 are the same)
+    Hms,
+    WebPush,
     #[cfg(any(debug_assertions, test))]
     Noop,
 }
@@ -106,6 +185,8 @@ impl ProviderKind {
             Self::Apns => PROVIDER_APNS,
             Self::ApnsSandbox => PROVIDER_APNS_SANDBOX,
             Self::Fcm => PROVIDER_FCM,
+            Self::Hms => PROVIDER_HMS,
+            Self::WebPush => PROVIDER_WEB_PUSH,
             #[cfg(any(debug_assertions, test))]
             Self::Noop => PROVIDER_NOOP,
         }
@@ -144,6 +225,8 @@ impl TryFrom<&str> for ProviderKind {
             PROVIDER_APNS => Ok(Self::Apns),
             PROVIDER_APNS_SANDBOX => Ok(Self::ApnsSandbox),
             PROVIDER_FCM => Ok(Self::Fcm),
+            PROVIDER_HMS => Ok(Self::Hms),
+            PROVIDER_WEB_PUSH => Ok(Self::WebPush),
             #[cfg(any(debug_assertions, test))]
             PROVIDER_NOOP => Ok(Self::Noop),
             _ => Err(error::Error::ProviderNotFound(value.to_owned())),
@@ -157,20 +240,43 @@ pub enum Provider {
     Fcm(FcmProvider),
     FcmV1(FcmV1Provider),
     Apns(ApnsProvider),
+    Hms(HmsProvider),
+    WebPush(WebPushProvider),
     #[cfg(any(debug_assertions, test))]
     Noop(NoopProvider),
 }
 
+impl Provider {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Provider::Fcm(_) => PROVIDER_FCM,
+            Provider::FcmV1(_) => PROVIDER_FCM_V1,
+            Provider::Apns(_) => PROVIDER_APNS,
+            Provider::Hms(_) => PROVIDER_HMS,
+            Provider::WebPush(_) => PROVIDER_WEB_PUSH,
+            #[cfg(any(debug_assertions, test))]
+            Provider::Noop(_) => PROVIDER_NOOP,
+        }
+    }
+}
+
 #[async_trait]
 impl PushProvider for Provider {
     #[instrument(name = "send_notification")]
-    async fn send_notification(&self, token: String, body: PushMessage) -> error::Result<()> {
+    async fn send_notification(
+        &self,
+        token: String,
+        body: PushMessage,
+        options: PushOptions,
+    ) -> error::Result<Option<String>> {
         match self {
-            Provider::Fcm(p) => p.send_notification(token, body).await,
-            Provider::FcmV1(p) => p.send_notification(token, body).await,
-            Provider::Apns(p) => p.send_notification(token, body).await,
+            Provider::Fcm(p) => p.send_notification(token, body, options).await,
+            Provider::FcmV1(p) => p.send_notification(token, body, options).await,
+            Provider::Apns(p) => p.send_notification(token, body, options).await,
+            Provider::Hms(p) => p.send_notification(token, body, options).await,
+            Provider::WebPush(p) => p.send_notification(token, body, options).await,
             #[cfg(any(debug_assertions, test))]
-            Provider::Noop(p) => p.send_notification(token, body).await,
+            Provider::Noop(p) => p.send_notification(token, body, options).await,
         }
     }
 }
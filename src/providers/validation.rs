@@ -0,0 +1,338 @@
+use {
+    crate::{
+        error::{Error, Result},
+        stores::tenant::TenantApnsUpdateAuth,
+    },
+    base64::Engine,
+    chrono::{DateTime, Utc},
+    jsonwebtoken::{Algorithm, EncodingKey, Header},
+    openssl::{asn1::Asn1Time, pkcs12::Pkcs12},
+    reqwest::StatusCode,
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    std::time::Duration,
+    tracing::{debug, instrument, warn},
+};
+
+pub const DEFAULT_APNS_PRODUCTION_URL: &str = "https://api.push.apple.com";
+pub const DEFAULT_APNS_SANDBOX_URL: &str = "https://api.sandbox.push.apple.com";
+pub const DEFAULT_FCM_API_URL: &str = "https://fcm.googleapis.com";
+
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+const VALIDATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Well formed but unregistered device token, APNs checks the credentials and
+/// topic before rejecting it with `BadDeviceToken`
+const APNS_DRY_RUN_TOKEN: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// FCM checks the credentials before rejecting this with `INVALID_ARGUMENT`
+const FCM_DRY_RUN_TOKEN: &str = "credential-validation";
+/// APNs reason for certificates used against the wrong environment
+const BAD_CERTIFICATE_ENVIRONMENT: &str = "BadCertificateEnvironment";
+
+/// Where credentials are dry-run against, configurable so tests can use a mock
+#[derive(Clone, Debug)]
+pub struct ValidationEndpoints {
+    pub apns_production_url: String,
+    pub apns_sandbox_url: String,
+    pub fcm_api_url: String,
+    /// Overrides the `token_uri` of the service account
+    pub fcm_token_url: Option<String>,
+}
+
+impl Default for ValidationEndpoints {
+    fn default() -> Self {
+        ValidationEndpoints {
+            apns_production_url: DEFAULT_APNS_PRODUCTION_URL.to_string(),
+            apns_sandbox_url: DEFAULT_APNS_SANDBOX_URL.to_string(),
+            fcm_api_url: DEFAULT_FCM_API_URL.to_string(),
+            fcm_token_url: None,
+        }
+    }
+}
+
+#[cfg(feature = "multitenant")]
+impl From<&crate::config::Config> for ValidationEndpoints {
+    fn from(config: &crate::config::Config) -> Self {
+        ValidationEndpoints {
+            apns_production_url: config.validation_apns_production_url.clone(),
+            apns_sandbox_url: config.validation_apns_sandbox_url.clone(),
+            fcm_api_url: config.validation_fcm_api_url.clone(),
+            fcm_token_url: config.validation_fcm_token_url.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ApnsClaims<'a> {
+    iss: &'a str,
+    iat: i64,
+}
+
+#[derive(Deserialize)]
+struct ApnsErrorResponse {
+    reason: String,
+}
+
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    project_id: String,
+    private_key: String,
+    client_email: String,
+    token_uri: String,
+}
+
+#[derive(Serialize)]
+struct ServiceAccountClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// Returns when the certificate in a base64 encoded p12 bundle expires
+pub fn apns_certificate_expiry(certificate: &str, password: &str) -> Result<DateTime<Utc>> {
+    let der = base64::engine::general_purpose::STANDARD.decode(certificate)?;
+    let cert = Pkcs12::from_der(&der)
+        .and_then(|pkcs12| pkcs12.parse2(password))
+        .ok()
+        .and_then(|parsed| parsed.cert)
+        .ok_or(Error::BadApnsCredentials)?;
+
+    let diff = Asn1Time::from_unix(0)
+        .and_then(|epoch| epoch.diff(cert.not_after()))
+        .map_err(|_| Error::BadApnsCredentials)?;
+    DateTime::from_timestamp(i64::from(diff.days) * 86400 + i64::from(diff.secs), 0)
+        .ok_or(Error::BadApnsCredentials)
+}
+
+/// Dry-runs the APNs credentials by pushing to an unregistered device token,
+/// which APNs only rejects as such once the credentials and topic are accepted
+#[instrument(skip_all, fields(topic = %topic))]
+pub async fn validate_apns(
+    endpoints: &ValidationEndpoints,
+    topic: &str,
+    auth: &TenantApnsUpdateAuth,
+) -> Result<()> {
+    let (client, authorization) = match auth {
+        TenantApnsUpdateAuth::Certificate {
+            apns_certificate,
+            apns_certificate_password,
+        } => {
+            let expiry = apns_certificate_expiry(apns_certificate, apns_certificate_password)?;
+            if expiry < Utc::now() {
+                return Err(Error::CredentialValidation(format!(
+                    "The APNs certificate expired at {expiry}"
+                )));
+            }
+
+            let der = base64::engine::general_purpose::STANDARD.decode(apns_certificate)?;
+            let identity = reqwest::Identity::from_pkcs12_der(&der, apns_certificate_password)
+                .map_err(|e| {
+                    warn!("Error loading APNs certificate for validation: {e:?}");
+                    Error::BadApnsCredentials
+                })?;
+            (validation_client(Some(identity))?, None)
+        }
+        TenantApnsUpdateAuth::Token {
+            apns_pkcs8_pem,
+            apns_key_id,
+            apns_team_id,
+        } => {
+            let jwt = apns_jwt(apns_pkcs8_pem, apns_key_id, apns_team_id)?;
+            (validation_client(None)?, Some(format!("bearer {jwt}")))
+        }
+    };
+
+    // Certificates are issued per environment so sandbox ones are retried there
+    match apns_dry_run(
+        &client,
+        &endpoints.apns_production_url,
+        topic,
+        authorization.as_deref(),
+    )
+    .await
+    {
+        Err(Error::CredentialValidation(reason)) if reason == BAD_CERTIFICATE_ENVIRONMENT => {
+            apns_dry_run(
+                &client,
+                &endpoints.apns_sandbox_url,
+                topic,
+                authorization.as_deref(),
+            )
+            .await
+        }
+        result => result,
+    }
+}
+
+fn apns_jwt(pkcs8_pem: &str, key_id: &str, team_id: &str) -> Result<String> {
+    let pem = base64::engine::general_purpose::STANDARD.decode(pkcs8_pem)?;
+    let key = EncodingKey::from_ec_pem(&pem).map_err(|_| Error::BadApnsCredentials)?;
+
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some(key_id.to_string());
+    let claims = ApnsClaims {
+        iss: team_id,
+        iat: Utc::now().timestamp(),
+    };
+
+    jsonwebtoken::encode(&header, &claims, &key).map_err(|_| Error::BadApnsCredentials)
+}
+
+async fn apns_dry_run(
+    client: &reqwest::Client,
+    base_url: &str,
+    topic: &str,
+    authorization: Option<&str>,
+) -> Result<()> {
+    debug!("dry-running apns credentials against {base_url}");
+    let mut request = client
+        .post(format!(
+            "{}/3/device/{APNS_DRY_RUN_TOKEN}",
+            base_url.trim_end_matches('/')
+        ))
+        .header("apns-topic", topic)
+        .header("apns-push-type", "alert")
+        .json(&json!({ "aps": { "alert": "Credential validation" } }));
+    if let Some(authorization) = authorization {
+        request = request.header(reqwest::header::AUTHORIZATION, authorization);
+    }
+    let response = request.send().await.map_err(|e| {
+        warn!("APNs credential validation request failed: {e:?}");
+        if format!("{e:?}").contains("CertificateExpired") {
+            Error::CredentialValidation("The APNs certificate has expired".to_string())
+        } else {
+            Error::ProviderUnavailable(None)
+        }
+    })?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        return Err(Error::ProviderUnavailable(None));
+    }
+
+    let reason = response
+        .json::<ApnsErrorResponse>()
+        .await
+        .map(|body| body.reason)
+        .unwrap_or_else(|_| status.to_string());
+    match reason.as_str() {
+        "BadDeviceToken" | "DeviceTokenNotForTopic" | "Unregistered" => Ok(()),
+        "InvalidProviderToken" | "ExpiredProviderToken" => Err(Error::CredentialValidation(
+            format!("APNs rejected the provider token: {reason}"),
+        )),
+        "BadCertificate" | "MissingProviderToken" => Err(Error::BadApnsCredentials),
+        "BadTopic" | "TopicDisallowed" | "MissingTopic" => Err(Error::CredentialValidation(
+            format!("APNs rejected the topic `{topic}`: {reason}"),
+        )),
+        _ => Err(Error::CredentialValidation(reason)),
+    }
+}
+
+/// Dry-runs FCM v1 credentials by fetching an OAuth token for the service
+/// account and sending a `validate_only` message with it
+#[instrument(skip_all)]
+pub async fn validate_fcm_v1(endpoints: &ValidationEndpoints, credentials: &str) -> Result<()> {
+    let key: ServiceAccountKey =
+        serde_json::from_str(credentials).map_err(Error::FcmV1InvalidServiceAccountKey)?;
+    let client = validation_client(None)?;
+
+    let token_url = endpoints.fcm_token_url.as_deref().unwrap_or(&key.token_uri);
+    let access_token = fcm_access_token(&client, &key, token_url).await?;
+
+    debug!("dry-running fcm v1 credentials");
+    let response = client
+        .post(format!(
+            "{}/v1/projects/{}/messages:send",
+            endpoints.fcm_api_url.trim_end_matches('/'),
+            key.project_id
+        ))
+        .bearer_auth(access_token)
+        .json(&json!({
+            "validate_only": true,
+            "message": { "token": FCM_DRY_RUN_TOKEN },
+        }))
+        .send()
+        .await
+        .map_err(|_| Error::ProviderUnavailable(None))?;
+
+    match response.status() {
+        // The dry-run token is expected to be rejected as invalid
+        status if status.is_success() || status == StatusCode::BAD_REQUEST => Ok(()),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => {
+            Err(Error::BadFcmV1Credentials)
+        }
+        status if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() => {
+            Err(Error::ProviderUnavailable(None))
+        }
+        status => Err(Error::CredentialValidation(format!(
+            "FCM responded with {status}"
+        ))),
+    }
+}
+
+async fn fcm_access_token(
+    client: &reqwest::Client,
+    key: &ServiceAccountKey,
+    token_url: &str,
+) -> Result<String> {
+    let encoding_key = EncodingKey::from_rsa_pem(key.private_key.as_bytes())
+        .map_err(|_| Error::BadFcmV1Credentials)?;
+    let now = Utc::now().timestamp();
+    let claims = ServiceAccountClaims {
+        iss: &key.client_email,
+        scope: FCM_SCOPE,
+        aud: &key.token_uri,
+        iat: now,
+        exp: now + 3600,
+    };
+    let assertion = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &encoding_key)
+        .map_err(|_| Error::BadFcmV1Credentials)?;
+
+    debug!("fetching fcm v1 access token from {token_url}");
+    let response = client
+        .post(token_url)
+        .form(&[
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", assertion.as_str()),
+        ])
+        .send()
+        .await
+        .map_err(|_| Error::ProviderUnavailable(None))?;
+
+    match response.status() {
+        status if status.is_success() => {}
+        StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            return Err(Error::BadFcmV1Credentials)
+        }
+        status if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() => {
+            return Err(Error::ProviderUnavailable(None))
+        }
+        status => {
+            return Err(Error::CredentialValidation(format!(
+                "The OAuth token endpoint responded with {status}"
+            )))
+        }
+    }
+
+    let TokenResponse { access_token } = response.json().await?;
+    Ok(access_token)
+}
+
+fn validation_client(identity: Option<reqwest::Identity>) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().timeout(VALIDATION_TIMEOUT);
+    if let Some(identity) = identity {
+        builder = builder.identity(identity);
+    }
+
+    Ok(builder.build()?)
+}
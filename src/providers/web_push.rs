@@ -0,0 +1,250 @@
+use {
+    super::{LegacyPushMessage, PushMessage, PushOptions, PushPriority},
+    crate::{
+        error::Error,
+        providers::PushProvider,
+        webhooks::{self, PinnedClients},
+    },
+    async_trait::async_trait,
+    base64::Engine as _,
+    chrono::Utc,
+    jsonwebtoken::{Algorithm, EncodingKey, Header},
+    openssl::{bn::BigNumContext, ec::PointConversionForm, nid::Nid, pkey::PKey},
+    reqwest::{
+        header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE, LOCATION, RETRY_AFTER},
+        StatusCode, Url,
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{Debug, Formatter},
+        time::Duration,
+    },
+    tracing::{debug, instrument},
+};
+
+/// How long the push service should keep a message for an offline browser, in
+/// seconds
+const WEB_PUSH_TTL: u64 = 24 * 60 * 60;
+
+/// Lifetime of the VAPID JWTs, RFC 8292 allows at most 24 hours
+const VAPID_JWT_EXPIRY: i64 = 12 * 60 * 60;
+
+const P256DH_LENGTH: usize = 65;
+const AUTH_SECRET_LENGTH: usize = 16;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct WebPushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// A browser push subscription as returned by `PushSubscription.toJSON()`,
+/// stored as the client's device token
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct WebPushSubscription {
+    pub endpoint: String,
+    pub keys: WebPushSubscriptionKeys,
+}
+
+impl WebPushSubscription {
+    pub fn from_token(token: &str) -> Result<Self, Error> {
+        let subscription: Self = serde_json::from_str(token)
+            .map_err(|e| Error::BadDeviceToken(format!("Invalid Web Push subscription: {e}")))?;
+
+        // Validate the subscription up front so bad registrations are rejected
+        subscription.endpoint_url()?;
+        subscription.decoded_keys()?;
+
+        Ok(subscription)
+    }
+
+    /// Push services are public https endpoints, anything else is refused so
+    /// subscriptions can't be used to reach internal hosts
+    fn endpoint_url(&self) -> Result<Url, Error> {
+        webhooks::validate_url(&self.endpoint).map_err(|_| invalid_endpoint())
+    }
+
+    fn decoded_keys(&self) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let p256dh = decode_base64_url(&self.keys.p256dh)
+            .filter(|key| key.len() == P256DH_LENGTH)
+            .ok_or_else(|| Error::BadDeviceToken("Invalid Web Push p256dh key".to_string()))?;
+        let auth = decode_base64_url(&self.keys.auth)
+            .filter(|secret| secret.len() == AUTH_SECRET_LENGTH)
+            .ok_or_else(|| Error::BadDeviceToken("Invalid Web Push auth secret".to_string()))?;
+
+        Ok((p256dh, auth))
+    }
+}
+
+fn invalid_endpoint() -> Error {
+    Error::BadDeviceToken("Web Push endpoint must be an https: URL of a public host".to_string())
+}
+
+/// Browsers encode subscription keys as unpadded base64url, but padded values
+/// are accepted too
+fn decode_base64_url(value: &str) -> Option<Vec<u8>> {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .ok()
+}
+
+/// Derives the JWT signing key and the base64url encoded public key (the
+/// `applicationServerKey` browsers subscribe with) from a PEM encoded P-256
+/// private key
+pub fn vapid_keys(pem: &[u8]) -> Result<(EncodingKey, String), Error> {
+    let key = PKey::private_key_from_pem(pem).map_err(|_| Error::BadWebPushCredentials)?;
+    let ec_key = key.ec_key().map_err(|_| Error::BadWebPushCredentials)?;
+    if ec_key.group().curve_name() != Some(Nid::X9_62_PRIME256V1) {
+        return Err(Error::BadWebPushCredentials);
+    }
+
+    let mut ctx = BigNumContext::new().map_err(|_| Error::BadWebPushCredentials)?;
+    let public_key = ec_key
+        .public_key()
+        .to_bytes(ec_key.group(), PointConversionForm::UNCOMPRESSED, &mut ctx)
+        .map_err(|_| Error::BadWebPushCredentials)?;
+
+    // `jsonwebtoken` only reads PKCS#8, so SEC1 keys are converted first
+    let pkcs8 = key
+        .private_key_to_pem_pkcs8()
+        .map_err(|_| Error::BadWebPushCredentials)?;
+    let encoding_key =
+        EncodingKey::from_ec_pem(&pkcs8).map_err(|_| Error::BadWebPushCredentials)?;
+
+    Ok((
+        encoding_key,
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(public_key),
+    ))
+}
+
+#[derive(Serialize)]
+struct VapidClaims<'a> {
+    aud: String,
+    exp: i64,
+    sub: &'a str,
+}
+
+#[derive(Clone)]
+pub struct WebPushProvider {
+    encoding_key: EncodingKey,
+    public_key: String,
+    subject: String,
+    clients: PinnedClients,
+}
+
+impl WebPushProvider {
+    pub fn new(vapid_pem: &[u8], subject: String, clients: PinnedClients) -> Result<Self, Error> {
+        let (encoding_key, public_key) = vapid_keys(vapid_pem)?;
+        Ok(WebPushProvider {
+            encoding_key,
+            public_key,
+            subject,
+            clients,
+        })
+    }
+
+    fn vapid_authorization(&self, endpoint: &Url) -> Result<String, Error> {
+        let claims = VapidClaims {
+            aud: endpoint.origin().ascii_serialization(),
+            exp: Utc::now().timestamp() + VAPID_JWT_EXPIRY,
+            sub: &self.subject,
+        };
+        let jwt =
+            jsonwebtoken::encode(&Header::new(Algorithm::ES256), &claims, &self.encoding_key)?;
+
+        Ok(format!("vapid t={jwt}, k={}", self.public_key))
+    }
+}
+
+#[async_trait]
+impl PushProvider for WebPushProvider {
+    #[instrument(name = "send_web_push_notification")]
+    async fn send_notification(
+        &self,
+        token: String,
+        body: PushMessage,
+        options: PushOptions,
+    ) -> crate::error::Result<Option<String>> {
+        let subscription = WebPushSubscription::from_token(&token)?;
+        let endpoint = subscription.endpoint_url()?;
+        let (p256dh, auth) = subscription.decoded_keys()?;
+
+        // The service worker receives the same data as the FCM data messages
+        let payload = match body {
+            PushMessage::RawPushMessage(message) => {
+                debug!("Sending raw encrypted message");
+                serde_json::to_vec(&message)
+            }
+            PushMessage::LegacyPushMessage(LegacyPushMessage { id: _, payload }) => {
+                debug!("Sending legacy message");
+                serde_json::to_vec(&payload)
+            }
+        }
+        .map_err(Error::InternalSerializationError)?;
+        let encrypted = ece::encrypt(&p256dh, &auth, &payload)?;
+
+        let urgency = match options.priority() {
+            Some(PushPriority::Normal) => "normal",
+            Some(PushPriority::High) | None => "high",
+        };
+
+        // The host is resolved again as it may point elsewhere since the
+        // subscription was registered
+        let client = self.clients.get(&endpoint).await.map_err(|e| match e {
+            Error::InvalidWebhookUrl => invalid_endpoint(),
+            e => e,
+        })?;
+        let response = client
+            .post(endpoint.clone())
+            .header("TTL", options.ttl().map_or(WEB_PUSH_TTL, u64::from))
+            .header("Urgency", urgency)
+            .header(CONTENT_ENCODING, "aes128gcm")
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header(AUTHORIZATION, self.vapid_authorization(&endpoint)?)
+            .body(encrypted)
+            .send()
+            .await?;
+
+        response_result(&response)
+    }
+}
+
+/// Maps the push service's response to the message id or the error to report
+pub fn response_result(response: &reqwest::Response) -> Result<Option<String>, Error> {
+    match response.status() {
+        // The push service identifies the message by its `Location`
+        status if status.is_success() => Ok(response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string)),
+        // The subscription has expired or was unsubscribed, or was created for
+        // another `applicationServerKey`, e.g. before the VAPID keys were rotated
+        StatusCode::NOT_FOUND | StatusCode::GONE | StatusCode::FORBIDDEN => {
+            Err(Error::BadDeviceToken(format!(
+                "Web Push subscription is no longer valid: {}",
+                response.status()
+            )))
+        }
+        // The VAPID JWT was rejected, which new credentials resolve
+        StatusCode::UNAUTHORIZED => Err(Error::BadWebPushCredentials),
+        StatusCode::PAYLOAD_TOO_LARGE => Err(Error::PayloadTooLarge),
+        status if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() => {
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs);
+            Err(Error::ProviderUnavailable(retry_after))
+        }
+        status => Err(Error::WebPushResponse(status)),
+    }
+}
+
+// Manual Impl Because `EncodingKey` does not derive Debug
+impl Debug for WebPushProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[WebPushProvider] subject = {}", self.subject)
+    }
+}
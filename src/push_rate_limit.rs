@@ -0,0 +1,159 @@
+use {
+    crate::{config::Config, stores::tenant::Tenant},
+    moka::future::Cache,
+    std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// Buckets that haven't been used for this long are evicted, at which point
+/// they would have been refilled anyway
+const BUCKET_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+const MAX_BUCKETS: u64 = 1_000_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Rate at which the bucket refills
+    pub per_minute: u32,
+    /// Number of pushes that can be sent at once with a full bucket
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Builds a limit from its configured parts, the burst defaults to the
+    /// per minute rate
+    pub fn from_parts(per_minute: Option<u32>, burst: Option<u32>) -> Option<Self> {
+        let per_minute = per_minute.filter(|per_minute| *per_minute > 0)?;
+        Some(RateLimit {
+            per_minute,
+            burst: burst.unwrap_or(per_minute).max(1),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit, now: Instant) -> Self {
+        TokenBucket {
+            tokens: limit.burst as f64,
+            updated_at: now,
+        }
+    }
+
+    /// Takes a token from the bucket, or returns how long until one is
+    /// available
+    pub fn try_take(&mut self, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        let per_second = limit.per_minute as f64 / 60.0;
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(limit.burst as f64);
+        self.updated_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / per_second))
+        }
+    }
+
+    /// Returns a token taken for a push that was rejected anyway
+    pub fn refund(&mut self, limit: RateLimit) {
+        self.tokens = (self.tokens + 1.0).min(limit.burst as f64);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RateLimitScope {
+    Tenant,
+    Client,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    pub scope: RateLimitScope,
+    pub retry_after: Duration,
+}
+
+/// Token bucket rate limits for pushes per tenant and per client. Limits set
+/// on the tenant take precedence over the configured defaults
+#[derive(Clone)]
+pub struct PushRateLimiter {
+    buckets: Cache<String, Arc<Mutex<TokenBucket>>>,
+    tenant_default: Option<RateLimit>,
+    client_default: Option<RateLimit>,
+}
+
+impl PushRateLimiter {
+    pub fn new(config: &Config) -> Self {
+        PushRateLimiter {
+            buckets: Cache::builder()
+                .max_capacity(MAX_BUCKETS)
+                .time_to_idle(BUCKET_IDLE_TIMEOUT)
+                .build(),
+            tenant_default: RateLimit::from_parts(
+                config.push_rate_limit_per_minute,
+                config.push_rate_limit_burst,
+            ),
+            client_default: RateLimit::from_parts(
+                config.client_push_rate_limit_per_minute,
+                config.client_push_rate_limit_burst,
+            ),
+        }
+    }
+
+    pub async fn check(&self, tenant: &Tenant, client_id: &str) -> Result<(), RateLimited> {
+        let client_key = format!("client:{}:{client_id}", tenant.id);
+        let client_limit = tenant.client_push_rate_limit().or(self.client_default);
+        if let Some(limit) = client_limit {
+            self.take(&client_key, limit)
+                .await
+                .map_err(|retry_after| RateLimited {
+                    scope: RateLimitScope::Client,
+                    retry_after,
+                })?;
+        }
+
+        let tenant_limit = tenant.push_rate_limit().or(self.tenant_default);
+        if let Some(limit) = tenant_limit {
+            if let Err(retry_after) = self.take(&format!("tenant:{}", tenant.id), limit).await {
+                // The push isn't sent, so it doesn't count against the client
+                if let Some(client_limit) = client_limit {
+                    self.refund(&client_key, client_limit).await;
+                }
+                return Err(RateLimited {
+                    scope: RateLimitScope::Tenant,
+                    retry_after,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn bucket(&self, key: &str, limit: RateLimit) -> Arc<Mutex<TokenBucket>> {
+        self.buckets
+            .get_with(key.to_string(), async move {
+                Arc::new(Mutex::new(TokenBucket::new(limit, Instant::now())))
+            })
+            .await
+    }
+
+    async fn take(&self, key: &str, limit: RateLimit) -> Result<(), Duration> {
+        let bucket = self.bucket(key, limit).await;
+        let mut bucket = bucket.lock().unwrap_or_else(|e| e.into_inner());
+        bucket.try_take(limit, Instant::now())
+    }
+
+    async fn refund(&self, key: &str, limit: RateLimit) {
+        let bucket = self.bucket(key, limit).await;
+        let mut bucket = bucket.lock().unwrap_or_else(|e| e.into_inner());
+        bucket.refund(limit);
+    }
+}
@@ -2,16 +2,20 @@ use {
     crate::{
         config::Config,
         metrics::Metrics,
-        middleware::rate_limit,
+        middleware::rate_limit::{self, RateLimiter},
         networking,
         providers::Provider,
+        push_rate_limit::PushRateLimiter,
         relay::RelayClient,
-        stores::{client::ClientStore, notification::NotificationStore, tenant::TenantStore},
+        stores::{
+            client::ClientStore, delivery::DeliveryStore, notification::NotificationStore,
+            tenant::TenantStore, webhook::WebhookStore,
+        },
+        webhooks::PinnedClients,
     },
     build_info::BuildInfo,
     moka::future::Cache,
     std::{net::IpAddr, sync::Arc},
-    tokio::time::Duration,
     wc::geoip::{block::middleware::GeoBlockLayer, MaxMindResolver},
 };
 
@@ -21,8 +25,11 @@ use crate::analytics::PushAnalytics;
 use crate::jwt_validation::JwtValidationClient;
 
 pub type ClientStoreArc = Arc<dyn ClientStore + Send + Sync + 'static>;
+pub type DeliveryStoreArc = Arc<dyn DeliveryStore + Send + Sync + 'static>;
 pub type NotificationStoreArc = Arc<dyn NotificationStore + Send + Sync + 'static>;
 pub type TenantStoreArc = Arc<dyn TenantStore + Send + Sync + 'static>;
+pub type WebhookStoreArc = Arc<dyn WebhookStore + Send + Sync + 'static>;
+pub type RateLimiterArc = Arc<dyn RateLimiter + Send + Sync + 'static>;
 
 pub trait State {
     fn config(&self) -> Config;
@@ -30,6 +37,8 @@ pub trait State {
     fn client_store(&self) -> ClientStoreArc;
     fn notification_store(&self) -> NotificationStoreArc;
     fn tenant_store(&self) -> TenantStoreArc;
+    fn delivery_store(&self) -> DeliveryStoreArc;
+    fn webhook_store(&self) -> WebhookStoreArc;
     fn relay_client(&self) -> RelayClient;
     fn is_multitenant(&self) -> bool;
     fn validate_signatures(&self) -> bool;
@@ -45,6 +54,8 @@ pub struct AppState {
     pub client_store: ClientStoreArc,
     pub notification_store: NotificationStoreArc,
     pub tenant_store: TenantStoreArc,
+    pub delivery_store: DeliveryStoreArc,
+    pub webhook_store: WebhookStoreArc,
     pub relay_client: RelayClient,
     #[cfg(feature = "multitenant")]
     pub jwt_validation_client: JwtValidationClient,
@@ -57,7 +68,10 @@ pub struct AppState {
     pub uptime: std::time::Instant,
     pub http_client: reqwest::Client,
     pub provider_cache: Cache<String, Provider>,
-    pub rate_limit: rate_limit::RateLimiter,
+    /// Clients for URLs that tenants and clients register
+    pub pinned_clients: PinnedClients,
+    pub rate_limit: RateLimiterArc,
+    pub push_rate_limiter: PushRateLimiter,
 }
 
 build_info::build_info!(fn build_info);
@@ -67,6 +81,8 @@ pub fn new_state(
     client_store: ClientStoreArc,
     notification_store: NotificationStoreArc,
     tenant_store: TenantStoreArc,
+    delivery_store: DeliveryStoreArc,
+    webhook_store: WebhookStoreArc,
 ) -> crate::error::Result<AppState> {
     let build_info: &BuildInfo = build_info();
 
@@ -81,6 +97,9 @@ pub fn new_state(
 
     let public_ip = networking::find_public_ip_addr().ok();
 
+    let rate_limit = rate_limit::new_rate_limiter(&config)?;
+    let push_rate_limiter = PushRateLimiter::new(&config);
+
     Ok(AppState {
         config: config.clone(),
         build_info: build_info.clone(),
@@ -90,6 +109,8 @@ pub fn new_state(
         client_store,
         notification_store,
         tenant_store,
+        delivery_store,
+        webhook_store,
         relay_client: RelayClient::new(config.relay_public_key)?,
         #[cfg(feature = "multitenant")]
         jwt_validation_client: JwtValidationClient::new(jwt_secret),
@@ -100,7 +121,9 @@ pub fn new_state(
         uptime: std::time::Instant::now(),
         http_client: reqwest::Client::new(),
         provider_cache: Cache::new(100),
-        rate_limit: rate_limit::RateLimiter::new(100, Duration::from_secs(60)),
+        pinned_clients: PinnedClients::new(),
+        rate_limit,
+        push_rate_limiter,
     })
 }
 
@@ -131,6 +154,14 @@ impl State for Arc<AppState> {
         self.tenant_store.clone()
     }
 
+    fn delivery_store(&self) -> DeliveryStoreArc {
+        self.delivery_store.clone()
+    }
+
+    fn webhook_store(&self) -> WebhookStoreArc {
+        self.webhook_store.clone()
+    }
+
     fn relay_client(&self) -> RelayClient {
         self.relay_client.clone()
     }
@@ -5,7 +5,8 @@ use {
         stores::{self, StoreError::NotFound},
     },
     async_trait::async_trait,
-    sqlx::Executor,
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
     std::time::Instant,
     tracing::{debug, instrument},
 };
@@ -17,6 +18,215 @@ pub struct Client {
     #[sqlx(rename = "device_token")]
     pub token: String,
     pub always_raw: bool,
+    #[sqlx(flatten)]
+    pub metadata: ClientMetadata,
+}
+
+/// Optional details the client reports about itself when registering
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, sqlx::FromRow)]
+pub struct ClientMetadata {
+    /// e.g. `ios`, `android` or `web`
+    pub platform: Option<String>,
+    pub os_version: Option<String>,
+    pub app_bundle_id: Option<String>,
+    pub app_version: Option<String>,
+    pub sdk_name: Option<String>,
+    pub sdk_version: Option<String>,
+    /// BCP 47 language tag, e.g. `en-US`
+    pub locale: Option<String>,
+    /// IANA time zone, e.g. `Europe/Berlin`
+    pub timezone: Option<String>,
+}
+
+impl ClientMetadata {
+    pub const MAX_LENGTH: usize = 255;
+
+    /// The name and value of each field, in column order
+    pub fn fields(&self) -> [(&'static str, Option<&str>); 8] {
+        [
+            ("platform", self.platform.as_deref()),
+            ("os_version", self.os_version.as_deref()),
+            ("app_bundle_id", self.app_bundle_id.as_deref()),
+            ("app_version", self.app_version.as_deref()),
+            ("sdk_name", self.sdk_name.as_deref()),
+            ("sdk_version", self.sdk_version.as_deref()),
+            ("locale", self.locale.as_deref()),
+            ("timezone", self.timezone.as_deref()),
+        ]
+    }
+}
+
+/// A metadata field tenants can count their clients by
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientSegment {
+    Platform,
+    OsVersion,
+    AppBundleId,
+    AppVersion,
+    SdkName,
+    SdkVersion,
+    Locale,
+    Timezone,
+}
+
+impl ClientSegment {
+    pub fn column(&self) -> &'static str {
+        match self {
+            Self::Platform => "platform",
+            Self::OsVersion => "os_version",
+            Self::AppBundleId => "app_bundle_id",
+            Self::AppVersion => "app_version",
+            Self::SdkName => "sdk_name",
+            Self::SdkVersion => "sdk_version",
+            Self::Locale => "locale",
+            Self::Timezone => "timezone",
+        }
+    }
+
+    pub fn value<'a>(&self, metadata: &'a ClientMetadata) -> Option<&'a str> {
+        match self {
+            Self::Platform => metadata.platform.as_deref(),
+            Self::OsVersion => metadata.os_version.as_deref(),
+            Self::AppBundleId => metadata.app_bundle_id.as_deref(),
+            Self::AppVersion => metadata.app_version.as_deref(),
+            Self::SdkName => metadata.sdk_name.as_deref(),
+            Self::SdkVersion => metadata.sdk_version.as_deref(),
+            Self::Locale => metadata.locale.as_deref(),
+            Self::Timezone => metadata.timezone.as_deref(),
+        }
+    }
+}
+
+/// The number of a tenant's clients with a value of a segment, `None` counts
+/// the clients that didn't report it
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClientSegmentCount {
+    pub value: Option<String>,
+    pub clients: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "client_event_type")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ClientEventType {
+    Registered,
+    Updated,
+    /// The device token was registered under another client id, which was
+    /// deleted along with its notifications
+    TokenMoved,
+    Deleted,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "client_event_reason")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ClientEventReason {
+    Register,
+    BadDeviceToken,
+    ManualDelete,
+}
+
+/// An entry of the append-only client history. The `previous_` fields hold
+/// the values the event replaced
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClientEvent {
+    pub id: i64,
+    pub event_type: ClientEventType,
+    pub reason: ClientEventReason,
+    pub client_id: String,
+    pub tenant_id: String,
+    pub device_token: Option<String>,
+    pub previous_client_id: Option<String>,
+    pub previous_tenant_id: Option<String>,
+    pub previous_device_token: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An event to append to the client history
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NewClientEvent<'a> {
+    pub event_type: ClientEventType,
+    pub reason: ClientEventReason,
+    pub client_id: &'a str,
+    pub tenant_id: &'a str,
+    pub device_token: Option<&'a str>,
+    pub previous_client_id: Option<&'a str>,
+    pub previous_tenant_id: Option<&'a str>,
+    pub previous_device_token: Option<&'a str>,
+}
+
+impl<'a> NewClientEvent<'a> {
+    pub fn new(
+        event_type: ClientEventType,
+        reason: ClientEventReason,
+        client_id: &'a str,
+        tenant_id: &'a str,
+    ) -> Self {
+        Self {
+            event_type,
+            reason,
+            client_id,
+            tenant_id,
+            device_token: None,
+            previous_client_id: None,
+            previous_tenant_id: None,
+            previous_device_token: None,
+        }
+    }
+}
+
+/// Filters for the client history. Events are newest first, pages continue
+/// before the id of the last event of the previous page
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct ClientEventListParams {
+    /// Matches the tenant before or after the event
+    pub tenant_id: Option<String>,
+    /// Matches the client id before or after the event
+    pub client_id: Option<String>,
+    /// Matches the device token before or after the event
+    pub device_token: Option<String>,
+    pub before_id: Option<i64>,
+    pub limit: i64,
+}
+
+/// The client holding the id or device token of a registration
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub(crate) struct ClientSelect {
+    pub id: String,
+    pub device_token: String,
+    pub tenant_id: String,
+}
+
+/// The event recording a registration, `existing` is the client that held the
+/// id or the device token before
+pub(crate) fn registration_event<'a>(
+    tenant_id: &'a str,
+    id: &'a str,
+    token: &'a str,
+    existing: Option<&'a ClientSelect>,
+) -> NewClientEvent<'a> {
+    let mut event = NewClientEvent::new(
+        ClientEventType::Registered,
+        ClientEventReason::Register,
+        id,
+        tenant_id,
+    );
+    event.device_token = Some(token);
+    if let Some(existing) = existing {
+        event.previous_tenant_id = Some(&existing.tenant_id);
+        if existing.id == id {
+            event.event_type = ClientEventType::Updated;
+            event.previous_device_token = Some(&existing.device_token);
+        } else {
+            event.event_type = ClientEventType::TokenMoved;
+            event.previous_client_id = Some(&existing.id);
+        }
+    }
+
+    event
 }
 
 #[async_trait]
@@ -29,7 +239,40 @@ pub trait ClientStore {
         metrics: Option<&Metrics>,
     ) -> stores::Result<()>;
     async fn get_client(&self, tenant_id: &str, id: &str) -> stores::Result<Client>;
-    async fn delete_client(&self, tenant_id: &str, id: &str) -> stores::Result<()>;
+    async fn delete_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        reason: ClientEventReason,
+    ) -> stores::Result<()>;
+    async fn get_client_events(
+        &self,
+        params: ClientEventListParams,
+    ) -> stores::Result<Vec<ClientEvent>>;
+    /// Counts the tenant's clients by the segment's values, most common first
+    async fn get_client_segments(
+        &self,
+        tenant_id: &str,
+        segment: ClientSegment,
+        limit: i64,
+    ) -> stores::Result<Vec<ClientSegmentCount>>;
+    /// Subscribes the client to the topic, subscribing twice is a no-op
+    async fn subscribe_client(&self, tenant_id: &str, id: &str, topic: &str) -> stores::Result<()>;
+    async fn unsubscribe_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        topic: &str,
+    ) -> stores::Result<()>;
+    /// Ids of the tenant's clients subscribed to the topic, in id order
+    /// starting after `after_id` so large topics can be paged through
+    async fn get_topic_subscribers(
+        &self,
+        tenant_id: &str,
+        topic: &str,
+        after_id: Option<&str>,
+        limit: i64,
+    ) -> stores::Result<Vec<String>>;
 }
 
 #[async_trait]
@@ -47,13 +290,6 @@ impl ClientStore for sqlx::PgPool {
             client.token
         );
 
-        #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
-        pub struct ClientSelect {
-            pub id: String,
-            pub device_token: String,
-            pub tenant_id: String,
-        }
-
         let start = Instant::now();
         let mut transaction = self.begin().await?;
         if let Some(metrics) = metrics {
@@ -100,6 +336,16 @@ impl ClientStore for sqlx::PgPool {
         #[cfg(feature = "functional_tests")]
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
+        let start = Instant::now();
+        insert_client_event(
+            &mut transaction,
+            registration_event(tenant_id, id, &client.token, existing_client.as_ref()),
+        )
+        .await?;
+        if let Some(metrics) = metrics {
+            metrics.postgres_query("create_client_insert_event", start);
+        }
+
         if let Some(existing_client) = existing_client {
             if existing_client.id == id && existing_client.device_token != client.token {
                 let query = "
@@ -123,6 +369,46 @@ impl ClientStore for sqlx::PgPool {
                     metrics.postgres_query("create_client_update_device_token", start);
                 }
             } else if existing_client.device_token == client.token && existing_client.id != id {
+                let query = "
+                    DELETE FROM public.notification_queue
+                    WHERE client_id = $1
+                ";
+                let start = Instant::now();
+                sqlx::query(query)
+                    .bind(existing_client.id.clone())
+                    .execute(&mut transaction)
+                    .await?;
+                if let Some(metrics) = metrics {
+                    metrics.postgres_query("create_client_delete_queued_deliveries", start);
+                }
+
+                let query = "
+                    DELETE FROM public.scheduled_notifications
+                    WHERE client_id = $1
+                ";
+                let start = Instant::now();
+                sqlx::query(query)
+                    .bind(existing_client.id.clone())
+                    .execute(&mut transaction)
+                    .await?;
+                if let Some(metrics) = metrics {
+                    metrics.postgres_query("create_client_delete_scheduled_notifications", start);
+                }
+
+                // The subscriptions were made by the device's previous client
+                let query = "
+                    DELETE FROM public.client_subscriptions
+                    WHERE client_id = $1
+                ";
+                let start = Instant::now();
+                sqlx::query(query)
+                    .bind(existing_client.id.clone())
+                    .execute(&mut transaction)
+                    .await?;
+                if let Some(metrics) = metrics {
+                    metrics.postgres_query("create_client_delete_subscriptions", start);
+                }
+
                 let query = "
                     DELETE FROM public.notifications
                     WHERE client_id = $1
@@ -205,6 +491,37 @@ impl ClientStore for sqlx::PgPool {
             }
         }
 
+        // Registering again replaces what the client reported before
+        let query = "
+            UPDATE public.clients
+            SET platform = $2,
+                os_version = $3,
+                app_bundle_id = $4,
+                app_version = $5,
+                sdk_name = $6,
+                sdk_version = $7,
+                locale = $8,
+                timezone = $9
+            WHERE id = $1
+        ";
+        let start = Instant::now();
+        let metadata = client.metadata;
+        sqlx::query(query)
+            .bind(id)
+            .bind(metadata.platform)
+            .bind(metadata.os_version)
+            .bind(metadata.app_bundle_id)
+            .bind(metadata.app_version)
+            .bind(metadata.sdk_name)
+            .bind(metadata.sdk_version)
+            .bind(metadata.locale)
+            .bind(metadata.timezone)
+            .execute(&mut transaction)
+            .await?;
+        if let Some(metrics) = metrics {
+            metrics.postgres_query("create_client_update_metadata", start);
+        }
+
         let start = Instant::now();
         transaction.commit().await?;
         if let Some(metrics) = metrics {
@@ -217,8 +534,7 @@ impl ClientStore for sqlx::PgPool {
     #[instrument(skip(self))]
     async fn get_client(&self, tenant_id: &str, id: &str) -> stores::Result<Client> {
         let res = sqlx::query_as::<sqlx::postgres::Postgres, Client>(
-            "SELECT tenant_id, push_type, device_token, always_raw FROM public.clients WHERE id = \
-             $1 and tenant_id = $2",
+            "SELECT * FROM public.clients WHERE id = $1 and tenant_id = $2",
         )
         .bind(id)
         .bind(tenant_id)
@@ -233,27 +549,222 @@ impl ClientStore for sqlx::PgPool {
     }
 
     #[instrument(skip(self))]
-    async fn delete_client(&self, tenant_id: &str, id: &str) -> stores::Result<()> {
-        debug!("ClientStore::delete_client tenant_id={tenant_id} id={id}");
-
-        let mut notification_query_builder =
-            sqlx::QueryBuilder::new("DELETE FROM public.notifications WHERE client_id = ");
-        notification_query_builder.push_bind(id);
-        notification_query_builder.push(" and tenant_id = ");
-        notification_query_builder.push_bind(tenant_id);
-        let notification_query = notification_query_builder.build();
-
-        self.execute(notification_query).await?;
-
-        let mut query_builder = sqlx::QueryBuilder::new("DELETE FROM public.clients WHERE id = ");
-        query_builder.push_bind(id);
-        query_builder.push(" and tenant_id = ");
-        query_builder.push_bind(tenant_id);
-        let query = query_builder.build();
-
-        match self.execute(query).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
+    async fn delete_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        reason: ClientEventReason,
+    ) -> stores::Result<()> {
+        debug!("ClientStore::delete_client tenant_id={tenant_id} id={id} reason={reason:?}");
+
+        let mut transaction = self.begin().await?;
+
+        let query = "
+            DELETE FROM public.notifications
+            WHERE client_id = $1
+                  AND tenant_id = $2
+        ";
+        sqlx::query(query)
+            .bind(id)
+            .bind(tenant_id)
+            .execute(&mut transaction)
+            .await?;
+
+        let query = "
+            DELETE FROM public.clients
+            WHERE id = $1
+                  AND tenant_id = $2
+            RETURNING device_token
+        ";
+        let device_token = sqlx::query_scalar::<sqlx::postgres::Postgres, String>(query)
+            .bind(id)
+            .bind(tenant_id)
+            .fetch_optional(&mut transaction)
+            .await?;
+
+        if let Some(device_token) = &device_token {
+            let mut event = NewClientEvent::new(ClientEventType::Deleted, reason, id, tenant_id);
+            event.previous_device_token = Some(device_token);
+            insert_client_event(&mut transaction, event).await?;
         }
+
+        transaction.commit().await?;
+
+        Ok(())
     }
+
+    #[instrument(skip(self))]
+    async fn get_client_events(
+        &self,
+        params: ClientEventListParams,
+    ) -> stores::Result<Vec<ClientEvent>> {
+        let mut query_builder =
+            sqlx::QueryBuilder::new("SELECT * FROM public.client_events WHERE TRUE");
+        if let Some(tenant_id) = &params.tenant_id {
+            query_builder
+                .push(" AND (tenant_id = ")
+                .push_bind(tenant_id)
+                .push(" OR previous_tenant_id = ")
+                .push_bind(tenant_id)
+                .push(")");
+        }
+        if let Some(client_id) = &params.client_id {
+            query_builder
+                .push(" AND (client_id = ")
+                .push_bind(client_id)
+                .push(" OR previous_client_id = ")
+                .push_bind(client_id)
+                .push(")");
+        }
+        if let Some(device_token) = &params.device_token {
+            query_builder
+                .push(" AND (device_token = ")
+                .push_bind(device_token)
+                .push(" OR previous_device_token = ")
+                .push_bind(device_token)
+                .push(")");
+        }
+        if let Some(before_id) = params.before_id {
+            query_builder.push(" AND id < ").push_bind(before_id);
+        }
+        query_builder
+            .push(" ORDER BY id DESC LIMIT ")
+            .push_bind(params.limit);
+
+        let res = query_builder
+            .build_query_as::<ClientEvent>()
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_client_segments(
+        &self,
+        tenant_id: &str,
+        segment: ClientSegment,
+        limit: i64,
+    ) -> stores::Result<Vec<ClientSegmentCount>> {
+        // The column comes from the enum, never from the request
+        let query = format!(
+            "SELECT {column} AS value, COUNT(*) AS clients
+            FROM public.clients
+            WHERE tenant_id = $1
+            GROUP BY {column}
+            ORDER BY clients DESC, value
+            LIMIT $2",
+            column = segment.column(),
+        );
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, ClientSegmentCount>(&query)
+            .bind(tenant_id)
+            .bind(limit)
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn subscribe_client(&self, tenant_id: &str, id: &str, topic: &str) -> stores::Result<()> {
+        let query = "
+            INSERT INTO public.client_subscriptions (client_id, topic)
+            SELECT id, $3
+            FROM public.clients
+            WHERE id = $1
+                  AND tenant_id = $2
+            ON CONFLICT (topic, client_id) DO NOTHING
+            RETURNING client_id
+        ";
+        let res = sqlx::query_scalar::<sqlx::postgres::Postgres, String>(query)
+            .bind(id)
+            .bind(tenant_id)
+            .bind(topic)
+            .fetch_optional(self)
+            .await?;
+
+        // Nothing is returned for existing subscriptions either
+        if res.is_none() {
+            self.get_client(tenant_id, id).await?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn unsubscribe_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        topic: &str,
+    ) -> stores::Result<()> {
+        let query = "
+            DELETE FROM public.client_subscriptions
+            USING public.clients
+            WHERE client_subscriptions.client_id = clients.id
+                  AND clients.id = $1
+                  AND clients.tenant_id = $2
+                  AND client_subscriptions.topic = $3
+        ";
+        sqlx::query(query)
+            .bind(id)
+            .bind(tenant_id)
+            .bind(topic)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_topic_subscribers(
+        &self,
+        tenant_id: &str,
+        topic: &str,
+        after_id: Option<&str>,
+        limit: i64,
+    ) -> stores::Result<Vec<String>> {
+        let query = "
+            SELECT s.client_id
+            FROM public.client_subscriptions s
+                JOIN public.clients c ON c.id = s.client_id
+            WHERE s.topic = $1
+                  AND c.tenant_id = $2
+                  AND ($3::varchar IS NULL OR s.client_id > $3)
+            ORDER BY s.client_id
+            LIMIT $4
+        ";
+        let res = sqlx::query_scalar::<sqlx::postgres::Postgres, String>(query)
+            .bind(topic)
+            .bind(tenant_id)
+            .bind(after_id)
+            .bind(limit)
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+}
+
+async fn insert_client_event(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event: NewClientEvent<'_>,
+) -> stores::Result<()> {
+    sqlx::query(
+        "INSERT INTO public.client_events (event_type, reason, client_id, tenant_id, \
+         device_token, previous_client_id, previous_tenant_id, previous_device_token) VALUES ($1, \
+         $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(event.event_type)
+    .bind(event.reason)
+    .bind(event.client_id)
+    .bind(event.tenant_id)
+    .bind(event.device_token)
+    .bind(event.previous_client_id)
+    .bind(event.previous_tenant_id)
+    .bind(event.previous_device_token)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
 }
@@ -0,0 +1,282 @@
+use {
+    crate::{handlers::push_message::PushMessageBody, stores},
+    async_trait::async_trait,
+    chrono::{DateTime, Utc},
+    sqlx::types::Json,
+    std::time::Duration,
+    tracing::instrument,
+};
+
+/// A notification waiting in the outbound queue to be sent to its provider
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct QueuedDelivery {
+    pub notification_id: String,
+    pub client_id: String,
+    pub tenant_id: String,
+
+    pub payload: Json<PushMessageBody>,
+    /// Number of send attempts, including the one in progress
+    pub attempts: i32,
+
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[async_trait]
+pub trait DeliveryStore {
+    /// Queues a notification and leases it to the caller for its first
+    /// attempt. Returns `None` if the notification is already queued
+    async fn enqueue_delivery(
+        &self,
+        notification_id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+        lease: Duration,
+    ) -> stores::Result<Option<QueuedDelivery>>;
+    /// Leases up to `limit` deliveries that are due for another attempt
+    async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<QueuedDelivery>>;
+    async fn reschedule_delivery(
+        &self,
+        notification_id: &str,
+        client_id: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> stores::Result<()>;
+    async fn remove_delivery(&self, notification_id: &str, client_id: &str) -> stores::Result<()>;
+    /// Keeps the notification until `send_at`. Returns `false` if it is
+    /// already scheduled
+    async fn schedule_delivery(
+        &self,
+        notification_id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+        send_at: DateTime<Utc>,
+    ) -> stores::Result<bool>;
+    /// Moves up to `limit` scheduled notifications that are due to the queue,
+    /// leased to the caller for their first attempt
+    async fn claim_due_scheduled_deliveries(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<QueuedDelivery>>;
+    /// Cancels the tenant's scheduled notification, returning the ids of the
+    /// clients it was scheduled for
+    async fn cancel_scheduled_delivery(
+        &self,
+        tenant_id: &str,
+        notification_id: &str,
+    ) -> stores::Result<Vec<String>>;
+}
+
+pub(crate) fn lease_expiry(lease: Duration) -> DateTime<Utc> {
+    Utc::now() + chrono::Duration::from_std(lease).unwrap_or_else(|_| chrono::Duration::zero())
+}
+
+#[async_trait]
+impl DeliveryStore for sqlx::PgPool {
+    #[instrument(skip(self, payload))]
+    async fn enqueue_delivery(
+        &self,
+        notification_id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+        lease: Duration,
+    ) -> stores::Result<Option<QueuedDelivery>> {
+        let query = "
+            INSERT INTO public.notification_queue
+                (notification_id, client_id, tenant_id, payload, attempts, next_attempt_at)
+            VALUES ($1, $2, $3, $4, 1, $5)
+            ON CONFLICT (notification_id, client_id) DO NOTHING
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, QueuedDelivery>(query)
+            .bind(notification_id)
+            .bind(client_id)
+            .bind(tenant_id)
+            .bind(Json(payload))
+            .bind(lease_expiry(lease))
+            .fetch_optional(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<QueuedDelivery>> {
+        // Leasing pushes `next_attempt_at` forward so other instances skip the
+        // rows while they are being worked on. If this instance dies the lease
+        // expires and the delivery is picked up again
+        let query = "
+            UPDATE public.notification_queue
+            SET attempts = attempts + 1,
+                next_attempt_at = $2
+            WHERE (notification_id, client_id) IN (
+                SELECT notification_id, client_id
+                FROM public.notification_queue
+                WHERE next_attempt_at <= now()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, QueuedDelivery>(query)
+            .bind(limit)
+            .bind(lease_expiry(lease))
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn reschedule_delivery(
+        &self,
+        notification_id: &str,
+        client_id: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> stores::Result<()> {
+        let query = "
+            UPDATE public.notification_queue
+            SET next_attempt_at = $3
+            WHERE notification_id = $1
+                  AND client_id = $2
+        ";
+        sqlx::query(query)
+            .bind(notification_id)
+            .bind(client_id)
+            .bind(next_attempt_at)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_delivery(&self, notification_id: &str, client_id: &str) -> stores::Result<()> {
+        let query = "
+            DELETE FROM public.notification_queue
+            WHERE notification_id = $1
+                  AND client_id = $2
+        ";
+        sqlx::query(query)
+            .bind(notification_id)
+            .bind(client_id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self, payload))]
+    async fn schedule_delivery(
+        &self,
+        notification_id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+        send_at: DateTime<Utc>,
+    ) -> stores::Result<bool> {
+        let query = "
+            INSERT INTO public.scheduled_notifications
+                (notification_id, client_id, tenant_id, payload, send_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (notification_id, client_id) DO NOTHING
+        ";
+        let res = sqlx::query(query)
+            .bind(notification_id)
+            .bind(client_id)
+            .bind(tenant_id)
+            .bind(Json(payload))
+            .bind(send_at)
+            .execute(self)
+            .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    #[instrument(skip(self))]
+    async fn claim_due_scheduled_deliveries(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<QueuedDelivery>> {
+        // Moving the rows in one statement means a notification is either
+        // still scheduled or leased in the queue, other instances skip the
+        // rows that are being moved. A row is only removed once it's queued,
+        // one whose client already has the notification queued stays
+        // scheduled and is tried again when the lease expires
+        let query = "
+            WITH due AS (
+                SELECT notification_id, client_id, tenant_id, payload
+                FROM public.scheduled_notifications
+                WHERE send_at <= now()
+                ORDER BY send_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            ), queued AS (
+                INSERT INTO public.notification_queue
+                    (notification_id, client_id, tenant_id, payload, attempts, next_attempt_at)
+                SELECT notification_id, client_id, tenant_id, payload, 1, $2
+                FROM due
+                ON CONFLICT (notification_id, client_id) DO NOTHING
+                RETURNING *
+            ), moved AS (
+                DELETE FROM public.scheduled_notifications s
+                USING queued
+                WHERE s.notification_id = queued.notification_id
+                      AND s.client_id = queued.client_id
+            ), deferred AS (
+                UPDATE public.scheduled_notifications s
+                SET send_at = $2
+                FROM due
+                WHERE s.notification_id = due.notification_id
+                      AND s.client_id = due.client_id
+                      AND NOT EXISTS (
+                          SELECT 1 FROM queued
+                          WHERE queued.notification_id = due.notification_id
+                                AND queued.client_id = due.client_id
+                      )
+            )
+            SELECT * FROM queued
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, QueuedDelivery>(query)
+            .bind(limit)
+            .bind(lease_expiry(lease))
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn cancel_scheduled_delivery(
+        &self,
+        tenant_id: &str,
+        notification_id: &str,
+    ) -> stores::Result<Vec<String>> {
+        let query = "
+            DELETE FROM public.scheduled_notifications
+            WHERE notification_id = $1
+                  AND tenant_id = $2
+            RETURNING client_id
+        ";
+        let res = sqlx::query_scalar::<sqlx::postgres::Postgres, String>(query)
+            .bind(notification_id)
+            .bind(tenant_id)
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+}
@@ -0,0 +1,366 @@
+use {
+    crate::{
+        encryption::CredentialCipher,
+        error::{Error, Result},
+        state::TenantStoreArc,
+        stores::tenant::{
+            NotificationTemplate, Tenant, TenantApnsUpdateAuth, TenantApnsUpdateParams,
+            TenantCredentials, TenantFcmUpdateParams, TenantFcmV1UpdateParams,
+            TenantHmsUpdateParams, TenantListParams, TenantRateLimitUpdateParams, TenantStore,
+            TenantSummary, TenantSuspendParams, TenantSuspension, TenantTemplateUpdateParams,
+            TenantUpdateParams, TenantWebPushUpdateParams, TenantWebhookUpdateParams,
+        },
+    },
+    async_trait::async_trait,
+    chrono::{DateTime, Utc},
+    tracing::{error, info, instrument},
+};
+
+/// Encrypts credentials before they are written to the wrapped store and
+/// decrypts them when tenants are read back
+pub struct EncryptedTenantStore {
+    store: TenantStoreArc,
+    cipher: CredentialCipher,
+}
+
+impl EncryptedTenantStore {
+    pub fn new(store: TenantStoreArc, cipher: CredentialCipher) -> Self {
+        EncryptedTenantStore { store, cipher }
+    }
+
+    async fn encrypt(&self, id: &str, column: &str, value: String) -> Result<String> {
+        self.cipher.encrypt(&value, &format!("{id}:{column}")).await
+    }
+
+    async fn encrypt_credentials(
+        &self,
+        id: &str,
+        mut credentials: TenantCredentials,
+    ) -> Result<TenantCredentials> {
+        for (column, value) in credential_columns(&mut credentials) {
+            if let Some(plaintext) = value.take() {
+                *value = Some(self.encrypt(id, column, plaintext).await?);
+            }
+        }
+
+        Ok(credentials)
+    }
+
+    async fn decrypt(&self, mut tenant: Tenant) -> Result<Tenant> {
+        let mut credentials = tenant.credentials();
+        for (column, value) in credential_columns(&mut credentials) {
+            if let Some(stored) = value.as_deref() {
+                let context = format!("{}:{column}", tenant.id);
+                *value = Some(self.cipher.decrypt(stored, &context).await?);
+            }
+        }
+        tenant.set_credentials(credentials);
+
+        Ok(tenant)
+    }
+
+    /// Encrypts any plaintext credentials of the tenant, or ones encrypted with
+    /// a previous master key, with the current master key. Returns whether the
+    /// tenant was updated
+    #[instrument(skip(self))]
+    pub async fn encrypt_tenant(&self, id: &str) -> Result<bool> {
+        let stored = self.store.get_tenant(id).await?;
+        let outdated = credential_columns(&mut stored.credentials())
+            .into_iter()
+            .any(|(_, value)| {
+                value
+                    .as_deref()
+                    .is_some_and(|value| self.cipher.needs_encryption(value))
+            });
+        if !outdated {
+            return Ok(false);
+        }
+
+        let tenant = self.decrypt(stored).await?;
+        self.update_tenant_credentials(id, tenant.credentials())
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Runs `encrypt_tenant` for every tenant, returning the number updated.
+    /// Tenants that fail are logged and skipped so the rest are still updated
+    #[instrument(skip(self))]
+    pub async fn encrypt_existing(&self) -> Result<usize> {
+        let mut updated = 0;
+        let mut failed = 0;
+        for id in self.store.get_tenant_ids().await? {
+            match self.encrypt_tenant(&id).await {
+                Ok(true) => {
+                    info!("encrypted credentials of tenant {id}");
+                    updated += 1;
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    error!("failed to encrypt credentials of tenant {id}: {e:?}");
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed > 0 {
+            return Err(Error::CredentialEncryption(format!(
+                "Failed to encrypt credentials of {failed} tenants, {updated} were updated"
+            )));
+        }
+
+        Ok(updated)
+    }
+}
+
+fn credential_columns(credentials: &mut TenantCredentials) -> [(&str, &mut Option<String>); 8] {
+    [
+        ("fcm_api_key", &mut credentials.fcm_api_key),
+        ("fcm_v1_credentials", &mut credentials.fcm_v1_credentials),
+        ("apns_certificate", &mut credentials.apns_certificate),
+        (
+            "apns_certificate_password",
+            &mut credentials.apns_certificate_password,
+        ),
+        ("apns_pkcs8_pem", &mut credentials.apns_pkcs8_pem),
+        ("hms_app_secret", &mut credentials.hms_app_secret),
+        ("web_push_vapid_key", &mut credentials.web_push_vapid_key),
+        ("webhook_secret", &mut credentials.webhook_secret),
+    ]
+}
+
+#[async_trait]
+impl TenantStore for EncryptedTenantStore {
+    async fn get_tenant(&self, id: &str) -> Result<Tenant> {
+        self.decrypt(self.store.get_tenant(id).await?).await
+    }
+
+    async fn get_tenant_ids(&self) -> Result<Vec<String>> {
+        self.store.get_tenant_ids().await
+    }
+
+    async fn list_tenants(&self, params: TenantListParams) -> Result<Vec<Tenant>> {
+        let mut tenants = vec![];
+        for tenant in self.store.list_tenants(params).await? {
+            tenants.push(self.decrypt(tenant).await?);
+        }
+
+        Ok(tenants)
+    }
+
+    async fn get_tenant_summary(&self) -> Result<TenantSummary> {
+        self.store.get_tenant_summary().await
+    }
+
+    async fn delete_tenant(&self, id: &str) -> Result<()> {
+        self.store.delete_tenant(id).await
+    }
+
+    async fn create_tenant(&self, params: TenantUpdateParams) -> Result<Tenant> {
+        self.decrypt(self.store.create_tenant(params).await?).await
+    }
+
+    async fn update_tenant_fcm(&self, id: &str, params: TenantFcmUpdateParams) -> Result<Tenant> {
+        let params = TenantFcmUpdateParams {
+            fcm_api_key: self.encrypt(id, "fcm_api_key", params.fcm_api_key).await?,
+        };
+        self.decrypt(self.store.update_tenant_fcm(id, params).await?)
+            .await
+    }
+
+    async fn update_tenant_delete_fcm(&self, id: &str) -> Result<Tenant> {
+        self.decrypt(self.store.update_tenant_delete_fcm(id).await?)
+            .await
+    }
+
+    async fn update_tenant_fcm_v1(
+        &self,
+        id: &str,
+        params: TenantFcmV1UpdateParams,
+    ) -> Result<Tenant> {
+        let params = TenantFcmV1UpdateParams {
+            fcm_v1_credentials: self
+                .encrypt(id, "fcm_v1_credentials", params.fcm_v1_credentials)
+                .await?,
+        };
+        self.decrypt(self.store.update_tenant_fcm_v1(id, params).await?)
+            .await
+    }
+
+    async fn update_tenant_delete_fcm_v1(&self, id: &str) -> Result<Tenant> {
+        self.decrypt(self.store.update_tenant_delete_fcm_v1(id).await?)
+            .await
+    }
+
+    async fn update_tenant_apns(&self, id: &str, params: TenantApnsUpdateParams) -> Result<Tenant> {
+        self.decrypt(self.store.update_tenant_apns(id, params).await?)
+            .await
+    }
+
+    async fn update_tenant_apns_auth(
+        &self,
+        id: &str,
+        params: TenantApnsUpdateAuth,
+    ) -> Result<Tenant> {
+        let params = match params {
+            TenantApnsUpdateAuth::Certificate {
+                apns_certificate,
+                apns_certificate_password,
+            } => TenantApnsUpdateAuth::Certificate {
+                apns_certificate: self
+                    .encrypt(id, "apns_certificate", apns_certificate)
+                    .await?,
+                apns_certificate_password: self
+                    .encrypt(id, "apns_certificate_password", apns_certificate_password)
+                    .await?,
+            },
+            TenantApnsUpdateAuth::Token {
+                apns_pkcs8_pem,
+                apns_key_id,
+                apns_team_id,
+            } => TenantApnsUpdateAuth::Token {
+                apns_pkcs8_pem: self.encrypt(id, "apns_pkcs8_pem", apns_pkcs8_pem).await?,
+                apns_key_id,
+                apns_team_id,
+            },
+        };
+        self.decrypt(self.store.update_tenant_apns_auth(id, params).await?)
+            .await
+    }
+
+    async fn update_tenant_delete_apns(&self, id: &str) -> Result<Tenant> {
+        self.decrypt(self.store.update_tenant_delete_apns(id).await?)
+            .await
+    }
+
+    async fn update_tenant_hms(&self, id: &str, params: TenantHmsUpdateParams) -> Result<Tenant> {
+        let params = TenantHmsUpdateParams {
+            hms_app_id: params.hms_app_id,
+            hms_app_secret: self
+                .encrypt(id, "hms_app_secret", params.hms_app_secret)
+                .await?,
+        };
+        self.decrypt(self.store.update_tenant_hms(id, params).await?)
+            .await
+    }
+
+    async fn update_tenant_delete_hms(&self, id: &str) -> Result<Tenant> {
+        self.decrypt(self.store.update_tenant_delete_hms(id).await?)
+            .await
+    }
+
+    async fn update_tenant_web_push(
+        &self,
+        id: &str,
+        params: TenantWebPushUpdateParams,
+    ) -> Result<Tenant> {
+        let params = TenantWebPushUpdateParams {
+            web_push_vapid_key: self
+                .encrypt(id, "web_push_vapid_key", params.web_push_vapid_key)
+                .await?,
+            web_push_vapid_subject: params.web_push_vapid_subject,
+        };
+        self.decrypt(self.store.update_tenant_web_push(id, params).await?)
+            .await
+    }
+
+    async fn update_tenant_delete_web_push(&self, id: &str) -> Result<Tenant> {
+        self.decrypt(self.store.update_tenant_delete_web_push(id).await?)
+            .await
+    }
+
+    async fn update_tenant_rate_limits(
+        &self,
+        id: &str,
+        params: TenantRateLimitUpdateParams,
+    ) -> Result<Tenant> {
+        self.decrypt(self.store.update_tenant_rate_limits(id, params).await?)
+            .await
+    }
+
+    async fn update_tenant_webhook(
+        &self,
+        id: &str,
+        params: TenantWebhookUpdateParams,
+    ) -> Result<Tenant> {
+        let params = TenantWebhookUpdateParams {
+            webhook_url: params.webhook_url,
+            webhook_secret: self
+                .encrypt(id, "webhook_secret", params.webhook_secret)
+                .await?,
+        };
+        self.decrypt(self.store.update_tenant_webhook(id, params).await?)
+            .await
+    }
+
+    async fn update_tenant_delete_webhook(&self, id: &str) -> Result<Tenant> {
+        self.decrypt(self.store.update_tenant_delete_webhook(id).await?)
+            .await
+    }
+
+    async fn update_tenant_credentials(
+        &self,
+        id: &str,
+        params: TenantCredentials,
+    ) -> Result<Tenant> {
+        let params = self.encrypt_credentials(id, params).await?;
+        self.decrypt(self.store.update_tenant_credentials(id, params).await?)
+            .await
+    }
+
+    async fn suspend_tenant(&self, id: &str, params: TenantSuspendParams) -> Result<bool> {
+        self.store.suspend_tenant(id, params).await
+    }
+
+    async fn unsuspend_tenant(&self, id: &str, reason: &str) -> Result<bool> {
+        self.store.unsuspend_tenant(id, reason).await
+    }
+
+    async fn get_tenant_suspension_history(&self, id: &str) -> Result<Vec<TenantSuspension>> {
+        self.store.get_tenant_suspension_history(id).await
+    }
+
+    async fn get_apns_certificate_tenants(&self) -> Result<Vec<Tenant>> {
+        let mut tenants = vec![];
+        for tenant in self.store.get_apns_certificate_tenants().await? {
+            tenants.push(self.decrypt(tenant).await?);
+        }
+
+        Ok(tenants)
+    }
+
+    async fn update_tenant_apns_certificate_expiry(
+        &self,
+        id: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        self.store
+            .update_tenant_apns_certificate_expiry(id, expires_at)
+            .await
+    }
+
+    async fn mark_tenant_apns_expiry_notified(&self, id: &str) -> Result<bool> {
+        self.store.mark_tenant_apns_expiry_notified(id).await
+    }
+
+    async fn get_tenant_templates(&self, id: &str) -> Result<Vec<NotificationTemplate>> {
+        self.store.get_tenant_templates(id).await
+    }
+
+    async fn get_tenant_template(&self, id: &str, name: &str) -> Result<NotificationTemplate> {
+        self.store.get_tenant_template(id, name).await
+    }
+
+    async fn update_tenant_template(
+        &self,
+        id: &str,
+        name: &str,
+        params: TenantTemplateUpdateParams,
+    ) -> Result<NotificationTemplate> {
+        self.store.update_tenant_template(id, name, params).await
+    }
+
+    async fn delete_tenant_template(&self, id: &str, name: &str) -> Result<()> {
+        self.store.delete_tenant_template(id, name).await
+    }
+}
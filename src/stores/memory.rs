@@ -0,0 +1,1333 @@
+use {
+    crate::{
+        error::{
+            Error::{InvalidTenantId, TemplateNotFound},
+            Result,
+        },
+        handlers::push_message::PushMessageBody,
+        metrics::Metrics,
+        stores::{
+            self,
+            client::{
+                Client, ClientEvent, ClientEventListParams, ClientEventReason, ClientEventType,
+                ClientSegment, ClientSegmentCount, ClientStore, NewClientEvent,
+            },
+            delivery::{lease_expiry, DeliveryStore, QueuedDelivery},
+            notification::{DeliveryStatus, Notification, NotificationStore},
+            tenant::{
+                ApnsType, NotificationTemplate, SuspensionAction, Tenant, TenantApnsUpdateAuth,
+                TenantApnsUpdateParams, TenantCredentials, TenantFcmUpdateParams,
+                TenantFcmV1UpdateParams, TenantHmsUpdateParams, TenantListParams,
+                TenantRateLimitUpdateParams, TenantStore, TenantSummary, TenantSuspendParams,
+                TenantSuspension, TenantTemplateUpdateParams, TenantUpdateParams,
+                TenantWebPushUpdateParams, TenantWebhookUpdateParams, APNS_SUSPENSION_REASONS,
+                CREDENTIALS_UPDATED_REASON, FCM_SUSPENSION_REASONS, HMS_SUSPENSION_REASONS,
+                WEB_PUSH_SUSPENSION_REASONS,
+            },
+            webhook::{WebhookDelivery, WebhookEvent, WebhookStore},
+            StoreError::NotFound,
+        },
+    },
+    async_trait::async_trait,
+    chrono::{DateTime, NaiveDate, Utc},
+    sqlx::types::Json,
+    std::{
+        collections::{BTreeMap, BTreeSet, HashMap},
+        sync::{Arc, Mutex, MutexGuard, PoisonError},
+        time::Duration,
+    },
+    uuid::Uuid,
+};
+
+/// Keeps every store in memory, used when no database is configured. Nothing
+/// survives a restart and instances don't share state, so this is meant for
+/// local development, single instance edge deployments and tests
+#[derive(Clone, Default)]
+pub struct MemoryStore(Arc<Mutex<Tables>>);
+
+#[derive(Default)]
+struct Tables {
+    clients: HashMap<String, Client>,
+    /// Device token to client id, tokens are unique like in the clients table
+    client_tokens: HashMap<String, String>,
+    /// Keyed by notification and client id
+    notifications: HashMap<(String, String), NotificationRow>,
+    /// Keyed by notification and client id
+    queue: HashMap<(String, String), QueuedDelivery>,
+    /// Keyed by notification and client id
+    scheduled: HashMap<(String, String), ScheduledRow>,
+    webhook_events: HashMap<String, WebhookDelivery>,
+    tenants: BTreeMap<String, Tenant>,
+    /// Tenant id and entry, in the order they were recorded
+    suspension_history: Vec<(String, TenantSuspension)>,
+    /// In the order they were recorded, ids count up from 1
+    client_events: Vec<ClientEvent>,
+    /// Keyed by tenant id and template name
+    templates: BTreeMap<(String, String), NotificationTemplate>,
+    /// Topic and client id, ordered like the subscribers are paged through
+    subscriptions: BTreeSet<(String, String)>,
+}
+
+struct NotificationRow {
+    tenant_id: String,
+    notification: Notification,
+}
+
+struct ScheduledRow {
+    tenant_id: String,
+    payload: PushMessageBody,
+    send_at: DateTime<Utc>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every operation holds the lock for its duration, which gives the same
+    /// atomicity as the transactions of the Postgres stores
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Tables {
+    fn delete_client_notifications(&mut self, client_id: &str, tenant_id: &str) {
+        self.notifications
+            .retain(|(_, id), row| id != client_id || row.tenant_id != tenant_id);
+    }
+
+    /// Removes the client along with its deliveries, notifications and
+    /// subscriptions, like the cascading foreign keys do
+    fn remove_client(&mut self, id: &str) -> Option<Client> {
+        let client = self.clients.remove(id)?;
+        self.client_tokens.remove(&client.token);
+        self.queue.retain(|(_, client_id), _| client_id != id);
+        self.scheduled.retain(|(_, client_id), _| client_id != id);
+        self.notifications
+            .retain(|(_, client_id), _| client_id != id);
+        self.subscriptions.retain(|(_, client_id)| client_id != id);
+
+        Some(client)
+    }
+
+    fn record_client_event(&mut self, event: NewClientEvent<'_>) {
+        self.client_events.push(ClientEvent {
+            id: self.client_events.len() as i64 + 1,
+            event_type: event.event_type,
+            reason: event.reason,
+            client_id: event.client_id.to_string(),
+            tenant_id: event.tenant_id.to_string(),
+            device_token: event.device_token.map(ToString::to_string),
+            previous_client_id: event.previous_client_id.map(ToString::to_string),
+            previous_tenant_id: event.previous_tenant_id.map(ToString::to_string),
+            previous_device_token: event.previous_device_token.map(ToString::to_string),
+            created_at: Utc::now(),
+        });
+    }
+
+    fn tenant_mut(&mut self, id: &str) -> Result<&mut Tenant> {
+        // Matches the error of an update that finds no row
+        self.tenants
+            .get_mut(id)
+            .ok_or_else(|| sqlx::Error::RowNotFound.into())
+    }
+
+    fn record_suspension(
+        &mut self,
+        id: &str,
+        action: SuspensionAction,
+        reason: &str,
+        client_id: Option<String>,
+        notification_id: Option<String>,
+    ) {
+        self.suspension_history.push((
+            id.to_string(),
+            TenantSuspension {
+                action,
+                reason: Some(reason.to_string()),
+                client_id,
+                notification_id,
+                created_at: Utc::now(),
+            },
+        ));
+    }
+
+    fn unsuspend(&mut self, id: &str, reason: &str) -> Result<bool> {
+        let tenant = self.tenant_mut(id)?;
+        if !tenant.suspended {
+            return Ok(false);
+        }
+        tenant.suspended = false;
+        tenant.suspended_reason = None;
+        tenant.updated_at = Utc::now();
+        self.record_suspension(id, SuspensionAction::Unsuspended, reason, None, None);
+
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl ClientStore for MemoryStore {
+    async fn create_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        client: Client,
+        _metrics: Option<&Metrics>,
+    ) -> stores::Result<()> {
+        let mut tables = self.tables();
+        let token_owner = tables.client_tokens.get(&client.token).cloned();
+        let existing = tables.clients.get(id).cloned();
+
+        let mut event = NewClientEvent::new(
+            ClientEventType::Registered,
+            ClientEventReason::Register,
+            id,
+            tenant_id,
+        );
+        event.device_token = Some(&client.token);
+        let previous_owner = token_owner.filter(|previous_id| previous_id != id);
+        let previous_client = previous_owner
+            .as_deref()
+            .and_then(|previous_id| tables.clients.get(previous_id).cloned());
+        if let Some(previous_client) = &previous_client {
+            event.event_type = ClientEventType::TokenMoved;
+            event.previous_client_id = previous_owner.as_deref();
+            event.previous_tenant_id = Some(&previous_client.tenant_id);
+        } else if let Some(existing) = &existing {
+            event.event_type = ClientEventType::Updated;
+            event.previous_tenant_id = Some(&existing.tenant_id);
+            event.previous_device_token = Some(&existing.token);
+        }
+        tables.record_client_event(event);
+
+        // Tokens are unique, a device registering under another id releases
+        // the token from the client that held it before, whose notifications
+        // are no longer relevant
+        if let Some(previous_id) = &previous_owner {
+            tables.remove_client(previous_id);
+        }
+
+        if let Some(existing) = existing {
+            tables.client_tokens.remove(&existing.token);
+        }
+        tables
+            .client_tokens
+            .insert(client.token.clone(), id.to_string());
+        tables.clients.insert(
+            id.to_string(),
+            Client {
+                tenant_id: tenant_id.to_string(),
+                ..client
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn get_client(&self, tenant_id: &str, id: &str) -> stores::Result<Client> {
+        self.tables()
+            .clients
+            .get(id)
+            .filter(|client| client.tenant_id == tenant_id)
+            .cloned()
+            .ok_or_else(|| NotFound("client".to_string(), id.to_string()))
+    }
+
+    async fn delete_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        reason: ClientEventReason,
+    ) -> stores::Result<()> {
+        let mut tables = self.tables();
+        tables.delete_client_notifications(id, tenant_id);
+        if tables
+            .clients
+            .get(id)
+            .is_some_and(|client| client.tenant_id == tenant_id)
+        {
+            if let Some(client) = tables.remove_client(id) {
+                let mut event =
+                    NewClientEvent::new(ClientEventType::Deleted, reason, id, tenant_id);
+                event.previous_device_token = Some(&client.token);
+                tables.record_client_event(event);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_client_events(
+        &self,
+        params: ClientEventListParams,
+    ) -> stores::Result<Vec<ClientEvent>> {
+        /// Whether the filter is unset or matches the value before or after
+        fn matches(filter: &Option<String>, current: Option<&str>, previous: Option<&str>) -> bool {
+            filter
+                .as_deref()
+                .is_none_or(|filter| current == Some(filter) || previous == Some(filter))
+        }
+
+        Ok(self
+            .tables()
+            .client_events
+            .iter()
+            .rev()
+            .filter(|event| {
+                params
+                    .before_id
+                    .is_none_or(|before_id| event.id < before_id)
+            })
+            .filter(|event| {
+                matches(
+                    &params.tenant_id,
+                    Some(&event.tenant_id),
+                    event.previous_tenant_id.as_deref(),
+                ) && matches(
+                    &params.client_id,
+                    Some(&event.client_id),
+                    event.previous_client_id.as_deref(),
+                ) && matches(
+                    &params.device_token,
+                    event.device_token.as_deref(),
+                    event.previous_device_token.as_deref(),
+                )
+            })
+            .take(params.limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_client_segments(
+        &self,
+        tenant_id: &str,
+        segment: ClientSegment,
+        limit: i64,
+    ) -> stores::Result<Vec<ClientSegmentCount>> {
+        let mut counts = HashMap::<Option<String>, i64>::new();
+        for client in self
+            .tables()
+            .clients
+            .values()
+            .filter(|client| client.tenant_id == tenant_id)
+        {
+            *counts
+                .entry(segment.value(&client.metadata).map(ToString::to_string))
+                .or_default() += 1;
+        }
+
+        let mut segments = counts
+            .into_iter()
+            .map(|(value, clients)| ClientSegmentCount { value, clients })
+            .collect::<Vec<_>>();
+        // Postgres sorts nulls last
+        segments.sort_by(|a, b| {
+            b.clients
+                .cmp(&a.clients)
+                .then_with(|| a.value.is_none().cmp(&b.value.is_none()))
+                .then_with(|| a.value.cmp(&b.value))
+        });
+        segments.truncate(limit.max(0) as usize);
+
+        Ok(segments)
+    }
+
+    async fn subscribe_client(&self, tenant_id: &str, id: &str, topic: &str) -> stores::Result<()> {
+        let mut tables = self.tables();
+        if !tables
+            .clients
+            .get(id)
+            .is_some_and(|client| client.tenant_id == tenant_id)
+        {
+            return Err(NotFound("client".to_string(), id.to_string()));
+        }
+        tables
+            .subscriptions
+            .insert((topic.to_string(), id.to_string()));
+
+        Ok(())
+    }
+
+    async fn unsubscribe_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        topic: &str,
+    ) -> stores::Result<()> {
+        let mut tables = self.tables();
+        if tables
+            .clients
+            .get(id)
+            .is_some_and(|client| client.tenant_id == tenant_id)
+        {
+            tables
+                .subscriptions
+                .remove(&(topic.to_string(), id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn get_topic_subscribers(
+        &self,
+        tenant_id: &str,
+        topic: &str,
+        after_id: Option<&str>,
+        limit: i64,
+    ) -> stores::Result<Vec<String>> {
+        let tables = self.tables();
+        let start = (topic.to_string(), after_id.unwrap_or_default().to_string());
+        Ok(tables
+            .subscriptions
+            .range(start..)
+            .take_while(|(subscribed_topic, _)| subscribed_topic == topic)
+            .map(|(_, client_id)| client_id)
+            .filter(|client_id| Some(client_id.as_str()) != after_id)
+            .filter(|client_id| {
+                tables
+                    .clients
+                    .get(*client_id)
+                    .is_some_and(|client| client.tenant_id == tenant_id)
+            })
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl NotificationStore for MemoryStore {
+    async fn claim_notification(
+        &self,
+        id: &str,
+        tenant_id: &str,
+        client_id: &str,
+    ) -> stores::Result<bool> {
+        // Like Postgres nothing is recorded until the notification is stored
+        Ok(!self
+            .tables()
+            .notifications
+            .get(&(id.to_string(), client_id.to_string()))
+            .is_some_and(|row| row.tenant_id == tenant_id))
+    }
+
+    async fn release_notification(
+        &self,
+        _id: &str,
+        _tenant_id: &str,
+        _client_id: &str,
+    ) -> stores::Result<()> {
+        Ok(())
+    }
+
+    async fn create_or_update_notification(
+        &self,
+        id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+    ) -> stores::Result<Notification> {
+        let now = Utc::now();
+        let mut tables = self.tables();
+
+        if let Some(row) = tables
+            .notifications
+            .get_mut(&(id.to_string(), client_id.to_string()))
+        {
+            row.notification.last_received_at = now;
+            return Ok(row.notification.clone());
+        }
+
+        let payload = serde_json::to_value(payload).map_err(|e| sqlx::Error::Encode(e.into()))?;
+        let notification = Notification {
+            id: id.to_string(),
+            client_id: client_id.to_string(),
+            last_payload: Json(payload),
+            previous_payloads: vec![],
+            status: DeliveryStatus::Queued,
+            attempts: 0,
+            last_error: None,
+            delivered_at: None,
+            provider: None,
+            provider_message_id: None,
+            last_received_at: now,
+            created_at: now,
+        };
+        tables.notifications.insert(
+            (id.to_string(), client_id.to_string()),
+            NotificationRow {
+                tenant_id: tenant_id.to_string(),
+                notification: notification.clone(),
+            },
+        );
+
+        Ok(notification)
+    }
+
+    async fn get_notification(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+    ) -> stores::Result<Notification> {
+        self.tables()
+            .notifications
+            .get(&(id.to_string(), client_id.to_string()))
+            .filter(|row| row.tenant_id == tenant_id)
+            .map(|row| row.notification.clone())
+            .ok_or_else(|| NotFound("notification".to_string(), id.to_string()))
+    }
+
+    async fn delete_notification(&self, id: &str, tenant_id: &str) -> stores::Result<()> {
+        self.tables()
+            .notifications
+            .retain(|(notification_id, _), row| {
+                notification_id != id || row.tenant_id != tenant_id
+            });
+
+        Ok(())
+    }
+
+    async fn update_notification_status(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+        status: DeliveryStatus,
+        attempts: i32,
+        last_error: Option<&str>,
+    ) -> stores::Result<()> {
+        let mut tables = self.tables();
+        if let Some(row) = tables
+            .notifications
+            .get_mut(&(id.to_string(), client_id.to_string()))
+            .filter(|row| row.tenant_id == tenant_id)
+        {
+            let notification = &mut row.notification;
+            notification.status = status;
+            notification.attempts = attempts;
+            notification.last_error = last_error.map(ToString::to_string);
+            notification.delivered_at = (status == DeliveryStatus::Delivered).then(Utc::now);
+        }
+
+        Ok(())
+    }
+
+    async fn update_notification_provider(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+        provider: &str,
+        provider_message_id: Option<&str>,
+    ) -> stores::Result<()> {
+        let mut tables = self.tables();
+        if let Some(row) = tables
+            .notifications
+            .get_mut(&(id.to_string(), client_id.to_string()))
+            .filter(|row| row.tenant_id == tenant_id)
+        {
+            row.notification.provider = Some(provider.to_string());
+            row.notification.provider_message_id = provider_message_id.map(ToString::to_string);
+        }
+
+        Ok(())
+    }
+
+    async fn get_client_notifications(
+        &self,
+        tenant_id: &str,
+        client_id: &str,
+        limit: i64,
+    ) -> stores::Result<Vec<Notification>> {
+        let mut notifications = self
+            .tables()
+            .notifications
+            .values()
+            .filter(|row| row.tenant_id == tenant_id && row.notification.client_id == client_id)
+            .map(|row| row.notification.clone())
+            .collect::<Vec<_>>();
+        notifications.sort_by(|a, b| {
+            b.last_received_at
+                .cmp(&a.last_received_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        notifications.truncate(limit.max(0) as usize);
+
+        Ok(notifications)
+    }
+
+    async fn create_notification_partition(&self, _day: NaiveDate) -> stores::Result<()> {
+        Ok(())
+    }
+
+    async fn drop_notification_partitions(&self, _before: DateTime<Utc>) -> stores::Result<u64> {
+        // There are no partitions, expired notifications are only pruned
+        Ok(0)
+    }
+
+    async fn prune_notifications(&self, before: DateTime<Utc>, limit: i64) -> stores::Result<u64> {
+        let mut tables = self.tables();
+        let expired = tables
+            .notifications
+            .iter()
+            .filter(|(_, row)| row.notification.created_at < before)
+            .map(|(key, _)| key.clone())
+            .take(limit.max(0) as usize)
+            .collect::<Vec<_>>();
+        for key in &expired {
+            tables.notifications.remove(key);
+        }
+
+        Ok(expired.len() as u64)
+    }
+
+    async fn backfill_notification_partitions(&self, _limit: i64) -> stores::Result<u64> {
+        Ok(0)
+    }
+}
+
+#[async_trait]
+impl DeliveryStore for MemoryStore {
+    async fn enqueue_delivery(
+        &self,
+        notification_id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+        lease: Duration,
+    ) -> stores::Result<Option<QueuedDelivery>> {
+        let mut tables = self.tables();
+        let key = (notification_id.to_string(), client_id.to_string());
+        if tables.queue.contains_key(&key) {
+            return Ok(None);
+        }
+
+        let delivery = QueuedDelivery {
+            notification_id: notification_id.to_string(),
+            client_id: client_id.to_string(),
+            tenant_id: tenant_id.to_string(),
+            payload: Json(payload.clone()),
+            attempts: 1,
+            next_attempt_at: lease_expiry(lease),
+            created_at: Utc::now(),
+        };
+        tables.queue.insert(key, delivery.clone());
+
+        Ok(Some(delivery))
+    }
+
+    async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<QueuedDelivery>> {
+        let now = Utc::now();
+        let mut tables = self.tables();
+        let mut due = tables
+            .queue
+            .values_mut()
+            .filter(|delivery| delivery.next_attempt_at <= now)
+            .collect::<Vec<_>>();
+        due.sort_by_key(|delivery| delivery.next_attempt_at);
+
+        let expiry = lease_expiry(lease);
+        Ok(due
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|delivery| {
+                delivery.attempts += 1;
+                delivery.next_attempt_at = expiry;
+                delivery.clone()
+            })
+            .collect())
+    }
+
+    async fn reschedule_delivery(
+        &self,
+        notification_id: &str,
+        client_id: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> stores::Result<()> {
+        if let Some(delivery) = self
+            .tables()
+            .queue
+            .get_mut(&(notification_id.to_string(), client_id.to_string()))
+        {
+            delivery.next_attempt_at = next_attempt_at;
+        }
+
+        Ok(())
+    }
+
+    async fn remove_delivery(&self, notification_id: &str, client_id: &str) -> stores::Result<()> {
+        self.tables()
+            .queue
+            .remove(&(notification_id.to_string(), client_id.to_string()));
+
+        Ok(())
+    }
+
+    async fn schedule_delivery(
+        &self,
+        notification_id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+        send_at: DateTime<Utc>,
+    ) -> stores::Result<bool> {
+        let mut tables = self.tables();
+        let key = (notification_id.to_string(), client_id.to_string());
+        if tables.scheduled.contains_key(&key) {
+            return Ok(false);
+        }
+        tables.scheduled.insert(
+            key,
+            ScheduledRow {
+                tenant_id: tenant_id.to_string(),
+                payload: payload.clone(),
+                send_at,
+            },
+        );
+
+        Ok(true)
+    }
+
+    async fn claim_due_scheduled_deliveries(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<QueuedDelivery>> {
+        let now = Utc::now();
+        let mut tables = self.tables();
+        let mut due = tables
+            .scheduled
+            .iter()
+            .filter(|(_, row)| row.send_at <= now)
+            .map(|(key, row)| (key.clone(), row.send_at))
+            .collect::<Vec<_>>();
+        due.sort_by_key(|(_, send_at)| *send_at);
+        due.truncate(limit.max(0) as usize);
+
+        let expiry = lease_expiry(lease);
+        let mut deliveries = vec![];
+        for (key, _) in due {
+            // Tried again when the lease expires
+            if tables.queue.contains_key(&key) {
+                if let Some(row) = tables.scheduled.get_mut(&key) {
+                    row.send_at = expiry;
+                }
+                continue;
+            }
+            let Some(row) = tables.scheduled.remove(&key) else {
+                continue;
+            };
+            let delivery = QueuedDelivery {
+                notification_id: key.0.clone(),
+                client_id: key.1.clone(),
+                tenant_id: row.tenant_id,
+                payload: Json(row.payload),
+                attempts: 1,
+                next_attempt_at: expiry,
+                created_at: now,
+            };
+            tables.queue.insert(key, delivery.clone());
+            deliveries.push(delivery);
+        }
+
+        Ok(deliveries)
+    }
+
+    async fn cancel_scheduled_delivery(
+        &self,
+        tenant_id: &str,
+        notification_id: &str,
+    ) -> stores::Result<Vec<String>> {
+        let mut client_ids = vec![];
+        self.tables().scheduled.retain(|(id, client_id), row| {
+            let cancelled = id == notification_id && row.tenant_id == tenant_id;
+            if cancelled {
+                client_ids.push(client_id.clone());
+            }
+            !cancelled
+        });
+
+        Ok(client_ids)
+    }
+}
+
+#[async_trait]
+impl WebhookStore for MemoryStore {
+    async fn enqueue_webhook_event(
+        &self,
+        tenant_id: &str,
+        event: &WebhookEvent,
+    ) -> stores::Result<WebhookDelivery> {
+        let now = Utc::now();
+        let delivery = WebhookDelivery {
+            id: Uuid::new_v4().to_string(),
+            tenant_id: tenant_id.to_string(),
+            event_type: event.event_type().to_string(),
+            payload: Json(event.clone()),
+            status: DeliveryStatus::Queued,
+            attempts: 0,
+            response_status: None,
+            last_error: None,
+            next_attempt_at: now,
+            delivered_at: None,
+            created_at: now,
+        };
+        self.tables()
+            .webhook_events
+            .insert(delivery.id.clone(), delivery.clone());
+
+        Ok(delivery)
+    }
+
+    async fn claim_due_webhook_events(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<WebhookDelivery>> {
+        let now = Utc::now();
+        let mut tables = self.tables();
+        let mut due = tables
+            .webhook_events
+            .values_mut()
+            .filter(|event| event.status == DeliveryStatus::Queued && event.next_attempt_at <= now)
+            .collect::<Vec<_>>();
+        due.sort_by_key(|event| event.next_attempt_at);
+
+        let expiry = lease_expiry(lease);
+        Ok(due
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|event| {
+                event.attempts += 1;
+                event.next_attempt_at = expiry;
+                event.clone()
+            })
+            .collect())
+    }
+
+    async fn update_webhook_event(
+        &self,
+        id: &str,
+        status: DeliveryStatus,
+        response_status: Option<i32>,
+        last_error: Option<&str>,
+        next_attempt_at: DateTime<Utc>,
+    ) -> stores::Result<()> {
+        if let Some(event) = self.tables().webhook_events.get_mut(id) {
+            event.status = status;
+            event.response_status = response_status;
+            event.last_error = last_error.map(ToString::to_string);
+            event.next_attempt_at = next_attempt_at;
+            event.delivered_at = (status == DeliveryStatus::Delivered).then(Utc::now);
+        }
+
+        Ok(())
+    }
+
+    async fn get_webhook_events(
+        &self,
+        tenant_id: &str,
+        limit: i64,
+    ) -> stores::Result<Vec<WebhookDelivery>> {
+        let mut events = self
+            .tables()
+            .webhook_events
+            .values()
+            .filter(|event| event.tenant_id == tenant_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        events.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        events.truncate(limit.max(0) as usize);
+
+        Ok(events)
+    }
+}
+
+impl MemoryStore {
+    fn update_tenant(&self, id: &str, update: impl FnOnce(&mut Tenant)) -> Result<Tenant> {
+        let mut tables = self.tables();
+        let tenant = tables.tenant_mut(id)?;
+        update(tenant);
+        tenant.updated_at = Utc::now();
+
+        Ok(tenant.clone())
+    }
+
+    /// Applies a credential update and lifts the tenant's suspension when the
+    /// provider's new credentials resolve what it was suspended for
+    fn update_credentials(
+        &self,
+        id: &str,
+        resolves: &[&str],
+        update: impl FnOnce(&mut Tenant),
+    ) -> Result<Tenant> {
+        let mut tables = self.tables();
+        let tenant = tables.tenant_mut(id)?;
+        update(tenant);
+        tenant.updated_at = Utc::now();
+        let resolved = tenant
+            .suspended_reason
+            .as_deref()
+            .map_or(false, |reason| resolves.contains(&reason));
+        if resolved {
+            tables.unsuspend(id, CREDENTIALS_UPDATED_REASON)?;
+        }
+
+        tables.tenant_mut(id).map(|tenant| tenant.clone())
+    }
+}
+
+#[async_trait]
+impl TenantStore for MemoryStore {
+    async fn get_tenant(&self, id: &str) -> Result<Tenant> {
+        self.tables()
+            .tenants
+            .get(id)
+            .cloned()
+            .ok_or_else(|| InvalidTenantId(id.into()))
+    }
+
+    async fn get_tenant_ids(&self) -> Result<Vec<String>> {
+        Ok(self.tables().tenants.keys().cloned().collect())
+    }
+
+    async fn list_tenants(&self, params: TenantListParams) -> Result<Vec<Tenant>> {
+        Ok(self
+            .tables()
+            .tenants
+            .values()
+            .filter(|tenant| {
+                params
+                    .suspended
+                    .is_none_or(|suspended| tenant.suspended == suspended)
+                    && params
+                        .provider
+                        .is_none_or(|provider| tenant.providers().contains(&provider))
+                    && params
+                        .updated_after
+                        .is_none_or(|updated_after| tenant.updated_at >= updated_after)
+                    && params
+                        .updated_before
+                        .is_none_or(|updated_before| tenant.updated_at < updated_before)
+                    && params
+                        .id_prefix
+                        .as_ref()
+                        .is_none_or(|id_prefix| tenant.id.starts_with(id_prefix.as_str()))
+                    && params
+                        .after_id
+                        .as_ref()
+                        .is_none_or(|after_id| tenant.id > *after_id)
+            })
+            .take(params.limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_client_segments(
+        &self,
+        tenant_id: &str,
+        segment: ClientSegment,
+        limit: i64,
+    ) -> stores::Result<Vec<ClientSegmentCount>> {
+        let mut counts = HashMap::<Option<String>, i64>::new();
+        for client in self
+            .tables()
+            .clients
+            .values()
+            .filter(|client| client.tenant_id == tenant_id)
+        {
+            *counts
+                .entry(segment.value(&client.metadata).map(ToString::to_string))
+                .or_default() += 1;
+        }
+
+        let mut segments = counts
+            .into_iter()
+            .map(|(value, clients)| ClientSegmentCount { value, clients })
+            .collect::<Vec<_>>();
+        // Postgres sorts nulls last
+        segments.sort_by(|a, b| {
+            b.clients
+                .cmp(&a.clients)
+                .then_with(|| a.value.is_none().cmp(&b.value.is_none()))
+                .then_with(|| a.value.cmp(&b.value))
+        });
+        segments.truncate(limit.max(0) as usize);
+
+        Ok(segments)
+    }
+
+    async fn get_tenant_summary(&self) -> Result<TenantSummary> {
+        let tables = self.tables();
+        let count = |filter: fn(&Tenant) -> bool| {
+            tables
+                .tenants
+                .values()
+                .filter(|tenant| filter(tenant))
+                .count() as i64
+        };
+
+        Ok(TenantSummary {
+            total: count(|_| true),
+            suspended: count(|tenant| tenant.suspended),
+            apns: count(|tenant| tenant.get_apns_type().is_some()),
+            fcm: count(|tenant| {
+                tenant.fcm_api_key.is_some() || tenant.fcm_v1_credentials.is_some()
+            }),
+            fcm_v1: count(|tenant| tenant.fcm_v1_credentials.is_some()),
+            hms: count(|tenant| tenant.hms_app_id.is_some() && tenant.hms_app_secret.is_some()),
+            web_push: count(|tenant| {
+                tenant.web_push_vapid_key.is_some() && tenant.web_push_vapid_subject.is_some()
+            }),
+        })
+    }
+
+    async fn delete_tenant(&self, id: &str) -> Result<()> {
+        let mut tables = self.tables();
+        tables.tenants.remove(id);
+        tables
+            .suspension_history
+            .retain(|(tenant_id, _)| tenant_id != id);
+        tables.templates.retain(|(tenant_id, _), _| tenant_id != id);
+
+        Ok(())
+    }
+
+    async fn create_tenant(&self, params: TenantUpdateParams) -> Result<Tenant> {
+        let now = Utc::now();
+        let mut tables = self.tables();
+        let tenant = tables
+            .tenants
+            .entry(params.id.clone())
+            .and_modify(|tenant| tenant.updated_at = now)
+            .or_insert_with(|| Tenant {
+                id: params.id,
+                fcm_api_key: None,
+                fcm_v1_credentials: None,
+                apns_type: None,
+                apns_topic: None,
+                apns_certificate: None,
+                apns_certificate_password: None,
+                apns_pkcs8_pem: None,
+                apns_key_id: None,
+                apns_team_id: None,
+                hms_app_id: None,
+                hms_app_secret: None,
+                web_push_vapid_key: None,
+                web_push_vapid_subject: None,
+                push_rate_limit_per_minute: None,
+                push_rate_limit_burst: None,
+                client_push_rate_limit_per_minute: None,
+                client_push_rate_limit_burst: None,
+                suspended: false,
+                suspended_reason: None,
+                apns_certificate_expires_at: None,
+                apns_expiry_notified_at: None,
+                webhook_url: None,
+                webhook_secret: None,
+                created_at: now,
+                updated_at: now,
+            });
+
+        Ok(tenant.clone())
+    }
+
+    async fn update_tenant_fcm(&self, id: &str, params: TenantFcmUpdateParams) -> Result<Tenant> {
+        self.update_credentials(id, FCM_SUSPENSION_REASONS, |tenant| {
+            tenant.fcm_api_key = Some(params.fcm_api_key);
+        })
+    }
+
+    async fn update_tenant_delete_fcm(&self, id: &str) -> Result<Tenant> {
+        self.update_tenant(id, |tenant| {
+            tenant.fcm_api_key = None;
+        })
+    }
+
+    async fn update_tenant_fcm_v1(
+        &self,
+        id: &str,
+        params: TenantFcmV1UpdateParams,
+    ) -> Result<Tenant> {
+        self.update_credentials(id, FCM_SUSPENSION_REASONS, |tenant| {
+            tenant.fcm_v1_credentials = Some(params.fcm_v1_credentials);
+        })
+    }
+
+    async fn update_tenant_delete_fcm_v1(&self, id: &str) -> Result<Tenant> {
+        self.update_tenant(id, |tenant| {
+            tenant.fcm_v1_credentials = None;
+        })
+    }
+
+    async fn update_tenant_apns(&self, id: &str, params: TenantApnsUpdateParams) -> Result<Tenant> {
+        self.update_tenant(id, |tenant| {
+            tenant.apns_topic = Some(params.apns_topic);
+        })
+    }
+
+    async fn update_tenant_apns_auth(
+        &self,
+        id: &str,
+        params: TenantApnsUpdateAuth,
+    ) -> Result<Tenant> {
+        self.update_credentials(id, APNS_SUSPENSION_REASONS, |tenant| {
+            tenant.apns_certificate_expires_at = None;
+            tenant.apns_expiry_notified_at = None;
+            match params {
+                TenantApnsUpdateAuth::Certificate {
+                    apns_certificate,
+                    apns_certificate_password,
+                } => {
+                    tenant.apns_type = Some(ApnsType::Certificate);
+                    tenant.apns_certificate = Some(apns_certificate);
+                    tenant.apns_certificate_password = Some(apns_certificate_password);
+                    tenant.apns_pkcs8_pem = None;
+                    tenant.apns_team_id = None;
+                    tenant.apns_key_id = None;
+                }
+                TenantApnsUpdateAuth::Token {
+                    apns_pkcs8_pem,
+                    apns_key_id,
+                    apns_team_id,
+                } => {
+                    tenant.apns_type = Some(ApnsType::Token);
+                    tenant.apns_pkcs8_pem = Some(apns_pkcs8_pem);
+                    tenant.apns_team_id = Some(apns_team_id);
+                    tenant.apns_key_id = Some(apns_key_id);
+                    tenant.apns_certificate = None;
+                    tenant.apns_certificate_password = None;
+                }
+            }
+        })
+    }
+
+    async fn update_tenant_delete_apns(&self, id: &str) -> Result<Tenant> {
+        self.update_tenant(id, |tenant| {
+            tenant.apns_topic = None;
+            tenant.apns_type = None;
+            tenant.apns_certificate = None;
+            tenant.apns_certificate_password = None;
+            tenant.apns_pkcs8_pem = None;
+            tenant.apns_team_id = None;
+            tenant.apns_key_id = None;
+            tenant.apns_certificate_expires_at = None;
+            tenant.apns_expiry_notified_at = None;
+        })
+    }
+
+    async fn update_tenant_hms(&self, id: &str, params: TenantHmsUpdateParams) -> Result<Tenant> {
+        self.update_credentials(id, HMS_SUSPENSION_REASONS, |tenant| {
+            tenant.hms_app_id = Some(params.hms_app_id);
+            tenant.hms_app_secret = Some(params.hms_app_secret);
+        })
+    }
+
+    async fn update_tenant_delete_hms(&self, id: &str) -> Result<Tenant> {
+        self.update_tenant(id, |tenant| {
+            tenant.hms_app_id = None;
+            tenant.hms_app_secret = None;
+        })
+    }
+
+    async fn update_tenant_web_push(
+        &self,
+        id: &str,
+        params: TenantWebPushUpdateParams,
+    ) -> Result<Tenant> {
+        self.update_credentials(id, WEB_PUSH_SUSPENSION_REASONS, |tenant| {
+            tenant.web_push_vapid_key = Some(params.web_push_vapid_key);
+            tenant.web_push_vapid_subject = Some(params.web_push_vapid_subject);
+        })
+    }
+
+    async fn update_tenant_delete_web_push(&self, id: &str) -> Result<Tenant> {
+        self.update_tenant(id, |tenant| {
+            tenant.web_push_vapid_key = None;
+            tenant.web_push_vapid_subject = None;
+        })
+    }
+
+    async fn update_tenant_rate_limits(
+        &self,
+        id: &str,
+        params: TenantRateLimitUpdateParams,
+    ) -> Result<Tenant> {
+        self.update_tenant(id, |tenant| {
+            tenant.push_rate_limit_per_minute = params.push_rate_limit_per_minute;
+            tenant.push_rate_limit_burst = params.push_rate_limit_burst;
+            tenant.client_push_rate_limit_per_minute = params.client_push_rate_limit_per_minute;
+            tenant.client_push_rate_limit_burst = params.client_push_rate_limit_burst;
+        })
+    }
+
+    async fn update_tenant_webhook(
+        &self,
+        id: &str,
+        params: TenantWebhookUpdateParams,
+    ) -> Result<Tenant> {
+        self.update_tenant(id, |tenant| {
+            tenant.webhook_url = Some(params.webhook_url);
+            tenant.webhook_secret = Some(params.webhook_secret);
+        })
+    }
+
+    async fn update_tenant_delete_webhook(&self, id: &str) -> Result<Tenant> {
+        self.update_tenant(id, |tenant| {
+            tenant.webhook_url = None;
+            tenant.webhook_secret = None;
+        })
+    }
+
+    async fn update_tenant_credentials(
+        &self,
+        id: &str,
+        params: TenantCredentials,
+    ) -> Result<Tenant> {
+        self.update_tenant(id, |tenant| tenant.set_credentials(params))
+    }
+
+    async fn suspend_tenant(&self, id: &str, params: TenantSuspendParams) -> Result<bool> {
+        let mut tables = self.tables();
+        let Some(tenant) = tables
+            .tenants
+            .get_mut(id)
+            .filter(|tenant| !tenant.suspended)
+        else {
+            return Ok(false);
+        };
+        tenant.suspended = true;
+        tenant.suspended_reason = Some(params.reason.clone());
+        tenant.updated_at = Utc::now();
+        tables.record_suspension(
+            id,
+            SuspensionAction::Suspended,
+            &params.reason,
+            params.client_id,
+            params.notification_id,
+        );
+
+        Ok(true)
+    }
+
+    async fn unsuspend_tenant(&self, id: &str, reason: &str) -> Result<bool> {
+        let mut tables = self.tables();
+        if !tables.tenants.contains_key(id) {
+            return Ok(false);
+        }
+
+        tables.unsuspend(id, reason)
+    }
+
+    async fn get_tenant_suspension_history(&self, id: &str) -> Result<Vec<TenantSuspension>> {
+        Ok(self
+            .tables()
+            .suspension_history
+            .iter()
+            .rev()
+            .filter(|(tenant_id, _)| tenant_id == id)
+            .map(|(_, suspension)| suspension.clone())
+            .collect())
+    }
+
+    async fn get_apns_certificate_tenants(&self) -> Result<Vec<Tenant>> {
+        Ok(self
+            .tables()
+            .tenants
+            .values()
+            .filter(|tenant| tenant.apns_type == Some(ApnsType::Certificate))
+            .cloned()
+            .collect())
+    }
+
+    async fn update_tenant_apns_certificate_expiry(
+        &self,
+        id: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        if let Some(tenant) = self.tables().tenants.get_mut(id) {
+            tenant.apns_certificate_expires_at = expires_at;
+        }
+
+        Ok(())
+    }
+
+    async fn mark_tenant_apns_expiry_notified(&self, id: &str) -> Result<bool> {
+        let mut tables = self.tables();
+        let Some(tenant) = tables
+            .tenants
+            .get_mut(id)
+            .filter(|tenant| tenant.apns_expiry_notified_at.is_none())
+        else {
+            return Ok(false);
+        };
+        tenant.apns_expiry_notified_at = Some(Utc::now());
+
+        Ok(true)
+    }
+
+    async fn get_tenant_templates(&self, id: &str) -> Result<Vec<NotificationTemplate>> {
+        Ok(self
+            .tables()
+            .templates
+            .values()
+            .filter(|template| template.tenant_id == id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_tenant_template(&self, id: &str, name: &str) -> Result<NotificationTemplate> {
+        self.tables()
+            .templates
+            .get(&(id.to_string(), name.to_string()))
+            .cloned()
+            .ok_or_else(|| TemplateNotFound(name.to_string()))
+    }
+
+    async fn update_tenant_template(
+        &self,
+        id: &str,
+        name: &str,
+        params: TenantTemplateUpdateParams,
+    ) -> Result<NotificationTemplate> {
+        let now = Utc::now();
+        let mut tables = self.tables();
+        let template = tables
+            .templates
+            .entry((id.to_string(), name.to_string()))
+            .or_insert_with(|| NotificationTemplate {
+                tenant_id: id.to_string(),
+                name: name.to_string(),
+                mode: params.mode,
+                default_locale: String::new(),
+                localizations: Json(BTreeMap::new()),
+                created_at: now,
+                updated_at: now,
+            });
+        template.mode = params.mode;
+        template.default_locale = params.default_locale;
+        template.localizations = Json(params.localizations);
+        template.updated_at = now;
+
+        Ok(template.clone())
+    }
+
+    async fn delete_tenant_template(&self, id: &str, name: &str) -> Result<()> {
+        self.tables()
+            .templates
+            .remove(&(id.to_string(), name.to_string()))
+            .map(|_| ())
+            .ok_or_else(|| TemplateNotFound(name.to_string()))
+    }
+}
@@ -1,14 +1,35 @@
 pub mod client;
+pub mod delivery;
+pub mod encrypted_tenant;
+pub mod memory;
 pub mod notification;
+pub mod redis_notification;
+pub mod sqlite;
 pub mod tenant;
+pub mod webhook;
 
 type Result<T> = std::result::Result<T, StoreError>;
 
+/// Where clients, notifications, the queues and tenants are stored
+#[derive(serde::Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    /// Lost on restart and not shared between instances
+    Memory,
+    /// A file at `DATABASE_URL`, for a single single-tenant instance
+    Sqlite,
+    /// `DATABASE_URL`, with tenants at `TENANT_DATABASE_URL`
+    Postgres,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum StoreError {
     #[error(transparent)]
     Database(#[from] sqlx::Error),
 
+    #[error(transparent)]
+    Redis(#[from] redis::RedisError),
+
     /// Not found error, params are entity name and identifier
     #[error("Cannot find {0} with specified identifier {1}")]
     NotFound(String, String),
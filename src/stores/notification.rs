@@ -4,12 +4,32 @@ use {
         stores::{self, StoreError::NotFound},
     },
     async_trait::async_trait,
-    chrono::{DateTime, Utc},
+    chrono::{DateTime, Days, NaiveDate, Utc},
+    serde::{Deserialize, Serialize},
     serde_json::Value,
     sqlx::{types::Json, Executor},
     tracing::instrument,
 };
 
+/// Daily partitions of the notifications table are named with this prefix
+/// followed by the day, e.g. `notifications_p20241031`
+const PARTITION_PREFIX: &str = "notifications_p";
+const PARTITION_DATE_FORMAT: &str = "%Y%m%d";
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "delivery_status")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Queued,
+    Delivered,
+    Failed,
+    /// Waiting for its `send_at` time
+    Scheduled,
+    /// Cancelled before its `send_at` time
+    Cancelled,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
 pub struct Notification {
     pub id: String,
@@ -18,12 +38,36 @@ pub struct Notification {
     pub last_payload: Json<Value>,
     pub previous_payloads: Vec<Json<Value>>,
 
+    // Delivery
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub provider: Option<String>,
+    pub provider_message_id: Option<String>,
+
     pub last_received_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
 }
 
 #[async_trait]
 pub trait NotificationStore {
+    /// Claims the message id for the client before the notification is
+    /// stored, returns false if it was already received
+    async fn claim_notification(
+        &self,
+        id: &str,
+        tenant_id: &str,
+        client_id: &str,
+    ) -> stores::Result<bool>;
+    /// Releases the claim on a notification that wasn't stored after all, so
+    /// it can be sent again
+    async fn release_notification(
+        &self,
+        id: &str,
+        tenant_id: &str,
+        client_id: &str,
+    ) -> stores::Result<()>;
     async fn create_or_update_notification(
         &self,
         id: &str,
@@ -38,10 +82,73 @@ pub trait NotificationStore {
         tenant_id: &str,
     ) -> stores::Result<Notification>;
     async fn delete_notification(&self, id: &str, tenant_id: &str) -> stores::Result<()>;
+    async fn update_notification_status(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+        status: DeliveryStatus,
+        attempts: i32,
+        last_error: Option<&str>,
+    ) -> stores::Result<()>;
+    /// Records the provider that accepted the notification and its id for it
+    async fn update_notification_provider(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+        provider: &str,
+        provider_message_id: Option<&str>,
+    ) -> stores::Result<()>;
+    /// The client's most recently received notifications, newest first
+    async fn get_client_notifications(
+        &self,
+        tenant_id: &str,
+        client_id: &str,
+        limit: i64,
+    ) -> stores::Result<Vec<Notification>>;
+    /// Creates the partition holding notifications received on `day` if it
+    /// doesn't exist yet
+    async fn create_notification_partition(&self, day: NaiveDate) -> stores::Result<()>;
+    /// Drops the daily partitions that only hold notifications received before
+    /// `before`, returning how many were dropped
+    async fn drop_notification_partitions(&self, before: DateTime<Utc>) -> stores::Result<u64>;
+    /// Deletes up to `limit` notifications received before `before`, returning
+    /// how many were deleted
+    async fn prune_notifications(&self, before: DateTime<Utc>, limit: i64) -> stores::Result<u64>;
+    /// Moves up to `limit` notifications stored before the table was
+    /// partitioned into their daily partitions, dropping the old table once
+    /// it's empty. Returns how many were moved
+    async fn backfill_notification_partitions(&self, limit: i64) -> stores::Result<u64>;
 }
 
 #[async_trait]
 impl NotificationStore for sqlx::PgPool {
+    #[instrument(skip(self))]
+    async fn claim_notification(
+        &self,
+        id: &str,
+        tenant_id: &str,
+        client_id: &str,
+    ) -> stores::Result<bool> {
+        // Nothing is recorded until the notification is stored, which is
+        // deduplicated under an advisory lock
+        match self.get_notification(id, client_id, tenant_id).await {
+            Ok(_) => Ok(false),
+            Err(NotFound(..)) => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn release_notification(
+        &self,
+        _id: &str,
+        _tenant_id: &str,
+        _client_id: &str,
+    ) -> stores::Result<()> {
+        Ok(())
+    }
+
     #[instrument(skip(self, payload))]
     async fn create_or_update_notification(
         &self,
@@ -57,21 +164,39 @@ impl NotificationStore for sqlx::PgPool {
             .execute(&mut transaction)
             .await?;
 
-        let res = sqlx::query_as::<sqlx::postgres::Postgres, Notification>(
+        // The table is partitioned so (id, client_id) can't have a unique
+        // constraint, the advisory lock keeps the update or insert atomic
+        let existing = sqlx::query_as::<sqlx::postgres::Postgres, Notification>(
             "
-            INSERT INTO public.notifications (id, tenant_id, client_id, last_payload)
-            VALUES ($1, $2, $3, $4)
-            ON CONFLICT (id, client_id)
-                DO UPDATE SET last_received_at  = now()
+            UPDATE public.notifications
+            SET last_received_at = now()
+            WHERE id = $1 AND client_id = $2
             RETURNING *;",
         )
         .bind(id)
-        .bind(tenant_id)
         .bind(client_id)
-        .bind(Json(payload))
-        .fetch_one(&mut transaction)
+        .fetch_optional(&mut transaction)
         .await;
 
+        let res = match existing {
+            Ok(Some(row)) => Ok(row),
+            Ok(None) => {
+                sqlx::query_as::<sqlx::postgres::Postgres, Notification>(
+                    "
+                    INSERT INTO public.notifications (id, tenant_id, client_id, last_payload)
+                    VALUES ($1, $2, $3, $4)
+                    RETURNING *;",
+                )
+                .bind(id)
+                .bind(tenant_id)
+                .bind(client_id)
+                .bind(Json(payload))
+                .fetch_one(&mut transaction)
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
         transaction.commit().await?;
 
         match res {
@@ -121,4 +246,285 @@ impl NotificationStore for sqlx::PgPool {
 
         Ok(())
     }
+
+    #[instrument(skip(self))]
+    async fn update_notification_status(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+        status: DeliveryStatus,
+        attempts: i32,
+        last_error: Option<&str>,
+    ) -> stores::Result<()> {
+        let query = "
+            UPDATE public.notifications
+            SET status = $4,
+                attempts = $5,
+                last_error = $6,
+                delivered_at = CASE WHEN $4 = 'delivered'::delivery_status THEN now() END
+            WHERE id = $1
+                  AND client_id = $2
+                  AND tenant_id = $3
+        ";
+        sqlx::query(query)
+            .bind(id)
+            .bind(client_id)
+            .bind(tenant_id)
+            .bind(status)
+            .bind(attempts)
+            .bind(last_error)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn update_notification_provider(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+        provider: &str,
+        provider_message_id: Option<&str>,
+    ) -> stores::Result<()> {
+        let query = "
+            UPDATE public.notifications
+            SET provider = $4,
+                provider_message_id = $5
+            WHERE id = $1
+                  AND client_id = $2
+                  AND tenant_id = $3
+        ";
+        sqlx::query(query)
+            .bind(id)
+            .bind(client_id)
+            .bind(tenant_id)
+            .bind(provider)
+            .bind(provider_message_id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_client_notifications(
+        &self,
+        tenant_id: &str,
+        client_id: &str,
+        limit: i64,
+    ) -> stores::Result<Vec<Notification>> {
+        let query = "
+            SELECT *
+            FROM public.notifications
+            WHERE tenant_id = $1 AND client_id = $2
+            ORDER BY last_received_at DESC, id
+            LIMIT $3
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Notification>(query)
+            .bind(tenant_id)
+            .bind(client_id)
+            .bind(limit)
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn create_notification_partition(&self, day: NaiveDate) -> stores::Result<()> {
+        let next_day = day + Days::new(1);
+        // DDL can't take bind parameters, the values are formatted dates
+        let query = format!(
+            "CREATE TABLE IF NOT EXISTS public.{PARTITION_PREFIX}{}
+                PARTITION OF public.notifications
+                FOR VALUES FROM ('{day}') TO ('{next_day}')",
+            day.format(PARTITION_DATE_FORMAT),
+        );
+        sqlx::query(&query).execute(self).await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn drop_notification_partitions(&self, before: DateTime<Utc>) -> stores::Result<u64> {
+        let partitions = sqlx::query_scalar::<sqlx::postgres::Postgres, String>(
+            "
+            SELECT child.relname::text
+            FROM pg_inherits
+                JOIN pg_class child ON child.oid = pg_inherits.inhrelid
+            WHERE pg_inherits.inhparent = 'public.notifications'::regclass",
+        )
+        .fetch_all(self)
+        .await?;
+
+        let mut dropped = 0;
+        for partition in partitions {
+            // Skips the default partition
+            let Some(end) = partition
+                .strip_prefix(PARTITION_PREFIX)
+                .and_then(|day| NaiveDate::parse_from_str(day, PARTITION_DATE_FORMAT).ok())
+                .and_then(|day| (day + Days::new(1)).and_hms_opt(0, 0, 0))
+            else {
+                continue;
+            };
+            if end.and_utc() > before {
+                continue;
+            }
+
+            sqlx::query(&format!("DROP TABLE IF EXISTS public.{partition}"))
+                .execute(self)
+                .await?;
+            dropped += 1;
+        }
+
+        Ok(dropped)
+    }
+
+    #[instrument(skip(self))]
+    async fn prune_notifications(&self, before: DateTime<Utc>, limit: i64) -> stores::Result<u64> {
+        let query = "
+            DELETE FROM public.notifications
+            WHERE (id, client_id, created_at) IN (
+                SELECT id, client_id, created_at
+                FROM public.notifications
+                WHERE created_at < $1
+                LIMIT $2
+            )
+        ";
+        let res = sqlx::query(query)
+            .bind(before)
+            .bind(limit)
+            .execute(self)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    #[instrument(skip(self))]
+    async fn backfill_notification_partitions(&self, limit: i64) -> stores::Result<u64> {
+        // Ordered by the old primary key so batches don't scan the whole table,
+        // other instances backfilling at the same time skip the locked rows
+        const BATCH_QUERY: &str = "
+            SELECT id, client_id, created_at
+            FROM public.notifications_unpartitioned
+            ORDER BY id, client_id
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        ";
+
+        let mut transaction = self.begin().await?;
+
+        let exists = sqlx::query_scalar::<sqlx::postgres::Postgres, bool>(
+            "SELECT to_regclass('public.notifications_unpartitioned') IS NOT NULL",
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+        if !exists {
+            return Ok(0);
+        }
+
+        let mut batch =
+            sqlx::query_as::<sqlx::postgres::Postgres, (String, String, DateTime<Utc>)>(
+                BATCH_QUERY,
+            )
+            .bind(limit)
+            .fetch_all(&mut transaction)
+            .await?;
+        if batch.is_empty() {
+            // Waits for the batches of other instances, rows that are still
+            // there afterwards were rolled back and are moved here
+            sqlx::query("LOCK TABLE public.notifications_unpartitioned IN ACCESS EXCLUSIVE MODE")
+                .execute(&mut transaction)
+                .await?;
+            batch = sqlx::query_as::<sqlx::postgres::Postgres, (String, String, DateTime<Utc>)>(
+                BATCH_QUERY,
+            )
+            .bind(limit)
+            .fetch_all(&mut transaction)
+            .await?;
+            if batch.is_empty() {
+                sqlx::query("DROP TABLE public.notifications_unpartitioned")
+                    .execute(&mut transaction)
+                    .await?;
+                transaction.commit().await?;
+                return Ok(0);
+            }
+        }
+
+        let mut days = batch
+            .iter()
+            .map(|(_, _, created_at)| created_at.date_naive())
+            .collect::<Vec<_>>();
+        days.sort();
+        days.dedup();
+        for day in days {
+            // Rows of a day whose partition can't be created land in the
+            // default partition
+            let _ = self.create_notification_partition(day).await;
+        }
+
+        let (ids, client_ids): (Vec<_>, Vec<_>) = batch
+            .into_iter()
+            .map(|(id, client_id, _)| (id, client_id))
+            .unzip();
+
+        // Taken in a consistent order so concurrent batches can't deadlock
+        sqlx::query(
+            "
+            SELECT pg_advisory_xact_lock(key)
+            FROM (
+                SELECT DISTINCT abs(hashtext(client_id)) AS key
+                FROM unnest($1::text[]) AS client_id
+                ORDER BY key
+            ) AS keys",
+        )
+        .bind(&client_ids)
+        .execute(&mut transaction)
+        .await?;
+
+        // Under the advisory locks, a notification received again since the
+        // migration is kept instead of its old row
+        let query = "
+            WITH moved AS (
+                DELETE FROM public.notifications_unpartitioned u
+                USING unnest($1::text[], $2::text[]) AS batch (id, client_id)
+                WHERE u.id = batch.id AND u.client_id = batch.client_id
+                RETURNING u.*
+            )
+            INSERT INTO public.notifications (id, client_id, tenant_id, last_payload,
+                                              previous_payloads, status, attempts, last_error,
+                                              delivered_at, provider, provider_message_id,
+                                              last_received_at, created_at)
+            SELECT id,
+                   client_id,
+                   tenant_id,
+                   last_payload,
+                   previous_payloads,
+                   status,
+                   attempts,
+                   last_error,
+                   delivered_at,
+                   provider,
+                   provider_message_id,
+                   last_received_at,
+                   created_at
+            FROM moved
+            WHERE NOT EXISTS (
+                SELECT 1
+                FROM public.notifications n
+                WHERE n.id = moved.id AND n.client_id = moved.client_id
+            )
+        ";
+        sqlx::query(query)
+            .bind(&ids)
+            .bind(&client_ids)
+            .execute(&mut transaction)
+            .await?;
+        transaction.commit().await?;
+
+        Ok(ids.len() as u64)
+    }
 }
@@ -0,0 +1,184 @@
+use {
+    crate::{
+        handlers::push_message::PushMessageBody,
+        state::NotificationStoreArc,
+        stores::{
+            self,
+            notification::{DeliveryStatus, Notification, NotificationStore},
+        },
+    },
+    async_trait::async_trait,
+    chrono::{DateTime, NaiveDate, Utc},
+    serde::Deserialize,
+    std::time::Duration,
+    tokio::sync::OnceCell,
+    tracing::instrument,
+};
+
+/// Where pushes are checked for message ids that were already received
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationDedupeBackend {
+    Database,
+    Redis,
+}
+
+fn dedupe_key(id: &str, tenant_id: &str, client_id: &str) -> String {
+    format!("notification:{tenant_id}:{client_id}:{id}")
+}
+
+/// Claims message ids in Redis so duplicates are rejected without querying the
+/// database, which stores the notifications themselves. Ids are only
+/// remembered for the TTL, after which the wrapped store dedupes
+pub struct RedisNotificationStore {
+    store: NotificationStoreArc,
+    client: redis::Client,
+    connection: OnceCell<redis::aio::ConnectionManager>,
+    ttl: Duration,
+}
+
+impl RedisNotificationStore {
+    pub fn new(
+        store: NotificationStoreArc,
+        redis_url: &str,
+        ttl: Duration,
+    ) -> stores::Result<Self> {
+        Ok(Self {
+            store,
+            client: redis::Client::open(redis_url)?,
+            connection: OnceCell::new(),
+            ttl,
+        })
+    }
+
+    async fn connection(&self) -> stores::Result<redis::aio::ConnectionManager> {
+        // Connected on first use so startup doesn't depend on Redis
+        Ok(self
+            .connection
+            .get_or_try_init(|| self.client.get_connection_manager())
+            .await?
+            .clone())
+    }
+}
+
+#[async_trait]
+impl NotificationStore for RedisNotificationStore {
+    #[instrument(skip(self))]
+    async fn claim_notification(
+        &self,
+        id: &str,
+        tenant_id: &str,
+        client_id: &str,
+    ) -> stores::Result<bool> {
+        let mut connection = self.connection().await?;
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(dedupe_key(id, tenant_id, client_id))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.ttl.as_secs().max(1))
+            .query_async(&mut connection)
+            .await?;
+
+        Ok(claimed.is_some())
+    }
+
+    #[instrument(skip(self))]
+    async fn release_notification(
+        &self,
+        id: &str,
+        tenant_id: &str,
+        client_id: &str,
+    ) -> stores::Result<()> {
+        let mut connection = self.connection().await?;
+        redis::cmd("DEL")
+            .arg(dedupe_key(id, tenant_id, client_id))
+            .query_async::<_, ()>(&mut connection)
+            .await?;
+
+        Ok(())
+    }
+
+    // Only reached when the claim succeeded, a duplicate rejected by Redis
+    // never gets here. The row is still written as it is the notification's
+    // delivery record, which the queue, receipts and status lookups update and
+    // read, and it dedupes ids whose claim has expired
+    async fn create_or_update_notification(
+        &self,
+        id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+    ) -> stores::Result<Notification> {
+        self.store
+            .create_or_update_notification(id, tenant_id, client_id, payload)
+            .await
+    }
+
+    async fn get_notification(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+    ) -> stores::Result<Notification> {
+        self.store.get_notification(id, client_id, tenant_id).await
+    }
+
+    async fn delete_notification(&self, id: &str, tenant_id: &str) -> stores::Result<()> {
+        self.store.delete_notification(id, tenant_id).await
+    }
+
+    async fn update_notification_status(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+        status: DeliveryStatus,
+        attempts: i32,
+        last_error: Option<&str>,
+    ) -> stores::Result<()> {
+        self.store
+            .update_notification_status(id, client_id, tenant_id, status, attempts, last_error)
+            .await
+    }
+
+    async fn update_notification_provider(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+        provider: &str,
+        provider_message_id: Option<&str>,
+    ) -> stores::Result<()> {
+        self.store
+            .update_notification_provider(id, client_id, tenant_id, provider, provider_message_id)
+            .await
+    }
+
+    async fn get_client_notifications(
+        &self,
+        tenant_id: &str,
+        client_id: &str,
+        limit: i64,
+    ) -> stores::Result<Vec<Notification>> {
+        self.store
+            .get_client_notifications(tenant_id, client_id, limit)
+            .await
+    }
+
+    async fn create_notification_partition(&self, day: NaiveDate) -> stores::Result<()> {
+        self.store.create_notification_partition(day).await
+    }
+
+    async fn drop_notification_partitions(&self, before: DateTime<Utc>) -> stores::Result<u64> {
+        self.store.drop_notification_partitions(before).await
+    }
+
+    async fn prune_notifications(&self, before: DateTime<Utc>, limit: i64) -> stores::Result<u64> {
+        self.store.prune_notifications(before, limit).await
+    }
+
+    async fn backfill_notification_partitions(&self, limit: i64) -> stores::Result<u64> {
+        self.store.backfill_notification_partitions(limit).await
+    }
+}
@@ -0,0 +1,973 @@
+use {
+    crate::{
+        handlers::push_message::PushMessageBody,
+        metrics::Metrics,
+        stores::{
+            self,
+            client::{
+                registration_event, Client, ClientEvent, ClientEventListParams, ClientEventReason,
+                ClientEventType, ClientSegment, ClientSegmentCount, ClientSelect, ClientStore,
+                NewClientEvent,
+            },
+            delivery::{lease_expiry, DeliveryStore, QueuedDelivery},
+            notification::{DeliveryStatus, Notification, NotificationStore},
+            webhook::{WebhookDelivery, WebhookEvent, WebhookStore},
+            StoreError::NotFound,
+        },
+    },
+    async_trait::async_trait,
+    chrono::{DateTime, NaiveDate, Utc},
+    serde_json::Value,
+    sqlx::{types::Json, Sqlite, SqlitePool},
+    std::time::Duration,
+    tracing::{debug, instrument},
+};
+
+/// SQLite can't store arrays, so previous payloads are not kept
+#[derive(Debug, sqlx::FromRow)]
+struct NotificationRow {
+    id: String,
+    client_id: String,
+    last_payload: Json<Value>,
+    status: DeliveryStatus,
+    attempts: i32,
+    last_error: Option<String>,
+    delivered_at: Option<DateTime<Utc>>,
+    provider: Option<String>,
+    provider_message_id: Option<String>,
+    last_received_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<NotificationRow> for Notification {
+    fn from(row: NotificationRow) -> Self {
+        Self {
+            id: row.id,
+            client_id: row.client_id,
+            last_payload: row.last_payload,
+            previous_payloads: vec![],
+            status: row.status,
+            attempts: row.attempts,
+            last_error: row.last_error,
+            delivered_at: row.delivered_at,
+            provider: row.provider,
+            provider_message_id: row.provider_message_id,
+            last_received_at: row.last_received_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[async_trait]
+impl ClientStore for SqlitePool {
+    #[instrument(skip(self, client, _metrics))]
+    async fn create_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        client: Client,
+        _metrics: Option<&Metrics>,
+    ) -> stores::Result<()> {
+        debug!(
+            "ClientStore::create_client tenant_id={tenant_id} id={id} token={}",
+            client.token
+        );
+
+        // SQLite has a single writer, so the transaction serializes concurrent
+        // registrations where Postgres takes advisory locks
+        let mut transaction = self.begin().await?;
+
+        let existing_client = sqlx::query_as::<Sqlite, ClientSelect>(
+            "SELECT id, device_token, tenant_id FROM clients WHERE id = ?1 OR device_token = ?2",
+        )
+        .bind(id)
+        .bind(&client.token)
+        .fetch_optional(&mut transaction)
+        .await?;
+
+        insert_client_event(
+            &mut transaction,
+            registration_event(tenant_id, id, &client.token, existing_client.as_ref()),
+        )
+        .await?;
+
+        match existing_client {
+            Some(existing_client)
+                if existing_client.id == id && existing_client.device_token != client.token =>
+            {
+                sqlx::query(
+                    "
+                    UPDATE clients
+                    SET device_token = ?2,
+                        push_type = ?3,
+                        always_raw = ?4,
+                        tenant_id = ?5
+                    WHERE id = ?1",
+                )
+                .bind(id)
+                .bind(client.token)
+                .bind(client.push_type)
+                .bind(client.always_raw)
+                .bind(tenant_id)
+                .execute(&mut transaction)
+                .await?;
+            }
+            Some(existing_client)
+                if existing_client.device_token == client.token && existing_client.id != id =>
+            {
+                sqlx::query("DELETE FROM notification_queue WHERE client_id = ?1")
+                    .bind(&existing_client.id)
+                    .execute(&mut transaction)
+                    .await?;
+                sqlx::query("DELETE FROM scheduled_notifications WHERE client_id = ?1")
+                    .bind(&existing_client.id)
+                    .execute(&mut transaction)
+                    .await?;
+                sqlx::query("DELETE FROM client_subscriptions WHERE client_id = ?1")
+                    .bind(&existing_client.id)
+                    .execute(&mut transaction)
+                    .await?;
+                sqlx::query("DELETE FROM notifications WHERE client_id = ?1 AND tenant_id = ?2")
+                    .bind(&existing_client.id)
+                    .bind(&existing_client.tenant_id)
+                    .execute(&mut transaction)
+                    .await?;
+                sqlx::query(
+                    "
+                    UPDATE clients
+                    SET id = ?2,
+                        push_type = ?3,
+                        always_raw = ?4,
+                        tenant_id = ?5
+                    WHERE device_token = ?1",
+                )
+                .bind(client.token)
+                .bind(id)
+                .bind(client.push_type)
+                .bind(client.always_raw)
+                .bind(tenant_id)
+                .execute(&mut transaction)
+                .await?;
+            }
+            Some(_) => {
+                sqlx::query(
+                    "
+                    UPDATE clients
+                    SET push_type = ?2,
+                        always_raw = ?3,
+                        tenant_id = ?4
+                    WHERE id = ?1",
+                )
+                .bind(id)
+                .bind(client.push_type)
+                .bind(client.always_raw)
+                .bind(tenant_id)
+                .execute(&mut transaction)
+                .await?;
+            }
+            None => {
+                sqlx::query(
+                    "
+                    INSERT INTO clients
+                        (id, tenant_id, push_type, device_token, always_raw, created_at)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )
+                .bind(id)
+                .bind(tenant_id)
+                .bind(client.push_type)
+                .bind(client.token)
+                .bind(client.always_raw)
+                .bind(Utc::now())
+                .execute(&mut transaction)
+                .await?;
+            }
+        }
+
+        // Registering again replaces what the client reported before
+        let metadata = client.metadata;
+        sqlx::query(
+            "
+            UPDATE clients
+            SET platform = ?2,
+                os_version = ?3,
+                app_bundle_id = ?4,
+                app_version = ?5,
+                sdk_name = ?6,
+                sdk_version = ?7,
+                locale = ?8,
+                timezone = ?9
+            WHERE id = ?1",
+        )
+        .bind(id)
+        .bind(metadata.platform)
+        .bind(metadata.os_version)
+        .bind(metadata.app_bundle_id)
+        .bind(metadata.app_version)
+        .bind(metadata.sdk_name)
+        .bind(metadata.sdk_version)
+        .bind(metadata.locale)
+        .bind(metadata.timezone)
+        .execute(&mut transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_client(&self, tenant_id: &str, id: &str) -> stores::Result<Client> {
+        let res = sqlx::query_as::<Sqlite, Client>(
+            "SELECT * FROM clients WHERE id = ?1 AND tenant_id = ?2",
+        )
+        .bind(id)
+        .bind(tenant_id)
+        .fetch_optional(self)
+        .await?;
+
+        res.ok_or_else(|| NotFound("client".to_string(), id.to_string()))
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        reason: ClientEventReason,
+    ) -> stores::Result<()> {
+        debug!("ClientStore::delete_client tenant_id={tenant_id} id={id} reason={reason:?}");
+
+        let mut transaction = self.begin().await?;
+
+        sqlx::query("DELETE FROM notifications WHERE client_id = ?1 AND tenant_id = ?2")
+            .bind(id)
+            .bind(tenant_id)
+            .execute(&mut transaction)
+            .await?;
+        let device_token = sqlx::query_scalar::<Sqlite, String>(
+            "DELETE FROM clients WHERE id = ?1 AND tenant_id = ?2 RETURNING device_token",
+        )
+        .bind(id)
+        .bind(tenant_id)
+        .fetch_optional(&mut transaction)
+        .await?;
+
+        if let Some(device_token) = &device_token {
+            let mut event = NewClientEvent::new(ClientEventType::Deleted, reason, id, tenant_id);
+            event.previous_device_token = Some(device_token);
+            insert_client_event(&mut transaction, event).await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_client_events(
+        &self,
+        params: ClientEventListParams,
+    ) -> stores::Result<Vec<ClientEvent>> {
+        let mut query_builder = sqlx::QueryBuilder::new("SELECT * FROM client_events WHERE TRUE");
+        if let Some(tenant_id) = &params.tenant_id {
+            query_builder
+                .push(" AND (tenant_id = ")
+                .push_bind(tenant_id)
+                .push(" OR previous_tenant_id = ")
+                .push_bind(tenant_id)
+                .push(")");
+        }
+        if let Some(client_id) = &params.client_id {
+            query_builder
+                .push(" AND (client_id = ")
+                .push_bind(client_id)
+                .push(" OR previous_client_id = ")
+                .push_bind(client_id)
+                .push(")");
+        }
+        if let Some(device_token) = &params.device_token {
+            query_builder
+                .push(" AND (device_token = ")
+                .push_bind(device_token)
+                .push(" OR previous_device_token = ")
+                .push_bind(device_token)
+                .push(")");
+        }
+        if let Some(before_id) = params.before_id {
+            query_builder.push(" AND id < ").push_bind(before_id);
+        }
+        query_builder
+            .push(" ORDER BY id DESC LIMIT ")
+            .push_bind(params.limit);
+
+        let res = query_builder
+            .build_query_as::<ClientEvent>()
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_client_segments(
+        &self,
+        tenant_id: &str,
+        segment: ClientSegment,
+        limit: i64,
+    ) -> stores::Result<Vec<ClientSegmentCount>> {
+        // The column comes from the enum, never from the request
+        let query = format!(
+            "SELECT {column} AS value, COUNT(*) AS clients
+            FROM clients
+            WHERE tenant_id = ?1
+            GROUP BY {column}
+            ORDER BY clients DESC, value
+            LIMIT ?2",
+            column = segment.column(),
+        );
+        let res = sqlx::query_as::<Sqlite, ClientSegmentCount>(&query)
+            .bind(tenant_id)
+            .bind(limit)
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn subscribe_client(&self, tenant_id: &str, id: &str, topic: &str) -> stores::Result<()> {
+        let res = sqlx::query_scalar::<Sqlite, String>(
+            "
+            INSERT INTO client_subscriptions (client_id, topic, created_at)
+            SELECT id, ?3, ?4
+            FROM clients
+            WHERE id = ?1 AND tenant_id = ?2
+            ON CONFLICT (topic, client_id) DO NOTHING
+            RETURNING client_id",
+        )
+        .bind(id)
+        .bind(tenant_id)
+        .bind(topic)
+        .bind(Utc::now())
+        .fetch_optional(self)
+        .await?;
+
+        // Nothing is returned for existing subscriptions either
+        if res.is_none() {
+            self.get_client(tenant_id, id).await?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn unsubscribe_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        topic: &str,
+    ) -> stores::Result<()> {
+        sqlx::query(
+            "
+            DELETE FROM client_subscriptions
+            WHERE topic = ?3
+              AND client_id IN (SELECT id FROM clients WHERE id = ?1 AND tenant_id = ?2)",
+        )
+        .bind(id)
+        .bind(tenant_id)
+        .bind(topic)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_topic_subscribers(
+        &self,
+        tenant_id: &str,
+        topic: &str,
+        after_id: Option<&str>,
+        limit: i64,
+    ) -> stores::Result<Vec<String>> {
+        let res = sqlx::query_scalar::<Sqlite, String>(
+            "
+            SELECT s.client_id
+            FROM client_subscriptions s
+                JOIN clients c ON c.id = s.client_id
+            WHERE s.topic = ?1
+              AND c.tenant_id = ?2
+              AND (?3 IS NULL OR s.client_id > ?3)
+            ORDER BY s.client_id
+            LIMIT ?4",
+        )
+        .bind(topic)
+        .bind(tenant_id)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(self)
+        .await?;
+
+        Ok(res)
+    }
+}
+
+async fn insert_client_event(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    event: NewClientEvent<'_>,
+) -> stores::Result<()> {
+    sqlx::query(
+        "INSERT INTO client_events (event_type, reason, client_id, tenant_id, device_token, \
+         previous_client_id, previous_tenant_id, previous_device_token, created_at) VALUES (?1, \
+         ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )
+    .bind(event.event_type)
+    .bind(event.reason)
+    .bind(event.client_id)
+    .bind(event.tenant_id)
+    .bind(event.device_token)
+    .bind(event.previous_client_id)
+    .bind(event.previous_tenant_id)
+    .bind(event.previous_device_token)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl NotificationStore for SqlitePool {
+    #[instrument(skip(self))]
+    async fn claim_notification(
+        &self,
+        id: &str,
+        tenant_id: &str,
+        client_id: &str,
+    ) -> stores::Result<bool> {
+        // Nothing is recorded until the notification is stored, which is
+        // deduplicated by its primary key
+        match self.get_notification(id, client_id, tenant_id).await {
+            Ok(_) => Ok(false),
+            Err(NotFound(..)) => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn release_notification(
+        &self,
+        _id: &str,
+        _tenant_id: &str,
+        _client_id: &str,
+    ) -> stores::Result<()> {
+        Ok(())
+    }
+
+    #[instrument(skip(self, payload))]
+    async fn create_or_update_notification(
+        &self,
+        id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+    ) -> stores::Result<Notification> {
+        let now = Utc::now();
+        let res = sqlx::query_as::<Sqlite, NotificationRow>(
+            "
+            INSERT INTO notifications
+                (id, tenant_id, client_id, last_payload, last_received_at, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+            ON CONFLICT (id, client_id) DO UPDATE
+            SET last_received_at = excluded.last_received_at
+            RETURNING *",
+        )
+        .bind(id)
+        .bind(tenant_id)
+        .bind(client_id)
+        .bind(Json(payload))
+        .bind(now)
+        .fetch_one(self)
+        .await?;
+
+        Ok(res.into())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_notification(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+    ) -> stores::Result<Notification> {
+        let res = sqlx::query_as::<Sqlite, NotificationRow>(
+            "
+            SELECT *
+            FROM notifications
+            WHERE id = ?1 AND client_id = ?2 AND tenant_id = ?3",
+        )
+        .bind(id)
+        .bind(client_id)
+        .bind(tenant_id)
+        .fetch_optional(self)
+        .await?;
+
+        res.map(Into::into)
+            .ok_or_else(|| NotFound("notification".to_string(), id.to_string()))
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_notification(&self, id: &str, tenant_id: &str) -> stores::Result<()> {
+        sqlx::query("DELETE FROM notifications WHERE id = ?1 AND tenant_id = ?2")
+            .bind(id)
+            .bind(tenant_id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn update_notification_status(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+        status: DeliveryStatus,
+        attempts: i32,
+        last_error: Option<&str>,
+    ) -> stores::Result<()> {
+        let query = "
+            UPDATE notifications
+            SET status = ?4,
+                attempts = ?5,
+                last_error = ?6,
+                delivered_at = CASE WHEN ?4 = 'delivered' THEN ?7 END
+            WHERE id = ?1
+                  AND client_id = ?2
+                  AND tenant_id = ?3
+        ";
+        sqlx::query(query)
+            .bind(id)
+            .bind(client_id)
+            .bind(tenant_id)
+            .bind(status)
+            .bind(attempts)
+            .bind(last_error)
+            .bind(Utc::now())
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn update_notification_provider(
+        &self,
+        id: &str,
+        client_id: &str,
+        tenant_id: &str,
+        provider: &str,
+        provider_message_id: Option<&str>,
+    ) -> stores::Result<()> {
+        let query = "
+            UPDATE notifications
+            SET provider = ?4,
+                provider_message_id = ?5
+            WHERE id = ?1
+                  AND client_id = ?2
+                  AND tenant_id = ?3
+        ";
+        sqlx::query(query)
+            .bind(id)
+            .bind(client_id)
+            .bind(tenant_id)
+            .bind(provider)
+            .bind(provider_message_id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_client_notifications(
+        &self,
+        tenant_id: &str,
+        client_id: &str,
+        limit: i64,
+    ) -> stores::Result<Vec<Notification>> {
+        let query = "
+            SELECT *
+            FROM notifications
+            WHERE tenant_id = ?1 AND client_id = ?2
+            ORDER BY last_received_at DESC, id
+            LIMIT ?3
+        ";
+        let res = sqlx::query_as::<Sqlite, NotificationRow>(query)
+            .bind(tenant_id)
+            .bind(client_id)
+            .bind(limit)
+            .fetch_all(self)
+            .await?;
+
+        Ok(res.into_iter().map(Into::into).collect())
+    }
+
+    async fn create_notification_partition(&self, _day: NaiveDate) -> stores::Result<()> {
+        Ok(())
+    }
+
+    async fn drop_notification_partitions(&self, _before: DateTime<Utc>) -> stores::Result<u64> {
+        Ok(0)
+    }
+
+    #[instrument(skip(self))]
+    async fn prune_notifications(&self, before: DateTime<Utc>, limit: i64) -> stores::Result<u64> {
+        let query = "
+            DELETE FROM notifications
+            WHERE rowid IN (
+                SELECT rowid
+                FROM notifications
+                WHERE created_at < ?1
+                LIMIT ?2
+            )
+        ";
+        let res = sqlx::query(query)
+            .bind(before)
+            .bind(limit)
+            .execute(self)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn backfill_notification_partitions(&self, _limit: i64) -> stores::Result<u64> {
+        Ok(0)
+    }
+}
+
+#[async_trait]
+impl DeliveryStore for SqlitePool {
+    #[instrument(skip(self, payload))]
+    async fn enqueue_delivery(
+        &self,
+        notification_id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+        lease: Duration,
+    ) -> stores::Result<Option<QueuedDelivery>> {
+        let query = "
+            INSERT INTO notification_queue
+                (notification_id, client_id, tenant_id, payload, attempts, next_attempt_at,
+                 created_at)
+            VALUES (?1, ?2, ?3, ?4, 1, ?5, ?6)
+            ON CONFLICT (notification_id, client_id) DO NOTHING
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<Sqlite, QueuedDelivery>(query)
+            .bind(notification_id)
+            .bind(client_id)
+            .bind(tenant_id)
+            .bind(Json(payload))
+            .bind(lease_expiry(lease))
+            .bind(Utc::now())
+            .fetch_optional(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<QueuedDelivery>> {
+        // A single statement is atomic, so no other instance can lease the
+        // same rows in between
+        let query = "
+            UPDATE notification_queue
+            SET attempts = attempts + 1,
+                next_attempt_at = ?2
+            WHERE (notification_id, client_id) IN (
+                SELECT notification_id, client_id
+                FROM notification_queue
+                WHERE next_attempt_at <= ?3
+                ORDER BY next_attempt_at
+                LIMIT ?1
+            )
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<Sqlite, QueuedDelivery>(query)
+            .bind(limit)
+            .bind(lease_expiry(lease))
+            .bind(Utc::now())
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn reschedule_delivery(
+        &self,
+        notification_id: &str,
+        client_id: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> stores::Result<()> {
+        let query = "
+            UPDATE notification_queue
+            SET next_attempt_at = ?3
+            WHERE notification_id = ?1
+                  AND client_id = ?2
+        ";
+        sqlx::query(query)
+            .bind(notification_id)
+            .bind(client_id)
+            .bind(next_attempt_at)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_delivery(&self, notification_id: &str, client_id: &str) -> stores::Result<()> {
+        sqlx::query("DELETE FROM notification_queue WHERE notification_id = ?1 AND client_id = ?2")
+            .bind(notification_id)
+            .bind(client_id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self, payload))]
+    async fn schedule_delivery(
+        &self,
+        notification_id: &str,
+        tenant_id: &str,
+        client_id: &str,
+        payload: &PushMessageBody,
+        send_at: DateTime<Utc>,
+    ) -> stores::Result<bool> {
+        let query = "
+            INSERT INTO scheduled_notifications
+                (notification_id, client_id, tenant_id, payload, send_at, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (notification_id, client_id) DO NOTHING
+        ";
+        let res = sqlx::query(query)
+            .bind(notification_id)
+            .bind(client_id)
+            .bind(tenant_id)
+            .bind(Json(payload))
+            .bind(send_at)
+            .bind(Utc::now())
+            .execute(self)
+            .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    #[instrument(skip(self))]
+    async fn claim_due_scheduled_deliveries(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<QueuedDelivery>> {
+        // The transaction serializes the move where Postgres skips locked rows
+        let mut transaction = self.begin().await?;
+
+        let due = sqlx::query_as::<Sqlite, (String, String, String, Json<PushMessageBody>)>(
+            "
+            SELECT notification_id, client_id, tenant_id, payload
+            FROM scheduled_notifications
+            WHERE send_at <= ?2
+            ORDER BY send_at
+            LIMIT ?1",
+        )
+        .bind(limit)
+        .bind(Utc::now())
+        .fetch_all(&mut transaction)
+        .await?;
+
+        let mut deliveries = Vec::with_capacity(due.len());
+        for (notification_id, client_id, tenant_id, payload) in due {
+            let delivery = sqlx::query_as::<Sqlite, QueuedDelivery>(
+                "
+                INSERT INTO notification_queue
+                    (notification_id, client_id, tenant_id, payload, attempts, next_attempt_at,
+                     created_at)
+                VALUES (?1, ?2, ?3, ?4, 1, ?5, ?6)
+                ON CONFLICT (notification_id, client_id) DO NOTHING
+                RETURNING *",
+            )
+            .bind(&notification_id)
+            .bind(&client_id)
+            .bind(tenant_id)
+            .bind(payload)
+            .bind(lease_expiry(lease))
+            .bind(Utc::now())
+            .fetch_optional(&mut transaction)
+            .await?;
+
+            // Only queued rows are removed, one whose client already has the
+            // notification queued is tried again when the lease expires
+            if delivery.is_some() {
+                sqlx::query(
+                    "DELETE FROM scheduled_notifications WHERE notification_id = ?1 AND \
+                     client_id = ?2",
+                )
+                .bind(notification_id)
+                .bind(client_id)
+                .execute(&mut transaction)
+                .await?;
+            } else {
+                sqlx::query(
+                    "UPDATE scheduled_notifications SET send_at = ?3 WHERE notification_id = ?1 \
+                     AND client_id = ?2",
+                )
+                .bind(notification_id)
+                .bind(client_id)
+                .bind(lease_expiry(lease))
+                .execute(&mut transaction)
+                .await?;
+            }
+            deliveries.extend(delivery);
+        }
+
+        transaction.commit().await?;
+
+        Ok(deliveries)
+    }
+
+    #[instrument(skip(self))]
+    async fn cancel_scheduled_delivery(
+        &self,
+        tenant_id: &str,
+        notification_id: &str,
+    ) -> stores::Result<Vec<String>> {
+        let res = sqlx::query_scalar::<Sqlite, String>(
+            "DELETE FROM scheduled_notifications WHERE notification_id = ?1 AND tenant_id = ?2 \
+             RETURNING client_id",
+        )
+        .bind(notification_id)
+        .bind(tenant_id)
+        .fetch_all(self)
+        .await?;
+
+        Ok(res)
+    }
+}
+
+#[async_trait]
+impl WebhookStore for SqlitePool {
+    #[instrument(skip(self, event))]
+    async fn enqueue_webhook_event(
+        &self,
+        tenant_id: &str,
+        event: &WebhookEvent,
+    ) -> stores::Result<WebhookDelivery> {
+        let query = "
+            INSERT INTO webhook_events
+                (id, tenant_id, event_type, payload, next_attempt_at, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<Sqlite, WebhookDelivery>(query)
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(tenant_id)
+            .bind(event.event_type())
+            .bind(Json(event))
+            .bind(Utc::now())
+            .fetch_one(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn claim_due_webhook_events(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<WebhookDelivery>> {
+        let query = "
+            UPDATE webhook_events
+            SET attempts = attempts + 1,
+                next_attempt_at = ?2
+            WHERE id IN (
+                SELECT id
+                FROM webhook_events
+                WHERE status = 'queued'
+                      AND next_attempt_at <= ?3
+                ORDER BY next_attempt_at
+                LIMIT ?1
+            )
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<Sqlite, WebhookDelivery>(query)
+            .bind(limit)
+            .bind(lease_expiry(lease))
+            .bind(Utc::now())
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn update_webhook_event(
+        &self,
+        id: &str,
+        status: DeliveryStatus,
+        response_status: Option<i32>,
+        last_error: Option<&str>,
+        next_attempt_at: DateTime<Utc>,
+    ) -> stores::Result<()> {
+        let query = "
+            UPDATE webhook_events
+            SET status = ?2,
+                response_status = ?3,
+                last_error = ?4,
+                next_attempt_at = ?5,
+                delivered_at = CASE WHEN ?2 = 'delivered' THEN ?6 END
+            WHERE id = ?1
+        ";
+        sqlx::query(query)
+            .bind(id)
+            .bind(status)
+            .bind(response_status)
+            .bind(last_error)
+            .bind(next_attempt_at)
+            .bind(Utc::now())
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_webhook_events(
+        &self,
+        tenant_id: &str,
+        limit: i64,
+    ) -> stores::Result<Vec<WebhookDelivery>> {
+        let query = "
+            SELECT *
+            FROM webhook_events
+            WHERE tenant_id = ?1
+            ORDER BY created_at DESC, id
+            LIMIT ?2
+        ";
+        let res = sqlx::query_as::<Sqlite, WebhookDelivery>(query)
+            .bind(tenant_id)
+            .bind(limit)
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+}
@@ -11,9 +11,13 @@ use {
             apns::ApnsProvider,
             fcm::FcmProvider,
             fcm_v1::FcmV1Provider,
-            Provider::{self, Apns, Fcm, FcmV1},
-            ProviderKind,
+            hms::HmsProvider,
+            web_push::{vapid_keys, WebPushProvider},
+            Provider::{self, Apns, Fcm, FcmV1, Hms, WebPush},
+            ProviderKind, PROVIDER_FCM_V1,
         },
+        push_rate_limit::RateLimit,
+        webhooks::PinnedClients,
     },
     async_trait::async_trait,
     base64::Engine as _,
@@ -21,7 +25,8 @@ use {
     moka::future::Cache,
     reqwest::Client,
     serde::{Deserialize, Serialize},
-    sqlx::{Executor, PgPool},
+    sqlx::{types::Json, Executor, PgPool},
+    std::collections::BTreeMap,
     tracing::{debug, instrument},
 };
 
@@ -33,6 +38,17 @@ const APNS_TYPE_TOKEN: &str = "token";
 
 pub const DEFAULT_TENANT_ID: &str = "0000-0000-0000-0000";
 
+// SQL conditions matching the tenants `Tenant::providers` includes a provider for
+const APNS_CONFIGURED: &str = "(apns_topic IS NOT NULL AND ((apns_type = 'certificate' AND \
+                               apns_certificate IS NOT NULL AND apns_certificate_password IS NOT \
+                               NULL) OR (apns_type = 'token' AND apns_pkcs8_pem IS NOT NULL AND \
+                               apns_key_id IS NOT NULL AND apns_team_id IS NOT NULL)))";
+const FCM_CONFIGURED: &str = "(fcm_api_key IS NOT NULL OR fcm_v1_credentials IS NOT NULL)";
+const FCM_V1_CONFIGURED: &str = "(fcm_v1_credentials IS NOT NULL)";
+const HMS_CONFIGURED: &str = "(hms_app_id IS NOT NULL AND hms_app_secret IS NOT NULL)";
+const WEB_PUSH_CONFIGURED: &str =
+    "(web_push_vapid_key IS NOT NULL AND web_push_vapid_subject IS NOT NULL)";
+
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type)]
 #[sqlx(type_name = "apns_type")]
 #[sqlx(rename_all = "lowercase")]
@@ -99,10 +115,32 @@ pub struct Tenant {
     pub apns_key_id: Option<String>,
     pub apns_team_id: Option<String>,
 
+    // HMS
+    pub hms_app_id: Option<String>,
+    pub hms_app_secret: Option<String>,
+
+    // Web Push
+    pub web_push_vapid_key: Option<String>,
+    pub web_push_vapid_subject: Option<String>,
+
+    // Push rate limits, unset values fall back to the configured defaults
+    pub push_rate_limit_per_minute: Option<i32>,
+    pub push_rate_limit_burst: Option<i32>,
+    pub client_push_rate_limit_per_minute: Option<i32>,
+    pub client_push_rate_limit_burst: Option<i32>,
+
     // Suspension
     pub suspended: bool,
     pub suspended_reason: Option<String>,
 
+    // Certificate expiry, parsed from the p12 when it is uploaded
+    pub apns_certificate_expires_at: Option<DateTime<Utc>>,
+    pub apns_expiry_notified_at: Option<DateTime<Utc>>,
+
+    // Webhook events are signed with the secret
+    pub webhook_url: Option<String>,
+    pub webhook_secret: Option<String>,
+
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -122,6 +160,136 @@ pub struct TenantFcmV1UpdateParams {
     pub fcm_v1_credentials: String,
 }
 
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TenantHmsUpdateParams {
+    pub hms_app_id: String,
+    pub hms_app_secret: String,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TenantWebPushUpdateParams {
+    pub web_push_vapid_key: String,
+    pub web_push_vapid_subject: String,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TenantRateLimitUpdateParams {
+    pub push_rate_limit_per_minute: Option<i32>,
+    pub push_rate_limit_burst: Option<i32>,
+    pub client_push_rate_limit_per_minute: Option<i32>,
+    pub client_push_rate_limit_burst: Option<i32>,
+}
+
+#[derive(Eq, PartialEq, Clone)]
+pub struct TenantWebhookUpdateParams {
+    pub webhook_url: String,
+    pub webhook_secret: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "suspension_action")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SuspensionAction {
+    Suspended,
+    Unsuspended,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct TenantSuspendParams {
+    pub reason: String,
+    /// The delivery that failed because of the tenant's configuration
+    pub client_id: Option<String>,
+    pub notification_id: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, Eq, PartialEq, Clone)]
+pub struct TenantSuspension {
+    pub action: SuspensionAction,
+    pub reason: Option<String>,
+    pub client_id: Option<String>,
+    pub notification_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "template_mode")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateMode {
+    /// The title and body are rendered in the client's locale
+    #[default]
+    Render,
+    /// Localization keys are sent for the app to render with its own strings
+    Passthrough,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LocalizedTemplate {
+    pub title: String,
+    pub body: String,
+}
+
+/// A tenant's notification, with `{placeholder}`s filled in from the
+/// variables of the push request
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NotificationTemplate {
+    pub tenant_id: String,
+    pub name: String,
+    pub mode: TemplateMode,
+    /// Used for clients without a locale or with one the template lacks
+    pub default_locale: String,
+    /// Keyed by locale, e.g. `en` or `pt-BR`
+    pub localizations: Json<BTreeMap<String, LocalizedTemplate>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantTemplateUpdateParams {
+    pub mode: TemplateMode,
+    pub default_locale: String,
+    pub localizations: BTreeMap<String, LocalizedTemplate>,
+}
+
+/// Filters for listing tenants. Tenants are ordered by id, pages continue
+/// after the id of the last tenant of the previous page
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct TenantListParams {
+    pub suspended: Option<bool>,
+    pub provider: Option<ProviderKind>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    pub id_prefix: Option<String>,
+    pub after_id: Option<String>,
+    pub limit: i64,
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, Default, Eq, PartialEq, Clone)]
+pub struct TenantSummary {
+    pub total: i64,
+    pub suspended: i64,
+    pub apns: i64,
+    pub fcm: i64,
+    pub fcm_v1: i64,
+    pub hms: i64,
+    pub web_push: i64,
+}
+
+/// Secret columns of a tenant, encrypted at rest when a master key is
+/// configured
+#[derive(Default, Eq, PartialEq, Clone)]
+pub struct TenantCredentials {
+    pub fcm_api_key: Option<String>,
+    pub fcm_v1_credentials: Option<String>,
+    pub apns_certificate: Option<String>,
+    pub apns_certificate_password: Option<String>,
+    pub apns_pkcs8_pem: Option<String>,
+    pub hms_app_secret: Option<String>,
+    pub web_push_vapid_key: Option<String>,
+    pub webhook_secret: Option<String>,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub enum TenantApnsUpdateAuth {
     Certificate {
@@ -153,6 +321,14 @@ impl Tenant {
             supported.push(ProviderKind::Fcm);
         }
 
+        if self.hms_app_id.is_some() && self.hms_app_secret.is_some() {
+            supported.push(ProviderKind::Hms);
+        }
+
+        if self.web_push_vapid_key.is_some() && self.web_push_vapid_subject.is_some() {
+            supported.push(ProviderKind::WebPush);
+        }
+
         // Only available in debug/testing
         #[cfg(any(debug_assertions, test))]
         supported.push(ProviderKind::Noop);
@@ -160,6 +336,20 @@ impl Tenant {
         supported
     }
 
+    /// Providers as reported by the API, which lists FCM v1 separately
+    pub fn enabled_providers(&self) -> Vec<String> {
+        self.providers()
+            .iter()
+            .map(Into::into)
+            // Special case on fcm_v1 for credentials because providers() is also used for token management (of which FCM and FCM V1 tokens are the same)
+            .chain(if self.fcm_v1_credentials.is_some() {
+                vec![PROVIDER_FCM_V1.to_string()]
+            } else {
+                vec![]
+            })
+            .collect()
+    }
+
     pub fn get_apns_type(&self) -> Option<ApnsType> {
         if let Some(apns_type) = &self.apns_type {
             // Check if APNS config is correct
@@ -187,12 +377,79 @@ impl Tenant {
         }
     }
 
+    /// The configured APNs topic and authentication, if complete
+    pub fn apns_auth(&self) -> Option<(String, TenantApnsUpdateAuth)> {
+        let auth = match self.get_apns_type()? {
+            ApnsType::Certificate => TenantApnsUpdateAuth::Certificate {
+                apns_certificate: self.apns_certificate.clone()?,
+                apns_certificate_password: self.apns_certificate_password.clone()?,
+            },
+            ApnsType::Token => TenantApnsUpdateAuth::Token {
+                apns_pkcs8_pem: self.apns_pkcs8_pem.clone()?,
+                apns_key_id: self.apns_key_id.clone()?,
+                apns_team_id: self.apns_team_id.clone()?,
+            },
+        };
+
+        Some((self.apns_topic.clone()?, auth))
+    }
+
+    pub fn credentials(&self) -> TenantCredentials {
+        TenantCredentials {
+            fcm_api_key: self.fcm_api_key.clone(),
+            fcm_v1_credentials: self.fcm_v1_credentials.clone(),
+            apns_certificate: self.apns_certificate.clone(),
+            apns_certificate_password: self.apns_certificate_password.clone(),
+            apns_pkcs8_pem: self.apns_pkcs8_pem.clone(),
+            hms_app_secret: self.hms_app_secret.clone(),
+            web_push_vapid_key: self.web_push_vapid_key.clone(),
+            webhook_secret: self.webhook_secret.clone(),
+        }
+    }
+
+    pub fn set_credentials(&mut self, credentials: TenantCredentials) {
+        self.fcm_api_key = credentials.fcm_api_key;
+        self.fcm_v1_credentials = credentials.fcm_v1_credentials;
+        self.apns_certificate = credentials.apns_certificate;
+        self.apns_certificate_password = credentials.apns_certificate_password;
+        self.apns_pkcs8_pem = credentials.apns_pkcs8_pem;
+        self.hms_app_secret = credentials.hms_app_secret;
+        self.web_push_vapid_key = credentials.web_push_vapid_key;
+        self.webhook_secret = credentials.webhook_secret;
+    }
+
+    pub fn push_rate_limit(&self) -> Option<RateLimit> {
+        RateLimit::from_parts(
+            self.push_rate_limit_per_minute
+                .and_then(|v| v.try_into().ok()),
+            self.push_rate_limit_burst.and_then(|v| v.try_into().ok()),
+        )
+    }
+
+    pub fn client_push_rate_limit(&self) -> Option<RateLimit> {
+        RateLimit::from_parts(
+            self.client_push_rate_limit_per_minute
+                .and_then(|v| v.try_into().ok()),
+            self.client_push_rate_limit_burst
+                .and_then(|v| v.try_into().ok()),
+        )
+    }
+
+    /// The `applicationServerKey` browsers need to subscribe with
+    pub fn web_push_public_key(&self) -> Option<String> {
+        let pem = base64::engine::general_purpose::STANDARD
+            .decode(self.web_push_vapid_key.as_ref()?)
+            .ok()?;
+        vapid_keys(&pem).ok().map(|(_, public_key)| public_key)
+    }
+
     #[instrument(skip_all, fields(tenant_id = %self.id, provider = %provider.as_str()))]
     pub async fn provider(
         &self,
         provider: &ProviderKind,
         http_client: Client,
         provider_cache: &Cache<String, Provider>,
+        pinned_clients: &PinnedClients,
     ) -> Result<Provider> {
         if !self.providers().contains(provider) {
             return Err(ProviderNotAvailable(provider.into()));
@@ -284,6 +541,37 @@ impl Tenant {
                     None => Err(ProviderNotAvailable(provider.into())),
                 },
             },
+            ProviderKind::Hms => match (&self.hms_app_id, &self.hms_app_secret) {
+                (Some(app_id), Some(app_secret)) => {
+                    debug!("hms provider is matched");
+                    // Cached so the OAuth access token is reused between requests,
+                    // keyed by a digest so the secret isn't held in plain text
+                    let secret_digest = openssl::sha::sha256(app_secret.as_bytes());
+                    let cache_key = format!("hms:{app_id}:{}", hex::encode(secret_digest));
+                    if let Some(provider) = provider_cache.get(&cache_key).await {
+                        return Ok(provider);
+                    }
+                    let hms = Hms(HmsProvider::new(
+                        app_id.clone(),
+                        app_secret.clone(),
+                        http_client,
+                    ));
+                    provider_cache.insert(cache_key, hms.clone()).await;
+                    Ok(hms)
+                }
+                _ => Err(ProviderNotAvailable(provider.into())),
+            },
+            ProviderKind::WebPush => match (&self.web_push_vapid_key, &self.web_push_vapid_subject)
+            {
+                (Some(vapid_key), Some(subject)) => {
+                    debug!("web push provider is matched");
+                    let pem = base64::engine::general_purpose::STANDARD.decode(vapid_key)?;
+                    let web_push =
+                        WebPushProvider::new(&pem, subject.clone(), pinned_clients.clone())?;
+                    Ok(WebPush(web_push))
+                }
+                _ => Err(ProviderNotAvailable(provider.into())),
+            },
             #[cfg(any(debug_assertions, test))]
             ProviderKind::Noop => {
                 debug!("noop provider is matched");
@@ -293,9 +581,23 @@ impl Tenant {
     }
 }
 
+fn provider_condition(provider: &ProviderKind) -> &'static str {
+    match provider {
+        ProviderKind::Apns | ProviderKind::ApnsSandbox => APNS_CONFIGURED,
+        ProviderKind::Fcm => FCM_CONFIGURED,
+        ProviderKind::Hms => HMS_CONFIGURED,
+        ProviderKind::WebPush => WEB_PUSH_CONFIGURED,
+        #[cfg(any(debug_assertions, test))]
+        ProviderKind::Noop => "TRUE",
+    }
+}
+
 #[async_trait]
 pub trait TenantStore {
     async fn get_tenant(&self, id: &str) -> Result<Tenant>;
+    async fn get_tenant_ids(&self) -> Result<Vec<String>>;
+    async fn list_tenants(&self, params: TenantListParams) -> Result<Vec<Tenant>>;
+    async fn get_tenant_summary(&self) -> Result<TenantSummary>;
     async fn delete_tenant(&self, id: &str) -> Result<()>;
     async fn create_tenant(&self, params: TenantUpdateParams) -> Result<Tenant>;
     async fn update_tenant_fcm(&self, id: &str, params: TenantFcmUpdateParams) -> Result<Tenant>;
@@ -313,8 +615,57 @@ pub trait TenantStore {
         params: TenantApnsUpdateAuth,
     ) -> Result<Tenant>;
     async fn update_tenant_delete_apns(&self, id: &str) -> Result<Tenant>;
-    async fn suspend_tenant(&self, id: &str, reason: &str) -> Result<()>;
-    async fn unsuspend_tenant(&self, id: &str) -> Result<()>;
+    async fn update_tenant_hms(&self, id: &str, params: TenantHmsUpdateParams) -> Result<Tenant>;
+    async fn update_tenant_delete_hms(&self, id: &str) -> Result<Tenant>;
+    async fn update_tenant_web_push(
+        &self,
+        id: &str,
+        params: TenantWebPushUpdateParams,
+    ) -> Result<Tenant>;
+    async fn update_tenant_delete_web_push(&self, id: &str) -> Result<Tenant>;
+    async fn update_tenant_rate_limits(
+        &self,
+        id: &str,
+        params: TenantRateLimitUpdateParams,
+    ) -> Result<Tenant>;
+    async fn update_tenant_webhook(
+        &self,
+        id: &str,
+        params: TenantWebhookUpdateParams,
+    ) -> Result<Tenant>;
+    async fn update_tenant_delete_webhook(&self, id: &str) -> Result<Tenant>;
+    /// Overwrites all credential columns as they are given
+    async fn update_tenant_credentials(
+        &self,
+        id: &str,
+        params: TenantCredentials,
+    ) -> Result<Tenant>;
+    /// Suspends the tenant unless it already is, returns whether it was
+    async fn suspend_tenant(&self, id: &str, params: TenantSuspendParams) -> Result<bool>;
+    /// Lifts the tenant's suspension, returns whether it was suspended
+    async fn unsuspend_tenant(&self, id: &str, reason: &str) -> Result<bool>;
+    async fn get_tenant_suspension_history(&self, id: &str) -> Result<Vec<TenantSuspension>>;
+    /// Tenants authenticating with APNs using a certificate
+    async fn get_apns_certificate_tenants(&self) -> Result<Vec<Tenant>>;
+    async fn update_tenant_apns_certificate_expiry(
+        &self,
+        id: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<()>;
+    /// Records that the tenant is being warned about its expiring certificate,
+    /// returns false if another instance already did
+    async fn mark_tenant_apns_expiry_notified(&self, id: &str) -> Result<bool>;
+    /// The tenant's templates, ordered by name
+    async fn get_tenant_templates(&self, id: &str) -> Result<Vec<NotificationTemplate>>;
+    async fn get_tenant_template(&self, id: &str, name: &str) -> Result<NotificationTemplate>;
+    /// Creates the template or replaces the existing one
+    async fn update_tenant_template(
+        &self,
+        id: &str,
+        name: &str,
+        params: TenantTemplateUpdateParams,
+    ) -> Result<NotificationTemplate>;
+    async fn delete_tenant_template(&self, id: &str, name: &str) -> Result<()>;
 }
 
 #[async_trait]
@@ -335,6 +686,81 @@ impl TenantStore for PgPool {
         }
     }
 
+    #[instrument(skip(self))]
+    async fn get_tenant_ids(&self) -> Result<Vec<String>> {
+        let ids = sqlx::query_scalar::<sqlx::postgres::Postgres, String>(
+            "SELECT id FROM public.tenants ORDER BY id",
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(ids)
+    }
+
+    #[instrument(skip(self))]
+    async fn list_tenants(&self, params: TenantListParams) -> Result<Vec<Tenant>> {
+        let mut query_builder = sqlx::QueryBuilder::new("SELECT * FROM public.tenants WHERE TRUE");
+        if let Some(suspended) = params.suspended {
+            query_builder.push(" AND suspended = ").push_bind(suspended);
+        }
+        if let Some(provider) = &params.provider {
+            query_builder
+                .push(" AND ")
+                .push(provider_condition(provider));
+        }
+        if let Some(updated_after) = params.updated_after {
+            query_builder
+                .push(" AND updated_at >= ")
+                .push_bind(updated_after);
+        }
+        if let Some(updated_before) = params.updated_before {
+            query_builder
+                .push(" AND updated_at < ")
+                .push_bind(updated_before);
+        }
+        if let Some(id_prefix) = params.id_prefix {
+            let pattern = id_prefix
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            query_builder
+                .push(" AND id LIKE ")
+                .push_bind(format!("{pattern}%"));
+        }
+        if let Some(after_id) = params.after_id {
+            query_builder.push(" AND id > ").push_bind(after_id);
+        }
+        query_builder
+            .push(" ORDER BY id LIMIT ")
+            .push_bind(params.limit);
+
+        let res = query_builder
+            .build_query_as::<Tenant>()
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_tenant_summary(&self) -> Result<TenantSummary> {
+        let query = format!(
+            "SELECT COUNT(*) AS total,
+                COUNT(*) FILTER (WHERE suspended) AS suspended,
+                COUNT(*) FILTER (WHERE {APNS_CONFIGURED}) AS apns,
+                COUNT(*) FILTER (WHERE {FCM_CONFIGURED}) AS fcm,
+                COUNT(*) FILTER (WHERE {FCM_V1_CONFIGURED}) AS fcm_v1,
+                COUNT(*) FILTER (WHERE {HMS_CONFIGURED}) AS hms,
+                COUNT(*) FILTER (WHERE {WEB_PUSH_CONFIGURED}) AS web_push
+            FROM public.tenants"
+        );
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, TenantSummary>(&query)
+            .fetch_one(self)
+            .await?;
+
+        Ok(res)
+    }
+
     #[instrument(skip(self))]
     async fn delete_tenant(&self, id: &str) -> Result<()> {
         let mut query_builder = sqlx::QueryBuilder::new("DELETE FROM public.tenants WHERE id = ");
@@ -364,14 +790,13 @@ impl TenantStore for PgPool {
 
     #[instrument(skip(self))]
     async fn update_tenant_fcm(&self, id: &str, params: TenantFcmUpdateParams) -> Result<Tenant> {
-        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+        let query = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
             "UPDATE public.tenants SET fcm_api_key = $2, updated_at = NOW() WHERE id = $1 \
              RETURNING *;",
         )
         .bind(id)
-        .bind(params.fcm_api_key)
-        .fetch_one(self)
-        .await?;
+        .bind(params.fcm_api_key);
+        let res = update_credentials(self, id, query, FCM_SUSPENSION_REASONS).await?;
 
         Ok(res)
     }
@@ -385,10 +810,8 @@ impl TenantStore for PgPool {
             WHERE id = $1
             RETURNING *
         ";
-        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query)
-            .bind(id)
-            .fetch_one(self)
-            .await?;
+        let query = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query).bind(id);
+        let res = query.fetch_one(self).await?;
 
         Ok(res)
     }
@@ -399,14 +822,13 @@ impl TenantStore for PgPool {
         id: &str,
         params: TenantFcmV1UpdateParams,
     ) -> Result<Tenant> {
-        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+        let query = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
             "UPDATE public.tenants SET fcm_v1_credentials = $2, updated_at = NOW() WHERE id = $1 \
              RETURNING *;",
         )
         .bind(id)
-        .bind(params.fcm_v1_credentials)
-        .fetch_one(self)
-        .await?;
+        .bind(params.fcm_v1_credentials);
+        let res = update_credentials(self, id, query, FCM_SUSPENSION_REASONS).await?;
 
         Ok(res)
     }
@@ -420,10 +842,8 @@ impl TenantStore for PgPool {
             WHERE id = $1
             RETURNING *
         ";
-        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query)
-            .bind(id)
-            .fetch_one(self)
-            .await?;
+        let query = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query).bind(id);
+        let res = query.fetch_one(self).await?;
 
         Ok(res)
     }
@@ -448,14 +868,15 @@ impl TenantStore for PgPool {
         id: &str,
         params: TenantApnsUpdateAuth,
     ) -> Result<Tenant> {
-        let res = match params {
+        let query = match params {
             TenantApnsUpdateAuth::Certificate {
                 apns_certificate,
                 apns_certificate_password,
             } => sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
                 "UPDATE public.tenants SET apns_type = 'certificate'::apns_type, apns_certificate \
                  = $2, apns_certificate_password = $3, apns_pkcs8_pem = null, apns_team_id = \
-                 null, apns_key_id = null, updated_at = NOW() WHERE id = $1 RETURNING *;",
+                 null, apns_key_id = null, apns_certificate_expires_at = null, \
+                 apns_expiry_notified_at = null, updated_at = NOW() WHERE id = $1 RETURNING *;",
             )
             .bind(id)
             .bind(apns_certificate)
@@ -467,15 +888,15 @@ impl TenantStore for PgPool {
             } => sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
                 "UPDATE public.tenants SET apns_type = 'token'::apns_type, apns_pkcs8_pem = $2, \
                  apns_team_id = $3, apns_key_id = $4, apns_certificate = null, \
-                 apns_certificate_password = null, updated_at = NOW() WHERE id = $1 RETURNING *;",
+                 apns_certificate_password = null, apns_certificate_expires_at = null, \
+                 apns_expiry_notified_at = null, updated_at = NOW() WHERE id = $1 RETURNING *;",
             )
             .bind(id)
             .bind(apns_pkcs8_pem)
             .bind(apns_team_id)
             .bind(apns_key_id),
-        }
-        .fetch_one(self)
-        .await?;
+        };
+        let res = update_credentials(self, id, query, APNS_SUSPENSION_REASONS).await?;
 
         Ok(res)
     }
@@ -491,12 +912,138 @@ impl TenantStore for PgPool {
                 apns_certificate_password = NULL,
                 apns_pkcs8_pem = NULL,
                 apns_team_id = NULL,
-                apns_key_id = NULL
+                apns_key_id = NULL,
+                apns_certificate_expires_at = NULL,
+                apns_expiry_notified_at = NULL
+            WHERE id = $1
+            RETURNING *
+        ";
+        let query = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query).bind(id);
+        let res = query.fetch_one(self).await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self, params))]
+    async fn update_tenant_hms(&self, id: &str, params: TenantHmsUpdateParams) -> Result<Tenant> {
+        let query = "
+            UPDATE public.tenants
+            SET updated_at = NOW(),
+                hms_app_id = $2,
+                hms_app_secret = $3
+            WHERE id = $1
+            RETURNING *
+        ";
+        let query = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query)
+            .bind(id)
+            .bind(params.hms_app_id)
+            .bind(params.hms_app_secret);
+        let res = update_credentials(self, id, query, HMS_SUSPENSION_REASONS).await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn update_tenant_delete_hms(&self, id: &str) -> Result<Tenant> {
+        let query = "
+            UPDATE public.tenants
+            SET updated_at = NOW(),
+                hms_app_id = NULL,
+                hms_app_secret = NULL
+            WHERE id = $1
+            RETURNING *
+        ";
+        let query = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query).bind(id);
+        let res = query.fetch_one(self).await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self, params))]
+    async fn update_tenant_web_push(
+        &self,
+        id: &str,
+        params: TenantWebPushUpdateParams,
+    ) -> Result<Tenant> {
+        let query = "
+            UPDATE public.tenants
+            SET updated_at = NOW(),
+                web_push_vapid_key = $2,
+                web_push_vapid_subject = $3
+            WHERE id = $1
+            RETURNING *
+        ";
+        let query = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query)
+            .bind(id)
+            .bind(params.web_push_vapid_key)
+            .bind(params.web_push_vapid_subject);
+        let res = update_credentials(self, id, query, WEB_PUSH_SUSPENSION_REASONS).await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn update_tenant_delete_web_push(&self, id: &str) -> Result<Tenant> {
+        let query = "
+            UPDATE public.tenants
+            SET updated_at = NOW(),
+                web_push_vapid_key = NULL,
+                web_push_vapid_subject = NULL
+            WHERE id = $1
+            RETURNING *
+        ";
+        let query = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query).bind(id);
+        let res = query.fetch_one(self).await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn update_tenant_rate_limits(
+        &self,
+        id: &str,
+        params: TenantRateLimitUpdateParams,
+    ) -> Result<Tenant> {
+        let query = "
+            UPDATE public.tenants
+            SET updated_at = NOW(),
+                push_rate_limit_per_minute = $2,
+                push_rate_limit_burst = $3,
+                client_push_rate_limit_per_minute = $4,
+                client_push_rate_limit_burst = $5
             WHERE id = $1
             RETURNING *
         ";
         let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query)
             .bind(id)
+            .bind(params.push_rate_limit_per_minute)
+            .bind(params.push_rate_limit_burst)
+            .bind(params.client_push_rate_limit_per_minute)
+            .bind(params.client_push_rate_limit_burst)
+            .fetch_one(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self, params))]
+    async fn update_tenant_webhook(
+        &self,
+        id: &str,
+        params: TenantWebhookUpdateParams,
+    ) -> Result<Tenant> {
+        let query = "
+            UPDATE public.tenants
+            SET updated_at = NOW(),
+                webhook_url = $2,
+                webhook_secret = $3
+            WHERE id = $1
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query)
+            .bind(id)
+            .bind(params.webhook_url)
+            .bind(params.webhook_secret)
             .fetch_one(self)
             .await?;
 
@@ -504,35 +1051,320 @@ impl TenantStore for PgPool {
     }
 
     #[instrument(skip(self))]
-    async fn suspend_tenant(&self, id: &str, reason: &str) -> Result<()> {
-        let mut query_builder = sqlx::QueryBuilder::new(
-            "UPDATE public.tenants SET suspended = true, updated_at = NOW(), suspended_reason =",
-        );
-        query_builder.push_bind(reason);
-        query_builder.push(" WHERE id = ");
-        query_builder.push_bind(id);
-        let query = query_builder.build();
+    async fn update_tenant_delete_webhook(&self, id: &str) -> Result<Tenant> {
+        let query = "
+            UPDATE public.tenants
+            SET updated_at = NOW(),
+                webhook_url = NULL,
+                webhook_secret = NULL
+            WHERE id = $1
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query)
+            .bind(id)
+            .fetch_one(self)
+            .await?;
 
-        self.execute(query).await?;
+        Ok(res)
+    }
+
+    #[instrument(skip(self, params))]
+    async fn update_tenant_credentials(
+        &self,
+        id: &str,
+        params: TenantCredentials,
+    ) -> Result<Tenant> {
+        let query = "
+            UPDATE public.tenants
+            SET updated_at = NOW(),
+                fcm_api_key = $2,
+                fcm_v1_credentials = $3,
+                apns_certificate = $4,
+                apns_certificate_password = $5,
+                apns_pkcs8_pem = $6,
+                hms_app_secret = $7,
+                web_push_vapid_key = $8,
+                webhook_secret = $9
+            WHERE id = $1
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(query)
+            .bind(id)
+            .bind(params.fcm_api_key)
+            .bind(params.fcm_v1_credentials)
+            .bind(params.apns_certificate)
+            .bind(params.apns_certificate_password)
+            .bind(params.apns_pkcs8_pem)
+            .bind(params.hms_app_secret)
+            .bind(params.web_push_vapid_key)
+            .bind(params.webhook_secret)
+            .fetch_one(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn suspend_tenant(&self, id: &str, params: TenantSuspendParams) -> Result<bool> {
+        let mut tx = self.begin().await?;
+
+        // Concurrent failing deliveries only record the first suspension
+        let suspended = sqlx::query(
+            "UPDATE public.tenants SET suspended = true, updated_at = NOW(), suspended_reason = $2 \
+             WHERE id = $1 AND NOT suspended",
+        )
+        .bind(id)
+        .bind(&params.reason)
+        .execute(&mut tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        if suspended {
+            insert_suspension_history(
+                &mut tx,
+                id,
+                SuspensionAction::Suspended,
+                &params.reason,
+                params.client_id.as_deref(),
+                params.notification_id.as_deref(),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(suspended)
+    }
+
+    #[instrument(skip(self))]
+    async fn unsuspend_tenant(&self, id: &str, reason: &str) -> Result<bool> {
+        let mut tx = self.begin().await?;
+        let unsuspended = unsuspend(&mut tx, id, reason, None).await?;
+        tx.commit().await?;
+
+        Ok(unsuspended)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_tenant_suspension_history(&self, id: &str) -> Result<Vec<TenantSuspension>> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, TenantSuspension>(
+            "SELECT action, reason, client_id, notification_id, created_at
+            FROM public.tenant_suspension_history
+            WHERE tenant_id = $1
+            ORDER BY created_at DESC, id DESC",
+        )
+        .bind(id)
+        .fetch_all(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_apns_certificate_tenants(&self) -> Result<Vec<Tenant>> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "SELECT * FROM public.tenants WHERE apns_type = 'certificate'::apns_type ORDER BY id",
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn update_tenant_apns_certificate_expiry(
+        &self,
+        id: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE public.tenants SET apns_certificate_expires_at = $2 WHERE id = $1")
+            .bind(id)
+            .bind(expires_at)
+            .execute(self)
+            .await?;
 
         Ok(())
     }
 
     #[instrument(skip(self))]
-    async fn unsuspend_tenant(&self, id: &str) -> Result<()> {
-        let mut query_builder = sqlx::QueryBuilder::new(
-            "UPDATE public.tenants SET suspended = false, updated_at = NOW(), suspended_reason = \
-             null WHERE id = ",
-        );
-        query_builder.push_bind(id);
-        let query = query_builder.build();
+    async fn mark_tenant_apns_expiry_notified(&self, id: &str) -> Result<bool> {
+        let res = sqlx::query(
+            "UPDATE public.tenants SET apns_expiry_notified_at = NOW() WHERE id = $1 AND \
+             apns_expiry_notified_at IS NULL",
+        )
+        .bind(id)
+        .execute(self)
+        .await?;
 
-        self.execute(query).await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_tenant_templates(&self, id: &str) -> Result<Vec<NotificationTemplate>> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, NotificationTemplate>(
+            "SELECT * FROM public.tenant_templates WHERE tenant_id = $1 ORDER BY name",
+        )
+        .bind(id)
+        .fetch_all(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_tenant_template(&self, id: &str, name: &str) -> Result<NotificationTemplate> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, NotificationTemplate>(
+            "SELECT * FROM public.tenant_templates WHERE tenant_id = $1 AND name = $2",
+        )
+        .bind(id)
+        .bind(name)
+        .fetch_optional(self)
+        .await?;
+
+        res.ok_or_else(|| Error::TemplateNotFound(name.to_string()))
+    }
+
+    #[instrument(skip(self, params))]
+    async fn update_tenant_template(
+        &self,
+        id: &str,
+        name: &str,
+        params: TenantTemplateUpdateParams,
+    ) -> Result<NotificationTemplate> {
+        let query = "
+            INSERT INTO public.tenant_templates
+                (tenant_id, name, mode, default_locale, localizations)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (tenant_id, name)
+            DO UPDATE SET mode = excluded.mode,
+                          default_locale = excluded.default_locale,
+                          localizations = excluded.localizations,
+                          updated_at = NOW()
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, NotificationTemplate>(query)
+            .bind(id)
+            .bind(name)
+            .bind(params.mode)
+            .bind(params.default_locale)
+            .bind(Json(params.localizations))
+            .fetch_one(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_tenant_template(&self, id: &str, name: &str) -> Result<()> {
+        let res =
+            sqlx::query("DELETE FROM public.tenant_templates WHERE tenant_id = $1 AND name = $2")
+                .bind(id)
+                .bind(name)
+                .execute(self)
+                .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(Error::TemplateNotFound(name.to_string()));
+        }
 
         Ok(())
     }
 }
 
+/// Reason recorded when new credentials lift a suspension
+pub(crate) const CREDENTIALS_UPDATED_REASON: &str = "Credentials updated";
+
+/// Reasons recorded when a provider rejects the tenant's credentials
+pub(crate) const INVALID_APNS_CREDENTIALS_REASON: &str = "Invalid APNS Credentials";
+pub(crate) const APNS_CERTIFICATE_EXPIRED_REASON: &str = "APNs certificate expired";
+pub(crate) const APNS_CERTIFICATE_UNKNOWN_CA_REASON: &str = "Unknown APNs certificate's CA";
+pub(crate) const APNS_INVALID_PROVIDER_TOKEN_REASON: &str =
+    "APNs certificate invalid provider token";
+pub(crate) const INVALID_FCM_CREDENTIALS_REASON: &str = "Invalid FCM Credentials";
+pub(crate) const INVALID_HMS_CREDENTIALS_REASON: &str = "Invalid HMS Credentials";
+pub(crate) const INVALID_WEB_PUSH_CREDENTIALS_REASON: &str = "Invalid Web Push VAPID key";
+
+/// The suspensions new credentials of each provider resolve
+pub(crate) const APNS_SUSPENSION_REASONS: &[&str] = &[
+    INVALID_APNS_CREDENTIALS_REASON,
+    APNS_CERTIFICATE_EXPIRED_REASON,
+    APNS_CERTIFICATE_UNKNOWN_CA_REASON,
+    APNS_INVALID_PROVIDER_TOKEN_REASON,
+];
+pub(crate) const FCM_SUSPENSION_REASONS: &[&str] = &[INVALID_FCM_CREDENTIALS_REASON];
+pub(crate) const HMS_SUSPENSION_REASONS: &[&str] = &[INVALID_HMS_CREDENTIALS_REASON];
+pub(crate) const WEB_PUSH_SUSPENSION_REASONS: &[&str] = &[INVALID_WEB_PUSH_CREDENTIALS_REASON];
+
+/// Lifts the tenant's suspension, only if it was suspended for one of
+/// `suspended_for` when given
+async fn unsuspend(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: &str,
+    reason: &str,
+    suspended_for: Option<&[&str]>,
+) -> Result<bool> {
+    let unsuspended = sqlx::query(
+        "UPDATE public.tenants SET suspended = false, updated_at = NOW(), suspended_reason = null \
+         WHERE id = $1 AND suspended AND ($2::text[] IS NULL OR suspended_reason = ANY($2))",
+    )
+    .bind(id)
+    .bind(suspended_for)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if unsuspended {
+        insert_suspension_history(tx, id, SuspensionAction::Unsuspended, reason, None, None)
+            .await?;
+    }
+
+    Ok(unsuspended)
+}
+
+async fn insert_suspension_history(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: &str,
+    action: SuspensionAction,
+    reason: &str,
+    client_id: Option<&str>,
+    notification_id: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO public.tenant_suspension_history (tenant_id, action, reason, client_id, \
+         notification_id) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(id)
+    .bind(action)
+    .bind(reason)
+    .bind(client_id)
+    .bind(notification_id)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Runs a credential update and lifts the tenant's suspension in the same
+/// transaction when the provider's new credentials resolve what it was
+/// suspended for
+async fn update_credentials<'q>(
+    pool: &PgPool,
+    id: &str,
+    query: sqlx::query::QueryAs<'q, sqlx::Postgres, Tenant, sqlx::postgres::PgArguments>,
+    resolves: &[&str],
+) -> Result<Tenant> {
+    let mut tx = pool.begin().await?;
+    let mut tenant = query.fetch_one(&mut tx).await?;
+    if unsuspend(&mut tx, id, CREDENTIALS_UPDATED_REASON, Some(resolves)).await? {
+        tenant.suspended = false;
+        tenant.suspended_reason = None;
+    }
+    tx.commit().await?;
+
+    Ok(tenant)
+}
+
 #[cfg(not(feature = "multitenant"))]
 pub struct DefaultTenantStore(Tenant);
 
@@ -550,8 +1382,21 @@ impl DefaultTenantStore {
             apns_pkcs8_pem: config.apns_pkcs8_pem.clone(),
             apns_key_id: config.apns_key_id.clone(),
             apns_team_id: config.apns_team_id.clone(),
+            hms_app_id: config.hms_app_id.clone(),
+            hms_app_secret: config.hms_app_secret.clone(),
+            web_push_vapid_key: config.web_push_vapid_key.clone(),
+            web_push_vapid_subject: config.web_push_vapid_subject.clone(),
+            // The configured defaults apply in single tenant mode
+            push_rate_limit_per_minute: None,
+            push_rate_limit_burst: None,
+            client_push_rate_limit_per_minute: None,
+            client_push_rate_limit_burst: None,
             suspended: false,
             suspended_reason: None,
+            apns_certificate_expires_at: None,
+            apns_expiry_notified_at: None,
+            webhook_url: None,
+            webhook_secret: None,
             created_at: Default::default(),
             updated_at: Default::default(),
         }))
@@ -565,20 +1410,33 @@ impl TenantStore for DefaultTenantStore {
         Ok(self.0.clone())
     }
 
+    async fn get_tenant_ids(&self) -> Result<Vec<String>> {
+        Ok(vec![self.0.id.clone()])
+    }
+
+    // The tenant API is only available in multi-tenant mode
+    async fn list_tenants(&self, _params: TenantListParams) -> Result<Vec<Tenant>> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    async fn get_tenant_summary(&self) -> Result<TenantSummary> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
     async fn delete_tenant(&self, _id: &str) -> Result<()> {
-        panic!("Shouldn't have run in single tenant mode")
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 
     async fn create_tenant(&self, _params: TenantUpdateParams) -> Result<Tenant> {
-        panic!("Shouldn't have run in single tenant mode")
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 
     async fn update_tenant_fcm(&self, _id: &str, _params: TenantFcmUpdateParams) -> Result<Tenant> {
-        panic!("Shouldn't have run in single tenant mode")
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 
     async fn update_tenant_delete_fcm(&self, _id: &str) -> Result<Tenant> {
-        panic!("Shouldn't have run in single tenant mode")
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 
     async fn update_tenant_fcm_v1(
@@ -586,11 +1444,11 @@ impl TenantStore for DefaultTenantStore {
         _id: &str,
         _params: TenantFcmV1UpdateParams,
     ) -> Result<Tenant> {
-        panic!("Shouldn't have run in single tenant mode")
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 
     async fn update_tenant_delete_fcm_v1(&self, _id: &str) -> Result<Tenant> {
-        panic!("Shouldn't have run in single tenant mode")
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 
     async fn update_tenant_apns(
@@ -598,7 +1456,7 @@ impl TenantStore for DefaultTenantStore {
         _id: &str,
         _params: TenantApnsUpdateParams,
     ) -> Result<Tenant> {
-        panic!("Shouldn't have run in single tenant mode")
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 
     async fn update_tenant_apns_auth(
@@ -606,18 +1464,109 @@ impl TenantStore for DefaultTenantStore {
         _id: &str,
         _params: TenantApnsUpdateAuth,
     ) -> Result<Tenant> {
-        panic!("Shouldn't have run in single tenant mode")
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 
     async fn update_tenant_delete_apns(&self, _id: &str) -> Result<Tenant> {
-        panic!("Shouldn't have run in single tenant mode")
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    async fn update_tenant_hms(&self, _id: &str, _params: TenantHmsUpdateParams) -> Result<Tenant> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 
-    async fn suspend_tenant(&self, _id: &str, _reason: &str) -> Result<()> {
-        panic!("Shouldn't have run in single tenant mode")
+    async fn update_tenant_delete_hms(&self, _id: &str) -> Result<Tenant> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    async fn update_tenant_web_push(
+        &self,
+        _id: &str,
+        _params: TenantWebPushUpdateParams,
+    ) -> Result<Tenant> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    async fn update_tenant_delete_web_push(&self, _id: &str) -> Result<Tenant> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    async fn update_tenant_rate_limits(
+        &self,
+        _id: &str,
+        _params: TenantRateLimitUpdateParams,
+    ) -> Result<Tenant> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    async fn update_tenant_webhook(
+        &self,
+        _id: &str,
+        _params: TenantWebhookUpdateParams,
+    ) -> Result<Tenant> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    async fn update_tenant_delete_webhook(&self, _id: &str) -> Result<Tenant> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    async fn update_tenant_credentials(
+        &self,
+        _id: &str,
+        _params: TenantCredentials,
+    ) -> Result<Tenant> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    // The configured tenant is never suspended
+    async fn suspend_tenant(&self, _id: &str, _params: TenantSuspendParams) -> Result<bool> {
+        Ok(false)
+    }
+
+    async fn unsuspend_tenant(&self, _id: &str, _reason: &str) -> Result<bool> {
+        Ok(false)
+    }
+
+    async fn get_tenant_suspension_history(&self, _id: &str) -> Result<Vec<TenantSuspension>> {
+        Ok(vec![])
+    }
+
+    async fn get_apns_certificate_tenants(&self) -> Result<Vec<Tenant>> {
+        Ok(vec![])
+    }
+
+    async fn update_tenant_apns_certificate_expiry(
+        &self,
+        _id: &str,
+        _expires_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    async fn mark_tenant_apns_expiry_notified(&self, _id: &str) -> Result<bool> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
+    }
+
+    async fn get_tenant_templates(&self, _id: &str) -> Result<Vec<NotificationTemplate>> {
+        Ok(vec![])
+    }
+
+    // Templates can't be created without the tenant API
+    async fn get_tenant_template(&self, _id: &str, name: &str) -> Result<NotificationTemplate> {
+        Err(Error::TemplateNotFound(name.to_string()))
+    }
+
+    async fn update_tenant_template(
+        &self,
+        _id: &str,
+        _name: &str,
+        _params: TenantTemplateUpdateParams,
+    ) -> Result<NotificationTemplate> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 
-    async fn unsuspend_tenant(&self, _id: &str) -> Result<()> {
-        panic!("Shouldn't have run in single tenant mode")
+    async fn delete_tenant_template(&self, _id: &str, _name: &str) -> Result<()> {
+        Err(Error::IncludedTenantIdWhenNotNeeded)
     }
 }
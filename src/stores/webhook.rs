@@ -0,0 +1,214 @@
+use {
+    crate::{
+        handlers::push_batch::BatchPushOutcome,
+        stores::{self, notification::DeliveryStatus},
+    },
+    async_trait::async_trait,
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    sqlx::types::Json,
+    std::{collections::HashMap, time::Duration},
+    tracing::instrument,
+    uuid::Uuid,
+};
+
+/// Events sent to tenants' webhooks
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    TenantSuspended {
+        reason: String,
+        client_id: Option<String>,
+        notification_id: Option<String>,
+    },
+    ClientDeleted {
+        client_id: String,
+        reason: String,
+    },
+    CredentialsExpiring {
+        provider: String,
+        expires_at: DateTime<Utc>,
+    },
+    DeliveryFailed {
+        client_id: String,
+        notification_id: String,
+        error: String,
+    },
+    TopicPushCompleted {
+        job_id: String,
+        topic: String,
+        clients: usize,
+        outcomes: HashMap<BatchPushOutcome, usize>,
+    },
+}
+
+impl WebhookEvent {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Self::TenantSuspended { .. } => "tenant_suspended",
+            Self::ClientDeleted { .. } => "client_deleted",
+            Self::CredentialsExpiring { .. } => "credentials_expiring",
+            Self::DeliveryFailed { .. } => "delivery_failed",
+            Self::TopicPushCompleted { .. } => "topic_push_completed",
+        }
+    }
+}
+
+/// An event in the outbound webhook queue, kept as the delivery log once sent
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub tenant_id: String,
+    pub event_type: String,
+    pub payload: Json<WebhookEvent>,
+
+    pub status: DeliveryStatus,
+    /// Number of send attempts, including the one in progress
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[async_trait]
+pub trait WebhookStore {
+    async fn enqueue_webhook_event(
+        &self,
+        tenant_id: &str,
+        event: &WebhookEvent,
+    ) -> stores::Result<WebhookDelivery>;
+    /// Leases up to `limit` queued events that are due for an attempt
+    async fn claim_due_webhook_events(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<WebhookDelivery>>;
+    /// Records the outcome of an attempt. Queued events are retried at
+    /// `next_attempt_at`
+    async fn update_webhook_event(
+        &self,
+        id: &str,
+        status: DeliveryStatus,
+        response_status: Option<i32>,
+        last_error: Option<&str>,
+        next_attempt_at: DateTime<Utc>,
+    ) -> stores::Result<()>;
+    /// The tenant's most recent events, newest first
+    async fn get_webhook_events(
+        &self,
+        tenant_id: &str,
+        limit: i64,
+    ) -> stores::Result<Vec<WebhookDelivery>>;
+}
+
+#[async_trait]
+impl WebhookStore for sqlx::PgPool {
+    #[instrument(skip(self, event))]
+    async fn enqueue_webhook_event(
+        &self,
+        tenant_id: &str,
+        event: &WebhookEvent,
+    ) -> stores::Result<WebhookDelivery> {
+        let query = "
+            INSERT INTO public.webhook_events (id, tenant_id, event_type, payload)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, WebhookDelivery>(query)
+            .bind(Uuid::new_v4().to_string())
+            .bind(tenant_id)
+            .bind(event.event_type())
+            .bind(Json(event))
+            .fetch_one(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn claim_due_webhook_events(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> stores::Result<Vec<WebhookDelivery>> {
+        let lease_expiry = Utc::now()
+            + chrono::Duration::from_std(lease).unwrap_or_else(|_| chrono::Duration::zero());
+        let query = "
+            UPDATE public.webhook_events
+            SET attempts = attempts + 1,
+                next_attempt_at = $2
+            WHERE id IN (
+                SELECT id
+                FROM public.webhook_events
+                WHERE status = 'queued'
+                      AND next_attempt_at <= now()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, WebhookDelivery>(query)
+            .bind(limit)
+            .bind(lease_expiry)
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    #[instrument(skip(self))]
+    async fn update_webhook_event(
+        &self,
+        id: &str,
+        status: DeliveryStatus,
+        response_status: Option<i32>,
+        last_error: Option<&str>,
+        next_attempt_at: DateTime<Utc>,
+    ) -> stores::Result<()> {
+        let query = "
+            UPDATE public.webhook_events
+            SET status = $2,
+                response_status = $3,
+                last_error = $4,
+                next_attempt_at = $5,
+                delivered_at = CASE WHEN $2 = 'delivered'::delivery_status THEN now() END
+            WHERE id = $1
+        ";
+        sqlx::query(query)
+            .bind(id)
+            .bind(status)
+            .bind(response_status)
+            .bind(last_error)
+            .bind(next_attempt_at)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_webhook_events(
+        &self,
+        tenant_id: &str,
+        limit: i64,
+    ) -> stores::Result<Vec<WebhookDelivery>> {
+        let query = "
+            SELECT *
+            FROM public.webhook_events
+            WHERE tenant_id = $1
+            ORDER BY created_at DESC, id
+            LIMIT $2
+        ";
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, WebhookDelivery>(query)
+            .bind(tenant_id)
+            .bind(limit)
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+}
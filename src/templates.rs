@@ -0,0 +1,180 @@
+use {
+    crate::{
+        blob::DecryptedPayloadBlob,
+        error::{Error, Result},
+        handlers::push_message::PushMessageBody,
+        providers::{LegacyPushMessage, MessagePayload, PushMessage},
+        state::TenantStoreArc,
+        stores::{
+            client::Client,
+            tenant::{LocalizedTemplate, NotificationTemplate, TemplateMode},
+        },
+    },
+    base64::Engine as _,
+    serde::{Deserialize, Serialize},
+    std::{collections::HashMap, sync::Arc},
+};
+
+/// A push of one of the tenant's templates, rendered for each client
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct TemplatePushMessage {
+    /// Message id, deduplicated like the `id` of legacy messages
+    pub id: Arc<str>,
+    pub topic: Arc<str>,
+    /// Name of the tenant's template
+    pub template_id: String,
+    /// Values of the template's `{placeholder}`s
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+/// The message to send to the client, with the tenant's template rendered in
+/// the client's locale when the push references one
+pub async fn push_message(
+    tenant_store: &TenantStoreArc,
+    tenant_id: &str,
+    client: &Client,
+    body: &PushMessageBody,
+) -> Result<PushMessage> {
+    let Some(message) = &body.template else {
+        return body.push_message(client.always_raw);
+    };
+    body.validate()?;
+
+    let template = tenant_store
+        .get_tenant_template(tenant_id, &message.template_id)
+        .await?;
+    render(&template, message, client.metadata.locale.as_deref())
+        .map(PushMessage::LegacyPushMessage)
+}
+
+/// Renders the template as a plain legacy message. In passthrough mode the
+/// localization keys `<template>_title` and `<template>_body` are sent with
+/// the variables of their placeholders as arguments, and the default locale
+/// as fallback
+pub fn render(
+    template: &NotificationTemplate,
+    message: &TemplatePushMessage,
+    locale: Option<&str>,
+) -> Result<LegacyPushMessage> {
+    let blob = match template.mode {
+        TemplateMode::Render => {
+            let localized = localize(template, locale)?;
+            DecryptedPayloadBlob {
+                title: substitute(&localized.title, &message.variables),
+                body: substitute(&localized.body, &message.variables),
+                ..Default::default()
+            }
+        }
+        TemplateMode::Passthrough => {
+            let localized = localize(template, None)?;
+            let args = |text: &str| -> Vec<String> {
+                placeholders(text)
+                    .into_iter()
+                    .map(|name| message.variables.get(name).cloned().unwrap_or_default())
+                    .collect()
+            };
+            DecryptedPayloadBlob {
+                title: substitute(&localized.title, &message.variables),
+                body: substitute(&localized.body, &message.variables),
+                title_loc_key: Some(format!("{}_title", template.name)),
+                title_loc_args: args(&localized.title),
+                body_loc_key: Some(format!("{}_body", template.name)),
+                body_loc_args: args(&localized.body),
+                ..Default::default()
+            }
+        }
+    };
+
+    let blob = serde_json::to_vec(&blob).map_err(Error::InternalSerializationError)?;
+    Ok(LegacyPushMessage {
+        id: message.id.clone(),
+        payload: MessagePayload {
+            topic: message.topic.clone(),
+            flags: 0,
+            blob: base64::engine::general_purpose::STANDARD
+                .encode(blob)
+                .into(),
+        },
+    })
+}
+
+/// Picks the localization of the locale, falling back from e.g. `pt-BR` to
+/// `pt` and then to the template's default locale
+pub fn localize<'a>(
+    template: &'a NotificationTemplate,
+    locale: Option<&str>,
+) -> Result<&'a LocalizedTemplate> {
+    let find = |locale: &str| {
+        template
+            .localizations
+            .iter()
+            .find(|(key, _)| key.replace('_', "-").eq_ignore_ascii_case(locale))
+            .map(|(_, localized)| localized)
+    };
+
+    let locale = locale.map(|locale| locale.replace('_', "-"));
+    locale
+        .as_deref()
+        .and_then(|locale| {
+            find(locale).or_else(|| {
+                let (language, _) = locale.split_once('-')?;
+                find(language)
+            })
+        })
+        .or_else(|| find(&template.default_locale))
+        .ok_or_else(|| {
+            Error::InvalidTemplate(format!(
+                "template {} has no localization for its default locale",
+                template.name
+            ))
+        })
+}
+
+/// Replaces the `{placeholder}`s with their variables, placeholders without a
+/// variable are kept as they are
+pub fn substitute(text: &str, variables: &HashMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after
+            .find('}')
+            .and_then(|end| Some((end, variables.get(&after[..end])?)))
+        {
+            Some((end, value)) => {
+                rendered.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+
+    rendered
+}
+
+/// Names of the `{placeholder}`s in the order they appear
+pub fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        let name = &after[..end];
+        if name.is_empty() || name.contains('{') {
+            rest = after;
+        } else {
+            names.push(name);
+            rest = &after[end + 1..];
+        }
+    }
+
+    names
+}
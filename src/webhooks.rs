@@ -0,0 +1,320 @@
+use {
+    crate::{
+        delivery::backoff_delay,
+        error::{Error, Result},
+        log::prelude::*,
+        state::AppState,
+        stores::{
+            notification::DeliveryStatus,
+            tenant::Tenant,
+            webhook::{WebhookDelivery, WebhookEvent},
+        },
+    },
+    chrono::{DateTime, Utc},
+    futures_util::StreamExt,
+    moka::future::Cache,
+    openssl::{hash::MessageDigest, pkey::PKey, sign::Signer},
+    reqwest::{redirect, Url},
+    serde::Serialize,
+    std::{
+        net::{IpAddr, SocketAddr},
+        sync::Arc,
+        time::Duration,
+    },
+    tracing::instrument,
+};
+
+pub const SIGNATURE_HEADER: &str = "X-Echo-Signature";
+pub const EVENT_HEADER: &str = "X-Echo-Event";
+pub const EVENT_ID_HEADER: &str = "X-Echo-Event-Id";
+
+/// How long an event is reserved for the instance sending it
+const WEBHOOK_LEASE: Duration = Duration::from_secs(60);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const WEBHOOK_BATCH_SIZE: i64 = 50;
+const WEBHOOK_CONCURRENCY: usize = 16;
+const MAX_PINNED_CLIENTS: u64 = 10_000;
+const PINNED_CLIENT_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// The JSON body POSTed to the webhook
+#[derive(Serialize, Debug)]
+pub struct WebhookBody<'a> {
+    pub id: &'a str,
+    pub tenant_id: &'a str,
+    pub created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: &'a WebhookEvent,
+}
+
+/// Signs `<timestamp>.<body>` with HMAC-SHA256, returning the value of the
+/// signature header
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> Result<String> {
+    let key = PKey::hmac(secret.as_bytes())?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(format!("{timestamp}.").as_bytes())?;
+    signer.update(body)?;
+
+    Ok(format!(
+        "t={timestamp},v1={}",
+        hex::encode(signer.sign_to_vec()?)
+    ))
+}
+
+/// Parses a webhook URL, which must be https and must not point to an internal
+/// host
+pub fn validate_url(url: &str) -> Result<Url> {
+    let url = Url::parse(url).map_err(|_| Error::InvalidWebhookUrl)?;
+    if url.scheme() != "https" {
+        return Err(Error::InvalidWebhookUrl);
+    }
+
+    let host = url
+        .host_str()
+        .ok_or(Error::InvalidWebhookUrl)?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    let internal = match host.parse::<IpAddr>() {
+        Ok(ip) => !is_public_ip(ip),
+        Err(_) => {
+            host == "localhost"
+                || host.ends_with(".localhost")
+                || host == "metadata.google.internal"
+        }
+    };
+    if internal {
+        return Err(Error::InvalidWebhookUrl);
+    }
+
+    Ok(url)
+}
+
+/// Whether webhooks may be sent to the address. Private, loopback and
+/// link-local networks, which include the cloud metadata endpoint, are off
+/// limits
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || a == 0
+                // Shared address space, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                // Unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Link-local, fe80::/10
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Resolves the webhook's host, refusing it if any of its addresses is
+/// internal. The request is pinned to the returned address so a second lookup
+/// can't point it elsewhere
+async fn resolve(url: &Url) -> Result<SocketAddr> {
+    let host = url.host_str().ok_or(Error::InvalidWebhookUrl)?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs = tokio::net::lookup_host((host.trim_start_matches('[').trim_end_matches(']'), port))
+        .await?
+        .collect::<Vec<_>>();
+    if addrs.is_empty() || addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        return Err(Error::InvalidWebhookUrl);
+    }
+
+    Ok(addrs[0])
+}
+
+/// HTTP clients for requests to URLs supplied by tenants and clients, each
+/// pinned to the address its host was checked to resolve to. Redirects aren't
+/// followed as they could lead to an internal host
+#[derive(Clone)]
+pub struct PinnedClients {
+    clients: Cache<(String, SocketAddr), reqwest::Client>,
+}
+
+impl Default for PinnedClients {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PinnedClients {
+    pub fn new() -> Self {
+        Self {
+            clients: Cache::builder()
+                .max_capacity(MAX_PINNED_CLIENTS)
+                .time_to_idle(PINNED_CLIENT_IDLE_TIMEOUT)
+                .build(),
+        }
+    }
+
+    /// Resolves the URL's host, refusing internal addresses, and returns a
+    /// client that connects to the resolved address
+    pub async fn get(&self, url: &Url) -> Result<reqwest::Client> {
+        let addr = resolve(url).await?;
+        let key = (url.host_str().unwrap_or_default().to_string(), addr);
+        if let Some(client) = self.clients.get(&key).await {
+            return Ok(client);
+        }
+
+        let client = reqwest::Client::builder()
+            .redirect(redirect::Policy::none())
+            .resolve(&key.0, addr)
+            .build()?;
+        self.clients.insert(key, client.clone()).await;
+
+        Ok(client)
+    }
+}
+
+/// Queues the event if the tenant has a webhook configured. Failures are
+/// logged as events must not fail the operation that emitted them
+pub async fn emit(state: &AppState, tenant: &Tenant, event: WebhookEvent) {
+    if tenant.webhook_url.is_none() {
+        return;
+    }
+
+    match state
+        .webhook_store
+        .enqueue_webhook_event(&tenant.id, &event)
+        .await
+    {
+        Ok(delivery) => debug!(
+            tenant_id = %tenant.id,
+            event_id = %delivery.id,
+            "queued {} webhook event",
+            delivery.event_type
+        ),
+        Err(e) => warn!(
+            tenant_id = %tenant.id,
+            "error queueing {} webhook event: {e:?}",
+            event.event_type()
+        ),
+    }
+}
+
+/// Sends a claimed event to the tenant's webhook, returning the response status
+async fn send(state: &AppState, tenant: &Tenant, event: &WebhookDelivery) -> Result<u16> {
+    let (Some(url), Some(secret)) = (&tenant.webhook_url, &tenant.webhook_secret) else {
+        return Err(Error::WebhookNotConfigured);
+    };
+
+    let body = serde_json::to_vec(&WebhookBody {
+        id: &event.id,
+        tenant_id: &event.tenant_id,
+        created_at: event.created_at,
+        event: &event.payload,
+    })
+    .map_err(Error::InternalSerializationError)?;
+    let signature = sign(secret, Utc::now().timestamp(), &body)?;
+
+    // Checked again as the URL may have been registered before it was
+    // validated, and its host may resolve to another address since
+    let url = validate_url(url)?;
+    let client = state.pinned_clients.get(&url).await?;
+
+    let response = client
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, signature)
+        .header(EVENT_HEADER, event.event_type.as_str())
+        .header(EVENT_ID_HEADER, event.id.as_str())
+        .body(body)
+        .send()
+        .await?;
+
+    Ok(response.status().as_u16())
+}
+
+#[instrument(skip_all, fields(tenant_id = %event.tenant_id, event_id = %event.id, attempt = event.attempts))]
+async fn deliver(state: &AppState, event: WebhookDelivery) {
+    let result = match state.tenant_store.get_tenant(&event.tenant_id).await {
+        Ok(tenant) => send(state, &tenant, &event).await,
+        Err(e) => Err(e),
+    };
+
+    let (response_status, error) = match result {
+        Ok(status) if (200..300).contains(&status) => (Some(status), None),
+        Ok(status) => (
+            Some(status),
+            Some(format!("webhook responded with {status}")),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let response_status = response_status.map(i32::from);
+
+    let (status, next_attempt_at) = match &error {
+        None => (DeliveryStatus::Delivered, Utc::now()),
+        Some(_) if event.attempts < state.config.webhook_max_attempts as i32 => {
+            let delay = backoff_delay(
+                event.attempts as u32,
+                Duration::from_millis(state.config.webhook_backoff_base_ms),
+                Duration::from_millis(state.config.webhook_backoff_max_ms),
+            );
+            let next_attempt_at = Utc::now()
+                + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+            (DeliveryStatus::Queued, next_attempt_at)
+        }
+        Some(error) => {
+            warn!("giving up on webhook event: {error}");
+            (DeliveryStatus::Failed, Utc::now())
+        }
+    };
+
+    if let Err(e) = state
+        .webhook_store
+        .update_webhook_event(
+            &event.id,
+            status,
+            response_status,
+            error.as_deref(),
+            next_attempt_at,
+        )
+        .await
+    {
+        // The lease will expire and the event will be sent again
+        warn!("error updating webhook event: {e:?}");
+    }
+}
+
+/// Polls the queue for webhook events that are due to be sent
+pub async fn run(state: Arc<AppState>) {
+    let poll_interval = Duration::from_millis(state.config.webhook_poll_interval_ms);
+
+    loop {
+        match state
+            .webhook_store
+            .claim_due_webhook_events(WEBHOOK_BATCH_SIZE, WEBHOOK_LEASE)
+            .await
+        {
+            Ok(events) => {
+                let claimed = events.len();
+                futures_util::stream::iter(events)
+                    .for_each_concurrent(WEBHOOK_CONCURRENCY, |event| deliver(&state, event))
+                    .await;
+
+                if claimed == WEBHOOK_BATCH_SIZE as usize {
+                    continue;
+                }
+            }
+            Err(e) => warn!("error claiming webhook events: {e:?}"),
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
@@ -0,0 +1,3 @@
+ALTER TABLE public.tenants
+  ADD COLUMN web_push_vapid_key TEXT NULL DEFAULT NULL,
+  ADD COLUMN web_push_vapid_subject TEXT NULL DEFAULT NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE public.tenants
+  ADD COLUMN hms_app_id TEXT NULL DEFAULT NULL,
+  ADD COLUMN hms_app_secret TEXT NULL DEFAULT NULL;
@@ -0,0 +1,5 @@
+ALTER TABLE public.tenants
+  ADD COLUMN push_rate_limit_per_minute INTEGER NULL DEFAULT NULL,
+  ADD COLUMN push_rate_limit_burst INTEGER NULL DEFAULT NULL,
+  ADD COLUMN client_push_rate_limit_per_minute INTEGER NULL DEFAULT NULL,
+  ADD COLUMN client_push_rate_limit_burst INTEGER NULL DEFAULT NULL;
@@ -0,0 +1,6 @@
+CREATE INDEX tenants_suspended_idx
+    ON public.tenants (id)
+    WHERE suspended = true;
+
+CREATE INDEX tenants_updated_at_idx
+    ON public.tenants (updated_at);
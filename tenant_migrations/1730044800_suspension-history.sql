@@ -0,0 +1,20 @@
+CREATE TYPE public.suspension_action AS ENUM ('suspended', 'unsuspended');
+
+CREATE TABLE public.tenant_suspension_history
+(
+    id              bigserial primary key,
+    tenant_id       varchar(255)             not null
+        REFERENCES public.tenants (id)
+        ON DELETE CASCADE,
+    action          public.suspension_action not null,
+    reason          text,
+
+    -- The delivery that triggered the suspension
+    client_id       varchar(255),
+    notification_id varchar(255),
+
+    created_at      timestamptz              not null default now()
+);
+
+CREATE INDEX tenant_suspension_history_tenant_id_idx
+    ON public.tenant_suspension_history (tenant_id, created_at);
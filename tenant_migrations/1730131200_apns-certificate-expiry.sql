@@ -0,0 +1,7 @@
+ALTER TABLE public.tenants
+  ADD COLUMN apns_certificate_expires_at timestamptz NULL DEFAULT NULL,
+  ADD COLUMN apns_expiry_notified_at timestamptz NULL DEFAULT NULL;
+
+CREATE INDEX tenants_apns_certificate_expires_at_idx
+    ON public.tenants (apns_certificate_expires_at)
+    WHERE apns_certificate_expires_at IS NOT NULL;
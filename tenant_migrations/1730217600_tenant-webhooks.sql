@@ -0,0 +1,3 @@
+ALTER TABLE public.tenants
+  ADD COLUMN webhook_url text NULL DEFAULT NULL,
+  ADD COLUMN webhook_secret text NULL DEFAULT NULL;
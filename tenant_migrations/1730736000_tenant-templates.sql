@@ -0,0 +1,18 @@
+CREATE TYPE public.template_mode AS ENUM ('render', 'passthrough');
+
+CREATE TABLE public.tenant_templates
+(
+    tenant_id      varchar(255)         not null
+        REFERENCES public.tenants (id)
+        ON DELETE CASCADE,
+    name           varchar(255)         not null,
+    mode           public.template_mode not null default 'render',
+    default_locale varchar(35)          not null,
+    -- Title and body keyed by locale
+    localizations  jsonb                not null,
+
+    created_at     timestamptz          not null default now(),
+    updated_at     timestamptz          not null default now(),
+
+    PRIMARY KEY (tenant_id, name)
+);
@@ -0,0 +1,1101 @@
+use {
+    chrono::Utc,
+    echo_server::{
+        error::Error,
+        handlers::push_message::PushMessageBody,
+        providers::ProviderKind,
+        state::{
+            ClientStoreArc, DeliveryStoreArc, NotificationStoreArc, TenantStoreArc, WebhookStoreArc,
+        },
+        stores::{
+            client::{
+                Client, ClientEventListParams, ClientEventReason, ClientEventType, ClientMetadata,
+                ClientSegment, ClientSegmentCount,
+            },
+            memory::MemoryStore,
+            notification::DeliveryStatus,
+            tenant::{
+                LocalizedTemplate, SuspensionAction, TemplateMode, TenantFcmUpdateParams,
+                TenantListParams, TenantSuspendParams, TenantTemplateUpdateParams,
+                TenantUpdateParams, TenantWebPushUpdateParams,
+            },
+            webhook::WebhookEvent,
+            StoreError,
+        },
+    },
+    sqlx::sqlite::SqlitePoolOptions,
+    std::{collections::BTreeMap, sync::Arc, time::Duration},
+    uuid::Uuid,
+};
+
+const TENANT_ID: &str = "000-000-000-000";
+const LEASE: Duration = Duration::from_secs(60);
+const PAYLOAD: PushMessageBody = PushMessageBody {
+    raw: None,
+    legacy: None,
+    options: None,
+    template: None,
+    send_at: None,
+    delay: None,
+};
+
+/// The stores of one storage backend
+pub struct Backend {
+    pub clients: ClientStoreArc,
+    pub notifications: NotificationStoreArc,
+    pub deliveries: DeliveryStoreArc,
+    pub webhooks: WebhookStoreArc,
+    pub tenants: TenantStoreArc,
+}
+
+impl Backend {
+    pub fn memory() -> Self {
+        let store = MemoryStore::new();
+        Self {
+            clients: Arc::new(store.clone()),
+            notifications: Arc::new(store.clone()),
+            deliveries: Arc::new(store.clone()),
+            webhooks: Arc::new(store.clone()),
+            tenants: Arc::new(store),
+        }
+    }
+
+    /// A fresh in-memory SQLite database, tenants are kept in memory as SQLite
+    /// doesn't store them
+    pub async fn sqlite() -> Self {
+        // Every connection to `sqlite::memory:` opens a new database
+        let store = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./sqlite_migrations")
+            .run(&store)
+            .await
+            .unwrap();
+
+        Self {
+            clients: Arc::new(store.clone()),
+            notifications: Arc::new(store.clone()),
+            deliveries: Arc::new(store.clone()),
+            webhooks: Arc::new(store),
+            tenants: Arc::new(MemoryStore::new()),
+        }
+    }
+}
+
+fn gen_id(prefix: &str) -> String {
+    format!("{prefix}-{}", Uuid::new_v4())
+}
+
+fn client(token: &str, push_type: ProviderKind) -> Client {
+    Client {
+        tenant_id: TENANT_ID.to_string(),
+        push_type,
+        token: token.to_string(),
+        always_raw: false,
+        metadata: Default::default(),
+    }
+}
+
+fn assert_not_found<T: std::fmt::Debug>(result: Result<T, StoreError>) {
+    assert!(
+        matches!(result, Err(StoreError::NotFound(..))),
+        "expected not found, got {result:?}"
+    );
+}
+
+pub async fn client_registration(backend: &Backend) {
+    let id = gen_id("id");
+    let token = gen_id("token");
+    backend
+        .clients
+        .create_client(TENANT_ID, &id, client(&token, ProviderKind::Noop), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        backend.clients.get_client(TENANT_ID, &id).await.unwrap(),
+        client(&token, ProviderKind::Noop)
+    );
+    assert_not_found(backend.clients.get_client("other-tenant", &id).await);
+
+    // Registering again with a new token updates the client
+    let new_token = gen_id("token");
+    backend
+        .clients
+        .create_client(TENANT_ID, &id, client(&new_token, ProviderKind::Fcm), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        backend.clients.get_client(TENANT_ID, &id).await.unwrap(),
+        client(&new_token, ProviderKind::Fcm)
+    );
+
+    backend
+        .clients
+        .delete_client(TENANT_ID, &id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
+    assert_not_found(backend.clients.get_client(TENANT_ID, &id).await);
+}
+
+pub async fn client_token_moves_to_new_id(backend: &Backend) {
+    let id = gen_id("id");
+    let token = gen_id("token");
+    backend
+        .clients
+        .create_client(TENANT_ID, &id, client(&token, ProviderKind::Noop), None)
+        .await
+        .unwrap();
+    let notification_id = gen_id("notification");
+    backend
+        .notifications
+        .create_or_update_notification(&notification_id, TENANT_ID, &id, &PAYLOAD)
+        .await
+        .unwrap();
+
+    // The device registered under a new id, the old id and its notifications
+    // are gone
+    let new_id = gen_id("id");
+    backend
+        .clients
+        .create_client(TENANT_ID, &new_id, client(&token, ProviderKind::Noop), None)
+        .await
+        .unwrap();
+    assert_not_found(backend.clients.get_client(TENANT_ID, &id).await);
+    assert_not_found(
+        backend
+            .notifications
+            .get_notification(&notification_id, &id, TENANT_ID)
+            .await,
+    );
+    assert_eq!(
+        backend
+            .clients
+            .get_client(TENANT_ID, &new_id)
+            .await
+            .unwrap(),
+        client(&token, ProviderKind::Noop)
+    );
+
+    backend
+        .clients
+        .delete_client(TENANT_ID, &new_id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
+}
+
+pub async fn client_registration_concurrent(backend: &Backend) {
+    let token = gen_id("token");
+    let ids = (0..5).map(|_| gen_id("id")).collect::<Vec<_>>();
+    let registrations = ids.iter().map(|id| {
+        backend
+            .clients
+            .create_client(TENANT_ID, id, client(&token, ProviderKind::Noop), None)
+    });
+    for result in futures_util::future::join_all(registrations).await {
+        result.unwrap();
+    }
+
+    // The token belongs to exactly one client, whichever registered last
+    let mut registered = vec![];
+    for id in &ids {
+        if backend.clients.get_client(TENANT_ID, id).await.is_ok() {
+            registered.push(id);
+        }
+    }
+    assert_eq!(registered.len(), 1, "token registered to {registered:?}");
+
+    backend
+        .clients
+        .delete_client(TENANT_ID, registered[0], ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
+}
+
+pub async fn client_metadata(backend: &Backend) {
+    let tenant_id = gen_id("tenant");
+    let register = |id: String, platform: Option<&str>| {
+        let client = Client {
+            tenant_id: tenant_id.clone(),
+            metadata: ClientMetadata {
+                platform: platform.map(ToString::to_string),
+                sdk_version: Some("1.0.0".to_string()),
+                locale: Some("en-US".to_string()),
+                ..Default::default()
+            },
+            ..client(&gen_id("token"), ProviderKind::Noop)
+        };
+        let tenant_id = tenant_id.clone();
+        async move {
+            backend
+                .clients
+                .create_client(&tenant_id, &id, client, None)
+                .await
+                .unwrap();
+            id
+        }
+    };
+    let ids = [
+        register(gen_id("id"), Some("ios")).await,
+        register(gen_id("id"), Some("ios")).await,
+        register(gen_id("id"), Some("android")).await,
+        register(gen_id("id"), None).await,
+    ];
+
+    let client = backend
+        .clients
+        .get_client(&tenant_id, &ids[0])
+        .await
+        .unwrap();
+    assert_eq!(client.metadata.platform.as_deref(), Some("ios"));
+    assert_eq!(client.metadata.locale.as_deref(), Some("en-US"));
+    assert_eq!(client.metadata.timezone, None);
+
+    let segments = backend
+        .clients
+        .get_client_segments(&tenant_id, ClientSegment::Platform, 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        segments[0],
+        ClientSegmentCount {
+            value: Some("ios".to_string()),
+            clients: 2,
+        }
+    );
+    assert_eq!(segments.len(), 3);
+    assert!(segments.contains(&ClientSegmentCount {
+        value: None,
+        clients: 1,
+    }));
+    let segments = backend
+        .clients
+        .get_client_segments(&tenant_id, ClientSegment::SdkVersion, 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        segments,
+        [ClientSegmentCount {
+            value: Some("1.0.0".to_string()),
+            clients: 4,
+        }]
+    );
+
+    // Registering again replaces the metadata
+    let id = register(ids[0].clone(), None).await;
+    let client = backend.clients.get_client(&tenant_id, &id).await.unwrap();
+    assert_eq!(client.metadata.platform, None);
+
+    for id in &ids {
+        backend
+            .clients
+            .delete_client(&tenant_id, id, ClientEventReason::ManualDelete)
+            .await
+            .unwrap();
+    }
+}
+
+pub async fn client_events(backend: &Backend) {
+    let id = gen_id("id");
+    let new_id = gen_id("id");
+    let token = gen_id("token");
+    let new_token = gen_id("token");
+    for (id, token) in [(&id, &token), (&id, &new_token), (&new_id, &new_token)] {
+        backend
+            .clients
+            .create_client(TENANT_ID, id, client(token, ProviderKind::Noop), None)
+            .await
+            .unwrap();
+    }
+    backend
+        .clients
+        .delete_client(TENANT_ID, &new_id, ClientEventReason::BadDeviceToken)
+        .await
+        .unwrap();
+
+    let events = backend
+        .clients
+        .get_client_events(ClientEventListParams {
+            client_id: Some(id.clone()),
+            limit: 10,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let types = events
+        .iter()
+        .map(|event| event.event_type)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        [
+            ClientEventType::TokenMoved,
+            ClientEventType::Updated,
+            ClientEventType::Registered,
+        ]
+    );
+    assert_eq!(events[0].client_id, new_id);
+    assert_eq!(events[0].previous_client_id.as_deref(), Some(id.as_str()));
+    assert_eq!(
+        events[1].previous_device_token.as_deref(),
+        Some(token.as_str())
+    );
+    assert_eq!(events[1].device_token.as_deref(), Some(new_token.as_str()));
+
+    // The token's history follows it across client ids
+    let events = backend
+        .clients
+        .get_client_events(ClientEventListParams {
+            device_token: Some(new_token.clone()),
+            limit: 10,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0].event_type, ClientEventType::Deleted);
+    assert_eq!(events[0].reason, ClientEventReason::BadDeviceToken);
+    assert_eq!(events[0].client_id, new_id);
+
+    // Pages continue before the last event of the previous page
+    let page = backend
+        .clients
+        .get_client_events(ClientEventListParams {
+            device_token: Some(new_token),
+            before_id: Some(events[0].id),
+            limit: 1,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(page, events[1..2]);
+
+    // Deleting a client that doesn't exist records nothing
+    backend
+        .clients
+        .delete_client(TENANT_ID, &id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
+    let events = backend
+        .clients
+        .get_client_events(ClientEventListParams {
+            client_id: Some(id),
+            limit: 10,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(events[0].event_type, ClientEventType::TokenMoved);
+}
+
+async fn create_client(backend: &Backend) -> String {
+    let id = gen_id("id");
+    backend
+        .clients
+        .create_client(
+            TENANT_ID,
+            &id,
+            client(&gen_id("token"), ProviderKind::Noop),
+            None,
+        )
+        .await
+        .unwrap();
+
+    id
+}
+
+pub async fn client_subscriptions(backend: &Backend) {
+    let tenant_id = gen_id("tenant");
+    let topic = gen_id("topic");
+    let mut ids = vec![];
+    for _ in 0..5 {
+        let id = gen_id("id");
+        let client = Client {
+            tenant_id: tenant_id.clone(),
+            ..client(&gen_id("token"), ProviderKind::Noop)
+        };
+        backend
+            .clients
+            .create_client(&tenant_id, &id, client, None)
+            .await
+            .unwrap();
+        backend
+            .clients
+            .subscribe_client(&tenant_id, &id, &topic)
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    ids.sort();
+
+    // Subscribing twice is a no-op, unknown clients and other tenants' clients
+    // can't subscribe
+    backend
+        .clients
+        .subscribe_client(&tenant_id, &ids[0], &topic)
+        .await
+        .unwrap();
+    assert_not_found(
+        backend
+            .clients
+            .subscribe_client(&tenant_id, &gen_id("id"), &topic)
+            .await,
+    );
+    assert_not_found(
+        backend
+            .clients
+            .subscribe_client("other-tenant", &ids[0], &topic)
+            .await,
+    );
+
+    // Subscribers are paged through in id order
+    let page = backend
+        .clients
+        .get_topic_subscribers(&tenant_id, &topic, None, 3)
+        .await
+        .unwrap();
+    assert_eq!(page, ids[..3]);
+    let page = backend
+        .clients
+        .get_topic_subscribers(&tenant_id, &topic, Some(&ids[2]), 3)
+        .await
+        .unwrap();
+    assert_eq!(page, ids[3..]);
+    assert!(backend
+        .clients
+        .get_topic_subscribers("other-tenant", &topic, None, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    backend
+        .clients
+        .unsubscribe_client(&tenant_id, &ids[0], &topic)
+        .await
+        .unwrap();
+    backend
+        .clients
+        .delete_client(&tenant_id, &ids[1], ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
+
+    // A device registering under a new id starts without subscriptions
+    let token = backend
+        .clients
+        .get_client(&tenant_id, &ids[2])
+        .await
+        .unwrap()
+        .token;
+    let new_id = gen_id("id");
+    let client = Client {
+        tenant_id: tenant_id.clone(),
+        ..client(&token, ProviderKind::Noop)
+    };
+    backend
+        .clients
+        .create_client(&tenant_id, &new_id, client, None)
+        .await
+        .unwrap();
+
+    let subscribers = backend
+        .clients
+        .get_topic_subscribers(&tenant_id, &topic, None, 10)
+        .await
+        .unwrap();
+    assert_eq!(subscribers, ids[3..]);
+
+    for id in ids[3..].iter().chain([&new_id]) {
+        backend
+            .clients
+            .delete_client(&tenant_id, id, ClientEventReason::ManualDelete)
+            .await
+            .unwrap();
+    }
+}
+
+pub async fn notification_dedupe(backend: &Backend) {
+    let client_id = create_client(backend).await;
+    let id = gen_id("notification");
+
+    let received = (0..5).map(|_| {
+        backend
+            .notifications
+            .create_or_update_notification(&id, TENANT_ID, &client_id, &PAYLOAD)
+    });
+    let notifications = futures_util::future::join_all(received)
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(notifications
+        .iter()
+        .all(|notification| notification.created_at == notifications[0].created_at));
+    assert_eq!(
+        backend
+            .notifications
+            .get_client_notifications(TENANT_ID, &client_id, 10)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    backend
+        .clients
+        .delete_client(TENANT_ID, &client_id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
+}
+
+pub async fn notification_claim(backend: &Backend) {
+    let client_id = create_client(backend).await;
+    let id = gen_id("notification");
+
+    assert!(backend
+        .notifications
+        .claim_notification(&id, TENANT_ID, &client_id)
+        .await
+        .unwrap());
+    backend
+        .notifications
+        .create_or_update_notification(&id, TENANT_ID, &client_id, &PAYLOAD)
+        .await
+        .unwrap();
+    assert!(!backend
+        .notifications
+        .claim_notification(&id, TENANT_ID, &client_id)
+        .await
+        .unwrap());
+
+    backend
+        .clients
+        .delete_client(TENANT_ID, &client_id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
+}
+
+pub async fn notification_status(backend: &Backend) {
+    let client_id = create_client(backend).await;
+    let id = gen_id("notification");
+
+    let notification = backend
+        .notifications
+        .create_or_update_notification(&id, TENANT_ID, &client_id, &PAYLOAD)
+        .await
+        .unwrap();
+    assert_eq!(notification.status, DeliveryStatus::Queued);
+    assert_eq!(notification.attempts, 0);
+    assert_not_found(
+        backend
+            .notifications
+            .get_notification(&id, &client_id, "other-tenant")
+            .await,
+    );
+
+    backend
+        .notifications
+        .update_notification_status(
+            &id,
+            &client_id,
+            TENANT_ID,
+            DeliveryStatus::Delivered,
+            2,
+            None,
+        )
+        .await
+        .unwrap();
+    backend
+        .notifications
+        .update_notification_provider(&id, &client_id, TENANT_ID, "noop", Some("message-id"))
+        .await
+        .unwrap();
+    let notification = backend
+        .notifications
+        .get_notification(&id, &client_id, TENANT_ID)
+        .await
+        .unwrap();
+    assert_eq!(notification.status, DeliveryStatus::Delivered);
+    assert_eq!(notification.attempts, 2);
+    assert!(notification.delivered_at.is_some());
+    assert_eq!(notification.provider.as_deref(), Some("noop"));
+    assert_eq!(
+        notification.provider_message_id.as_deref(),
+        Some("message-id")
+    );
+
+    backend
+        .notifications
+        .delete_notification(&id, TENANT_ID)
+        .await
+        .unwrap();
+    assert_not_found(
+        backend
+            .notifications
+            .get_notification(&id, &client_id, TENANT_ID)
+            .await,
+    );
+
+    backend
+        .clients
+        .delete_client(TENANT_ID, &client_id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
+}
+
+pub async fn delivery_queue(backend: &Backend) {
+    let client_id = create_client(backend).await;
+    let id = gen_id("notification");
+
+    let delivery = backend
+        .deliveries
+        .enqueue_delivery(&id, TENANT_ID, &client_id, &PAYLOAD, LEASE)
+        .await
+        .unwrap()
+        .expect("delivery was not queued");
+    assert_eq!(delivery.attempts, 1);
+    assert!(backend
+        .deliveries
+        .enqueue_delivery(&id, TENANT_ID, &client_id, &PAYLOAD, LEASE)
+        .await
+        .unwrap()
+        .is_none());
+
+    backend
+        .deliveries
+        .reschedule_delivery(&id, &client_id, Utc::now())
+        .await
+        .unwrap();
+    let claimed = backend
+        .deliveries
+        .claim_due_deliveries(1000, LEASE)
+        .await
+        .unwrap();
+    let delivery = claimed
+        .iter()
+        .find(|delivery| delivery.notification_id == id)
+        .expect("due delivery was not claimed");
+    assert_eq!(delivery.attempts, 2);
+
+    // Leased deliveries are not claimed twice
+    let claimed = backend
+        .deliveries
+        .claim_due_deliveries(1000, LEASE)
+        .await
+        .unwrap();
+    assert!(!claimed
+        .iter()
+        .any(|delivery| delivery.notification_id == id));
+
+    backend
+        .deliveries
+        .remove_delivery(&id, &client_id)
+        .await
+        .unwrap();
+    backend
+        .clients
+        .delete_client(TENANT_ID, &client_id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
+}
+
+pub async fn scheduled_delivery(backend: &Backend) {
+    let client_id = create_client(backend).await;
+    let id = gen_id("notification");
+    let later_id = gen_id("notification");
+    let queued_id = gen_id("notification");
+
+    assert!(backend
+        .deliveries
+        .schedule_delivery(&id, TENANT_ID, &client_id, &PAYLOAD, Utc::now())
+        .await
+        .unwrap());
+    assert!(!backend
+        .deliveries
+        .schedule_delivery(&id, TENANT_ID, &client_id, &PAYLOAD, Utc::now())
+        .await
+        .unwrap());
+    let send_at = Utc::now() + chrono::Duration::hours(1);
+    assert!(backend
+        .deliveries
+        .schedule_delivery(&later_id, TENANT_ID, &client_id, &PAYLOAD, send_at)
+        .await
+        .unwrap());
+    // Already queued for the client, so it can't be moved yet
+    assert!(backend
+        .deliveries
+        .schedule_delivery(&queued_id, TENANT_ID, &client_id, &PAYLOAD, Utc::now())
+        .await
+        .unwrap());
+    backend
+        .deliveries
+        .enqueue_delivery(&queued_id, TENANT_ID, &client_id, &PAYLOAD, LEASE)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Due notifications move to the queue, leased for their first attempt
+    let claimed = backend
+        .deliveries
+        .claim_due_scheduled_deliveries(1000, LEASE)
+        .await
+        .unwrap();
+    let delivery = claimed
+        .iter()
+        .find(|delivery| delivery.notification_id == id)
+        .expect("due notification was not claimed");
+    assert_eq!(delivery.attempts, 1);
+    assert!(!claimed
+        .iter()
+        .any(|delivery| delivery.notification_id == later_id
+            || delivery.notification_id == queued_id));
+    assert!(!backend
+        .deliveries
+        .claim_due_scheduled_deliveries(1000, LEASE)
+        .await
+        .unwrap()
+        .iter()
+        .any(|delivery| delivery.notification_id == id));
+    assert!(backend
+        .deliveries
+        .enqueue_delivery(&id, TENANT_ID, &client_id, &PAYLOAD, LEASE)
+        .await
+        .unwrap()
+        .is_none());
+
+    // The conflicting notification is kept rather than dropped
+    assert_eq!(
+        backend
+            .deliveries
+            .cancel_scheduled_delivery(TENANT_ID, &queued_id)
+            .await
+            .unwrap(),
+        [client_id.clone()]
+    );
+    backend
+        .deliveries
+        .remove_delivery(&queued_id, &client_id)
+        .await
+        .unwrap();
+
+    // Only the tenant of the notification can cancel it
+    assert!(backend
+        .deliveries
+        .cancel_scheduled_delivery("other-tenant", &later_id)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        backend
+            .deliveries
+            .cancel_scheduled_delivery(TENANT_ID, &later_id)
+            .await
+            .unwrap(),
+        [client_id.clone()]
+    );
+    assert!(backend
+        .deliveries
+        .cancel_scheduled_delivery(TENANT_ID, &later_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    backend
+        .deliveries
+        .remove_delivery(&id, &client_id)
+        .await
+        .unwrap();
+    backend
+        .clients
+        .delete_client(TENANT_ID, &client_id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
+}
+
+pub async fn webhook_queue(backend: &Backend) {
+    let tenant_id = gen_id("tenant");
+    let event = WebhookEvent::ClientDeleted {
+        client_id: gen_id("id"),
+        reason: "test".to_string(),
+    };
+
+    let queued = backend
+        .webhooks
+        .enqueue_webhook_event(&tenant_id, &event)
+        .await
+        .unwrap();
+    assert_eq!(queued.status, DeliveryStatus::Queued);
+    assert_eq!(queued.event_type, event.event_type());
+
+    let claimed = backend
+        .webhooks
+        .claim_due_webhook_events(1000, LEASE)
+        .await
+        .unwrap();
+    let claimed = claimed
+        .iter()
+        .find(|claimed| claimed.id == queued.id)
+        .expect("due event was not claimed");
+    assert_eq!(claimed.attempts, 1);
+
+    backend
+        .webhooks
+        .update_webhook_event(
+            &queued.id,
+            DeliveryStatus::Delivered,
+            Some(200),
+            None,
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+    let events = backend
+        .webhooks
+        .get_webhook_events(&tenant_id, 10)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].status, DeliveryStatus::Delivered);
+    assert_eq!(events[0].response_status, Some(200));
+    assert!(events[0].delivered_at.is_some());
+
+    // Delivered events are not claimed again
+    let claimed = backend
+        .webhooks
+        .claim_due_webhook_events(1000, LEASE)
+        .await
+        .unwrap();
+    assert!(!claimed.iter().any(|claimed| claimed.id == queued.id));
+}
+
+pub async fn tenant_suspension(backend: &Backend) {
+    let id = gen_id("tenant");
+    let tenant = backend
+        .tenants
+        .create_tenant(TenantUpdateParams { id: id.clone() })
+        .await
+        .unwrap();
+    assert!(!tenant.suspended);
+
+    let suspend = TenantSuspendParams {
+        reason: "Invalid FCM Credentials".to_string(),
+        ..Default::default()
+    };
+    assert!(backend
+        .tenants
+        .suspend_tenant(&id, suspend.clone())
+        .await
+        .unwrap());
+    assert!(!backend.tenants.suspend_tenant(&id, suspend).await.unwrap());
+    assert!(backend.tenants.get_tenant(&id).await.unwrap().suspended);
+
+    // Deleting credentials doesn't resolve anything
+    let tenant = backend.tenants.update_tenant_delete_fcm(&id).await.unwrap();
+    assert!(tenant.suspended);
+
+    // Neither do credentials of another provider
+    let tenant = backend
+        .tenants
+        .update_tenant_web_push(
+            &id,
+            TenantWebPushUpdateParams {
+                web_push_vapid_key: "key".to_string(),
+                web_push_vapid_subject: "mailto:test@example.com".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    assert!(tenant.suspended);
+    assert_eq!(
+        tenant.suspended_reason.as_deref(),
+        Some("Invalid FCM Credentials")
+    );
+
+    // New credentials of the rejected provider lift the suspension
+    let tenant = backend
+        .tenants
+        .update_tenant_fcm(
+            &id,
+            TenantFcmUpdateParams {
+                fcm_api_key: "key".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!tenant.suspended);
+    assert_eq!(tenant.fcm_api_key.as_deref(), Some("key"));
+
+    let history = backend
+        .tenants
+        .get_tenant_suspension_history(&id)
+        .await
+        .unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|suspension| suspension.action)
+            .collect::<Vec<_>>(),
+        vec![SuspensionAction::Unsuspended, SuspensionAction::Suspended]
+    );
+    assert_eq!(history[0].reason.as_deref(), Some("Credentials updated"));
+
+    backend.tenants.delete_tenant(&id).await.unwrap();
+}
+
+pub async fn tenant_templates(backend: &Backend) {
+    let id = gen_id("tenant");
+    backend
+        .tenants
+        .create_tenant(TenantUpdateParams { id: id.clone() })
+        .await
+        .unwrap();
+
+    let localized = |title: &str| LocalizedTemplate {
+        title: title.to_string(),
+        body: "From {dapp}".to_string(),
+    };
+    let params = TenantTemplateUpdateParams {
+        mode: TemplateMode::Render,
+        default_locale: "en".to_string(),
+        localizations: BTreeMap::from([("en".to_string(), localized("Sign request"))]),
+    };
+    let created = backend
+        .tenants
+        .update_tenant_template(&id, "sign", params.clone())
+        .await
+        .unwrap();
+    assert_eq!(created.name, "sign");
+    assert_eq!(created.localizations.0, params.localizations);
+
+    // Updates replace the template
+    let params = TenantTemplateUpdateParams {
+        mode: TemplateMode::Passthrough,
+        localizations: BTreeMap::from([
+            ("en".to_string(), localized("Sign request")),
+            ("de".to_string(), localized("Signaturanfrage")),
+        ]),
+        ..params
+    };
+    backend
+        .tenants
+        .update_tenant_template(&id, "sign", params.clone())
+        .await
+        .unwrap();
+    let template = backend
+        .tenants
+        .get_tenant_template(&id, "sign")
+        .await
+        .unwrap();
+    assert_eq!(template.mode, TemplateMode::Passthrough);
+    assert_eq!(template.localizations.0, params.localizations);
+    assert_eq!(template.created_at, created.created_at);
+
+    backend
+        .tenants
+        .update_tenant_template(&id, "approve", params)
+        .await
+        .unwrap();
+    let names = backend
+        .tenants
+        .get_tenant_templates(&id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|template| template.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["approve", "sign"]);
+
+    backend
+        .tenants
+        .delete_tenant_template(&id, "sign")
+        .await
+        .unwrap();
+    for result in [
+        backend
+            .tenants
+            .get_tenant_template(&id, "sign")
+            .await
+            .map(|_| ()),
+        backend.tenants.delete_tenant_template(&id, "sign").await,
+    ] {
+        assert!(matches!(result, Err(Error::TemplateNotFound(_))));
+    }
+
+    backend.tenants.delete_tenant(&id).await.unwrap();
+    assert!(backend
+        .tenants
+        .get_tenant_templates(&id)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+pub async fn tenant_listing(backend: &Backend) {
+    let prefix = gen_id("tenant");
+    for n in 0..3 {
+        backend
+            .tenants
+            .create_tenant(TenantUpdateParams {
+                id: format!("{prefix}-{n}"),
+            })
+            .await
+            .unwrap();
+    }
+    backend
+        .tenants
+        .update_tenant_fcm(
+            &format!("{prefix}-1"),
+            TenantFcmUpdateParams {
+                fcm_api_key: "key".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+    let list = |provider, after_id| TenantListParams {
+        provider,
+        id_prefix: Some(prefix.clone()),
+        after_id,
+        limit: 2,
+        ..Default::default()
+    };
+    let ids = |tenants: Vec<echo_server::stores::tenant::Tenant>| {
+        tenants
+            .into_iter()
+            .map(|tenant| tenant.id)
+            .collect::<Vec<_>>()
+    };
+
+    let page = backend
+        .tenants
+        .list_tenants(list(None, None))
+        .await
+        .unwrap();
+    assert_eq!(
+        ids(page),
+        vec![format!("{prefix}-0"), format!("{prefix}-1")]
+    );
+    let page = backend
+        .tenants
+        .list_tenants(list(None, Some(format!("{prefix}-1"))))
+        .await
+        .unwrap();
+    assert_eq!(ids(page), vec![format!("{prefix}-2")]);
+    let page = backend
+        .tenants
+        .list_tenants(list(Some(ProviderKind::Fcm), None))
+        .await
+        .unwrap();
+    assert_eq!(ids(page), vec![format!("{prefix}-1")]);
+
+    for n in 0..3 {
+        let id = format!("{prefix}-{n}");
+        backend.tenants.delete_tenant(&id).await.unwrap();
+        assert!(matches!(
+            backend.tenants.get_tenant(&id).await,
+            Err(Error::InvalidTenantId(_))
+        ));
+    }
+}
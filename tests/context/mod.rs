@@ -1,9 +1,15 @@
 #[cfg(feature = "functional_tests")]
-use echo_server::state::{ClientStoreArc, NotificationStoreArc, TenantStoreArc};
+use echo_server::state::{
+    ClientStoreArc, DeliveryStoreArc, NotificationStoreArc, TenantStoreArc, WebhookStoreArc,
+};
 use {
     self::server::EchoServer,
     async_trait::async_trait,
-    echo_server::config::Config,
+    echo_server::{
+        config::Config,
+        middleware::rate_limit::RateLimitBackend,
+        stores::{redis_notification::NotificationDedupeBackend, StoreBackend},
+    },
     sqlx::{Pool, Postgres},
     std::{env, sync::Arc},
     test_context::{AsyncTestContext, TestContext},
@@ -37,6 +43,10 @@ pub struct StoreContext {
     pub notifications: NotificationStoreArc,
     #[cfg(feature = "functional_tests")]
     pub tenants: TenantStoreArc,
+    #[cfg(feature = "functional_tests")]
+    pub deliveries: DeliveryStoreArc,
+    #[cfg(feature = "functional_tests")]
+    pub webhooks: WebhookStoreArc,
 }
 
 impl TestContext for ConfigContext {
@@ -54,14 +64,66 @@ impl TestContext for ConfigContext {
                 // TODO I don't think this is used in the tests, so this should be refactored/removed
                 "ff469faa970df23c23a6542765ce8dba2a907538522833b2327a153e365d138e".to_string(),
             ),
-            database_url: env::var("DATABASE_URL")
-                .expect("DATABASE_URL environment variable is not set"),
-            tenant_database_url: env::var("TENANT_DATABASE_URL")
-                .expect("TENANT_DATABASE_URL environment variable is not set"),
+            store_backend: StoreBackend::Postgres,
+            database_url: Some(
+                env::var("DATABASE_URL").expect("DATABASE_URL environment variable is not set"),
+            ),
+            tenant_database_url: Some(
+                env::var("TENANT_DATABASE_URL")
+                    .expect("TENANT_DATABASE_URL environment variable is not set"),
+            ),
             #[cfg(feature = "multitenant")]
             jwt_secret: "n/a".to_string(),
+            #[cfg(feature = "multitenant")]
+            credentials_master_keys: None,
+            #[cfg(feature = "multitenant")]
+            credentials_master_keys_file: None,
+            #[cfg(feature = "multitenant")]
+            admin_api_token: None,
+            #[cfg(feature = "multitenant")]
+            validate_credentials: false,
+            #[cfg(feature = "multitenant")]
+            validation_apns_production_url: "https://api.push.apple.com".to_string(),
+            #[cfg(feature = "multitenant")]
+            validation_apns_sandbox_url: "https://api.sandbox.push.apple.com".to_string(),
+            #[cfg(feature = "multitenant")]
+            validation_fcm_api_url: "https://fcm.googleapis.com".to_string(),
+            #[cfg(feature = "multitenant")]
+            validation_fcm_token_url: None,
+            #[cfg(feature = "multitenant")]
+            apns_expiry_webhook_url: None,
+            #[cfg(feature = "multitenant")]
+            apns_expiry_warning_days: 30,
+            #[cfg(feature = "multitenant")]
+            apns_expiry_check_interval_secs: 3600,
             otel_exporter_otlp_endpoint: None,
             telemetry_prometheus_port: Some(self::server::get_random_port()),
+            delivery_max_attempts: 3,
+            delivery_backoff_base_ms: 100,
+            delivery_backoff_max_ms: 1000,
+            delivery_poll_interval_ms: 100,
+            delivery_batch_size: 10,
+            rate_limit_backend: RateLimitBackend::Memory,
+            redis_url: None,
+            rate_limit_default_max_requests: 100,
+            rate_limit_default_window_secs: 60,
+            rate_limit_tenants_max_requests: 100,
+            rate_limit_tenants_window_secs: 60,
+            rate_limit_clients_max_requests: 100,
+            rate_limit_clients_window_secs: 60,
+            notification_retention_days: 30,
+            notification_prune_interval_secs: 3600,
+            notification_prune_batch_size: 1000,
+            notification_dedupe_backend: NotificationDedupeBackend::Database,
+            notification_dedupe_ttl_secs: 86_400,
+            webhook_max_attempts: 3,
+            webhook_backoff_base_ms: 100,
+            webhook_backoff_max_ms: 1000,
+            webhook_poll_interval_ms: 100,
+            push_rate_limit_per_minute: None,
+            push_rate_limit_burst: None,
+            client_push_rate_limit_per_minute: None,
+            client_push_rate_limit_burst: None,
             #[cfg(not(feature = "multitenant"))]
             apns_type: None,
             #[cfg(not(feature = "multitenant"))]
@@ -80,6 +142,14 @@ impl TestContext for ConfigContext {
             fcm_api_key: None,
             #[cfg(not(feature = "multitenant"))]
             fcm_v1_credentials: None,
+            #[cfg(not(feature = "multitenant"))]
+            hms_app_id: None,
+            #[cfg(not(feature = "multitenant"))]
+            hms_app_secret: None,
+            #[cfg(not(feature = "multitenant"))]
+            web_push_vapid_key: None,
+            #[cfg(not(feature = "multitenant"))]
+            web_push_vapid_subject: None,
             #[cfg(any(feature = "analytics", feature = "geoblock"))]
             s3_endpoint: None,
             #[cfg(any(feature = "analytics", feature = "geoblock"))]
@@ -97,6 +167,22 @@ impl TestContext for ConfigContext {
     }
 }
 
+impl EchoServerContext {
+    /// Starts a server with a config other than the default test config
+    pub async fn with_config(config: Config) -> Self {
+        Self {
+            server: EchoServer::start(config.clone()).await,
+            #[cfg(all(
+                feature = "multitenant",
+                feature = "apns_tests",
+                feature = "fcm_tests",
+                feature = "fcmv1_tests"
+            ))]
+            config,
+        }
+    }
+}
+
 #[async_trait]
 impl AsyncTestContext for EchoServerContext {
     async fn setup() -> Self {
@@ -121,8 +207,11 @@ impl AsyncTestContext for EchoServerContext {
 impl AsyncTestContext for StoreContext {
     async fn setup() -> Self {
         let config = ConfigContext::setup().config;
-        let (db, tenant_db) =
-            stores::open_pg_connections(&config.database_url, &config.tenant_database_url).await;
+        let (db, tenant_db) = stores::open_pg_connections(
+            config.database_url.as_deref().unwrap_or_default(),
+            config.tenant_database_url.as_deref().unwrap_or_default(),
+        )
+        .await;
 
         let db_arc = Arc::new(db);
         let tenant_db_arc = Arc::new(tenant_db);
@@ -136,6 +225,10 @@ impl AsyncTestContext for StoreContext {
             notifications: db_arc.clone(),
             #[cfg(feature = "functional_tests")]
             tenants: tenant_db_arc.clone(),
+            #[cfg(feature = "functional_tests")]
+            deliveries: db_arc.clone(),
+            #[cfg(feature = "functional_tests")]
+            webhooks: db_arc.clone(),
         }
     }
 
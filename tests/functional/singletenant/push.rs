@@ -1,14 +1,23 @@
 use {
-    crate::context::EchoServerContext,
+    crate::context::{ConfigContext, EchoServerContext},
+    base64::Engine as _,
     echo_server::{
-        handlers::{push_message::PushMessageBody, register_client::RegisterBody},
+        handlers::{
+            push_batch::{BatchPushBody, BatchPushOutcome, BatchPushResponse},
+            push_message::{PushMessageBody, PushOutcome, PushReceipt},
+            register_client::RegisterBody,
+        },
         providers::{LegacyPushMessage, MessagePayload, RawPushMessage},
     },
     ed25519_dalek::SigningKey,
     hyper::StatusCode,
+    openssl::{
+        ec::{EcGroup, EcKey},
+        nid::Nid,
+    },
     relay_rpc::domain::{ClientId, DecodedClientId},
     std::sync::Arc,
-    test_context::test_context,
+    test_context::{test_context, TestContext},
     uuid::Uuid,
     wiremock::{http::Method, matchers::method, Mock, MockServer, ResponseTemplate},
 };
@@ -44,6 +53,7 @@ async fn create_client(ctx: &mut EchoServerContext, always_raw: bool) -> (Client
         push_type: "noop".to_string(),
         token: token.clone(),
         always_raw: Some(always_raw),
+        metadata: Default::default(),
     };
 
     // Register client
@@ -84,6 +94,10 @@ async fn test_push(ctx: &mut EchoServerContext) {
             id: push_message_id,
             payload: push_message_payload,
         }),
+        options: None,
+        template: None,
+        send_at: None,
+        delay: None,
     };
 
     // Push
@@ -103,6 +117,10 @@ async fn test_push(ctx: &mut EchoServerContext) {
         response.status().is_success(),
         "Response was not successful"
     );
+    let receipt = response.json::<PushReceipt>().await.unwrap();
+    assert_eq!(receipt.outcome, PushOutcome::Delivered);
+    assert_eq!(receipt.provider, "noop");
+    assert!(!receipt.client_deleted);
 
     // Push the same payload again and ensure it's deduped
     let client = reqwest::Client::new();
@@ -123,6 +141,9 @@ async fn test_push(ctx: &mut EchoServerContext) {
         already_pushed_status_code,
         "Response was not successful"
     );
+    let receipt = response.json::<PushReceipt>().await.unwrap();
+    assert_eq!(receipt.outcome, PushOutcome::AlreadyReceived);
+    assert!(receipt.provider_message_id.is_none());
 }
 
 #[test_context(EchoServerContext)]
@@ -146,6 +167,10 @@ async fn test_push_multiple_clients(ctx: &mut EchoServerContext) {
             id: push_message_id.clone(),
             payload: push_message_payload,
         }),
+        options: None,
+        template: None,
+        send_at: None,
+        delay: None,
     };
 
     // Push client 1
@@ -206,6 +231,10 @@ async fn test_push_always_raw(ctx: &mut EchoServerContext) {
             id: push_message_id,
             payload: push_message_payload,
         }),
+        options: None,
+        template: None,
+        send_at: None,
+        delay: None,
     };
     let response = client
         .post(format!(
@@ -227,6 +256,10 @@ async fn test_push_always_raw(ctx: &mut EchoServerContext) {
             message: blob,
         }),
         legacy: None,
+        options: None,
+        template: None,
+        send_at: None,
+        delay: None,
     };
     let response = client
         .post(format!(
@@ -240,3 +273,142 @@ async fn test_push_always_raw(ctx: &mut EchoServerContext) {
         .expect("Call failed");
     assert_eq!(response.status(), StatusCode::ACCEPTED);
 }
+
+#[test_context(EchoServerContext)]
+#[tokio::test]
+async fn test_push_batch(ctx: &mut EchoServerContext) {
+    let (client_id1, _mock_server1) = create_client(ctx, false).await;
+    let (client_id2, _mock_server2) = create_client(ctx, false).await;
+    let unknown_client_id = Uuid::new_v4().to_string();
+
+    let payload = BatchPushBody::Broadcast {
+        client_ids: vec![
+            client_id1.value().to_string(),
+            client_id2.value().to_string(),
+            unknown_client_id.clone(),
+        ],
+        body: PushMessageBody {
+            raw: None,
+            legacy: Some(LegacyPushMessage {
+                id: Uuid::new_v4().to_string().into(),
+                payload: MessagePayload {
+                    topic: Uuid::new_v4().to_string().into(),
+                    blob: Uuid::new_v4().to_string().into(),
+                    flags: 0,
+                },
+            }),
+            options: None,
+            template: None,
+            send_at: None,
+            delay: None,
+        },
+    };
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("http://{}/batch", ctx.server.public_addr))
+        .json(&payload)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(
+        response.status().is_success(),
+        "Response was not successful"
+    );
+
+    let response = response
+        .json::<BatchPushResponse>()
+        .await
+        .expect("Failed to parse response");
+    let outcomes = response
+        .results
+        .iter()
+        .map(|result| (result.client_id.clone(), result.outcome))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        outcomes,
+        vec![
+            (client_id1.value().to_string(), BatchPushOutcome::Delivered),
+            (client_id2.value().to_string(), BatchPushOutcome::Delivered),
+            (unknown_client_id, BatchPushOutcome::NotFound),
+        ]
+    );
+}
+
+#[test_context(EchoServerContext)]
+#[tokio::test]
+async fn test_notification_status_requires_client_jwt(ctx: &mut EchoServerContext) {
+    let (client_id, _mock_server) = create_client(ctx, false).await;
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!(
+            "http://{}/clients/{}/notifications",
+            ctx.server.public_addr, client_id
+        ))
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+/// Web Push subscriptions must point to a public push service, a local one is
+/// refused when the client registers
+#[tokio::test]
+async fn test_register_internal_web_push_endpoint() {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let vapid_key = EcKey::generate(&group)
+        .unwrap()
+        .private_key_to_pem()
+        .unwrap();
+    let mut config = ConfigContext::setup().config;
+    config.web_push_vapid_key = Some(base64::engine::general_purpose::STANDARD.encode(vapid_key));
+    config.web_push_vapid_subject = Some("mailto:test@example.com".to_string());
+    let mut ctx = EchoServerContext::with_config(config).await;
+
+    let push_service = MockServer::start().await;
+    Mock::given(method(Method::POST))
+        .respond_with(ResponseTemplate::new(StatusCode::CREATED))
+        .expect(0)
+        .mount(&push_service)
+        .await;
+    let token = serde_json::json!({
+        "endpoint": format!("{}/push", push_service.uri()),
+        "keys": {
+            "p256dh": concat!(
+                "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-",
+                "AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4"
+            ),
+            "auth": "BTBZMqHH6r4Tts7J_aSIgg",
+        },
+    })
+    .to_string();
+
+    let keypair = SigningKey::generate(&mut rand::thread_rng());
+    let client_id = ClientId::from(DecodedClientId::from_key(&keypair.verifying_key()));
+    let jwt = relay_rpc::auth::AuthToken::new(client_id.value().to_string())
+        .aud(format!(
+            "http://127.0.0.1:{}",
+            ctx.server.public_addr.port()
+        ))
+        .as_jwt(&keypair)
+        .unwrap()
+        .to_string();
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/clients", ctx.server.public_addr))
+        .json(&RegisterBody {
+            client_id,
+            push_type: "webpush".to_string(),
+            token,
+            always_raw: Some(false),
+            metadata: Default::default(),
+        })
+        .header("Authorization", jwt)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    ctx.server.shutdown().await;
+}
@@ -18,6 +18,7 @@ async fn test_registration(ctx: &mut EchoServerContext) {
         push_type: "noop".to_string(),
         token: "test".to_string(),
         always_raw: Some(false),
+        metadata: Default::default(),
     };
 
     let jwt = relay_rpc::auth::AuthToken::new(client_id.value().to_string())
@@ -50,6 +51,7 @@ async fn test_registration(ctx: &mut EchoServerContext) {
         push_type: "noop".to_string(),
         token: "new_token".to_string(),
         always_raw: Some(false),
+        metadata: Default::default(),
     };
     let response = client
         .post(format!("http://{}/clients", ctx.server.public_addr))
@@ -87,6 +89,7 @@ async fn test_deregistration(ctx: &mut EchoServerContext) {
         push_type: "noop".to_string(),
         token: "test".to_string(),
         always_raw: Some(false),
+        metadata: Default::default(),
     };
 
     let client = reqwest::Client::new();
@@ -4,7 +4,9 @@ use {
         functional::stores::{gen_id, TENANT_ID},
     },
     echo_server::{
-        handlers::push_message::PushMessageBody, providers::ProviderKind, stores::client::Client,
+        handlers::push_message::PushMessageBody,
+        providers::ProviderKind,
+        stores::client::{Client, ClientEventReason},
     },
     test_context::test_context,
 };
@@ -23,13 +25,17 @@ async fn client_creation(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Noop,
                 token,
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
         .await
         .unwrap();
     // Cleaning up records
-    ctx.clients.delete_client(TENANT_ID, &id).await.unwrap();
+    ctx.clients
+        .delete_client(TENANT_ID, &id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
 }
 
 #[test_context(StoreContext)]
@@ -52,6 +58,7 @@ async fn client_creation_concurrent(ctx: &mut StoreContext) {
                         push_type: ProviderKind::Noop,
                         token,
                         always_raw: false,
+                        metadata: Default::default(),
                     },
                     None,
                 )
@@ -64,7 +71,10 @@ async fn client_creation_concurrent(ctx: &mut StoreContext) {
         result.unwrap(); //.unwrap();
     }
     // Cleaning up records
-    ctx.clients.delete_client(TENANT_ID, &id).await.unwrap();
+    ctx.clients
+        .delete_client(TENANT_ID, &id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
 }
 
 #[test_context(StoreContext)]
@@ -81,13 +91,17 @@ async fn client_creation_fcm(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Fcm,
                 token,
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
         .await
         .unwrap();
     // Cleaning up records
-    ctx.clients.delete_client(TENANT_ID, &id).await.unwrap();
+    ctx.clients
+        .delete_client(TENANT_ID, &id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
 }
 
 #[test_context(StoreContext)]
@@ -104,13 +118,17 @@ async fn client_creation_apns(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Apns,
                 token,
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
         .await
         .unwrap();
     // Cleaning up records
-    ctx.clients.delete_client(TENANT_ID, &id).await.unwrap();
+    ctx.clients
+        .delete_client(TENANT_ID, &id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
 }
 
 #[test_context(StoreContext)]
@@ -129,6 +147,7 @@ async fn client_upsert_token(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Fcm,
                 token: token.clone(),
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
@@ -149,6 +168,10 @@ async fn client_upsert_token(ctx: &mut StoreContext) {
             &PushMessageBody {
                 raw: None,
                 legacy: None,
+                options: None,
+                template: None,
+                send_at: None,
+                delay: None,
             },
         )
         .await
@@ -171,6 +194,7 @@ async fn client_upsert_token(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Apns,
                 token: updated_token.clone(),
                 always_raw: true,
+                metadata: Default::default(),
             },
             None,
         )
@@ -182,7 +206,7 @@ async fn client_upsert_token(ctx: &mut StoreContext) {
 
     // Cleaning up records
     ctx.clients
-        .delete_client(TENANT_ID, &client_id)
+        .delete_client(TENANT_ID, &client_id, ClientEventReason::ManualDelete)
         .await
         .unwrap();
 }
@@ -203,6 +227,7 @@ async fn client_upsert_id(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Fcm,
                 token: token.clone(),
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
@@ -223,6 +248,10 @@ async fn client_upsert_id(ctx: &mut StoreContext) {
             &PushMessageBody {
                 raw: None,
                 legacy: None,
+                options: None,
+                template: None,
+                send_at: None,
+                delay: None,
             },
         )
         .await
@@ -245,6 +274,7 @@ async fn client_upsert_id(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Fcm,
                 token: token.clone(),
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
@@ -259,7 +289,7 @@ async fn client_upsert_id(ctx: &mut StoreContext) {
 
     // Cleaning up records
     ctx.clients
-        .delete_client(TENANT_ID, &updated_id)
+        .delete_client(TENANT_ID, &updated_id, ClientEventReason::ManualDelete)
         .await
         .unwrap();
 }
@@ -280,6 +310,7 @@ async fn client_create_same_id_and_token(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Fcm,
                 token: token.clone(),
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
@@ -300,6 +331,10 @@ async fn client_create_same_id_and_token(ctx: &mut StoreContext) {
             &PushMessageBody {
                 raw: None,
                 legacy: None,
+                options: None,
+                template: None,
+                send_at: None,
+                delay: None,
             },
         )
         .await
@@ -322,6 +357,7 @@ async fn client_create_same_id_and_token(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Noop,
                 token: token.clone(),
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
@@ -333,7 +369,7 @@ async fn client_create_same_id_and_token(ctx: &mut StoreContext) {
 
     // Cleaning up records
     ctx.clients
-        .delete_client(TENANT_ID, &client_id)
+        .delete_client(TENANT_ID, &client_id, ClientEventReason::ManualDelete)
         .await
         .unwrap();
 }
@@ -353,12 +389,16 @@ async fn client_deletion(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Noop,
                 token,
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
         .await
         .unwrap();
-    ctx.clients.delete_client(TENANT_ID, &id).await.unwrap();
+    ctx.clients
+        .delete_client(TENANT_ID, &id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
 }
 
 #[test_context(StoreContext)]
@@ -376,6 +416,7 @@ async fn client_fetch(ctx: &mut StoreContext) {
                 push_type: ProviderKind::Noop,
                 token: token.clone(),
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
@@ -388,5 +429,8 @@ async fn client_fetch(ctx: &mut StoreContext) {
     assert_eq!(client.push_type, ProviderKind::Noop);
 
     // Cleaning up records
-    ctx.clients.delete_client(TENANT_ID, &id).await.unwrap();
+    ctx.clients
+        .delete_client(TENANT_ID, &id, ClientEventReason::ManualDelete)
+        .await
+        .unwrap();
 }
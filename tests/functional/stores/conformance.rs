@@ -0,0 +1,107 @@
+use {
+    crate::{
+        conformance::{self, Backend},
+        context::StoreContext,
+    },
+    test_context::test_context,
+};
+
+fn postgres(ctx: &StoreContext) -> Backend {
+    Backend {
+        clients: ctx.clients.clone(),
+        notifications: ctx.notifications.clone(),
+        deliveries: ctx.deliveries.clone(),
+        webhooks: ctx.webhooks.clone(),
+        tenants: ctx.tenants.clone(),
+    }
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn client_registration(ctx: &mut StoreContext) {
+    conformance::client_registration(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn client_token_moves_to_new_id(ctx: &mut StoreContext) {
+    conformance::client_token_moves_to_new_id(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn client_registration_concurrent(ctx: &mut StoreContext) {
+    conformance::client_registration_concurrent(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn client_metadata(ctx: &mut StoreContext) {
+    conformance::client_metadata(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn client_events(ctx: &mut StoreContext) {
+    conformance::client_events(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn client_subscriptions(ctx: &mut StoreContext) {
+    conformance::client_subscriptions(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn notification_dedupe(ctx: &mut StoreContext) {
+    conformance::notification_dedupe(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn notification_claim(ctx: &mut StoreContext) {
+    conformance::notification_claim(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn notification_status(ctx: &mut StoreContext) {
+    conformance::notification_status(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn delivery_queue(ctx: &mut StoreContext) {
+    conformance::delivery_queue(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn scheduled_delivery(ctx: &mut StoreContext) {
+    conformance::scheduled_delivery(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn webhook_queue(ctx: &mut StoreContext) {
+    conformance::webhook_queue(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_suspension(ctx: &mut StoreContext) {
+    conformance::tenant_suspension(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_templates(ctx: &mut StoreContext) {
+    conformance::tenant_templates(&postgres(ctx)).await;
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_listing(ctx: &mut StoreContext) {
+    conformance::tenant_listing(&postgres(ctx)).await;
+}
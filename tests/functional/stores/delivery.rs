@@ -0,0 +1,103 @@
+use {
+    crate::{
+        context::StoreContext,
+        functional::stores::{gen_id, notification::create_client, TENANT_ID},
+    },
+    chrono::Utc,
+    echo_server::handlers::push_message::PushMessageBody,
+    std::time::Duration,
+    test_context::test_context,
+};
+
+const PAYLOAD: PushMessageBody = PushMessageBody {
+    raw: None,
+    legacy: None,
+    options: None,
+    template: None,
+    send_at: None,
+    delay: None,
+};
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn delivery_enqueue_once(ctx: &mut StoreContext) {
+    let client_id = create_client(&ctx.clients).await;
+    let notification_id = gen_id();
+
+    let delivery = ctx
+        .deliveries
+        .enqueue_delivery(
+            &notification_id,
+            TENANT_ID,
+            &client_id,
+            &PAYLOAD,
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap()
+        .expect("delivery was not queued");
+    assert_eq!(delivery.client_id, client_id);
+    assert_eq!(delivery.attempts, 1);
+
+    let duplicate = ctx
+        .deliveries
+        .enqueue_delivery(
+            &notification_id,
+            TENANT_ID,
+            &client_id,
+            &PAYLOAD,
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+    assert!(duplicate.is_none());
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn delivery_claim_due(ctx: &mut StoreContext) {
+    let client_id = create_client(&ctx.clients).await;
+    let notification_id = gen_id();
+
+    ctx.deliveries
+        .enqueue_delivery(
+            &notification_id,
+            TENANT_ID,
+            &client_id,
+            &PAYLOAD,
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+
+    // Make the delivery due immediately
+    ctx.deliveries
+        .reschedule_delivery(&notification_id, &client_id, Utc::now())
+        .await
+        .unwrap();
+
+    let claimed = ctx
+        .deliveries
+        .claim_due_deliveries(1000, Duration::from_secs(60))
+        .await
+        .unwrap();
+    let delivery = claimed
+        .iter()
+        .find(|d| d.notification_id == notification_id)
+        .expect("due delivery was not claimed");
+    assert_eq!(delivery.attempts, 2);
+    assert!(delivery.next_attempt_at > Utc::now());
+
+    // Leased deliveries are not claimed twice
+    let claimed = ctx
+        .deliveries
+        .claim_due_deliveries(1000, Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert!(!claimed.iter().any(|d| d.notification_id == notification_id));
+
+    ctx.deliveries
+        .remove_delivery(&notification_id, &client_id)
+        .await
+        .unwrap();
+}
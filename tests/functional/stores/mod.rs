@@ -1,9 +1,12 @@
 use uuid::Uuid;
 
 mod client;
+mod conformance;
+mod delivery;
 mod notification;
 /// Tests against the stores
 mod tenant;
+mod webhook;
 
 pub const TENANT_ID: &str = "000-000-000-000";
 
@@ -3,6 +3,7 @@ use {
         context::StoreContext,
         functional::stores::{gen_id, TENANT_ID},
     },
+    chrono::{NaiveDate, TimeZone, Utc},
     echo_server::{
         handlers::push_message::PushMessageBody, providers::ProviderKind, state::ClientStoreArc,
         stores::client::Client,
@@ -23,6 +24,7 @@ pub async fn create_client(client_store: &ClientStoreArc) -> String {
                 push_type: ProviderKind::Noop,
                 token,
                 always_raw: false,
+                metadata: Default::default(),
             },
             None,
         )
@@ -46,6 +48,10 @@ async fn notification(ctx: &mut StoreContext) {
             &PushMessageBody {
                 raw: None,
                 legacy: None,
+                options: None,
+                template: None,
+                send_at: None,
+                delay: None,
             },
         )
         .await;
@@ -60,6 +66,10 @@ async fn notification_multiple_clients_same_payload(ctx: &mut StoreContext) {
     let payload = PushMessageBody {
         raw: None,
         legacy: None,
+        options: None,
+        template: None,
+        send_at: None,
+        delay: None,
     };
 
     let client_id1 = create_client(&ctx.clients).await;
@@ -90,3 +100,182 @@ async fn notification_multiple_clients_same_payload(ctx: &mut StoreContext) {
         .unwrap();
     assert_eq!(notification2.client_id, client_id2);
 }
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn notification_provider_and_history(ctx: &mut StoreContext) {
+    let client_id = create_client(&ctx.clients).await;
+    let payload = PushMessageBody {
+        raw: None,
+        legacy: None,
+        options: None,
+        template: None,
+        send_at: None,
+        delay: None,
+    };
+
+    let first_id = gen_id();
+    ctx.notifications
+        .create_or_update_notification(&first_id, TENANT_ID, &client_id, &payload)
+        .await
+        .unwrap();
+    let second_id = gen_id();
+    ctx.notifications
+        .create_or_update_notification(&second_id, TENANT_ID, &client_id, &payload)
+        .await
+        .unwrap();
+
+    ctx.notifications
+        .update_notification_provider(&first_id, &client_id, TENANT_ID, "apns", Some("apns-id"))
+        .await
+        .unwrap();
+    let notification = ctx
+        .notifications
+        .get_notification(&first_id, &client_id, TENANT_ID)
+        .await
+        .unwrap();
+    assert_eq!(notification.provider.as_deref(), Some("apns"));
+    assert_eq!(notification.provider_message_id.as_deref(), Some("apns-id"));
+
+    let notifications = ctx
+        .notifications
+        .get_client_notifications(TENANT_ID, &client_id, 10)
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 2);
+    assert_eq!(notifications[0].id, second_id);
+    assert_eq!(notifications[1].id, first_id);
+
+    let notifications = ctx
+        .notifications
+        .get_client_notifications(TENANT_ID, &client_id, 1)
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 1);
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn notification_partitions(ctx: &mut StoreContext) {
+    // Far in the past so no other test's notifications are affected
+    let day = NaiveDate::from_ymd_opt(2001, 1, 1).unwrap();
+    ctx.notifications
+        .create_notification_partition(day)
+        .await
+        .unwrap();
+    // Creating it again is a no-op
+    ctx.notifications
+        .create_notification_partition(day)
+        .await
+        .unwrap();
+
+    // The partition still holds notifications from the end of the day
+    let dropped = ctx
+        .notifications
+        .drop_notification_partitions(Utc.with_ymd_and_hms(2001, 1, 1, 12, 0, 0).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(dropped, 0);
+
+    let dropped = ctx
+        .notifications
+        .drop_notification_partitions(Utc.with_ymd_and_hms(2001, 1, 2, 0, 0, 0).unwrap())
+        .await
+        .unwrap();
+    assert!(dropped >= 1);
+
+    let pruned = ctx
+        .notifications
+        .prune_notifications(Utc.with_ymd_and_hms(2001, 1, 2, 0, 0, 0).unwrap(), 100)
+        .await
+        .unwrap();
+    assert_eq!(pruned, 0);
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn notification_partitions_backfill(ctx: &mut StoreContext) {
+    let client_id = create_client(&ctx.clients).await;
+    let message_id = gen_id();
+
+    // As left by the migration partitioning the table
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS public.notifications_unpartitioned
+            (LIKE public.notifications INCLUDING DEFAULTS)",
+    )
+    .execute(ctx.pool.as_ref())
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO public.notifications_unpartitioned (id, client_id, tenant_id, created_at)
+        VALUES ($1, $2, $3, '2001-02-03T12:00:00Z')",
+    )
+    .bind(&message_id)
+    .bind(&client_id)
+    .bind(TENANT_ID)
+    .execute(ctx.pool.as_ref())
+    .await
+    .unwrap();
+
+    while ctx
+        .notifications
+        .backfill_notification_partitions(100)
+        .await
+        .unwrap()
+        > 0
+    {}
+
+    let notification = ctx
+        .notifications
+        .get_notification(&message_id, &client_id, TENANT_ID)
+        .await
+        .unwrap();
+    assert_eq!(
+        notification.created_at,
+        Utc.with_ymd_and_hms(2001, 2, 3, 12, 0, 0).unwrap()
+    );
+
+    // The old table is dropped once it's empty
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT to_regclass('public.notifications_unpartitioned') IS NOT NULL",
+    )
+    .fetch_one(ctx.pool.as_ref())
+    .await
+    .unwrap();
+    assert!(!exists);
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn notification_received_twice(ctx: &mut StoreContext) {
+    let client_id = create_client(&ctx.clients).await;
+    let message_id = gen_id();
+    let payload = PushMessageBody {
+        raw: None,
+        legacy: None,
+        options: None,
+        template: None,
+        send_at: None,
+        delay: None,
+    };
+
+    let first = ctx
+        .notifications
+        .create_or_update_notification(&message_id, TENANT_ID, &client_id, &payload)
+        .await
+        .unwrap();
+    let second = ctx
+        .notifications
+        .create_or_update_notification(&message_id, TENANT_ID, &client_id, &payload)
+        .await
+        .unwrap();
+    assert_eq!(first.created_at, second.created_at);
+    assert!(second.last_received_at >= first.last_received_at);
+
+    let notifications = ctx
+        .notifications
+        .get_client_notifications(TENANT_ID, &client_id, 10)
+        .await
+        .unwrap();
+    assert_eq!(notifications.len(), 1);
+}
@@ -1,9 +1,21 @@
 use {
     crate::context::StoreContext,
-    echo_server::stores::tenant::{
-        TenantApnsUpdateAuth, TenantApnsUpdateParams, TenantFcmUpdateParams,
-        TenantFcmV1UpdateParams, TenantUpdateParams,
+    chrono::{DurationRound, Utc},
+    echo_server::{
+        encryption::{CredentialCipher, LocalKeyProvider},
+        providers::ProviderKind,
+        push_rate_limit::RateLimit,
+        stores::{
+            encrypted_tenant::EncryptedTenantStore,
+            tenant::{
+                SuspensionAction, TenantApnsUpdateAuth, TenantApnsUpdateParams, TenantCredentials,
+                TenantFcmUpdateParams, TenantFcmV1UpdateParams, TenantHmsUpdateParams,
+                TenantListParams, TenantRateLimitUpdateParams, TenantSuspendParams,
+                TenantUpdateParams, TenantWebPushUpdateParams,
+            },
+        },
     },
+    std::sync::Arc,
     test_context::test_context,
     uuid::Uuid,
 };
@@ -401,3 +413,436 @@ async fn tenant_delete_apns_token_auth(ctx: &mut StoreContext) {
     assert_eq!(res.apns_certificate, None);
     assert_eq!(res.apns_certificate_password, None);
 }
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_web_push(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(TenantUpdateParams {
+            id: Uuid::new_v4().to_string(),
+        })
+        .await
+        .expect("creation failed");
+
+    let res = ctx
+        .tenants
+        .update_tenant_web_push(
+            &tenant.id,
+            TenantWebPushUpdateParams {
+                web_push_vapid_key: "test-vapid-key".to_string(),
+                web_push_vapid_subject: "mailto:test@example.com".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.web_push_vapid_key, Some("test-vapid-key".to_owned()));
+    assert!(res.providers().contains(&ProviderKind::WebPush));
+
+    let res = ctx
+        .tenants
+        .update_tenant_delete_web_push(&tenant.id)
+        .await
+        .unwrap();
+    assert!(res.web_push_vapid_key.is_none());
+    assert!(res.web_push_vapid_subject.is_none());
+    assert!(!res.providers().contains(&ProviderKind::WebPush));
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_hms(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(TenantUpdateParams {
+            id: Uuid::new_v4().to_string(),
+        })
+        .await
+        .expect("creation failed");
+
+    let res = ctx
+        .tenants
+        .update_tenant_hms(
+            &tenant.id,
+            TenantHmsUpdateParams {
+                hms_app_id: "test-app-id".to_string(),
+                hms_app_secret: "test-app-secret".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.hms_app_id, Some("test-app-id".to_owned()));
+    assert!(res.providers().contains(&ProviderKind::Hms));
+
+    let res = ctx
+        .tenants
+        .update_tenant_delete_hms(&tenant.id)
+        .await
+        .unwrap();
+    assert!(res.hms_app_id.is_none());
+    assert!(res.hms_app_secret.is_none());
+    assert!(!res.providers().contains(&ProviderKind::Hms));
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_rate_limits(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(TenantUpdateParams {
+            id: Uuid::new_v4().to_string(),
+        })
+        .await
+        .expect("creation failed");
+    assert!(tenant.push_rate_limit().is_none());
+    assert!(tenant.client_push_rate_limit().is_none());
+
+    let res = ctx
+        .tenants
+        .update_tenant_rate_limits(
+            &tenant.id,
+            TenantRateLimitUpdateParams {
+                push_rate_limit_per_minute: Some(600),
+                push_rate_limit_burst: Some(100),
+                client_push_rate_limit_per_minute: Some(60),
+                client_push_rate_limit_burst: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        res.push_rate_limit(),
+        Some(RateLimit {
+            per_minute: 600,
+            burst: 100
+        })
+    );
+    assert_eq!(
+        res.client_push_rate_limit(),
+        Some(RateLimit {
+            per_minute: 60,
+            burst: 60
+        })
+    );
+}
+
+fn credential_cipher(keys: &str) -> CredentialCipher {
+    CredentialCipher::new(Arc::new(
+        LocalKeyProvider::from_keys(keys).expect("failed to parse master keys"),
+    ))
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_credentials_encrypted(ctx: &mut StoreContext) {
+    let store = EncryptedTenantStore::new(
+        ctx.tenants.clone(),
+        credential_cipher("k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY="),
+    );
+    let tenant = store
+        .create_tenant(TenantUpdateParams {
+            id: Uuid::new_v4().to_string(),
+        })
+        .await
+        .expect("creation failed");
+
+    let res = store
+        .update_tenant_fcm(
+            &tenant.id,
+            TenantFcmUpdateParams {
+                fcm_api_key: "test-api-key".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.fcm_api_key, Some("test-api-key".to_owned()));
+
+    let stored = ctx.tenants.get_tenant(&tenant.id).await.unwrap();
+    let stored_key = stored.fcm_api_key.expect("missing fcm api key");
+    assert!(stored_key.starts_with("enc:v1:k1:"));
+
+    let res = store.get_tenant(&tenant.id).await.unwrap();
+    assert_eq!(res.fcm_api_key, Some("test-api-key".to_owned()));
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_credentials_backfill(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(TenantUpdateParams {
+            id: Uuid::new_v4().to_string(),
+        })
+        .await
+        .expect("creation failed");
+    ctx.tenants
+        .update_tenant_credentials(
+            &tenant.id,
+            TenantCredentials {
+                hms_app_secret: Some("test-app-secret".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let store = EncryptedTenantStore::new(
+        ctx.tenants.clone(),
+        credential_cipher("k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY="),
+    );
+    // Plaintext rows are readable before they are backfilled
+    let res = store.get_tenant(&tenant.id).await.unwrap();
+    assert_eq!(res.hms_app_secret, Some("test-app-secret".to_owned()));
+
+    assert!(store.encrypt_tenant(&tenant.id).await.unwrap());
+    assert!(!store.encrypt_tenant(&tenant.id).await.unwrap());
+    let stored = ctx.tenants.get_tenant(&tenant.id).await.unwrap();
+    assert!(stored
+        .hms_app_secret
+        .expect("missing hms app secret")
+        .starts_with("enc:v1:k1:"));
+
+    // Rotating the master key re-encrypts with the new one
+    let rotated = EncryptedTenantStore::new(
+        ctx.tenants.clone(),
+        credential_cipher(
+            "k2:ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=,\
+             k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=",
+        ),
+    );
+    assert!(rotated.encrypt_tenant(&tenant.id).await.unwrap());
+    let stored = ctx.tenants.get_tenant(&tenant.id).await.unwrap();
+    assert!(stored
+        .hms_app_secret
+        .expect("missing hms app secret")
+        .starts_with("enc:v1:k2:"));
+
+    let res = rotated.get_tenant(&tenant.id).await.unwrap();
+    assert_eq!(res.hms_app_secret, Some("test-app-secret".to_owned()));
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_list(ctx: &mut StoreContext) {
+    // Scoped by the id prefix as other tests share the database
+    let prefix = format!("{}-", Uuid::new_v4());
+    for i in 0..3 {
+        ctx.tenants
+            .create_tenant(TenantUpdateParams {
+                id: format!("{prefix}{i}"),
+            })
+            .await
+            .expect("creation failed");
+    }
+    ctx.tenants
+        .update_tenant_hms(
+            &format!("{prefix}1"),
+            TenantHmsUpdateParams {
+                hms_app_id: "test-app-id".to_string(),
+                hms_app_secret: "test-app-secret".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    ctx.tenants
+        .suspend_tenant(
+            &format!("{prefix}2"),
+            TenantSuspendParams {
+                reason: "test".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let page = ctx
+        .tenants
+        .list_tenants(TenantListParams {
+            id_prefix: Some(prefix.clone()),
+            limit: 2,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let ids = page.iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+    assert_eq!(ids, vec![format!("{prefix}0"), format!("{prefix}1")]);
+
+    let page = ctx
+        .tenants
+        .list_tenants(TenantListParams {
+            id_prefix: Some(prefix.clone()),
+            after_id: Some(format!("{prefix}1")),
+            limit: 2,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].id, format!("{prefix}2"));
+
+    let suspended = ctx
+        .tenants
+        .list_tenants(TenantListParams {
+            suspended: Some(true),
+            id_prefix: Some(prefix.clone()),
+            limit: 10,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(suspended.len(), 1);
+    assert_eq!(suspended[0].id, format!("{prefix}2"));
+
+    let hms = ctx
+        .tenants
+        .list_tenants(TenantListParams {
+            provider: Some(ProviderKind::Hms),
+            id_prefix: Some(prefix.clone()),
+            limit: 10,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(hms.len(), 1);
+    assert_eq!(hms[0].id, format!("{prefix}1"));
+
+    let summary = ctx.tenants.get_tenant_summary().await.unwrap();
+    assert!(summary.total >= 3);
+    assert!(summary.suspended >= 1);
+    assert!(summary.hms >= 1);
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_suspension(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(TenantUpdateParams {
+            id: Uuid::new_v4().to_string(),
+        })
+        .await
+        .expect("creation failed");
+
+    let params = TenantSuspendParams {
+        reason: "Invalid HMS Credentials".to_string(),
+        client_id: Some(Uuid::new_v4().to_string()),
+        notification_id: Some(Uuid::new_v4().to_string()),
+    };
+    assert!(ctx
+        .tenants
+        .suspend_tenant(&tenant.id, params.clone())
+        .await
+        .unwrap());
+    // Already suspended, so not recorded again
+    assert!(!ctx
+        .tenants
+        .suspend_tenant(&tenant.id, params.clone())
+        .await
+        .unwrap());
+
+    let res = ctx.tenants.get_tenant(&tenant.id).await.unwrap();
+    assert!(res.suspended);
+    assert_eq!(res.suspended_reason, Some(params.reason.clone()));
+
+    // New credentials lift the suspension
+    let res = ctx
+        .tenants
+        .update_tenant_hms(
+            &tenant.id,
+            TenantHmsUpdateParams {
+                hms_app_id: "test-app-id".to_string(),
+                hms_app_secret: "test-app-secret".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!res.suspended);
+    assert!(res.suspended_reason.is_none());
+    let res = ctx.tenants.get_tenant(&tenant.id).await.unwrap();
+    assert!(!res.suspended);
+
+    let history = ctx
+        .tenants
+        .get_tenant_suspension_history(&tenant.id)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].action, SuspensionAction::Unsuspended);
+    assert_eq!(history[1].action, SuspensionAction::Suspended);
+    assert_eq!(history[1].reason, Some(params.reason));
+    assert_eq!(history[1].client_id, params.client_id);
+    assert_eq!(history[1].notification_id, params.notification_id);
+
+    // Nothing to lift anymore
+    assert!(!ctx
+        .tenants
+        .unsuspend_tenant(&tenant.id, "test")
+        .await
+        .unwrap());
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn tenant_apns_certificate_expiry(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(TenantUpdateParams {
+            id: Uuid::new_v4().to_string(),
+        })
+        .await
+        .expect("creation failed");
+    ctx.tenants
+        .update_tenant_apns_auth(
+            &tenant.id,
+            TenantApnsUpdateAuth::Certificate {
+                apns_certificate: "example-certificate-string".to_string(),
+                apns_certificate_password: "password123".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+    let expires_at = Utc::now()
+        .duration_trunc(chrono::Duration::seconds(1))
+        .unwrap();
+    ctx.tenants
+        .update_tenant_apns_certificate_expiry(&tenant.id, Some(expires_at))
+        .await
+        .unwrap();
+    let res = ctx.tenants.get_tenant(&tenant.id).await.unwrap();
+    assert_eq!(res.apns_certificate_expires_at, Some(expires_at));
+    assert!(ctx
+        .tenants
+        .get_apns_certificate_tenants()
+        .await
+        .unwrap()
+        .iter()
+        .any(|t| t.id == tenant.id));
+
+    // Only the first instance claims the warning
+    assert!(ctx
+        .tenants
+        .mark_tenant_apns_expiry_notified(&tenant.id)
+        .await
+        .unwrap());
+    assert!(!ctx
+        .tenants
+        .mark_tenant_apns_expiry_notified(&tenant.id)
+        .await
+        .unwrap());
+
+    // Uploading a new certificate resets the expiry and the warning
+    let res = ctx
+        .tenants
+        .update_tenant_apns_auth(
+            &tenant.id,
+            TenantApnsUpdateAuth::Certificate {
+                apns_certificate: "new-certificate-string".to_string(),
+                apns_certificate_password: "password123".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.apns_certificate_expires_at, None);
+    assert_eq!(res.apns_expiry_notified_at, None);
+}
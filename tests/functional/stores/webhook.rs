@@ -0,0 +1,113 @@
+use {
+    crate::{context::StoreContext, functional::stores::gen_id},
+    chrono::Utc,
+    echo_server::stores::{notification::DeliveryStatus, webhook::WebhookEvent},
+    std::time::Duration,
+    test_context::test_context,
+};
+
+fn client_deleted() -> WebhookEvent {
+    WebhookEvent::ClientDeleted {
+        client_id: gen_id(),
+        reason: "bad device token".to_string(),
+    }
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn webhook_enqueue_and_claim(ctx: &mut StoreContext) {
+    let tenant_id = gen_id();
+    let event = client_deleted();
+
+    let queued = ctx
+        .webhooks
+        .enqueue_webhook_event(&tenant_id, &event)
+        .await
+        .unwrap();
+    assert_eq!(queued.tenant_id, tenant_id);
+    assert_eq!(queued.event_type, "client_deleted");
+    assert_eq!(queued.payload.0, event);
+    assert_eq!(queued.status, DeliveryStatus::Queued);
+    assert_eq!(queued.attempts, 0);
+
+    let claimed = ctx
+        .webhooks
+        .claim_due_webhook_events(1000, Duration::from_secs(60))
+        .await
+        .unwrap();
+    let claimed = claimed
+        .into_iter()
+        .find(|e| e.id == queued.id)
+        .expect("event was not claimed");
+    assert_eq!(claimed.attempts, 1);
+
+    // Leased events are not claimed again until the lease expires
+    let reclaimed = ctx
+        .webhooks
+        .claim_due_webhook_events(1000, Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert!(!reclaimed.iter().any(|e| e.id == queued.id));
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn webhook_delivery_log(ctx: &mut StoreContext) {
+    let tenant_id = gen_id();
+
+    let failed = ctx
+        .webhooks
+        .enqueue_webhook_event(&tenant_id, &client_deleted())
+        .await
+        .unwrap();
+    let delivered = ctx
+        .webhooks
+        .enqueue_webhook_event(&tenant_id, &client_deleted())
+        .await
+        .unwrap();
+
+    ctx.webhooks
+        .update_webhook_event(
+            &failed.id,
+            DeliveryStatus::Failed,
+            Some(500),
+            Some("webhook responded with 500"),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+    ctx.webhooks
+        .update_webhook_event(
+            &delivered.id,
+            DeliveryStatus::Delivered,
+            Some(200),
+            None,
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+
+    let events = ctx
+        .webhooks
+        .get_webhook_events(&tenant_id, 10)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 2);
+
+    let failed = events.iter().find(|e| e.id == failed.id).unwrap();
+    assert_eq!(failed.status, DeliveryStatus::Failed);
+    assert_eq!(failed.response_status, Some(500));
+    assert!(failed.delivered_at.is_none());
+
+    let delivered = events.iter().find(|e| e.id == delivered.id).unwrap();
+    assert_eq!(delivered.status, DeliveryStatus::Delivered);
+    assert!(delivered.last_error.is_none());
+    assert!(delivered.delivered_at.is_some());
+
+    let limited = ctx
+        .webhooks
+        .get_webhook_events(&tenant_id, 1)
+        .await
+        .unwrap();
+    assert_eq!(limited.len(), 1);
+}
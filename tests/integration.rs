@@ -1,6 +1,9 @@
 // mod env;
 // mod providers;
 // mod store; // Comment this out for now
+/// Behaviour every storage backend must share, run against the in-memory
+/// backend by the unit tests and against Postgres by the functional tests
+mod conformance;
 mod context;
 #[cfg(feature = "functional_tests")]
 mod functional;
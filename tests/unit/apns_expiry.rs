@@ -0,0 +1,64 @@
+use {
+    chrono::{Duration, Utc},
+    echo_server::{apns_expiry::needs_warning, stores::tenant::Tenant},
+};
+
+fn tenant() -> Tenant {
+    Tenant {
+        id: "tenant".to_string(),
+        fcm_api_key: None,
+        fcm_v1_credentials: None,
+        apns_type: None,
+        apns_topic: None,
+        apns_certificate: None,
+        apns_certificate_password: None,
+        apns_pkcs8_pem: None,
+        apns_key_id: None,
+        apns_team_id: None,
+        hms_app_id: None,
+        hms_app_secret: None,
+        web_push_vapid_key: None,
+        web_push_vapid_subject: None,
+        push_rate_limit_per_minute: None,
+        push_rate_limit_burst: None,
+        client_push_rate_limit_per_minute: None,
+        client_push_rate_limit_burst: None,
+        suspended: false,
+        suspended_reason: None,
+        apns_certificate_expires_at: None,
+        apns_expiry_notified_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+pub fn warn_within_warning_period() {
+    let now = Utc::now();
+    let warning_period = Duration::days(30);
+
+    let mut tenant = tenant();
+    assert!(!needs_warning(&tenant, warning_period, now));
+
+    tenant.apns_certificate_expires_at = Some(now + Duration::days(31));
+    assert!(!needs_warning(&tenant, warning_period, now));
+
+    tenant.apns_certificate_expires_at = Some(now + Duration::days(29));
+    assert!(needs_warning(&tenant, warning_period, now));
+
+    // Expired certificates are warned about too
+    tenant.apns_certificate_expires_at = Some(now - Duration::days(1));
+    assert!(needs_warning(&tenant, warning_period, now));
+}
+
+#[test]
+pub fn warn_once() {
+    let now = Utc::now();
+    let mut tenant = tenant();
+    tenant.apns_certificate_expires_at = Some(now + Duration::days(1));
+    tenant.apns_expiry_notified_at = Some(now - Duration::hours(1));
+
+    assert!(!needs_warning(&tenant, Duration::days(30), now));
+}
@@ -0,0 +1,23 @@
+use {echo_server::delivery::backoff_delay, std::time::Duration};
+
+const BASE: Duration = Duration::from_secs(1);
+const MAX: Duration = Duration::from_secs(60);
+
+#[test]
+pub fn backoff_doubles_every_attempt() {
+    for attempt in 1..=5 {
+        let expected = BASE * 2u32.pow(attempt - 1);
+        let delay = backoff_delay(attempt, BASE, MAX);
+
+        assert!(delay >= expected / 2, "attempt {attempt}: {delay:?}");
+        assert!(delay <= expected, "attempt {attempt}: {delay:?}");
+    }
+}
+
+#[test]
+pub fn backoff_is_capped() {
+    let delay = backoff_delay(1000, BASE, MAX);
+
+    assert!(delay >= MAX / 2);
+    assert!(delay <= MAX);
+}
@@ -0,0 +1,90 @@
+use {
+    echo_server::encryption::{CredentialCipher, LocalKeyProvider},
+    std::sync::Arc,
+};
+
+// Base64 encoded 32 byte keys
+const KEY_1: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+const KEY_2: &str = "ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=";
+
+fn cipher(keys: &str) -> CredentialCipher {
+    CredentialCipher::new(Arc::new(
+        LocalKeyProvider::from_keys(keys).expect("failed to parse master keys"),
+    ))
+}
+
+#[tokio::test]
+pub async fn encrypts_and_decrypts() {
+    let cipher = cipher(&format!("k1:{KEY_1}"));
+
+    let encrypted = cipher
+        .encrypt("secret", "tenant:fcm_api_key")
+        .await
+        .expect("failed to encrypt");
+    assert!(encrypted.starts_with("enc:v1:k1:"));
+    assert!(!encrypted.contains("secret"));
+    assert!(!cipher.needs_encryption(&encrypted));
+
+    let decrypted = cipher
+        .decrypt(&encrypted, "tenant:fcm_api_key")
+        .await
+        .expect("failed to decrypt");
+    assert_eq!(decrypted, "secret");
+}
+
+#[tokio::test]
+pub async fn rejects_other_context() {
+    let cipher = cipher(&format!("k1:{KEY_1}"));
+
+    let encrypted = cipher
+        .encrypt("secret", "tenant:fcm_api_key")
+        .await
+        .expect("failed to encrypt");
+
+    let res = cipher.decrypt(&encrypted, "other:fcm_api_key").await;
+    assert!(res.is_err());
+    assert!(res.unwrap_err().is_credential_encryption());
+}
+
+#[tokio::test]
+pub async fn passes_through_plaintext() {
+    let cipher = cipher(&format!("k1:{KEY_1}"));
+
+    assert!(cipher.needs_encryption("secret"));
+    let decrypted = cipher
+        .decrypt("secret", "tenant:fcm_api_key")
+        .await
+        .expect("failed to decrypt");
+    assert_eq!(decrypted, "secret");
+}
+
+#[tokio::test]
+pub async fn decrypts_after_rotation() {
+    let old = cipher(&format!("k1:{KEY_1}"));
+    let encrypted = old
+        .encrypt("secret", "tenant:fcm_api_key")
+        .await
+        .expect("failed to encrypt");
+
+    let rotated = cipher(&format!("k2:{KEY_2},k1:{KEY_1}"));
+    assert_eq!(rotated.current_key_id(), "k2");
+    assert!(rotated.needs_encryption(&encrypted));
+    let decrypted = rotated
+        .decrypt(&encrypted, "tenant:fcm_api_key")
+        .await
+        .expect("failed to decrypt");
+    assert_eq!(decrypted, "secret");
+
+    // Without the old key the value can't be read anymore
+    let res = cipher(&format!("k2:{KEY_2}"))
+        .decrypt(&encrypted, "tenant:fcm_api_key")
+        .await;
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn rejects_invalid_master_keys() {
+    assert!(LocalKeyProvider::from_keys("").is_err());
+    assert!(LocalKeyProvider::from_keys(KEY_1).is_err());
+    assert!(LocalKeyProvider::from_keys("k1:c2hvcnQ=").is_err());
+}
@@ -0,0 +1,76 @@
+use crate::conformance::{self, Backend};
+
+#[tokio::test]
+async fn client_registration() {
+    conformance::client_registration(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn client_token_moves_to_new_id() {
+    conformance::client_token_moves_to_new_id(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn client_registration_concurrent() {
+    conformance::client_registration_concurrent(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn client_metadata() {
+    conformance::client_metadata(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn client_events() {
+    conformance::client_events(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn client_subscriptions() {
+    conformance::client_subscriptions(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn notification_dedupe() {
+    conformance::notification_dedupe(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn notification_claim() {
+    conformance::notification_claim(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn notification_status() {
+    conformance::notification_status(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn delivery_queue() {
+    conformance::delivery_queue(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn scheduled_delivery() {
+    conformance::scheduled_delivery(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn webhook_queue() {
+    conformance::webhook_queue(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn tenant_suspension() {
+    conformance::tenant_suspension(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn tenant_templates() {
+    conformance::tenant_templates(&Backend::memory()).await;
+}
+
+#[tokio::test]
+async fn tenant_listing() {
+    conformance::tenant_listing(&Backend::memory()).await;
+}
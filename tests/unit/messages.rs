@@ -1,6 +1,8 @@
 use echo_server::{
     blob::{DecryptedPayloadBlob, ENCRYPTED_FLAG},
-    providers::MessagePayload,
+    error::Error,
+    handlers::push_message::{PushMessageBody, SendAt, MAX_SCHEDULE_DELAY},
+    providers::{MessagePayload, PushOptions, PushPriority, PushType},
 };
 
 const EXAMPLE_TOPIC: &str = "example-topic";
@@ -54,7 +56,15 @@ pub fn parse_blob_from_payload() {
             title: EXAMPLE_CLEARTEXT_BLOB_TITLE.to_string(),
             body: EXAMPLE_CLEARTEXT_BLOB_BODY.to_string(),
             image: None,
-            url: None
+            url: None,
+            badge: None,
+            sound: None,
+            category: None,
+            thread_id: None,
+            title_loc_key: None,
+            title_loc_args: vec![],
+            body_loc_key: None,
+            body_loc_args: vec![],
         }
     )
 }
@@ -70,7 +80,177 @@ pub fn parse_encoded_blob() {
             title: EXAMPLE_CLEARTEXT_BLOB_TITLE.to_string(),
             body: EXAMPLE_CLEARTEXT_BLOB_BODY.to_string(),
             image: None,
-            url: None
+            url: None,
+            badge: None,
+            sound: None,
+            category: None,
+            thread_id: None,
+            title_loc_key: None,
+            title_loc_args: vec![],
+            body_loc_key: None,
+            body_loc_args: vec![],
         }
     )
 }
+
+#[test]
+pub fn parse_push_options() {
+    let body: PushMessageBody = serde_json::from_value(serde_json::json!({
+        "topic": EXAMPLE_TOPIC,
+        "tag": 4002,
+        "message": EXAMPLE_ENCRYPTED_BLOB,
+        "options": {
+            "priority": "normal",
+            "ttl": 3600,
+            "collapse_key": "chat",
+            "push_type": "background",
+        },
+    }))
+    .unwrap();
+
+    assert!(body.raw.is_some());
+    assert_eq!(
+        body.options,
+        Some(PushOptions {
+            priority: Some(PushPriority::Normal),
+            ttl: Some(3600),
+            collapse_key: Some("chat".to_string()),
+            push_type: Some(PushType::Background),
+        })
+    );
+    assert!(body.push_message(true).is_ok());
+}
+
+#[test]
+pub fn push_options_defaults() {
+    let options = PushOptions {
+        ttl: Some(u32::MAX),
+        push_type: Some(PushType::Background),
+        ..Default::default()
+    };
+
+    assert_eq!(options.ttl(), Some(PushOptions::MAX_TTL));
+    // Background pushes can't be sent with high priority on APNs
+    assert_eq!(options.priority(), Some(PushPriority::Normal));
+    assert_eq!(PushOptions::default().priority(), None);
+}
+
+#[test]
+pub fn reject_long_collapse_key() {
+    let body = PushMessageBody {
+        raw: None,
+        legacy: None,
+        options: Some(PushOptions {
+            collapse_key: Some("a".repeat(PushOptions::MAX_COLLAPSE_KEY_LENGTH + 1)),
+            ..Default::default()
+        }),
+        template: None,
+        send_at: None,
+        delay: None,
+    };
+
+    assert!(matches!(
+        body.push_message(true),
+        Err(Error::FieldTooLong(field, _)) if field == "collapse_key"
+    ));
+}
+
+#[test]
+pub fn parse_rich_blob() {
+    let blob: DecryptedPayloadBlob = serde_json::from_value(serde_json::json!({
+        "title": EXAMPLE_CLEARTEXT_BLOB_TITLE,
+        "body": EXAMPLE_CLEARTEXT_BLOB_BODY,
+        "image": "https://example.com/image.png",
+        "url": "wc://request/1",
+        "badge": 3,
+        "sound": "default",
+        "category": "SIGN_REQUEST",
+        "thread_id": "example-dapp",
+    }))
+    .expect("Failed to parse rich blob");
+
+    assert_eq!(blob.image.as_deref(), Some("https://example.com/image.png"));
+    assert_eq!(blob.url.as_deref(), Some("wc://request/1"));
+    assert_eq!(blob.badge, Some(3));
+    assert_eq!(blob.sound.as_deref(), Some("default"));
+    assert_eq!(blob.category.as_deref(), Some("SIGN_REQUEST"));
+    assert_eq!(blob.thread_id.as_deref(), Some("example-dapp"));
+}
+
+#[test]
+pub fn push_schedule() {
+    let body: PushMessageBody = serde_json::from_value(serde_json::json!({
+        "id": "1",
+        "payload": {"topic": EXAMPLE_TOPIC, "flags": 0, "blob": EXAMPLE_CLEARTEXT_ENCODED_BLOB},
+        "delay": 600,
+    }))
+    .expect("Failed to parse delayed push");
+    let send_at = body
+        .scheduled_at(None)
+        .unwrap()
+        .expect("push was not scheduled");
+    assert!(send_at > chrono::Utc::now() + chrono::Duration::seconds(590));
+
+    // Times in the past are sent right away
+    let body = PushMessageBody {
+        send_at: Some(SendAt::Absolute(
+            chrono::Utc::now() - chrono::Duration::seconds(1),
+        )),
+        delay: None,
+        ..body
+    };
+    assert_eq!(body.scheduled_at(None).unwrap(), None);
+
+    let body = PushMessageBody {
+        delay: Some(MAX_SCHEDULE_DELAY + 60),
+        send_at: None,
+        ..body
+    };
+    assert!(matches!(body.validate(), Err(Error::InvalidSchedule(_))));
+    let body = PushMessageBody {
+        delay: Some(60),
+        send_at: Some(SendAt::Absolute(chrono::Utc::now())),
+        ..body
+    };
+    assert!(matches!(body.validate(), Err(Error::InvalidSchedule(_))));
+}
+
+#[test]
+pub fn push_schedule_local_time() {
+    let send_at = (chrono::Utc::now() + chrono::Duration::days(2)).date_naive();
+    let body: PushMessageBody = serde_json::from_value(serde_json::json!({
+        "id": "1",
+        "payload": {"topic": EXAMPLE_TOPIC, "flags": 0, "blob": EXAMPLE_CLEARTEXT_ENCODED_BLOB},
+        "send_at": format!("{send_at}T09:00:00"),
+    }))
+    .expect("Failed to parse local time push");
+    assert!(matches!(body.send_at, Some(SendAt::Local(_))));
+    // Resolved once the client's timezone is known
+    assert!(body.validate().is_ok());
+
+    let in_utc = body.scheduled_at(Some("UTC")).unwrap().unwrap();
+    let in_tokyo = body.scheduled_at(Some("Asia/Tokyo")).unwrap().unwrap();
+    assert_eq!(in_utc - in_tokyo, chrono::Duration::hours(9));
+
+    for timezone in [None, Some("Mars/Olympus_Mons")] {
+        assert!(matches!(
+            body.scheduled_at(timezone),
+            Err(Error::InvalidSchedule(_))
+        ));
+    }
+
+    // Skipped when the clocks go forward
+    let body = PushMessageBody {
+        send_at: Some(SendAt::Local(
+            chrono::NaiveDate::from_ymd_opt(2024, 3, 31)
+                .unwrap()
+                .and_hms_opt(2, 30, 0)
+                .unwrap(),
+        )),
+        ..body
+    };
+    assert!(matches!(
+        body.scheduled_at(Some("Europe/Berlin")),
+        Err(Error::InvalidSchedule(_))
+    ));
+}
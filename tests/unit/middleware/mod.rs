@@ -1 +1,2 @@
+mod rate_limit;
 mod validate_signature;
@@ -0,0 +1,83 @@
+use {
+    echo_server::middleware::rate_limit::{InMemoryRateLimiter, RateLimiter, RouteLimit},
+    std::time::Duration,
+};
+
+const LIMIT: RouteLimit = RouteLimit {
+    max_requests: 3,
+    window: Duration::from_secs(60),
+};
+
+#[tokio::test]
+pub async fn allows_requests_within_limit() {
+    let limiter = InMemoryRateLimiter::new();
+
+    for _ in 0..LIMIT.max_requests {
+        let res = limiter.check("clients:127.0.0.1", LIMIT).await;
+        assert!(res.expect("failed to check rate limit").is_none());
+    }
+}
+
+#[tokio::test]
+pub async fn rejects_requests_over_limit() {
+    let limiter = InMemoryRateLimiter::new();
+
+    for _ in 0..LIMIT.max_requests {
+        limiter
+            .check("clients:127.0.0.1", LIMIT)
+            .await
+            .expect("failed to check rate limit");
+    }
+
+    let retry_after = limiter
+        .check("clients:127.0.0.1", LIMIT)
+        .await
+        .expect("failed to check rate limit")
+        .expect("request should be rate limited");
+    assert!(retry_after <= LIMIT.window);
+}
+
+#[tokio::test]
+pub async fn limits_keys_separately() {
+    let limiter = InMemoryRateLimiter::new();
+
+    for _ in 0..LIMIT.max_requests {
+        limiter
+            .check("clients:127.0.0.1", LIMIT)
+            .await
+            .expect("failed to check rate limit");
+    }
+
+    // Same IP in another route group and another IP in the same group
+    for key in ["tenants:127.0.0.1", "clients:127.0.0.2"] {
+        let res = limiter.check(key, LIMIT).await;
+        assert!(res.expect("failed to check rate limit").is_none());
+    }
+}
+
+#[tokio::test]
+pub async fn resets_after_window() {
+    let limiter = InMemoryRateLimiter::new();
+    let limit = RouteLimit {
+        max_requests: 1,
+        window: Duration::from_millis(50),
+    };
+
+    limiter
+        .check("clients:127.0.0.1", limit)
+        .await
+        .expect("failed to check rate limit");
+    assert!(limiter
+        .check("clients:127.0.0.1", limit)
+        .await
+        .expect("failed to check rate limit")
+        .is_some());
+
+    tokio::time::sleep(limit.window).await;
+
+    assert!(limiter
+        .check("clients:127.0.0.1", limit)
+        .await
+        .expect("failed to check rate limit")
+        .is_none());
+}
@@ -1,2 +1,13 @@
+#[cfg(feature = "multitenant")]
+mod apns_expiry;
+mod delivery;
+mod encryption;
+mod memory_store;
 mod messages;
 mod middleware;
+mod push_rate_limit;
+mod sqlite_store;
+mod templates;
+mod validation;
+mod web_push;
+mod webhooks;
@@ -0,0 +1,80 @@
+use {
+    echo_server::push_rate_limit::{RateLimit, TokenBucket},
+    std::time::{Duration, Instant},
+};
+
+#[test]
+pub fn bucket_allows_burst() {
+    let limit = RateLimit {
+        per_minute: 60,
+        burst: 3,
+    };
+    let now = Instant::now();
+    let mut bucket = TokenBucket::new(limit, now);
+
+    for _ in 0..3 {
+        assert!(bucket.try_take(limit, now).is_ok());
+    }
+    let retry_after = bucket.try_take(limit, now).unwrap_err();
+    assert!(retry_after <= Duration::from_secs(1));
+    assert!(retry_after > Duration::ZERO);
+}
+
+#[test]
+pub fn bucket_refills() {
+    let limit = RateLimit {
+        per_minute: 60,
+        burst: 1,
+    };
+    let now = Instant::now();
+    let mut bucket = TokenBucket::new(limit, now);
+
+    assert!(bucket.try_take(limit, now).is_ok());
+    assert!(bucket.try_take(limit, now).is_err());
+    assert!(bucket.try_take(limit, now + Duration::from_secs(1)).is_ok());
+
+    // Refilling never exceeds the burst
+    let later = now + Duration::from_secs(60);
+    assert!(bucket.try_take(limit, later).is_ok());
+    assert!(bucket.try_take(limit, later).is_err());
+}
+
+#[test]
+pub fn bucket_refund() {
+    let limit = RateLimit {
+        per_minute: 60,
+        burst: 1,
+    };
+    let now = Instant::now();
+    let mut bucket = TokenBucket::new(limit, now);
+
+    assert!(bucket.try_take(limit, now).is_ok());
+    bucket.refund(limit);
+    assert!(bucket.try_take(limit, now).is_ok());
+
+    // Refunds never exceed the burst
+    bucket.refund(limit);
+    bucket.refund(limit);
+    assert!(bucket.try_take(limit, now).is_ok());
+    assert!(bucket.try_take(limit, now).is_err());
+}
+
+#[test]
+pub fn rate_limit_from_parts() {
+    assert_eq!(RateLimit::from_parts(None, Some(10)), None);
+    assert_eq!(RateLimit::from_parts(Some(0), None), None);
+    assert_eq!(
+        RateLimit::from_parts(Some(60), None),
+        Some(RateLimit {
+            per_minute: 60,
+            burst: 60
+        })
+    );
+    assert_eq!(
+        RateLimit::from_parts(Some(60), Some(5)),
+        Some(RateLimit {
+            per_minute: 60,
+            burst: 5
+        })
+    );
+}
@@ -0,0 +1,63 @@
+use crate::conformance::{self, Backend};
+
+// Tenants are not stored in SQLite, so the tenant suites are left out
+
+#[tokio::test]
+async fn client_registration() {
+    conformance::client_registration(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn client_token_moves_to_new_id() {
+    conformance::client_token_moves_to_new_id(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn client_registration_concurrent() {
+    conformance::client_registration_concurrent(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn client_metadata() {
+    conformance::client_metadata(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn client_events() {
+    conformance::client_events(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn client_subscriptions() {
+    conformance::client_subscriptions(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn notification_dedupe() {
+    conformance::notification_dedupe(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn notification_claim() {
+    conformance::notification_claim(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn notification_status() {
+    conformance::notification_status(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn delivery_queue() {
+    conformance::delivery_queue(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn scheduled_delivery() {
+    conformance::scheduled_delivery(&Backend::sqlite().await).await;
+}
+
+#[tokio::test]
+async fn webhook_queue() {
+    conformance::webhook_queue(&Backend::sqlite().await).await;
+}
@@ -0,0 +1,110 @@
+use {
+    chrono::Utc,
+    echo_server::{
+        blob::DecryptedPayloadBlob,
+        stores::tenant::{LocalizedTemplate, NotificationTemplate, TemplateMode},
+        templates::{localize, placeholders, render, substitute, TemplatePushMessage},
+    },
+    sqlx::types::Json,
+    std::collections::{BTreeMap, HashMap},
+};
+
+fn template(mode: TemplateMode) -> NotificationTemplate {
+    let localized = |title: &str, body: &str| LocalizedTemplate {
+        title: title.to_string(),
+        body: body.to_string(),
+    };
+    NotificationTemplate {
+        tenant_id: "tenant".to_string(),
+        name: "sign_request".to_string(),
+        mode,
+        default_locale: "en".to_string(),
+        localizations: Json(BTreeMap::from([
+            (
+                "en".to_string(),
+                localized("Sign request", "{dapp} asks you to sign {count} messages"),
+            ),
+            (
+                "pt".to_string(),
+                localized("Pedido de assinatura", "{dapp} pede {count} assinaturas"),
+            ),
+            (
+                "pt-BR".to_string(),
+                localized(
+                    "Solicitação de assinatura",
+                    "{dapp} pede {count} assinaturas",
+                ),
+            ),
+        ])),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn message() -> TemplatePushMessage {
+    TemplatePushMessage {
+        id: "message-id".into(),
+        topic: "topic".into(),
+        template_id: "sign_request".to_string(),
+        variables: HashMap::from([
+            ("dapp".to_string(), "Example".to_string()),
+            ("count".to_string(), "2".to_string()),
+        ]),
+    }
+}
+
+fn rendered_blob(mode: TemplateMode, locale: Option<&str>) -> DecryptedPayloadBlob {
+    let rendered = render(&template(mode), &message(), locale).unwrap();
+    assert_eq!(&*rendered.id, "message-id");
+    assert_eq!(&*rendered.payload.topic, "topic");
+    assert!(!rendered.payload.is_encrypted());
+    DecryptedPayloadBlob::from_base64_encoded(&rendered.payload.blob).unwrap()
+}
+
+#[test]
+fn substitutes_placeholders() {
+    let variables = HashMap::from([("name".to_string(), "Alice".to_string())]);
+
+    assert_eq!(substitute("Hi {name}!", &variables), "Hi Alice!");
+    // Unknown and malformed placeholders are kept
+    assert_eq!(
+        substitute("{greeting} {{name}} {name", &variables),
+        "{greeting} {Alice} {name"
+    );
+    assert_eq!(
+        placeholders("{dapp} asks you to sign {count} messages {}"),
+        vec!["dapp", "count"]
+    );
+}
+
+#[test]
+fn falls_back_to_language_and_default_locale() {
+    let template = template(TemplateMode::Render);
+    let title = |locale| localize(&template, locale).unwrap().title.as_str();
+
+    assert_eq!(title(Some("pt_BR")), "Solicitação de assinatura");
+    assert_eq!(title(Some("pt-PT")), "Pedido de assinatura");
+    assert_eq!(title(Some("de-DE")), "Sign request");
+    assert_eq!(title(None), "Sign request");
+}
+
+#[test]
+fn renders_in_client_locale() {
+    let blob = rendered_blob(TemplateMode::Render, Some("pt-BR"));
+
+    assert_eq!(blob.title, "Solicitação de assinatura");
+    assert_eq!(blob.body, "Example pede 2 assinaturas");
+    assert_eq!(blob.body_loc_key, None);
+}
+
+#[test]
+fn passes_localization_keys_through() {
+    let blob = rendered_blob(TemplateMode::Passthrough, Some("pt-BR"));
+
+    // The default locale is the fallback for apps without the strings
+    assert_eq!(blob.body, "Example asks you to sign 2 messages");
+    assert_eq!(blob.title_loc_key.as_deref(), Some("sign_request_title"));
+    assert!(blob.title_loc_args.is_empty());
+    assert_eq!(blob.body_loc_key.as_deref(), Some("sign_request_body"));
+    assert_eq!(blob.body_loc_args, vec!["Example", "2"]);
+}
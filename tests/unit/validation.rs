@@ -0,0 +1,254 @@
+use {
+    base64::Engine as _,
+    echo_server::{
+        providers::validation::{
+            apns_certificate_expiry, validate_apns, validate_fcm_v1, ValidationEndpoints,
+        },
+        stores::tenant::TenantApnsUpdateAuth,
+    },
+    openssl::{
+        asn1::Asn1Time,
+        ec::{EcGroup, EcKey},
+        hash::MessageDigest,
+        nid::Nid,
+        pkcs12::Pkcs12,
+        pkey::PKey,
+        rsa::Rsa,
+        x509::{X509NameBuilder, X509},
+    },
+    serde_json::json,
+    wiremock::{
+        matchers::{body_string_contains, header, header_exists, method, path, path_regex},
+        Mock, MockServer, ResponseTemplate,
+    },
+};
+
+const TOPIC: &str = "app.test";
+
+fn endpoints(server: &MockServer) -> ValidationEndpoints {
+    ValidationEndpoints {
+        apns_production_url: server.uri(),
+        apns_sandbox_url: format!("{}/sandbox", server.uri()),
+        fcm_api_url: server.uri(),
+        fcm_token_url: Some(format!("{}/token", server.uri())),
+    }
+}
+
+fn apns_token_auth() -> TenantApnsUpdateAuth {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+    TenantApnsUpdateAuth::Token {
+        apns_pkcs8_pem: base64::engine::general_purpose::STANDARD
+            .encode(key.private_key_to_pem_pkcs8().unwrap()),
+        apns_key_id: "KEYID".to_string(),
+        apns_team_id: "TEAMID".to_string(),
+    }
+}
+
+/// Self-signed p12 bundle which expired at `not_after`
+fn apns_certificate(not_after: i64) -> String {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "Apple Push Services: app.test")
+        .unwrap();
+    let name = name.build();
+
+    let mut cert = X509::builder().unwrap();
+    cert.set_version(2).unwrap();
+    cert.set_subject_name(&name).unwrap();
+    cert.set_issuer_name(&name).unwrap();
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::from_unix(not_after - 86400).unwrap())
+        .unwrap();
+    cert.set_not_after(&Asn1Time::from_unix(not_after).unwrap())
+        .unwrap();
+    cert.sign(&key, MessageDigest::sha256()).unwrap();
+    let cert = cert.build();
+
+    let pkcs12 = Pkcs12::builder()
+        .name("apns")
+        .pkey(&key)
+        .cert(&cert)
+        .build2("password")
+        .unwrap();
+    base64::engine::general_purpose::STANDARD.encode(pkcs12.to_der().unwrap())
+}
+
+fn fcm_v1_credentials(server: &MockServer) -> String {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    json!({
+        "type": "service_account",
+        "project_id": "echo-test",
+        "private_key": String::from_utf8(key.private_key_to_pem_pkcs8().unwrap()).unwrap(),
+        "client_email": "echo@echo-test.iam.gserviceaccount.com",
+        "token_uri": format!("{}/unused", server.uri()),
+    })
+    .to_string()
+}
+
+#[tokio::test]
+pub async fn apns_token_dry_run_accepted() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path_regex("^/3/device/[0-9a-f]{64}$"))
+        .and(header("apns-topic", TOPIC))
+        .and(header_exists("authorization"))
+        .respond_with(
+            ResponseTemplate::new(400).set_body_json(json!({ "reason": "BadDeviceToken" })),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    validate_apns(&endpoints(&server), TOPIC, &apns_token_auth())
+        .await
+        .expect("credentials should be accepted");
+}
+
+#[tokio::test]
+pub async fn apns_token_dry_run_rejected() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(403).set_body_json(json!({ "reason": "InvalidProviderToken" })),
+        )
+        .mount(&server)
+        .await;
+
+    assert!(
+        validate_apns(&endpoints(&server), TOPIC, &apns_token_auth())
+            .await
+            .unwrap_err()
+            .is_credential_validation()
+    );
+}
+
+#[tokio::test]
+pub async fn apns_dry_run_rejects_topic() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(400).set_body_json(json!({ "reason": "TopicDisallowed" })),
+        )
+        .mount(&server)
+        .await;
+
+    assert!(
+        validate_apns(&endpoints(&server), TOPIC, &apns_token_auth())
+            .await
+            .unwrap_err()
+            .is_credential_validation()
+    );
+}
+
+#[tokio::test]
+pub async fn apns_dry_run_unavailable() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+
+    assert!(
+        validate_apns(&endpoints(&server), TOPIC, &apns_token_auth())
+            .await
+            .unwrap_err()
+            .is_provider_unavailable()
+    );
+}
+
+#[tokio::test]
+pub async fn apns_expired_certificate() {
+    let not_after = chrono::Utc::now().timestamp() - 3600;
+    let certificate = apns_certificate(not_after);
+
+    let expiry = apns_certificate_expiry(&certificate, "password").unwrap();
+    assert_eq!(expiry.timestamp(), not_after);
+    assert!(apns_certificate_expiry(&certificate, "wrong")
+        .unwrap_err()
+        .is_bad_apns_credentials());
+
+    // Rejected before any request is made
+    let server = MockServer::start().await;
+    let auth = TenantApnsUpdateAuth::Certificate {
+        apns_certificate: certificate,
+        apns_certificate_password: "password".to_string(),
+    };
+    assert!(validate_apns(&endpoints(&server), TOPIC, &auth)
+        .await
+        .unwrap_err()
+        .is_credential_validation());
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+pub async fn fcm_v1_dry_run_accepted() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string_contains("grant-type%3Ajwt-bearer"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "access-token",
+            "expires_in": 3600,
+            "token_type": "Bearer",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/projects/echo-test/messages:send"))
+        .and(header("authorization", "Bearer access-token"))
+        .and(body_string_contains("validate_only"))
+        .respond_with(ResponseTemplate::new(400))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    validate_fcm_v1(&endpoints(&server), &fcm_v1_credentials(&server))
+        .await
+        .expect("credentials should be accepted");
+}
+
+#[tokio::test]
+pub async fn fcm_v1_token_rejected() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": "invalid_grant",
+            "error_description": "Invalid JWT Signature.",
+        })))
+        .mount(&server)
+        .await;
+
+    assert!(
+        validate_fcm_v1(&endpoints(&server), &fcm_v1_credentials(&server))
+            .await
+            .unwrap_err()
+            .is_bad_fcm_v1_credentials()
+    );
+}
+
+#[tokio::test]
+pub async fn fcm_v1_project_forbidden() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({ "access_token": "access-token" })),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/projects/echo-test/messages:send"))
+        .respond_with(ResponseTemplate::new(403))
+        .mount(&server)
+        .await;
+
+    assert!(
+        validate_fcm_v1(&endpoints(&server), &fcm_v1_credentials(&server))
+            .await
+            .unwrap_err()
+            .is_bad_fcm_v1_credentials()
+    );
+}
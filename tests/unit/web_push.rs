@@ -0,0 +1,142 @@
+use {
+    base64::Engine as _,
+    echo_server::{
+        error::Error,
+        providers::web_push::{response_result, vapid_keys, WebPushSubscription},
+    },
+    openssl::{
+        ec::{EcGroup, EcKey},
+        nid::Nid,
+    },
+    wiremock::{http::Method, matchers::method, Mock, MockServer, ResponseTemplate},
+};
+
+// Subscription keys taken from the RFC 8291 example
+const EXAMPLE_P256DH: &str =
+    "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+const EXAMPLE_AUTH: &str = "BTBZMqHH6r4Tts7J_aSIgg";
+
+fn subscription_token(p256dh: &str, auth: &str) -> String {
+    serde_json::json!({
+        "endpoint": "https://push.example.net/push/JzLQ3raZJfFBR0aqvOMsLrt54w4rJUsV",
+        "keys": {
+            "p256dh": p256dh,
+            "auth": auth,
+        },
+    })
+    .to_string()
+}
+
+#[test]
+pub fn parse_web_push_subscription() {
+    let subscription =
+        WebPushSubscription::from_token(&subscription_token(EXAMPLE_P256DH, EXAMPLE_AUTH))
+            .expect("valid subscription");
+
+    assert_eq!(subscription.keys.p256dh, EXAMPLE_P256DH);
+    assert_eq!(subscription.keys.auth, EXAMPLE_AUTH);
+}
+
+#[test]
+pub fn reject_invalid_web_push_subscription() {
+    assert!(WebPushSubscription::from_token("not-a-subscription")
+        .unwrap_err()
+        .is_bad_device_token());
+    assert!(
+        WebPushSubscription::from_token(&subscription_token("short", EXAMPLE_AUTH))
+            .unwrap_err()
+            .is_bad_device_token()
+    );
+    assert!(
+        WebPushSubscription::from_token(&subscription_token(EXAMPLE_P256DH, "short"))
+            .unwrap_err()
+            .is_bad_device_token()
+    );
+}
+
+#[test]
+pub fn derive_vapid_public_key() {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let key = EcKey::generate(&group).unwrap();
+    let pem = key.private_key_to_pem().unwrap();
+
+    let (_, public_key) = vapid_keys(&pem).expect("valid VAPID key");
+    let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(public_key)
+        .unwrap();
+
+    // Uncompressed P-256 point
+    assert_eq!(decoded.len(), 65);
+    assert_eq!(decoded[0], 0x04);
+}
+
+#[test]
+pub fn reject_non_p256_vapid_key() {
+    let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+    let key = EcKey::generate(&group).unwrap();
+    let pem = key.private_key_to_pem().unwrap();
+
+    assert!(matches!(
+        vapid_keys(&pem),
+        Err(Error::BadWebPushCredentials)
+    ));
+}
+
+async fn response_with_status(status: u16) -> Result<Option<String>, Error> {
+    let push_service = MockServer::start().await;
+    Mock::given(method(Method::POST))
+        .respond_with(ResponseTemplate::new(status))
+        .mount(&push_service)
+        .await;
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/push", push_service.uri()))
+        .send()
+        .await
+        .unwrap();
+    response_result(&response)
+}
+
+#[tokio::test]
+pub async fn web_push_response_status() {
+    assert!(response_with_status(201).await.is_ok());
+    // A subscription for other VAPID keys only drops that client
+    for status in [403, 404, 410] {
+        assert!(matches!(
+            response_with_status(status).await,
+            Err(Error::BadDeviceToken(_))
+        ));
+    }
+    // Rejected VAPID credentials don't invalidate the subscription
+    assert!(matches!(
+        response_with_status(401).await,
+        Err(Error::BadWebPushCredentials)
+    ));
+}
+
+#[test]
+pub fn reject_internal_web_push_endpoint() {
+    for endpoint in [
+        "http://push.example.net/push",
+        "https://localhost/push",
+        "https://127.0.0.1:8443/push",
+        "https://169.254.169.254/latest/meta-data",
+        "https://10.0.0.1/push",
+        "https://[::1]/push",
+    ] {
+        let token = serde_json::json!({
+            "endpoint": endpoint,
+            "keys": {
+                "p256dh": EXAMPLE_P256DH,
+                "auth": EXAMPLE_AUTH,
+            },
+        })
+        .to_string();
+        assert!(
+            WebPushSubscription::from_token(&token)
+                .unwrap_err()
+                .is_bad_device_token(),
+            "{endpoint} was accepted"
+        );
+    }
+}
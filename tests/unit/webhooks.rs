@@ -0,0 +1,139 @@
+use {
+    axum::{http::StatusCode, response::IntoResponse},
+    chrono::{TimeZone, Utc},
+    echo_server::{
+        error::Error,
+        handlers::push_batch::BatchPushOutcome,
+        stores::webhook::WebhookEvent,
+        webhooks::{is_public_ip, sign, validate_url, WebhookBody},
+    },
+};
+
+#[test]
+fn signature_format() {
+    let signature = sign("secret", 1700000000, b"{}").unwrap();
+    let (timestamp, digest) = signature.split_once(',').unwrap();
+    assert_eq!(timestamp, "t=1700000000");
+
+    let digest = digest.strip_prefix("v1=").unwrap();
+    assert_eq!(digest.len(), 64);
+    assert!(digest.chars().all(|c| c.is_ascii_hexdigit()));
+}
+
+#[test]
+fn signature_covers_secret_timestamp_and_body() {
+    let signature = sign("secret", 1700000000, b"{}").unwrap();
+    assert_eq!(signature, sign("secret", 1700000000, b"{}").unwrap());
+
+    assert_ne!(signature, sign("other", 1700000000, b"{}").unwrap());
+    assert_ne!(
+        signature.split_once(',').unwrap().1,
+        sign("secret", 1700000001, b"{}")
+            .unwrap()
+            .split_once(',')
+            .unwrap()
+            .1
+    );
+    assert_ne!(signature, sign("secret", 1700000000, b"[]").unwrap());
+}
+
+#[test]
+fn body_format() {
+    let event = WebhookEvent::CredentialsExpiring {
+        provider: "apns".to_string(),
+        expires_at: Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap(),
+    };
+    let body = serde_json::to_value(WebhookBody {
+        id: "event",
+        tenant_id: "tenant",
+        created_at: Utc.with_ymd_and_hms(2029, 12, 1, 0, 0, 0).unwrap(),
+        event: &event,
+    })
+    .unwrap();
+
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "id": "event",
+            "tenant_id": "tenant",
+            "created_at": "2029-12-01T00:00:00Z",
+            "type": "credentials_expiring",
+            "data": {
+                "provider": "apns",
+                "expires_at": "2030-01-01T00:00:00Z",
+            },
+        })
+    );
+}
+
+#[test]
+fn topic_push_completed_format() {
+    let event = WebhookEvent::TopicPushCompleted {
+        job_id: "job".to_string(),
+        topic: "news".to_string(),
+        clients: 3,
+        outcomes: [
+            (BatchPushOutcome::Delivered, 2),
+            (BatchPushOutcome::NotFound, 1),
+        ]
+        .into(),
+    };
+    assert_eq!(event.event_type(), "topic_push_completed");
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        serde_json::json!({
+            "type": "topic_push_completed",
+            "data": {
+                "job_id": "job",
+                "topic": "news",
+                "clients": 3,
+                "outcomes": {"delivered": 2, "not_found": 1},
+            },
+        })
+    );
+}
+
+#[test]
+fn webhook_url_must_be_https() {
+    assert!(validate_url("https://example.com/hooks").is_ok());
+    assert!(validate_url("http://example.com/hooks").is_err());
+    assert!(validate_url("ftp://example.com/hooks").is_err());
+    assert!(validate_url("not a url").is_err());
+}
+
+#[test]
+fn webhook_url_must_not_be_internal() {
+    for url in [
+        "https://localhost/hooks",
+        "https://app.localhost/hooks",
+        "https://127.0.0.1/hooks",
+        "https://10.0.0.1/hooks",
+        "https://172.16.0.1/hooks",
+        "https://192.168.1.1/hooks",
+        "https://169.254.169.254/latest/meta-data",
+        "https://metadata.google.internal/computeMetadata/v1",
+        "https://0.0.0.0/hooks",
+        "https://[::1]/hooks",
+        "https://[fd00::1]/hooks",
+        "https://[fe80::1]/hooks",
+        "https://[::ffff:127.0.0.1]/hooks",
+    ] {
+        assert!(validate_url(url).is_err(), "{url} was accepted");
+    }
+    assert!(validate_url("https://93.184.216.34/hooks").is_ok());
+    assert!(validate_url("https://[2606:2800:220:1::]/hooks").is_ok());
+}
+
+#[test]
+fn public_ips() {
+    assert!(is_public_ip("8.8.8.8".parse().unwrap()));
+    assert!(!is_public_ip("169.254.169.254".parse().unwrap()));
+    assert!(!is_public_ip("100.64.0.1".parse().unwrap()));
+    assert!(!is_public_ip("::ffff:10.0.0.1".parse().unwrap()));
+}
+
+#[test]
+fn webhook_not_configured_is_not_found() {
+    let response = Error::WebhookNotConfigured.into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}